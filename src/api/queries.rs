@@ -1,7 +1,165 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
 pub fn daily_note_uid_for_date(month: u32, day: u32, year: i32) -> String {
     format!("{:02}-{:02}-{}", month, day, year)
 }
 
+/// Parse a Roam daily note title such as `"February 21st, 2026"` into a date.
+///
+/// Returns `None` for titles that are not daily note pages.
+pub fn parse_daily_note_title(title: &str) -> Option<chrono::NaiveDate> {
+    let (month_day, year) = title.split_once(", ")?;
+    let (month_name, day) = month_day.split_once(' ')?;
+    let month = match month_name {
+        "January" => 1,
+        "February" => 2,
+        "March" => 3,
+        "April" => 4,
+        "May" => 5,
+        "June" => 6,
+        "July" => 7,
+        "August" => 8,
+        "September" => 9,
+        "October" => 10,
+        "November" => 11,
+        "December" => 12,
+        _ => return None,
+    };
+    let day = day
+        .strip_suffix("st")
+        .or_else(|| day.strip_suffix("nd"))
+        .or_else(|| day.strip_suffix("rd"))
+        .or_else(|| day.strip_suffix("th"))?;
+    let day: u32 = day.parse().ok()?;
+    let year: i32 = year.trim().parse().ok()?;
+    chrono::NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// Parse a date typed by a person, relative to `today`.
+///
+/// Accepts `YYYY-MM-DD`, daily note titles (`March 1st, 2026`), `today`,
+/// `yesterday`, `tomorrow`, weekdays (`friday` is the next one from today on,
+/// `next friday` the next after today, `last monday` the most recent before
+/// it), offsets (`in 3 days`, `2 weeks ago`, `next month`) and month/day pairs
+/// (`mar 5`, `5th march`, `mar 5 2027`; this year when none is given).
+/// Case and commas are ignored.
+pub fn parse_natural_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    if let Some(date) = parse_daily_note_title(input) {
+        return Some(date);
+    }
+    let lower = input.to_lowercase().replace(',', " ");
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] => Some(today),
+        ["yesterday"] => today.checked_sub_days(Days::new(1)),
+        ["tomorrow"] => today.checked_add_days(Days::new(1)),
+        ["in", n, unit] => shift(today, n.parse().ok()?, unit),
+        [n, unit, "ago"] => shift(today, n.parse::<i64>().ok()?.checked_neg()?, unit),
+        ["next", word] => match parse_weekday(word) {
+            Some(weekday) => today.checked_add_days(Days::new(days_until(today, weekday, 1))),
+            None => shift(today, 1, word),
+        },
+        ["last", word] => match parse_weekday(word) {
+            Some(weekday) => {
+                let back = (today.weekday().num_days_from_monday() + 7
+                    - weekday.num_days_from_monday()
+                    - 1)
+                    % 7
+                    + 1;
+                today.checked_sub_days(Days::new(back.into()))
+            }
+            None => shift(today, -1, word),
+        },
+        [word] | ["this", word] if parse_weekday(word).is_some() => {
+            let weekday = parse_weekday(word)?;
+            today.checked_add_days(Days::new(days_until(today, weekday, 0)))
+        }
+        [a, b] => month_day(a, b, today.year()).or_else(|| month_day(b, a, today.year())),
+        [a, b, year] => {
+            let year = year.parse().ok()?;
+            month_day(a, b, year).or_else(|| month_day(b, a, year))
+        }
+        _ => None,
+    }
+}
+
+/// `today` moved by `n` days, weeks, months or years.
+fn shift(today: NaiveDate, n: i64, unit: &str) -> Option<NaiveDate> {
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    let (amount, months) = match unit {
+        "day" => (n, false),
+        "week" => (n.checked_mul(7)?, false),
+        "month" => (n, true),
+        "year" => (n.checked_mul(12)?, true),
+        _ => return None,
+    };
+    let magnitude = amount.unsigned_abs();
+    match (months, amount >= 0) {
+        (false, true) => today.checked_add_days(Days::new(magnitude)),
+        (false, false) => today.checked_sub_days(Days::new(magnitude)),
+        (true, true) => today.checked_add_months(Months::new(u32::try_from(magnitude).ok()?)),
+        (true, false) => today.checked_sub_months(Months::new(u32::try_from(magnitude).ok()?)),
+    }
+}
+
+/// Days from `today` to the next `weekday` at least `min` days away.
+fn days_until(today: NaiveDate, weekday: Weekday, min: u32) -> u64 {
+    let from = today.weekday().num_days_from_monday() + min;
+    ((weekday.num_days_from_monday() + 7 - from % 7) % 7 + min).into()
+}
+
+/// A weekday by full name or abbreviation (`fri`, `tues`, `thurs`).
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    const NAMES: [(&str, Weekday); 7] = [
+        ("monday", Weekday::Mon),
+        ("tuesday", Weekday::Tue),
+        ("wednesday", Weekday::Wed),
+        ("thursday", Weekday::Thu),
+        ("friday", Weekday::Fri),
+        ("saturday", Weekday::Sat),
+        ("sunday", Weekday::Sun),
+    ];
+    if word.len() < 3 {
+        return None;
+    }
+    NAMES
+        .iter()
+        .find(|(name, _)| name.starts_with(word))
+        .map(|(_, weekday)| *weekday)
+}
+
+/// A date from a month name or abbreviation (`mar`, `sept`) and a day,
+/// optionally with an ordinal suffix (`5th`).
+fn month_day(month: &str, day: &str, year: i32) -> Option<NaiveDate> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    if month.len() < 3 {
+        return None;
+    }
+    let month = MONTHS.iter().position(|name| name.starts_with(month))? as u32 + 1;
+    let day = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| day.strip_suffix(suffix))
+        .unwrap_or(day);
+    NaiveDate::from_ymd_opt(year, month, day.parse().ok()?)
+}
+
 pub fn pull_daily_note(uid: &str) -> (serde_json::Value, String) {
     let eid = serde_json::Value::String(format!("[:block/uid \"{}\"]", uid));
     let selector = page_selector();
@@ -14,6 +172,20 @@ pub fn pull_page_by_title(title: &str) -> (serde_json::Value, String) {
     (eid, selector)
 }
 
+/// Pull the title of the page a block lives on.
+pub fn pull_block_page(uid: &str) -> (serde_json::Value, String) {
+    let eid = serde_json::Value::String(format!("[:block/uid \"{}\"]", uid));
+    let selector = "[{:block/page [:node/title]}]".to_string();
+    (eid, selector)
+}
+
+/// Pull a block with its children, for rendering a block embed.
+pub fn pull_block_tree(uid: &str) -> (serde_json::Value, String) {
+    let eid = serde_json::Value::String(format!("[:block/uid \"{}\"]", uid));
+    let selector = page_selector();
+    (eid, selector)
+}
+
 pub fn linked_refs_query(page_title: &str) -> String {
     let escaped = page_title.replace('"', r#"\""#);
     format!(
@@ -26,6 +198,52 @@ pub fn all_page_titles_query() -> String {
     "[:find ?title ?uid :where [?e :node/title ?title] [?e :block/uid ?uid]]".to_string()
 }
 
+/// Build a Datalog query for blocks whose text contains `text` (case-insensitive).
+///
+/// Rows are `[uid, block_string, page_title]`; parse them with
+/// `types::parse_block_search_results`.
+pub fn search_blocks_query(text: &str) -> String {
+    format!(
+        r#"[:find ?uid ?s ?page-title :where [(re-pattern "{}") ?re] [?b :block/string ?s] [(re-find ?re ?s)] [?b :block/uid ?uid] [?b :block/page ?p] [?p :node/title ?page-title]]"#,
+        search_pattern(text)
+    )
+}
+
+/// Build a Datalog query for pages whose title contains `text` (case-insensitive).
+///
+/// Rows are `[uid, title]`; parse them with `types::parse_page_search_results`.
+pub fn search_page_titles_query(text: &str) -> String {
+    format!(
+        r#"[:find ?uid ?title :where [(re-pattern "{}") ?re] [?p :node/title ?title] [(re-find ?re ?title)] [?p :block/uid ?uid]]"#,
+        search_pattern(text)
+    )
+}
+
+/// Build a Datalog query for the daily notes of a month that have blocks.
+///
+/// Rows are `[title]`; parse them with `types::parse_daily_note_dates`.
+pub fn daily_notes_with_content_query(year: i32, month: u32) -> String {
+    let month_name = chrono::NaiveDate::from_ymd_opt(year, month, 1)
+        .map(|date| date.format("%B").to_string())
+        .unwrap_or_default();
+    format!(
+        r#"[:find ?title :where [(re-pattern "^{} \\d+[a-z]{{2}}, {}$") ?re] [?p :node/title ?title] [(re-find ?re ?title)] [?p :block/children _]]"#,
+        month_name, year
+    )
+}
+
+/// Case-insensitive regex matching `text` literally, escaped for a Datalog string.
+fn search_pattern(text: &str) -> String {
+    let mut pattern = String::from("(?i)");
+    for c in text.chars() {
+        if r".^$*+?()[]{}|\".contains(c) {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.replace('\\', r"\\").replace('"', r#"\""#)
+}
+
 fn page_selector() -> String {
     "[:block/uid :node/title :block/string {:block/children [:block/uid :block/string :block/order :block/open {:block/refs [:block/uid :node/title :block/string]} {:block/children ...}]}]".to_string()
 }
@@ -41,6 +259,78 @@ mod tests {
         assert_eq!(daily_note_uid_for_date(1, 5, 2026), "01-05-2026");
     }
 
+    #[test]
+    fn parse_daily_note_title_handles_ordinal_suffixes() {
+        let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(
+            parse_daily_note_title("February 21st, 2026"),
+            date(2026, 2, 21)
+        );
+        assert_eq!(parse_daily_note_title("March 2nd, 2026"), date(2026, 3, 2));
+        assert_eq!(parse_daily_note_title("May 3rd, 2025"), date(2025, 5, 3));
+        assert_eq!(
+            parse_daily_note_title("December 11th, 2024"),
+            date(2024, 12, 11)
+        );
+    }
+
+    #[test]
+    fn daily_notes_with_content_query_matches_month_titles() {
+        let query = daily_notes_with_content_query(2026, 3);
+        assert!(query.contains(r#"(re-pattern "^March \\d+[a-z]{2}, 2026$")"#));
+        assert!(query.contains("[?p :block/children _]"));
+    }
+
+    #[test]
+    fn parse_daily_note_title_rejects_regular_pages() {
+        assert_eq!(parse_daily_note_title("My Page"), None);
+        assert_eq!(parse_daily_note_title("Project, Alpha"), None);
+        assert_eq!(parse_daily_note_title("February 30th, 2026"), None);
+        assert_eq!(parse_daily_note_title("February 21, 2026"), None);
+    }
+
+    #[test]
+    fn parse_natural_date_handles_relative_and_absolute_dates() {
+        // Saturday
+        let today = NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        let parse = |input| parse_natural_date(input, today);
+        assert_eq!(parse("2026-03-01"), date(2026, 3, 1));
+        assert_eq!(parse("March 1st, 2026"), date(2026, 3, 1));
+        assert_eq!(parse(" Yesterday "), date(2026, 2, 20));
+        assert_eq!(parse("tomorrow"), date(2026, 2, 22));
+        assert_eq!(parse("in 3 days"), date(2026, 2, 24));
+        assert_eq!(parse("in 1 week"), date(2026, 2, 28));
+        assert_eq!(parse("2 months ago"), date(2025, 12, 21));
+        assert_eq!(parse("next month"), date(2026, 3, 21));
+        assert_eq!(parse("last year"), date(2025, 2, 21));
+        assert_eq!(parse("mar 5"), date(2026, 3, 5));
+        assert_eq!(parse("5th March"), date(2026, 3, 5));
+        assert_eq!(parse("Sept 30, 2027"), date(2027, 9, 30));
+        assert_eq!(parse("someday"), None);
+        assert_eq!(parse("feb 30"), None);
+        assert_eq!(parse("in many days"), None);
+        // Out of range offsets fail instead of overflowing
+        assert_eq!(parse("-9223372036854775808 days ago"), None);
+        assert_eq!(parse("in 9223372036854775807 weeks"), None);
+    }
+
+    #[test]
+    fn parse_natural_date_handles_weekdays() {
+        // Saturday
+        let today = NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        let parse = |input| parse_natural_date(input, today);
+        assert_eq!(parse("friday"), date(2026, 2, 27));
+        assert_eq!(parse("saturday"), date(2026, 2, 21));
+        assert_eq!(parse("next friday"), date(2026, 2, 27));
+        assert_eq!(parse("next sat"), date(2026, 2, 28));
+        assert_eq!(parse("last monday"), date(2026, 2, 16));
+        assert_eq!(parse("last friday"), date(2026, 2, 20));
+        assert_eq!(parse("last saturday"), date(2026, 2, 14));
+        assert_eq!(parse("this Thurs"), date(2026, 2, 26));
+    }
+
     #[test]
     fn pull_daily_note_returns_correct_eid_format() {
         let (eid, _selector) = pull_daily_note("02-21-2026");
@@ -101,6 +391,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pull_block_page_selects_page_title() {
+        let (eid, selector) = pull_block_page("abc123");
+        assert_eq!(
+            eid,
+            serde_json::Value::String("[:block/uid \"abc123\"]".into())
+        );
+        assert!(selector.contains(":block/page"));
+        assert!(selector.contains(":node/title"));
+    }
+
     #[test]
     fn linked_refs_query_contains_page_title() {
         let q = linked_refs_query("My Page");
@@ -131,4 +432,29 @@ mod tests {
         let q = linked_refs_query(r#"Page "with" quotes"#);
         assert!(q.contains(r#"Page \"with\" quotes"#));
     }
+
+    #[test]
+    fn search_blocks_query_is_case_insensitive_regex() {
+        let q = search_blocks_query("Roam");
+        assert!(q.contains(r#"(re-pattern "(?i)Roam")"#));
+        assert!(q.contains("re-find"));
+        assert!(q.contains(":block/string"));
+        assert!(q.contains(":block/page"));
+        assert!(q.contains("?page-title"));
+    }
+
+    #[test]
+    fn search_page_titles_query_matches_titles() {
+        let q = search_page_titles_query("proj");
+        assert!(q.contains(r#"(re-pattern "(?i)proj")"#));
+        assert!(q.contains(":node/title"));
+        assert!(q.contains(":block/uid"));
+    }
+
+    #[test]
+    fn search_pattern_escapes_regex_and_quotes() {
+        assert_eq!(search_pattern("a.b"), r"(?i)a\\.b");
+        assert_eq!(search_pattern("[[x]]"), r"(?i)\\[\\[x\\]\\]");
+        assert_eq!(search_pattern(r#"say "hi""#), r#"(?i)say \"hi\""#);
+    }
 }
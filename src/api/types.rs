@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
@@ -26,6 +28,18 @@ pub struct Block {
     pub refs: Vec<RefEntity>,
 }
 
+impl Block {
+    /// `(name, value)` when the block is an attribute such as `Status:: Done`.
+    pub fn attribute(&self) -> Option<(String, String)> {
+        crate::markup::attribute(&self.string)
+    }
+
+    /// Parse the result of [`pull_block_tree`](crate::api::queries::pull_block_tree).
+    pub fn from_pull_response(result: &serde_json::Value) -> Self {
+        parse_block_from_json(result)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RefEntity {
     pub uid: String,
@@ -68,6 +82,16 @@ impl DailyNote {
             blocks,
         }
     }
+
+    /// Attributes set by the page's top-level blocks, keyed by name. When a
+    /// name repeats, the first block wins.
+    pub fn attributes(&self) -> HashMap<String, String> {
+        let mut attributes = HashMap::new();
+        for (name, value) in self.blocks.iter().filter_map(Block::attribute) {
+            attributes.entry(name).or_insert(value);
+        }
+        attributes
+    }
 }
 
 fn parse_block_from_json(val: &serde_json::Value) -> Block {
@@ -210,6 +234,133 @@ pub fn parse_linked_refs(
         .collect()
 }
 
+/// A graph-wide search match: either a block or a page whose title matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub uid: String,
+    /// Block text, or the page title for page hits.
+    pub string: String,
+    /// Title of the page containing the block (the page itself for page hits).
+    pub page_title: String,
+    pub is_page: bool,
+}
+
+/// Parse rows of `[uid, block_string, page_title]` from `queries::search_blocks_query`.
+pub fn parse_block_search_results(result: &[Vec<serde_json::Value>]) -> Vec<SearchHit> {
+    result
+        .iter()
+        .filter_map(|row| {
+            let uid = row.first()?.as_str()?;
+            let string = row.get(1)?.as_str()?;
+            let page_title = row.get(2)?.as_str()?;
+            if uid.is_empty() || string.is_empty() {
+                return None;
+            }
+            Some(SearchHit {
+                uid: uid.to_string(),
+                string: string.to_string(),
+                page_title: page_title.to_string(),
+                is_page: false,
+            })
+        })
+        .collect()
+}
+
+/// Parse rows of `[title]` from `queries::daily_notes_with_content_query`,
+/// skipping titles that aren't daily notes.
+pub fn parse_daily_note_dates(result: &[Vec<serde_json::Value>]) -> Vec<NaiveDate> {
+    let mut dates: Vec<NaiveDate> = result
+        .iter()
+        .filter_map(|row| crate::api::queries::parse_daily_note_title(row.first()?.as_str()?))
+        .collect();
+    dates.sort();
+    dates
+}
+
+/// Parse rows of `[uid, title]` from `queries::search_page_titles_query`.
+pub fn parse_page_search_results(result: &[Vec<serde_json::Value>]) -> Vec<SearchHit> {
+    let mut hits: Vec<SearchHit> = result
+        .iter()
+        .filter_map(|row| {
+            let uid = row.first()?.as_str()?;
+            let title = row.get(1)?.as_str()?;
+            if title.is_empty() {
+                return None;
+            }
+            Some(SearchHit {
+                uid: uid.to_string(),
+                string: title.to_string(),
+                page_title: title.to_string(),
+                is_page: true,
+            })
+        })
+        .collect();
+    // Shorter titles are usually the closer match
+    hits.sort_by(|a, b| {
+        a.string
+            .len()
+            .cmp(&b.string.len())
+            .then_with(|| a.string.cmp(&b.string))
+    });
+    hits
+}
+
+/// Ordering applied to linked reference groups.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkedRefSort {
+    /// Alphabetical by referencing page title (the default).
+    #[default]
+    PageTitle,
+    /// Daily notes first, most recent date first; other pages follow alphabetically.
+    NewestFirst,
+    /// Daily notes first, oldest date first; other pages follow alphabetically.
+    OldestFirst,
+}
+
+impl LinkedRefSort {
+    pub fn next(self) -> Self {
+        match self {
+            Self::PageTitle => Self::NewestFirst,
+            Self::NewestFirst => Self::OldestFirst,
+            Self::OldestFirst => Self::PageTitle,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PageTitle => "page title",
+            Self::NewestFirst => "newest first",
+            Self::OldestFirst => "oldest first",
+        }
+    }
+}
+
+/// Reorder linked reference groups in place.
+///
+/// Date-based sorts use the daily note date parsed from each group's page title;
+/// groups on regular pages are placed after all daily notes, alphabetically.
+pub fn sort_linked_ref_groups(groups: &mut [LinkedRefGroup], sort: LinkedRefSort) {
+    use crate::api::queries::parse_daily_note_title;
+
+    match sort {
+        LinkedRefSort::PageTitle => groups.sort_by(|a, b| a.page_title.cmp(&b.page_title)),
+        LinkedRefSort::NewestFirst | LinkedRefSort::OldestFirst => {
+            groups.sort_by(|a, b| {
+                let da = parse_daily_note_title(&a.page_title);
+                let db = parse_daily_note_title(&b.page_title);
+                match (da, db) {
+                    (Some(da), Some(db)) if sort == LinkedRefSort::NewestFirst => db.cmp(&da),
+                    (Some(da), Some(db)) => da.cmp(&db),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => a.page_title.cmp(&b.page_title),
+                }
+            });
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "action")]
 #[allow(clippy::enum_variant_names)]
@@ -228,6 +379,11 @@ pub enum WriteAction {
         block: BlockRef,
         location: BlockLocation,
     },
+    #[serde(rename = "update-page")]
+    UpdatePage { page: PageUpdate },
+    /// Several actions applied in one request, in order.
+    #[serde(rename = "batch-actions")]
+    BatchActions { actions: Vec<WriteAction> },
 }
 
 #[derive(Debug, Serialize)]
@@ -264,6 +420,12 @@ pub struct BlockRef {
     pub uid: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct PageUpdate {
+    pub uid: String,
+    pub title: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -338,6 +500,45 @@ mod tests {
         assert_eq!(json["location"]["order"], "last");
     }
 
+    #[test]
+    fn write_action_update_page_serializes() {
+        let action = WriteAction::UpdatePage {
+            page: PageUpdate {
+                uid: "page1".into(),
+                title: "New Title".into(),
+            },
+        };
+        let json = serde_json::to_value(&action).unwrap();
+        assert_eq!(json["action"], "update-page");
+        assert_eq!(json["page"]["uid"], "page1");
+        assert_eq!(json["page"]["title"], "New Title");
+    }
+
+    #[test]
+    fn write_action_batch_serializes_nested_actions() {
+        let action = WriteAction::BatchActions {
+            actions: vec![
+                WriteAction::UpdatePage {
+                    page: PageUpdate {
+                        uid: "page1".into(),
+                        title: "New".into(),
+                    },
+                },
+                WriteAction::UpdateBlock {
+                    block: BlockUpdate {
+                        uid: "b1".into(),
+                        string: "see [[New]]".into(),
+                    },
+                },
+            ],
+        };
+        let json = serde_json::to_value(&action).unwrap();
+        assert_eq!(json["action"], "batch-actions");
+        assert_eq!(json["actions"][0]["action"], "update-page");
+        assert_eq!(json["actions"][1]["action"], "update-block");
+        assert_eq!(json["actions"][1]["block"]["string"], "see [[New]]");
+    }
+
     #[test]
     fn write_action_update_block_serializes() {
         let action = WriteAction::UpdateBlock {
@@ -487,6 +688,55 @@ mod tests {
         assert_eq!(note.title, "February 21, 2026");
     }
 
+    #[test]
+    fn daily_note_attributes_from_top_level_blocks() {
+        let pull_result = json!({
+            ":node/title": "Project X",
+            ":block/children": [
+                {":block/uid": "a", ":block/string": "Status:: [[In Progress]]", ":block/order": 0},
+                {":block/uid": "b", ":block/string": "Owner:: Ana", ":block/order": 1},
+                {":block/uid": "c", ":block/string": "Status:: Done", ":block/order": 2},
+                {
+                    ":block/uid": "d",
+                    ":block/string": "Notes",
+                    ":block/order": 3,
+                    ":block/children": [
+                        {":block/uid": "e", ":block/string": "Due:: Friday", ":block/order": 0}
+                    ]
+                }
+            ]
+        });
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        let note = DailyNote::from_pull_response(date, "px".into(), &pull_result);
+
+        let attributes = note.attributes();
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes["Status"], "[[In Progress]]");
+        assert_eq!(attributes["Owner"], "Ana");
+        assert_eq!(
+            note.blocks[3].children[0].attribute(),
+            Some(("Due".into(), "Friday".into()))
+        );
+    }
+
+    #[test]
+    fn block_from_pull_response_keeps_sorted_children() {
+        let pull_result = json!({
+            ":block/uid": "root",
+            ":block/string": "Embedded",
+            ":block/children": [
+                {":block/uid": "c2", ":block/string": "Second", ":block/order": 1},
+                {":block/uid": "c1", ":block/string": "First", ":block/order": 0}
+            ]
+        });
+        let block = Block::from_pull_response(&pull_result);
+
+        assert_eq!(block.uid, "root");
+        assert_eq!(block.string, "Embedded");
+        assert_eq!(block.children[0].string, "First");
+        assert_eq!(block.children[1].string, "Second");
+    }
+
     #[test]
     fn block_parses_refs_from_pull_response() {
         let pull_result = json!({
@@ -640,6 +890,110 @@ mod tests {
         assert!(groups.is_empty());
     }
 
+    #[test]
+    fn parse_block_search_results_keeps_page_context() {
+        let rows = vec![
+            vec![json!("u1"), json!("hello world"), json!("Page A")],
+            vec![json!("u2"), json!(""), json!("Page B")],
+            vec![json!("u3")],
+        ];
+        let hits = parse_block_search_results(&rows);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].uid, "u1");
+        assert_eq!(hits[0].page_title, "Page A");
+        assert!(!hits[0].is_page);
+    }
+
+    #[test]
+    fn parse_page_search_results_sorts_shortest_first() {
+        let rows = vec![
+            vec![json!("p1"), json!("Project Alpha")],
+            vec![json!("p2"), json!("Project")],
+        ];
+        let hits = parse_page_search_results(&rows);
+        assert_eq!(hits[0].string, "Project");
+        assert_eq!(hits[0].page_title, "Project");
+        assert!(hits[0].is_page);
+        assert_eq!(hits[1].uid, "p1");
+    }
+
+    #[test]
+    fn parse_daily_note_dates_sorts_and_skips_other_pages() {
+        let rows = vec![
+            vec![json!("March 9th, 2026")],
+            vec![json!("March 2nd, 2026")],
+            vec![json!("March 40th, 2026")],
+        ];
+        assert_eq!(
+            parse_daily_note_dates(&rows),
+            vec![
+                NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 3, 9).unwrap(),
+            ]
+        );
+    }
+
+    fn group(title: &str) -> LinkedRefGroup {
+        LinkedRefGroup {
+            page_title: title.into(),
+            blocks: vec![],
+        }
+    }
+
+    fn titles(groups: &[LinkedRefGroup]) -> Vec<&str> {
+        groups.iter().map(|g| g.page_title.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_linked_ref_groups_newest_first() {
+        let mut groups = vec![
+            group("Zettel"),
+            group("February 1st, 2026"),
+            group("Alpha"),
+            group("February 21st, 2026"),
+        ];
+        sort_linked_ref_groups(&mut groups, LinkedRefSort::NewestFirst);
+        assert_eq!(
+            titles(&groups),
+            vec![
+                "February 21st, 2026",
+                "February 1st, 2026",
+                "Alpha",
+                "Zettel"
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_linked_ref_groups_oldest_first() {
+        let mut groups = vec![
+            group("March 3rd, 2026"),
+            group("Notes"),
+            group("December 31st, 2025"),
+        ];
+        sort_linked_ref_groups(&mut groups, LinkedRefSort::OldestFirst);
+        assert_eq!(
+            titles(&groups),
+            vec!["December 31st, 2025", "March 3rd, 2026", "Notes"]
+        );
+    }
+
+    #[test]
+    fn sort_linked_ref_groups_by_title_restores_alphabetical() {
+        let mut groups = vec![group("b"), group("February 1st, 2026"), group("a")];
+        sort_linked_ref_groups(&mut groups, LinkedRefSort::PageTitle);
+        assert_eq!(titles(&groups), vec!["February 1st, 2026", "a", "b"]);
+    }
+
+    #[test]
+    fn linked_ref_sort_cycles_through_all_modes() {
+        let sort = LinkedRefSort::default();
+        assert_eq!(sort, LinkedRefSort::PageTitle);
+        assert_eq!(sort.next(), LinkedRefSort::NewestFirst);
+        assert_eq!(sort.next().next(), LinkedRefSort::OldestFirst);
+        assert_eq!(sort.next().next().next(), LinkedRefSort::PageTitle);
+    }
+
     #[test]
     fn parse_linked_refs_sorts_blocks_within_group() {
         let result = vec![
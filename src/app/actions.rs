@@ -3,21 +3,51 @@ use crate::edit_buffer::EditBuffer;
 use crate::keys::preset::Action;
 use crate::markdown;
 
+use super::attributes::open_attribute_table;
 use super::blocks::{
     find_block_in_days, find_block_index_by_uid, generate_uid, insert_block_in_days,
     resolve_block_at_index, set_block_open,
 };
+use super::calendar::open_calendar;
+use super::editor::request_external_edit;
+use super::embeds::embedded_block_at;
+use super::help::toggle_help;
+use super::linked_refs::open_linked_ref_filter;
 use super::nav::{
     navigate_to_page, push_nav_snapshot, restore_nav_snapshot, save_nav_snapshot_at_index,
 };
-use super::search::{filter_blocks, SEARCH_LIMIT};
+use super::palette::{open_command_palette, open_date_prompt};
+use super::query_blocks::toggle_query_results;
+use super::rename::open_rename_page;
 use super::search::{filter_page_titles, QUICK_SWITCHER_LIMIT};
+use super::search::{local_search_results, SEARCH_LIMIT};
 use super::state::{
     count_blocks_recursive, AppState, CreateInfo, InputMode, LinkPickerState, LinkedRefItem,
     LoadRequest, QuickSwitcherState, SearchState, ViewMode, ViewSnapshot,
 };
+use super::visual::{extend_selection, set_selection_open, toggle_visual_mode};
 
 pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadRequest> {
+    // Motions extend a visual selection; anything else ends it
+    if state.visual_anchor.is_some()
+        && !matches!(
+            action,
+            Action::MoveUp
+                | Action::MoveDown
+                | Action::SelectUp
+                | Action::SelectDown
+                | Action::NextDay
+                | Action::PrevDay
+                | Action::CursorLeft
+                | Action::CursorRight
+                | Action::Collapse
+                | Action::Expand
+                | Action::VisualMode
+                | Action::Exit
+        )
+    {
+        state.visual_anchor = None;
+    }
     match action {
         Action::Quit => {
             state.should_quit = true;
@@ -72,14 +102,25 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
             {
                 return None;
             }
-            if let Some(info) =
-                resolve_block_at_index(&state.days, &state.linked_refs, state.selected_block)
-            {
+            // Embed lines edit the embedded block in place
+            let target = resolve_block_at_index(
+                &state.days,
+                &state.linked_refs,
+                &state.embeds,
+                state.selected_block,
+            )
+            .map(|info| (info.block_uid, info.text))
+            .or_else(|| {
+                embedded_block_at(state, state.selected_block)
+                    .map(|block| (block.uid.clone(), block.string.clone()))
+            });
+            if let Some((block_uid, text)) = target {
                 state.input_mode = InputMode::Insert {
-                    buffer: EditBuffer::new(&info.text),
-                    block_uid: info.block_uid,
-                    original_text: info.text,
+                    buffer: EditBuffer::new(&text),
+                    block_uid,
+                    original_text: text,
                     create_info: None,
+                    vim_normal: None,
                 };
             }
             None
@@ -92,9 +133,12 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
             {
                 return None;
             }
-            if let Some(info) =
-                resolve_block_at_index(&state.days, &state.linked_refs, state.selected_block)
-            {
+            if let Some(info) = resolve_block_at_index(
+                &state.days,
+                &state.linked_refs,
+                &state.embeds,
+                state.selected_block,
+            ) {
                 let new_uid = generate_uid();
                 let order = info.order + 1;
                 let parent_uid = info.parent_uid.clone();
@@ -107,9 +151,12 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
                     refs: vec![],
                 };
                 insert_block_in_days(&mut state.days, &parent_uid, order, placeholder);
-                if let Some(idx) =
-                    find_block_index_by_uid(&state.days, &state.linked_refs, &new_uid)
-                {
+                if let Some(idx) = find_block_index_by_uid(
+                    &state.days,
+                    &state.linked_refs,
+                    &state.embeds,
+                    &new_uid,
+                ) {
                     state.selected_block = idx;
                     state.cursor_col = 0;
                 }
@@ -118,6 +165,7 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
                     block_uid: new_uid,
                     original_text: String::new(),
                     create_info: Some(CreateInfo { parent_uid, order }),
+                    vim_normal: None,
                 };
             } else if let Some(day) = state.days.first() {
                 // No blocks yet — create the first block as child of the day page
@@ -142,6 +190,7 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
                         parent_uid,
                         order: 0,
                     }),
+                    vim_normal: None,
                 };
             }
             None
@@ -149,35 +198,58 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
         Action::Search => {
             state.search = Some(SearchState {
                 query: String::new(),
-                results: filter_blocks(&state.days, &state.block_ref_cache, "", SEARCH_LIMIT),
+                results: local_search_results(
+                    &state.days,
+                    &state.block_ref_cache,
+                    "",
+                    SEARCH_LIMIT,
+                ),
                 selected: 0,
+                remote: Vec::new(),
+                debounce_ticks: 0,
+                pending_requests: 0,
+                generation: 0,
             });
             None
         }
         Action::Collapse => {
+            if state.visual_anchor.is_some() {
+                set_selection_open(state, false);
+                return None;
+            }
             if state
                 .resolve_linked_ref_item(state.selected_block)
                 .is_some()
             {
                 return None;
             }
-            if let Some(info) =
-                resolve_block_at_index(&state.days, &state.linked_refs, state.selected_block)
-            {
+            if let Some(info) = resolve_block_at_index(
+                &state.days,
+                &state.linked_refs,
+                &state.embeds,
+                state.selected_block,
+            ) {
                 set_block_open(&mut state.days, &info.block_uid, false);
             }
             None
         }
         Action::Expand => {
+            if state.visual_anchor.is_some() {
+                set_selection_open(state, true);
+                return None;
+            }
             if state
                 .resolve_linked_ref_item(state.selected_block)
                 .is_some()
             {
                 return None;
             }
-            if let Some(info) =
-                resolve_block_at_index(&state.days, &state.linked_refs, state.selected_block)
-            {
+            if let Some(info) = resolve_block_at_index(
+                &state.days,
+                &state.linked_refs,
+                &state.embeds,
+                state.selected_block,
+            ) {
                 set_block_open(&mut state.days, &info.block_uid, true);
             }
             None
@@ -202,9 +274,15 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
                 }
                 return None;
             }
-            if let Some(info) =
-                resolve_block_at_index(&state.days, &state.linked_refs, state.selected_block)
-            {
+            if let Some(info) = resolve_block_at_index(
+                &state.days,
+                &state.linked_refs,
+                &state.embeds,
+                state.selected_block,
+            ) {
+                if toggle_query_results(state, &info.block_uid, &info.text) {
+                    return None;
+                }
                 let links = markdown::extract_page_links(&info.text);
                 match links.len() {
                     0 => {
@@ -234,11 +312,13 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
             if state.days.len() > 1 {
                 let mut block_count = 0;
                 for (i, day) in state.days.iter().enumerate() {
-                    let day_blocks = count_blocks_recursive(&day.blocks);
+                    let day_blocks = count_blocks_recursive(&day.blocks, &state.embeds);
                     if state.selected_block < block_count + day_blocks && i > 0 {
                         // Currently in this day, jump to previous day (more recent)
-                        state.selected_block = block_count
-                            .saturating_sub(count_blocks_recursive(&state.days[i - 1].blocks));
+                        state.selected_block = block_count.saturating_sub(count_blocks_recursive(
+                            &state.days[i - 1].blocks,
+                            &state.embeds,
+                        ));
                         state.cursor_col = 0;
                         break;
                     }
@@ -255,7 +335,7 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
             let mut block_count = 0;
             let mut found = false;
             for (i, day) in state.days.iter().enumerate() {
-                let day_blocks = count_blocks_recursive(&day.blocks);
+                let day_blocks = count_blocks_recursive(&day.blocks, &state.embeds);
                 if state.selected_block < block_count + day_blocks {
                     // Currently in day i, jump to day i+1 if exists
                     if i + 1 < state.days.len() {
@@ -302,13 +382,19 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
             None
         }
         Action::Help => {
-            state.show_help = !state.show_help;
+            toggle_help(state);
+            None
+        }
+        Action::CommandPalette => {
+            open_command_palette(state);
             None
         }
         Action::Exit => {
-            // Close any overlay, or do nothing
-            if state.show_help {
-                state.show_help = false;
+            // Leave visual mode, close any overlay, or do nothing
+            if state.visual_anchor.is_some() {
+                state.visual_anchor = None;
+            } else if state.help.is_some() {
+                state.help = None;
             }
             None
         }
@@ -319,9 +405,12 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
             None
         }
         Action::CursorRight => {
-            if let Some(info) =
-                resolve_block_at_index(&state.days, &state.linked_refs, state.selected_block)
-            {
+            if let Some(info) = resolve_block_at_index(
+                &state.days,
+                &state.linked_refs,
+                &state.embeds,
+                state.selected_block,
+            ) {
                 let first_line = info.text.split('\n').next().unwrap_or("");
                 let rendered_len = markdown::rendered_char_count(first_line);
                 if rendered_len > 0 && state.cursor_col < rendered_len - 1 {
@@ -363,7 +452,12 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
         }
         Action::QuickSwitcher => {
             let filtered = if !state.page_title_cache.is_empty() {
-                filter_page_titles(&state.page_title_cache, "", QUICK_SWITCHER_LIMIT)
+                filter_page_titles(
+                    &state.page_title_cache,
+                    "",
+                    QUICK_SWITCHER_LIMIT,
+                    &state.recent_pages(),
+                )
             } else {
                 Vec::new()
             };
@@ -376,6 +470,39 @@ pub fn handle_action(state: &mut AppState, action: &Action) -> Option<LoadReques
             });
             None
         }
+        Action::FilterLinkedRefs => {
+            open_linked_ref_filter(state);
+            None
+        }
+        Action::RenamePage => open_rename_page(state),
+        Action::AttributeTable => {
+            open_attribute_table(state);
+            None
+        }
+        Action::GoToDate => {
+            open_date_prompt(state);
+            None
+        }
+        Action::Calendar => {
+            open_calendar(state, chrono::Local::now().date_naive(), false);
+            None
+        }
+        Action::VisualMode => {
+            toggle_visual_mode(state);
+            None
+        }
+        Action::SelectUp => {
+            extend_selection(state, true);
+            None
+        }
+        Action::SelectDown => {
+            extend_selection(state, false);
+            None
+        }
+        Action::OpenInEditor | Action::OpenPageInEditor => {
+            request_external_edit(state, *action == Action::OpenPageInEditor);
+            None
+        }
         _ => None,
     }
 }
@@ -0,0 +1,287 @@
+use std::ops::Range;
+
+use chrono::{Days, NaiveDate};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::api::queries::parse_daily_note_title;
+use crate::api::types::{BlockUpdate, SearchHit, WriteAction};
+use crate::keys::mode::PopupAction;
+use crate::markup::{self, NodeKind};
+use crate::roam_query::QueryExpr;
+
+use super::blocks::{format_roam_daily_title, update_block_text_in_days};
+use super::state::{AgendaGrouping, AgendaItem, AgendaState, AppState, LoadRequest, UndoEntry};
+use super::visual::push_undo;
+
+/// What the main loop should do after a key in the agenda.
+#[derive(Debug)]
+pub(super) enum AgendaOutcome {
+    Load(LoadRequest),
+    Write(WriteAction),
+    /// Run the TODO query again.
+    Reload,
+}
+
+/// Open the agenda (or mark it as reloading) and return the Datalog that
+/// finds every TODO block.
+pub(super) fn start_agenda(state: &mut AppState) -> String {
+    let agenda = state.agenda.get_or_insert_with(AgendaState::default);
+    agenda.loading = true;
+    agenda.error = None;
+    QueryExpr::Ref("TODO".into()).to_datalog()
+}
+
+/// Keep the hits that are open TODOs themselves; the query also returns
+/// blocks nested under one.
+pub(super) fn agenda_loaded(state: &mut AppState, result: Result<Vec<SearchHit>, String>) {
+    let Some(agenda) = &mut state.agenda else {
+        return;
+    };
+    agenda.loading = false;
+    match result {
+        Ok(hits) => {
+            agenda.items = hits
+                .into_iter()
+                .filter(|hit| todo_span(&hit.string, false).is_some())
+                .map(|hit| AgendaItem {
+                    due: due_link(&hit.string).map(|(_, date)| date),
+                    uid: hit.uid,
+                    text: hit.string,
+                    page_title: hit.page_title,
+                    done: false,
+                })
+                .collect();
+            agenda.selected = 0;
+            agenda.error = None;
+        }
+        Err(e) => agenda.error = Some(e),
+    }
+}
+
+/// Span of the first `{{[[TODO]]}}` (or, with `done`, `{{[[DONE]]}}`) marker.
+fn todo_span(text: &str, done: bool) -> Option<Range<usize>> {
+    let mut found = None;
+    markup::walk(&markup::parse(text), &mut |node| {
+        if found.is_none() && node.kind == (NodeKind::Todo { done }) {
+            found = Some(node.span.clone());
+        }
+    });
+    found
+}
+
+/// Span and date of the first link to a daily note, the task's due date.
+fn due_link(text: &str) -> Option<(Range<usize>, NaiveDate)> {
+    let mut found = None;
+    markup::walk(&markup::parse(text), &mut |node| {
+        if found.is_some() {
+            return;
+        }
+        if let NodeKind::PageRef { title, .. } | NodeKind::Tag { title, .. } = &node.kind {
+            found = parse_daily_note_title(title).map(|date| (node.span.clone(), date));
+        }
+    });
+    found
+}
+
+/// `text` with its due date set to `date`: the first daily-note link is
+/// replaced, keeping its `[[date]]` or `#[[date]]` form, or a link is
+/// appended when there is none.
+fn reschedule(text: &str, date: NaiveDate) -> String {
+    let link = format!("[[{}]]", format_roam_daily_title(date));
+    match due_link(text) {
+        Some((span, _)) => {
+            let hash = if text[span.clone()].starts_with('#') {
+                "#"
+            } else {
+                ""
+            };
+            format!(
+                "{}{}{}{}",
+                &text[..span.start],
+                hash,
+                link,
+                &text[span.end..]
+            )
+        }
+        None => format!("{} {}", text.trim_end(), link),
+    }
+}
+
+/// Handle a key in the agenda. `today` anchors the date filters and
+/// rescheduling of undated tasks.
+pub(super) fn handle_agenda_key(
+    state: &mut AppState,
+    key: &KeyEvent,
+    today: NaiveDate,
+) -> Option<AgendaOutcome> {
+    let action = state.popup_keys.resolve(key);
+    if action.is_none()
+        && key.modifiers != KeyModifiers::NONE
+        && key.modifiers != KeyModifiers::SHIFT
+    {
+        return None;
+    }
+    let agenda = state.agenda.as_mut()?;
+    let count = agenda.visible_count(today);
+    match (action, key.code) {
+        (Some(PopupAction::Close), _) | (None, KeyCode::Char('q')) => state.agenda = None,
+        (Some(PopupAction::SelectPrev), _) | (None, KeyCode::Char('k')) => {
+            agenda.selected = agenda.selected.saturating_sub(1);
+        }
+        (Some(PopupAction::SelectNext), _) | (None, KeyCode::Char('j'))
+            if agenda.selected + 1 < count =>
+        {
+            agenda.selected += 1;
+        }
+        (Some(PopupAction::Accept), _) => {
+            let uid = agenda.selected_item(today)?.uid.clone();
+            state.agenda = None;
+            return Some(AgendaOutcome::Load(LoadRequest::BlockPage(uid)));
+        }
+        (Some(_), _) => {}
+        (None, KeyCode::Char('f')) => {
+            agenda.filter = agenda.filter.next();
+            agenda.selected = 0;
+        }
+        (None, KeyCode::Char('g')) => {
+            agenda.grouping = match agenda.grouping {
+                AgendaGrouping::DueDate => AgendaGrouping::Page,
+                AgendaGrouping::Page => AgendaGrouping::DueDate,
+            };
+            agenda.selected = 0;
+        }
+        (None, KeyCode::Char('r')) => return Some(AgendaOutcome::Reload),
+        (None, KeyCode::Char('x') | KeyCode::Char(' ')) => return toggle_selected(state, today),
+        (None, KeyCode::Char('>') | KeyCode::Char('<')) => {
+            let item = agenda.selected_item(today)?;
+            let base = item.due.unwrap_or(today);
+            let date = if key.code == KeyCode::Char('>') {
+                base.checked_add_days(Days::new(1))
+            } else {
+                base.checked_sub_days(Days::new(1))
+            }?;
+            return reschedule_selected(state, date, today);
+        }
+        (None, KeyCode::Char('t')) => return reschedule_selected(state, today, today),
+        _ => {}
+    }
+    None
+}
+
+/// Check off the selected task, or uncheck it when already done here.
+fn toggle_selected(state: &mut AppState, today: NaiveDate) -> Option<AgendaOutcome> {
+    let agenda = state.agenda.as_ref()?;
+    let item = agenda.selected_item(today)?;
+    let span = todo_span(&item.text, item.done)?;
+    let marker = if item.done {
+        "{{[[TODO]]}}"
+    } else {
+        "{{[[DONE]]}}"
+    };
+    let text = format!(
+        "{}{}{}",
+        &item.text[..span.start],
+        marker,
+        &item.text[span.end..]
+    );
+    let uid = item.uid.clone();
+    Some(update_item(state, &uid, text, |item| {
+        item.done = !item.done
+    }))
+}
+
+fn reschedule_selected(
+    state: &mut AppState,
+    date: NaiveDate,
+    today: NaiveDate,
+) -> Option<AgendaOutcome> {
+    let item = state.agenda.as_ref()?.selected_item(today)?;
+    let text = reschedule(&item.text, date);
+    let uid = item.uid.clone();
+    let outcome = update_item(state, &uid, text, |item| item.due = Some(date));
+    // Follow the task to its new group, unless it left the filtered list
+    if let Some(agenda) = &mut state.agenda {
+        let position = agenda
+            .groups(today)
+            .into_iter()
+            .flat_map(|(_, items)| items)
+            .position(|item| item.uid == uid);
+        let count = agenda.visible_count(today);
+        agenda.selected = position.unwrap_or(agenda.selected.min(count.saturating_sub(1)));
+    }
+    Some(outcome)
+}
+
+/// Apply new text to an agenda item and any loaded copy of the block.
+fn update_item(
+    state: &mut AppState,
+    uid: &str,
+    text: String,
+    change: impl FnOnce(&mut AgendaItem),
+) -> AgendaOutcome {
+    if let Some(item) = state
+        .agenda
+        .as_mut()
+        .and_then(|agenda| agenda.items.iter_mut().find(|item| item.uid == uid))
+    {
+        let old_text = std::mem::replace(&mut item.text, text.clone());
+        change(item);
+        let selected = state.selected_block;
+        push_undo(
+            state,
+            vec![UndoEntry::TextEdit {
+                block_uid: uid.to_string(),
+                old_text,
+            }],
+            selected,
+        );
+    }
+    update_block_text_in_days(&mut state.days, uid, &text);
+    // The task's page may not be loaded; undo finds its text here once the
+    // agenda is closed
+    state.block_ref_cache.insert(uid.to_string(), text.clone());
+    AgendaOutcome::Write(WriteAction::UpdateBlock {
+        block: BlockUpdate {
+            uid: uid.to_string(),
+            string: text,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn due_link_finds_first_daily_note() {
+        let text = "{{[[TODO]]}} ship [[Project]] by [[February 24th, 2026]]";
+        let (span, due) = due_link(text).unwrap();
+        assert_eq!(due, date(2026, 2, 24));
+        assert_eq!(&text[span], "[[February 24th, 2026]]");
+        assert_eq!(
+            due_link("{{[[TODO]]}} #[[March 1st, 2026]]").unwrap().1,
+            date(2026, 3, 1)
+        );
+        assert!(due_link("{{[[TODO]]}} [[Project]]").is_none());
+    }
+
+    #[test]
+    fn reschedule_replaces_or_appends_due_link() {
+        assert_eq!(
+            reschedule("{{[[TODO]]}} a [[February 24th, 2026]] b", date(2026, 3, 2)),
+            "{{[[TODO]]}} a [[March 2nd, 2026]] b"
+        );
+        assert_eq!(
+            reschedule("{{[[TODO]]}} a ", date(2026, 3, 2)),
+            "{{[[TODO]]}} a [[March 2nd, 2026]]"
+        );
+        assert_eq!(
+            reschedule("{{[[TODO]]}} a #[[February 24th, 2026]]", date(2026, 3, 2)),
+            "{{[[TODO]]}} a #[[March 2nd, 2026]]"
+        );
+    }
+}
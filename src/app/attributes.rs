@@ -0,0 +1,67 @@
+use crossterm::event::KeyEvent;
+
+use crate::api::types::Block;
+use crate::keys::mode::PopupAction;
+
+use super::editor::selected_day;
+use super::nav::navigate_to_page;
+use super::state::{AppState, AttributeTableState, LoadRequest};
+
+/// Open the attribute table for the page (or day) of the selected block.
+pub(super) fn open_attribute_table(state: &mut AppState) {
+    let Some(day) = selected_day(state) else {
+        return;
+    };
+    let mut rows = Vec::new();
+    collect_attributes(&day.blocks, &mut rows);
+    if rows.is_empty() {
+        state.status_message = Some(format!("No attributes on \"{}\"", day.title));
+        return;
+    }
+    state.attribute_table = Some(AttributeTableState {
+        page_title: day.title.clone(),
+        rows,
+        selected: 0,
+    });
+}
+
+/// `(name, value)` of every attribute block, in document order.
+fn collect_attributes(blocks: &[Block], rows: &mut Vec<(String, String)>) {
+    for block in blocks {
+        rows.extend(block.attribute());
+        collect_attributes(&block.children, rows);
+    }
+}
+
+/// Handle a key in the attribute table; `Accept` opens the attribute's page.
+pub(super) fn handle_attribute_table_key(
+    state: &mut AppState,
+    key: &KeyEvent,
+) -> Option<LoadRequest> {
+    match state.popup_keys.resolve(key) {
+        Some(PopupAction::Close) => {
+            state.attribute_table = None;
+        }
+        Some(PopupAction::SelectPrev) => {
+            if let Some(table) = &mut state.attribute_table {
+                table.selected = table.selected.saturating_sub(1);
+            }
+        }
+        Some(PopupAction::SelectNext) => {
+            if let Some(table) = &mut state.attribute_table {
+                if table.selected + 1 < table.rows.len() {
+                    table.selected += 1;
+                }
+            }
+        }
+        Some(PopupAction::Accept) => {
+            if let Some(table) = state.attribute_table.take() {
+                if let Some((name, _)) = table.rows.get(table.selected) {
+                    return Some(navigate_to_page(state, name.clone()));
+                }
+            }
+        }
+        _ => {}
+    }
+    None
+}
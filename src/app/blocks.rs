@@ -3,18 +3,109 @@ use std::collections::HashMap;
 use chrono::{Datelike, NaiveDate};
 
 use crate::api::types::{Block, DailyNote};
+use crate::markup::{self, EmbedTarget};
 
-use super::state::{linked_ref_section_count, BlockInfo, LinkedRefsState};
+use super::state::{linked_ref_section_count, BlockInfo, EmbedState, LinkedRefsState};
 
-pub fn resolve_block_at_index(
-    days: &[DailyNote],
+/// A navigable row of the outline: a block of a day, or a line of an embed
+/// drawn under the block that embeds it.
+pub(crate) enum OutlineRow<'a> {
+    Block {
+        block: &'a Block,
+        parent_uid: &'a str,
+        depth: usize,
+    },
+    Embedded(&'a Block),
+}
+
+/// Visit the rows of `blocks` in display order until `visit` returns `Some`.
+/// Embedded lines follow their host, before its children; children of tables
+/// and kanban boards are cells and don't show embeds.
+pub(crate) fn walk_rows<'a, T>(
+    blocks: &'a [Block],
+    parent_uid: &'a str,
+    depth: usize,
+    embeds: &'a HashMap<EmbedTarget, EmbedState>,
+    visit: &mut impl FnMut(OutlineRow<'a>) -> Option<T>,
+) -> Option<T> {
+    walk_rows_in(blocks, parent_uid, depth, Some(embeds), visit)
+}
+
+fn walk_rows_in<'a, T>(
+    blocks: &'a [Block],
+    parent_uid: &'a str,
+    depth: usize,
+    embeds: Option<&'a HashMap<EmbedTarget, EmbedState>>,
+    visit: &mut impl FnMut(OutlineRow<'a>) -> Option<T>,
+) -> Option<T> {
+    for block in blocks {
+        if let Some(found) = visit(OutlineRow::Block {
+            block,
+            parent_uid,
+            depth,
+        }) {
+            return Some(found);
+        }
+        if let Some((target, embed)) = embeds.and_then(|embeds| embed_of(block, embeds)) {
+            for (_, embedded) in embed.visible_blocks(target) {
+                if let Some(found) = visit(OutlineRow::Embedded(embedded)) {
+                    return Some(found);
+                }
+            }
+        }
+        if block.open {
+            let cells = matches!(
+                markup::block_macro(&block.string).as_deref(),
+                Some("table" | "kanban")
+            );
+            let embeds = if cells { None } else { embeds };
+            if let Some(found) = walk_rows_in(&block.children, &block.uid, depth + 1, embeds, visit)
+            {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+/// The loaded embed shown under `block`, if its text embeds anything.
+pub(crate) fn embed_of<'a>(
+    block: &Block,
+    embeds: &'a HashMap<EmbedTarget, EmbedState>,
+) -> Option<(&'a EmbedTarget, &'a EmbedState)> {
+    embeds.get_key_value(&markup::find_embed(&block.string)?)
+}
+
+/// Every block of every day, collapsed children included.
+pub(super) fn for_each_day_block(days: &[DailyNote], f: &mut impl FnMut(&Block)) {
+    fn visit(blocks: &[Block], f: &mut impl FnMut(&Block)) {
+        for block in blocks {
+            f(block);
+            visit(&block.children, f);
+        }
+    }
+    for day in days {
+        visit(&day.blocks, f);
+    }
+}
+
+/// The row at `index` of the flat navigable list, unless it's in a linked
+/// refs section.
+pub(crate) fn row_at_index<'a>(
+    days: &'a [DailyNote],
     linked_refs: &HashMap<String, LinkedRefsState>,
+    embeds: &'a HashMap<EmbedTarget, EmbedState>,
     index: usize,
-) -> Option<BlockInfo> {
+) -> Option<OutlineRow<'a>> {
     let mut counter = 0;
     for day in days {
-        if let Some(info) = resolve_in_blocks(&day.blocks, &day.uid, 0, index, &mut counter) {
-            return Some(info);
+        let found = walk_rows(&day.blocks, &day.uid, 0, embeds, &mut |row| {
+            let found = (counter == index).then_some(row);
+            counter += 1;
+            found
+        });
+        if found.is_some() {
+            return found;
         }
         // Skip past this day's linked ref section
         if let Some(lr) = linked_refs.get(&day.title) {
@@ -24,15 +115,45 @@ pub fn resolve_block_at_index(
     None
 }
 
+/// The block at `index`, or `None` for embedded lines and linked refs.
+pub fn resolve_block_at_index(
+    days: &[DailyNote],
+    linked_refs: &HashMap<String, LinkedRefsState>,
+    embeds: &HashMap<EmbedTarget, EmbedState>,
+    index: usize,
+) -> Option<BlockInfo> {
+    match row_at_index(days, linked_refs, embeds, index)? {
+        OutlineRow::Block {
+            block,
+            parent_uid,
+            depth,
+        } => Some(BlockInfo {
+            block_uid: block.uid.clone(),
+            parent_uid: parent_uid.to_string(),
+            text: block.string.clone(),
+            order: block.order,
+            depth,
+        }),
+        OutlineRow::Embedded(_) => None,
+    }
+}
+
 pub fn find_block_index_by_uid(
     days: &[DailyNote],
     linked_refs: &HashMap<String, LinkedRefsState>,
+    embeds: &HashMap<EmbedTarget, EmbedState>,
     uid: &str,
 ) -> Option<usize> {
     let mut counter = 0;
     for day in days {
-        if let Some(idx) = find_index_in_blocks(&day.blocks, uid, &mut counter) {
-            return Some(idx);
+        let found = walk_rows(&day.blocks, &day.uid, 0, embeds, &mut |row| {
+            let found = matches!(row, OutlineRow::Block { block, .. } if block.uid == uid)
+                .then_some(counter);
+            counter += 1;
+            found
+        });
+        if found.is_some() {
+            return found;
         }
         // Skip past this day's linked ref section
         if let Some(lr) = linked_refs.get(&day.title) {
@@ -42,52 +163,16 @@ pub fn find_block_index_by_uid(
     None
 }
 
-fn find_index_in_blocks(blocks: &[Block], uid: &str, counter: &mut usize) -> Option<usize> {
-    for block in blocks {
-        if block.uid == uid {
-            return Some(*counter);
-        }
-        *counter += 1;
-        if block.open {
-            if let Some(idx) = find_index_in_blocks(&block.children, uid, counter) {
-                return Some(idx);
-            }
-        }
-    }
-    None
-}
+// --- Optimistic local tree updates ---
 
-fn resolve_in_blocks(
-    blocks: &[Block],
-    parent_uid: &str,
-    depth: usize,
-    target: usize,
-    counter: &mut usize,
-) -> Option<BlockInfo> {
-    for block in blocks {
-        if *counter == target {
-            return Some(BlockInfo {
-                block_uid: block.uid.clone(),
-                parent_uid: parent_uid.to_string(),
-                text: block.string.clone(),
-                order: block.order,
-                depth,
-            });
-        }
-        *counter += 1;
-        if block.open {
-            if let Some(info) =
-                resolve_in_blocks(&block.children, &block.uid, depth + 1, target, counter)
-            {
-                return Some(info);
-            }
-        }
+/// Keep sibling orders equal to their positions, as Roam does after every
+/// insert, move or delete, so an order can be used as an insertion index.
+fn renumber(blocks: &mut [Block]) {
+    for (i, block) in blocks.iter_mut().enumerate() {
+        block.order = i as i64;
     }
-    None
 }
 
-// --- Optimistic local tree updates ---
-
 pub fn update_block_text_in_days(days: &mut [DailyNote], uid: &str, new_text: &str) -> bool {
     for day in days.iter_mut() {
         if update_block_text(&mut day.blocks, uid, new_text) {
@@ -122,6 +207,7 @@ pub fn remove_block_from_days(days: &mut [DailyNote], uid: &str) -> bool {
 fn remove_block(blocks: &mut Vec<Block>, uid: &str) -> bool {
     if let Some(pos) = blocks.iter().position(|b| b.uid == uid) {
         blocks.remove(pos);
+        renumber(blocks);
         return true;
     }
     for block in blocks.iter_mut() {
@@ -147,6 +233,7 @@ fn try_indent_in_list(blocks: &mut Vec<Block>, block_uid: &str) -> Option<(Strin
             return None;
         }
         let mut block = blocks.remove(pos);
+        renumber(blocks);
         let prev_sibling = &mut blocks[pos - 1];
         let new_order = prev_sibling
             .children
@@ -186,11 +273,11 @@ fn try_dedent_from_parent_list(
             .iter()
             .position(|b| b.uid == block_uid)
         {
-            let mut block = grandparent_children[parent_idx].children.remove(child_pos);
-            let new_order = grandparent_children[parent_idx].order + 1;
-            block.order = new_order;
+            let block = grandparent_children[parent_idx].children.remove(child_pos);
+            renumber(&mut grandparent_children[parent_idx].children);
             grandparent_children.insert(parent_idx + 1, block);
-            return Some((grandparent_uid.to_string(), new_order));
+            renumber(grandparent_children);
+            return Some((grandparent_uid.to_string(), (parent_idx + 1) as i64));
         }
     }
     for child in grandparent_children.iter_mut() {
@@ -216,6 +303,7 @@ pub fn insert_block_in_days(
                 .position(|b| b.order >= order)
                 .unwrap_or(day.blocks.len());
             day.blocks.insert(pos, new_block);
+            renumber(&mut day.blocks);
             return true;
         }
         if insert_block_in_children(&mut day.blocks, parent_uid, order, &new_block) {
@@ -239,6 +327,7 @@ fn insert_block_in_children(
                 .position(|b| b.order >= order)
                 .unwrap_or(block.children.len());
             block.children.insert(pos, new_block.clone());
+            renumber(&mut block.children);
             return true;
         }
         if insert_block_in_children(&mut block.children, parent_uid, order, new_block) {
@@ -316,6 +405,22 @@ fn find_parent_info_recursive(
     None
 }
 
+/// Uids of the direct children of a page or block, in order.
+pub(super) fn child_uids(days: &[DailyNote], parent_uid: &str) -> Option<Vec<String>> {
+    fn uids(blocks: &[Block]) -> Vec<String> {
+        blocks.iter().map(|b| b.uid.clone()).collect()
+    }
+    for day in days {
+        if day.uid == parent_uid {
+            return Some(uids(&day.blocks));
+        }
+        if let Some(block) = find_block_recursive(&day.blocks, parent_uid) {
+            return Some(uids(&block.children));
+        }
+    }
+    None
+}
+
 pub fn move_block_in_days(
     days: &mut [DailyNote],
     block_uid: &str,
@@ -333,12 +438,20 @@ pub fn move_block_in_days(
 }
 
 pub(super) fn generate_uid() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
     use std::time::{SystemTime, UNIX_EPOCH};
+    // Pasting a subtree asks for many uids at once, faster than the clock ticks
+    static LAST: AtomicU64 = AtomicU64::new(0);
     let nanos = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
-        .as_nanos();
-    format!("tui-{:x}", nanos)
+        .as_nanos() as u64;
+    let prev = LAST
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+            Some(nanos.max(last + 1))
+        })
+        .unwrap_or(0);
+    format!("tui-{:x}", nanos.max(prev + 1))
 }
 
 pub(crate) fn format_roam_daily_title(date: NaiveDate) -> String {
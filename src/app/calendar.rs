@@ -0,0 +1,127 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::keys::mode::PopupAction;
+
+use super::blocks::format_roam_daily_title;
+use super::editor::selected_day;
+use super::nav::go_to_date;
+use super::state::{AppState, CalendarState, InputMode, LoadRequest, ViewMode};
+
+/// Open the calendar on the selected day (daily notes) or today. With
+/// `insert`, choosing a day inserts its link into the block being edited.
+pub(super) fn open_calendar(state: &mut AppState, today: NaiveDate, insert: bool) {
+    let selected = match (&state.view_mode, insert) {
+        (ViewMode::DailyNotes, false) => selected_day(state).map_or(today, |day| day.date),
+        _ => today,
+    };
+    state.calendar = Some(CalendarState::new(selected, insert));
+}
+
+/// The month the calendar shows, when its content hasn't been fetched yet.
+/// Marks it as loading; one month is fetched at a time.
+pub(super) fn start_month_load(state: &mut AppState) -> Option<NaiveDate> {
+    let calendar = state.calendar.as_mut()?;
+    let month = calendar.month();
+    if calendar.loading.is_some() || calendar.loaded_months.contains(&month) {
+        return None;
+    }
+    calendar.loading = Some(month);
+    Some(month)
+}
+
+pub(super) fn month_loaded(
+    state: &mut AppState,
+    month: NaiveDate,
+    result: Result<Vec<NaiveDate>, String>,
+) {
+    let Some(calendar) = &mut state.calendar else {
+        return;
+    };
+    calendar.loading = None;
+    // Failed months count as loaded so they aren't retried every loop
+    calendar.loaded_months.insert(month);
+    match result {
+        Ok(dates) => calendar.with_content.extend(dates),
+        Err(e) => state.status_message = Some(format!("Failed to load calendar: {}", e)),
+    }
+}
+
+/// Handle a key in the calendar. Returns the daily note to load when a day
+/// is chosen outside insert mode.
+pub(super) fn handle_calendar_key(
+    state: &mut AppState,
+    key: &KeyEvent,
+    today: NaiveDate,
+) -> Option<LoadRequest> {
+    match state.popup_keys.resolve(key) {
+        Some(PopupAction::Close) => {
+            state.calendar = None;
+            return None;
+        }
+        Some(PopupAction::Accept) => return choose_day(state),
+        _ => {}
+    }
+    if key.modifiers != KeyModifiers::NONE && key.modifiers != KeyModifiers::SHIFT {
+        return None;
+    }
+    let calendar = state.calendar.as_mut()?;
+    let day = calendar.selected;
+    let moved = match key.code {
+        KeyCode::Char('q') => {
+            state.calendar = None;
+            return None;
+        }
+        KeyCode::Left | KeyCode::Char('h') => day.checked_sub_days(Days::new(1)),
+        KeyCode::Right | KeyCode::Char('l') => day.checked_add_days(Days::new(1)),
+        KeyCode::Up | KeyCode::Char('k') => day.checked_sub_days(Days::new(7)),
+        KeyCode::Down | KeyCode::Char('j') => day.checked_add_days(Days::new(7)),
+        KeyCode::PageUp | KeyCode::Char('<') => day.checked_sub_months(Months::new(1)),
+        KeyCode::PageDown | KeyCode::Char('>') => day.checked_add_months(Months::new(1)),
+        KeyCode::Char('t') => Some(today),
+        _ => None,
+    };
+    if let Some(day) = moved {
+        calendar.selected = day;
+    }
+    None
+}
+
+/// Insert the selected day's link, or go to its daily note.
+fn choose_day(state: &mut AppState) -> Option<LoadRequest> {
+    let calendar = state.calendar.take()?;
+    if !calendar.insert {
+        return Some(go_to_date(state, calendar.selected));
+    }
+    if let InputMode::Insert { buffer, .. } = &mut state.input_mode {
+        buffer.insert_str(&format!(
+            "[[{}]]",
+            format_roam_daily_title(calendar.selected)
+        ));
+    }
+    None
+}
+
+/// Rows of the month grid, Monday first; `None` pads the days outside the month.
+pub fn month_weeks(month: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let first = month.with_day(1).unwrap_or(month);
+    let mut weeks = Vec::new();
+    let mut week = [None; 7];
+    let mut day = first;
+    while day.month() == first.month() {
+        let col = day.weekday().num_days_from_monday() as usize;
+        week[col] = Some(day);
+        if col == 6 {
+            weeks.push(week);
+            week = [None; 7];
+        }
+        match day.succ_opt() {
+            Some(next) => day = next,
+            None => break,
+        }
+    }
+    if week.iter().any(Option::is_some) {
+        weeks.push(week);
+    }
+    weeks
+}
@@ -0,0 +1,382 @@
+//! Editing a block, or a whole page, in `$EDITOR` as a Markdown outline.
+//!
+//! The TUI is suspended while the editor runs. Each block is written with its
+//! uid in a trailing comment, and the edited outline is diffed against the
+//! blocks it came from: changed text becomes updates, new bullets creates,
+//! missing bullets deletes and reordered or re-indented bullets moves.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use ratatui::DefaultTerminal;
+
+use crate::api::types::{
+    Block, BlockLocation, BlockRef, BlockUpdate, DailyNote, NewBlock, OrderValue, WriteAction,
+};
+use crate::markdown::{self, OutlineNode};
+
+use super::blocks::{
+    find_block_in_days, find_block_index_by_uid, find_block_parent_info, generate_uid,
+    insert_block_in_days, move_block_in_days, remove_block_from_days, resolve_block_at_index,
+    update_block_text_in_days,
+};
+use super::state::{AppState, UndoEntry};
+use super::visual::{batch, plural, push_undo};
+
+/// Blocks handed to the external editor and where they live.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalEdit {
+    /// Page or block the edited blocks are children of.
+    pub parent_uid: String,
+    /// Order of the first edited block under the parent.
+    pub start_order: i64,
+    /// The edited blocks with their subtrees, as they were when written.
+    pub blocks: Vec<Block>,
+    /// The outline written to the file.
+    pub text: String,
+}
+
+/// The loaded page or day the selected block is on, or the first one when
+/// nothing editable is selected.
+pub(super) fn selected_day(state: &AppState) -> Option<&DailyNote> {
+    match resolve_block_at_index(
+        &state.days,
+        &state.linked_refs,
+        &state.embeds,
+        state.selected_block,
+    ) {
+        Some(info) => state
+            .days
+            .iter()
+            .find(|d| find_block_in_days(std::slice::from_ref(d), &info.block_uid).is_some()),
+        None => state.days.first(),
+    }
+}
+
+/// Queue the selected block, or the page it is on, for editing in `$EDITOR`.
+pub(super) fn request_external_edit(state: &mut AppState, whole_page: bool) {
+    let edit = if whole_page {
+        selected_day(state).map(|day| (day.uid.clone(), 0, day.blocks.clone()))
+    } else {
+        let info = resolve_block_at_index(
+            &state.days,
+            &state.linked_refs,
+            &state.embeds,
+            state.selected_block,
+        );
+        info.and_then(|info| {
+            let block = find_block_in_days(&state.days, &info.block_uid)?;
+            Some((info.parent_uid, info.order, vec![block]))
+        })
+    };
+    match edit {
+        Some((parent_uid, start_order, blocks)) => {
+            state.pending_external_edit = Some(ExternalEdit {
+                text: markdown::outline_with_uids(&blocks),
+                parent_uid,
+                start_order,
+                blocks,
+            });
+        }
+        None => {
+            state.status_message = Some("Select a block on this page to edit".into());
+        }
+    }
+}
+
+/// Suspend the TUI, open `text` in `$VISUAL` / `$EDITOR` and return the saved file.
+pub(super) fn run_external_editor(
+    terminal: &mut DefaultTerminal,
+    text: &str,
+    mouse: bool,
+) -> io::Result<String> {
+    let path = std::env::temp_dir().join(format!("roam-{}.md", generate_uid()));
+    std::fs::write(&path, text)?;
+
+    // Restore the terminal the way the panic hook does, then take it back
+    let _ = crossterm::execute!(io::stdout(), crossterm::event::PopKeyboardEnhancementFlags);
+    if mouse {
+        let _ = crossterm::execute!(io::stdout(), crossterm::event::DisableMouseCapture);
+    }
+    ratatui::restore();
+    let status = editor_command(&path).and_then(|mut cmd| cmd.status());
+    crossterm::terminal::enable_raw_mode()?;
+    crossterm::execute!(
+        io::stdout(),
+        crossterm::terminal::EnterAlternateScreen,
+        crossterm::event::PushKeyboardEnhancementFlags(
+            crossterm::event::KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+        )
+    )?;
+    if mouse {
+        crossterm::execute!(io::stdout(), crossterm::event::EnableMouseCapture)?;
+    }
+    terminal.clear()?;
+
+    let result = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!("editor exited with {}", status))),
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+fn editor_command(path: &Path) -> io::Result<Command> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".into());
+    // Allow arguments, as in EDITOR="code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| io::Error::other("$EDITOR is empty"))?;
+    let mut cmd = Command::new(program);
+    cmd.args(parts).arg(path);
+    Ok(cmd)
+}
+
+/// Apply the outline saved in the editor: local changes, one undo step and
+/// the writes to send.
+pub(super) fn apply_external_edit(
+    state: &mut AppState,
+    edit: &ExternalEdit,
+    text: &str,
+) -> Option<WriteAction> {
+    if text == edit.text {
+        state.status_message = Some("No changes".into());
+        return None;
+    }
+    let mut nodes = markdown::parse_outline(text);
+
+    let mut original = HashMap::new();
+    collect_parents(&edit.blocks, &edit.parent_uid, &mut original);
+    let mut kept = HashSet::new();
+    claim_uids(&mut nodes, &original, &mut kept);
+
+    // Deleted blocks whose parent stays; deleting them takes their children along
+    let deleted: Vec<String> = original_order(&edit.blocks)
+        .into_iter()
+        .filter(|uid| !kept.contains(uid) && kept_or_outside(&original[uid], &original, &kept))
+        .collect();
+    // Delete right away unless a descendant is kept and has to move out first
+    let (late_deletes, early_deletes): (Vec<String>, Vec<String>) =
+        deleted.into_iter().partition(|uid| {
+            find_block_in_days(&state.days, uid)
+                .is_some_and(|b| has_kept_descendant(&b.children, &kept))
+        });
+
+    let saved_selected = state.selected_block;
+    let mut changes = Changes::default();
+    for uid in &early_deletes {
+        changes.delete(state, uid, saved_selected);
+    }
+    changes.place(
+        state,
+        &nodes,
+        &edit.parent_uid,
+        edit.start_order,
+        saved_selected,
+    );
+    for uid in &late_deletes {
+        changes.delete(state, uid, saved_selected);
+    }
+
+    let count = changes.actions.len();
+    if count == 0 {
+        state.status_message = Some("No changes".into());
+        return None;
+    }
+    let first_uid = nodes.first().and_then(|n| n.uid.clone());
+    state.selected_block = first_uid
+        .and_then(|uid| {
+            find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, &uid)
+        })
+        .unwrap_or(saved_selected)
+        .min(state.flat_block_count().saturating_sub(1));
+    state.cursor_col = 0;
+    state.status_message = Some(format!(
+        "Applied {} change{} from editor",
+        count,
+        plural(count)
+    ));
+    push_undo(state, changes.entries, saved_selected);
+    batch(changes.actions)
+}
+
+/// Map each uid in the edited subtrees to its parent's uid.
+fn collect_parents(blocks: &[Block], parent_uid: &str, out: &mut HashMap<String, String>) {
+    for block in blocks {
+        out.insert(block.uid.clone(), parent_uid.to_string());
+        collect_parents(&block.children, &block.uid, out);
+    }
+}
+
+fn original_order(blocks: &[Block]) -> Vec<String> {
+    let mut uids = Vec::new();
+    for block in blocks {
+        uids.push(block.uid.clone());
+        uids.extend(original_order(&block.children));
+    }
+    uids
+}
+
+/// Keep each known uid for its first bullet; unknown uids and copies of a
+/// bullet become new blocks.
+fn claim_uids(
+    nodes: &mut [OutlineNode],
+    original: &HashMap<String, String>,
+    kept: &mut HashSet<String>,
+) {
+    for node in nodes {
+        if let Some(uid) = &node.uid {
+            if !original.contains_key(uid) || !kept.insert(uid.clone()) {
+                node.uid = None;
+            }
+        }
+        claim_uids(&mut node.children, original, kept);
+    }
+}
+
+fn kept_or_outside(
+    parent_uid: &str,
+    original: &HashMap<String, String>,
+    kept: &HashSet<String>,
+) -> bool {
+    !original.contains_key(parent_uid) || kept.contains(parent_uid)
+}
+
+fn has_kept_descendant(blocks: &[Block], kept: &HashSet<String>) -> bool {
+    blocks
+        .iter()
+        .any(|b| kept.contains(&b.uid) || has_kept_descendant(&b.children, kept))
+}
+
+#[derive(Default)]
+struct Changes {
+    entries: Vec<UndoEntry>,
+    actions: Vec<WriteAction>,
+}
+
+impl Changes {
+    fn delete(&mut self, state: &mut AppState, uid: &str, selected_block: usize) {
+        let Some(block) = find_block_in_days(&state.days, uid) else {
+            return;
+        };
+        let Some((parent_uid, order)) = find_block_parent_info(&state.days, uid) else {
+            return;
+        };
+        remove_block_from_days(&mut state.days, uid);
+        self.entries.push(UndoEntry::DeleteBlock {
+            block,
+            parent_uid,
+            order,
+            selected_block,
+        });
+        self.actions.push(WriteAction::DeleteBlock {
+            block: BlockRef {
+                uid: uid.to_string(),
+            },
+        });
+    }
+
+    /// Put each node at its place under `parent_uid`, in document order, so
+    /// the siblings before it are already where they belong.
+    fn place(
+        &mut self,
+        state: &mut AppState,
+        nodes: &[OutlineNode],
+        parent_uid: &str,
+        start_order: i64,
+        selected_block: usize,
+    ) {
+        for (i, node) in nodes.iter().enumerate() {
+            let order = start_order + i as i64;
+            let uid = match &node.uid {
+                Some(uid) => {
+                    self.update(state, uid, parent_uid, order, &node.text, selected_block);
+                    uid.clone()
+                }
+                None => self.create(state, parent_uid, order, &node.text),
+            };
+            self.place(state, &node.children, &uid, 0, selected_block);
+        }
+    }
+
+    fn update(
+        &mut self,
+        state: &mut AppState,
+        uid: &str,
+        parent_uid: &str,
+        order: i64,
+        text: &str,
+        selected_block: usize,
+    ) {
+        if let Some((old_parent_uid, old_order)) = find_block_parent_info(&state.days, uid) {
+            if old_parent_uid != parent_uid || old_order != order {
+                move_block_in_days(&mut state.days, uid, parent_uid, order);
+                self.entries.push(UndoEntry::MoveBlock {
+                    block_uid: uid.to_string(),
+                    old_parent_uid,
+                    old_order,
+                    selected_block,
+                });
+                self.actions.push(WriteAction::MoveBlock {
+                    block: BlockRef {
+                        uid: uid.to_string(),
+                    },
+                    location: BlockLocation {
+                        parent_uid: parent_uid.to_string(),
+                        order: OrderValue::Index(order),
+                    },
+                });
+            }
+        }
+        let Some(old_text) = find_block_in_days(&state.days, uid).map(|b| b.string) else {
+            return;
+        };
+        if old_text != text {
+            update_block_text_in_days(&mut state.days, uid, text);
+            self.entries.push(UndoEntry::TextEdit {
+                block_uid: uid.to_string(),
+                old_text,
+            });
+            self.actions.push(WriteAction::UpdateBlock {
+                block: BlockUpdate {
+                    uid: uid.to_string(),
+                    string: text.to_string(),
+                },
+            });
+        }
+    }
+
+    fn create(&mut self, state: &mut AppState, parent_uid: &str, order: i64, text: &str) -> String {
+        let uid = generate_uid();
+        let block = Block {
+            uid: uid.clone(),
+            string: text.to_string(),
+            order,
+            children: vec![],
+            open: true,
+            refs: vec![],
+        };
+        insert_block_in_days(&mut state.days, parent_uid, order, block);
+        self.entries.push(UndoEntry::CreateBlock {
+            block_uid: uid.clone(),
+        });
+        self.actions.push(WriteAction::CreateBlock {
+            location: BlockLocation {
+                parent_uid: parent_uid.to_string(),
+                order: OrderValue::Index(order),
+            },
+            block: NewBlock {
+                string: text.to_string(),
+                uid: Some(uid.clone()),
+                open: None,
+            },
+        });
+        uid
+    }
+}
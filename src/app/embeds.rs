@@ -0,0 +1,104 @@
+use crate::api::types::Block;
+use crate::markup::{self, EmbedTarget};
+
+use super::blocks::{for_each_day_block, row_at_index, OutlineRow};
+use super::state::AppState;
+
+/// Embeds whose content is missing. With `refresh`, every embed that isn't
+/// already loading is returned.
+pub(super) fn collect_stale_embeds(state: &AppState, refresh: bool) -> Vec<EmbedTarget> {
+    let mut stale = Vec::new();
+    for_each_day_block(&state.days, &mut |block| {
+        let Some(target) = markup::find_embed(&block.string) else {
+            return;
+        };
+        let is_stale = match state.embeds.get(&target) {
+            None => true,
+            Some(embed) => refresh && !embed.loading,
+        };
+        if is_stale && !stale.contains(&target) {
+            stale.push(target);
+        }
+    });
+    stale
+}
+
+/// Record that `target` is being fetched.
+pub(super) fn start_embed(state: &mut AppState, target: &EmbedTarget) {
+    state.embeds.entry(target.clone()).or_default().loading = true;
+}
+
+/// Store the fetched content of an embed. A block embed's text also goes
+/// into the block ref cache, which renders the embed line itself.
+pub(super) fn embed_loaded(
+    state: &mut AppState,
+    target: EmbedTarget,
+    result: Result<Vec<Block>, String>,
+) {
+    let entry = state.embeds.entry(target.clone()).or_default();
+    entry.loading = false;
+    match result {
+        Ok(blocks) => {
+            if let (EmbedTarget::Block(uid), Some(root)) = (&target, blocks.first()) {
+                state
+                    .block_ref_cache
+                    .insert(uid.clone(), root.string.clone());
+            }
+            entry.blocks = blocks;
+            entry.error = None;
+        }
+        Err(e) => entry.error = Some(e),
+    }
+}
+
+/// The embedded block drawn on row `index`, if that row is an embed line.
+pub(super) fn embedded_block_at(state: &AppState, index: usize) -> Option<&Block> {
+    match row_at_index(&state.days, &state.linked_refs, &state.embeds, index)? {
+        OutlineRow::Embedded(block) => Some(block),
+        OutlineRow::Block { .. } => None,
+    }
+}
+
+/// Current text of a block inside any loaded embed.
+pub(super) fn embedded_text(state: &AppState, uid: &str) -> Option<String> {
+    state
+        .embeds
+        .values()
+        .find_map(|embed| find_block(&embed.blocks, uid))
+        .map(|block| block.string.clone())
+}
+
+fn find_block<'a>(blocks: &'a [Block], uid: &str) -> Option<&'a Block> {
+    blocks.iter().find_map(|block| {
+        if block.uid == uid {
+            Some(block)
+        } else {
+            find_block(&block.children, uid)
+        }
+    })
+}
+
+/// Apply an edit of an embedded block to every embed that shows it.
+pub(super) fn update_embedded_text(state: &mut AppState, uid: &str, text: &str) {
+    let mut found = false;
+    for embed in state.embeds.values_mut() {
+        found |= update_in_blocks(&mut embed.blocks, uid, text);
+    }
+    if found || state.block_ref_cache.contains_key(uid) {
+        state
+            .block_ref_cache
+            .insert(uid.to_string(), text.to_string());
+    }
+}
+
+fn update_in_blocks(blocks: &mut [Block], uid: &str, text: &str) -> bool {
+    let mut found = false;
+    for block in blocks {
+        if block.uid == uid {
+            block.string = text.to_string();
+            found = true;
+        }
+        found |= update_in_blocks(&mut block.children, uid, text);
+    }
+    found
+}
@@ -0,0 +1,107 @@
+//! The help screen: every binding of the active keymap, grouped by category,
+//! scrollable and filtered by a search query.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::fuzzy;
+use crate::keys::mode::PopupAction;
+use crate::keys::KeySection;
+
+use super::state::{AppState, HelpState};
+
+/// Lines moved by PageUp / PageDown.
+const PAGE: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HelpLine {
+    Header(&'static str),
+    Binding { keys: String, label: &'static str },
+    Blank,
+}
+
+/// Lines of the help screen for bindings matching `query`; categories with
+/// no match are left out.
+pub fn help_lines(sections: &[KeySection], query: &str) -> Vec<HelpLine> {
+    let query_lower = query.to_lowercase();
+    let mut lines = Vec::new();
+    for section in sections {
+        let bindings: Vec<HelpLine> = section
+            .bindings
+            .iter()
+            .map(|(label, keys)| (*label, keys.join(", ")))
+            .filter(|(label, keys)| {
+                fuzzy::fuzzy_match(query, label).is_some()
+                    || keys.to_lowercase().contains(&query_lower)
+            })
+            .map(|(label, keys)| HelpLine::Binding { keys, label })
+            .collect();
+        if bindings.is_empty() {
+            continue;
+        }
+        if !lines.is_empty() {
+            lines.push(HelpLine::Blank);
+        }
+        lines.push(HelpLine::Header(section.title));
+        lines.extend(bindings);
+    }
+    lines
+}
+
+pub(super) fn toggle_help(state: &mut AppState) {
+    state.help = match state.help {
+        Some(_) => None,
+        None => Some(HelpState::default()),
+    };
+}
+
+pub(super) fn handle_help_key(state: &mut AppState, key: &KeyEvent) {
+    let line_count = match &state.help {
+        Some(help) => help_lines(&state.help_sections, &help.query).len(),
+        None => return,
+    };
+    let Some(help) = &mut state.help else {
+        return;
+    };
+    let last = line_count.saturating_sub(1);
+    let action = state.popup_keys.resolve(key);
+
+    if help.searching {
+        match (action, key.code) {
+            (Some(PopupAction::Close), _) => {
+                help.searching = false;
+                help.query.clear();
+            }
+            (Some(PopupAction::Accept), _) => help.searching = false,
+            (_, KeyCode::Backspace) => help.searching = help.query.pop().is_some(),
+            (None, KeyCode::Char(c)) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                help.query.push(c);
+                help.scroll = 0;
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    match (action, key.modifiers, key.code) {
+        (Some(PopupAction::Close), ..) | (None, _, KeyCode::Char('q' | '?')) => {
+            state.help = None;
+        }
+        (Some(PopupAction::SelectNext), ..) | (None, _, KeyCode::Char('j')) => {
+            help.scroll = (help.scroll + 1).min(last);
+        }
+        (Some(PopupAction::SelectPrev), ..) | (None, _, KeyCode::Char('k')) => {
+            help.scroll = help.scroll.saturating_sub(1);
+        }
+        (Some(_), ..) => {}
+        (None, _, KeyCode::Char('/')) => help.searching = true,
+        (None, _, KeyCode::PageDown) | (None, KeyModifiers::CONTROL, KeyCode::Char('d')) => {
+            help.scroll = (help.scroll + PAGE).min(last);
+        }
+        (None, _, KeyCode::PageUp) | (None, KeyModifiers::CONTROL, KeyCode::Char('u')) => {
+            help.scroll = help.scroll.saturating_sub(PAGE);
+        }
+        (None, _, KeyCode::Char('g') | KeyCode::Home) => help.scroll = 0,
+        (None, _, KeyCode::Char('G') | KeyCode::End) => help.scroll = last,
+        _ => {}
+    }
+}
@@ -1,39 +1,51 @@
+use chrono::Local;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+use crate::api::queries::parse_natural_date;
 use crate::api::types::{
-    BlockLocation, BlockRef, BlockUpdate, DailyNote, NewBlock, OrderValue, WriteAction,
+    BlockLocation, BlockRef, BlockUpdate, DailyNote, NewBlock, OrderValue, SearchHit, WriteAction,
 };
+use crate::edit_buffer::EditBuffer;
+use crate::keys::mode::{InsertAction, PopupAction};
+use crate::markdown;
 
 use super::blocks::{
-    dedent_block_in_days, find_block_in_days, find_block_index_by_uid, indent_block_in_days,
-    remove_block_from_days, resolve_block_at_index, update_block_text_in_days,
+    dedent_block_in_days, find_block_index_by_uid, indent_block_in_days, remove_block_from_days,
+    resolve_block_at_index, update_block_text_in_days,
 };
+use super::calendar::open_calendar;
 use super::nav::navigate_to_page;
 use super::search::{
-    filter_blocks, filter_page_titles, AUTOCOMPLETE_LIMIT, QUICK_SWITCHER_LIMIT, SEARCH_LIMIT,
+    filter_blocks, filter_page_titles, local_search_results, merge_search_results,
+    page_autocomplete_results, AUTOCOMPLETE_LIMIT, QUICK_SWITCHER_LIMIT, REMOTE_SEARCH_MIN_CHARS,
+    SEARCH_DEBOUNCE_TICKS, SEARCH_LIMIT,
+};
+use super::slash::{split_query, DateOffset, SlashAction};
+use super::state::{
+    AppState, AutocompleteKind, AutocompleteState, CreateInfo, InputMode, LoadRequest,
+    SearchResult, UndoEntry,
 };
-use super::state::{AppState, AutocompleteState, CreateInfo, InputMode, LoadRequest, UndoEntry};
 
 // --- Link picker key handling ---
 
 pub(super) fn handle_link_picker_key(state: &mut AppState, key: &KeyEvent) -> Option<LoadRequest> {
-    match (key.modifiers, key.code) {
-        (KeyModifiers::NONE, KeyCode::Esc) => {
+    match state.popup_keys.resolve(key) {
+        Some(PopupAction::Close) => {
             state.link_picker = None;
         }
-        (KeyModifiers::NONE, KeyCode::Up) => {
+        Some(PopupAction::SelectPrev) => {
             if let Some(lp) = &mut state.link_picker {
                 lp.selected = lp.selected.saturating_sub(1);
             }
         }
-        (KeyModifiers::NONE, KeyCode::Down) => {
+        Some(PopupAction::SelectNext) => {
             if let Some(lp) = &mut state.link_picker {
                 if !lp.links.is_empty() && lp.selected < lp.links.len() - 1 {
                     lp.selected += 1;
                 }
             }
         }
-        (KeyModifiers::NONE, KeyCode::Enter) => {
+        Some(PopupAction::Accept) => {
             if let Some(lp) = state.link_picker.take() {
                 if let Some(title) = lp.links.get(lp.selected) {
                     return Some(navigate_to_page(state, title.clone()));
@@ -47,54 +59,103 @@ pub(super) fn handle_link_picker_key(state: &mut AppState, key: &KeyEvent) -> Op
 
 // --- Search mode key handling ---
 
-pub fn handle_search_key(state: &mut AppState, key: &KeyEvent) {
-    match (key.modifiers, key.code) {
-        (KeyModifiers::NONE, KeyCode::Esc) => {
+pub fn handle_search_key(state: &mut AppState, key: &KeyEvent) -> Option<LoadRequest> {
+    match state.popup_keys.resolve(key) {
+        Some(PopupAction::Close) => {
             state.search = None;
         }
-        (KeyModifiers::NONE, KeyCode::Up) => {
+        Some(PopupAction::SelectPrev) => {
             if let Some(s) = &mut state.search {
                 s.selected = s.selected.saturating_sub(1);
             }
         }
-        (KeyModifiers::NONE, KeyCode::Down) => {
+        Some(PopupAction::SelectNext) => {
             if let Some(s) = &mut state.search {
                 if !s.results.is_empty() && s.selected < s.results.len() - 1 {
                     s.selected += 1;
                 }
             }
         }
-        (KeyModifiers::NONE, KeyCode::Enter) => {
+        Some(PopupAction::Accept) => {
             if let Some(s) = state.search.take() {
-                if let Some((uid, _)) = s.results.get(s.selected) {
-                    if let Some(idx) = find_block_index_by_uid(&state.days, &state.linked_refs, uid)
-                    {
-                        state.selected_block = idx;
-                        state.cursor_col = 0;
-                    }
+                if let Some(result) = s.results.get(s.selected) {
+                    return jump_to_search_result(state, result);
                 }
             }
         }
-        (KeyModifiers::NONE, KeyCode::Backspace) => {
-            if let Some(s) = &mut state.search {
-                s.query.pop();
-                let query = s.query.clone();
-                s.results =
-                    filter_blocks(&state.days, &state.block_ref_cache, &query, SEARCH_LIMIT);
-                s.selected = s.selected.min(s.results.len().saturating_sub(1));
+        _ => match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Backspace) => {
+                if let Some(s) = &mut state.search {
+                    s.query.pop();
+                }
+                update_search_results(state);
             }
-        }
-        (KeyModifiers::NONE, KeyCode::Char(c)) | (KeyModifiers::SHIFT, KeyCode::Char(c)) => {
-            if let Some(s) = &mut state.search {
-                s.query.push(c);
-                let query = s.query.clone();
-                s.results =
-                    filter_blocks(&state.days, &state.block_ref_cache, &query, SEARCH_LIMIT);
-                s.selected = s.selected.min(s.results.len().saturating_sub(1));
+            (KeyModifiers::NONE, KeyCode::Char(c)) | (KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                if let Some(s) = &mut state.search {
+                    s.query.push(c);
+                }
+                update_search_results(state);
             }
-        }
-        _ => {}
+            _ => {}
+        },
     }
+    None
+}
+
+/// Recompute search results after the query changed and restart the graph search debounce.
+/// Graph hits and requests for the old query are dropped; their responses are ignored.
+fn update_search_results(state: &mut AppState) {
+    let Some(s) = &mut state.search else {
+        return;
+    };
+    s.remote.clear();
+    s.pending_requests = 0;
+    s.generation += 1;
+    let local = local_search_results(&state.days, &state.block_ref_cache, &s.query, SEARCH_LIMIT);
+    s.results = merge_search_results(local, &s.remote, &s.query, SEARCH_LIMIT);
+    s.selected = s.selected.min(s.results.len().saturating_sub(1));
+    s.debounce_ticks = if s.query.chars().count() >= REMOTE_SEARCH_MIN_CHARS {
+        SEARCH_DEBOUNCE_TICKS
+    } else {
+        0
+    };
+}
+
+/// Merge graph-wide hits from search `generation` into the open search,
+/// ignoring responses to an earlier query.
+pub(super) fn apply_remote_search_results(
+    state: &mut AppState,
+    generation: u64,
+    hits: Vec<SearchHit>,
+) {
+    let Some(s) = &mut state.search else {
+        return;
+    };
+    if s.generation != generation {
+        return;
+    }
+    s.pending_requests = s.pending_requests.saturating_sub(1);
+    s.remote.extend(hits);
+    let local = local_search_results(&state.days, &state.block_ref_cache, &s.query, SEARCH_LIMIT);
+    s.results = merge_search_results(local, &s.remote, &s.query, SEARCH_LIMIT);
+    s.selected = s.selected.min(s.results.len().saturating_sub(1));
+}
+
+/// Select a loaded block, or open the page containing the result.
+fn jump_to_search_result(state: &mut AppState, result: &SearchResult) -> Option<LoadRequest> {
+    if result.is_page {
+        return Some(navigate_to_page(state, result.text.clone()));
+    }
+    if let Some(idx) =
+        find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, &result.uid)
+    {
+        state.selected_block = idx;
+        state.cursor_col = 0;
+        return None;
+    }
+    let page_title = result.page_title.clone()?;
+    state.pending_select_uid = Some(result.uid.clone());
+    Some(navigate_to_page(state, page_title))
 }
 
 // --- Quick Switcher key handling ---
@@ -103,64 +164,72 @@ pub(super) fn handle_quick_switcher_key(
     state: &mut AppState,
     key: &KeyEvent,
 ) -> Option<LoadRequest> {
-    match (key.modifiers, key.code) {
-        (KeyModifiers::NONE, KeyCode::Esc) => {
+    match state.popup_keys.resolve(key) {
+        Some(PopupAction::Close) => {
             state.quick_switcher = None;
         }
-        (KeyModifiers::NONE, KeyCode::Up) => {
+        Some(PopupAction::SelectPrev) => {
             if let Some(qs) = &mut state.quick_switcher {
                 qs.selected = qs.selected.saturating_sub(1);
             }
         }
-        (KeyModifiers::NONE, KeyCode::Down) => {
+        Some(PopupAction::SelectNext) => {
             if let Some(qs) = &mut state.quick_switcher {
                 if !qs.filtered.is_empty() && qs.selected < qs.filtered.len() - 1 {
                     qs.selected += 1;
                 }
             }
         }
-        (KeyModifiers::NONE, KeyCode::Enter) => {
+        Some(PopupAction::Accept) => {
             if let Some(qs) = state.quick_switcher.take() {
                 if let Some((title, _)) = qs.filtered.get(qs.selected) {
                     return Some(navigate_to_page(state, title.clone()));
                 }
             }
         }
-        (KeyModifiers::NONE, KeyCode::Backspace) => {
-            let should_close = state
-                .quick_switcher
-                .as_ref()
-                .is_some_and(|qs| qs.query.is_empty());
-            if should_close {
-                state.quick_switcher = None;
-            } else if let Some(qs) = &mut state.quick_switcher {
-                qs.query.pop();
-                qs.debounce_ticks = 2;
-                if !state.page_title_cache.is_empty() {
-                    qs.filtered = filter_page_titles(
-                        &state.page_title_cache,
-                        &qs.query,
-                        QUICK_SWITCHER_LIMIT,
-                    );
-                    qs.selected = qs.selected.min(qs.filtered.len().saturating_sub(1));
+        _ => match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Backspace) => {
+                let should_close = state
+                    .quick_switcher
+                    .as_ref()
+                    .is_some_and(|qs| qs.query.is_empty());
+                if should_close {
+                    state.quick_switcher = None;
+                    return None;
+                }
+                let recent = state.recent_pages();
+                if let Some(qs) = &mut state.quick_switcher {
+                    qs.query.pop();
+                    qs.debounce_ticks = 2;
+                    if !state.page_title_cache.is_empty() {
+                        qs.filtered = filter_page_titles(
+                            &state.page_title_cache,
+                            &qs.query,
+                            QUICK_SWITCHER_LIMIT,
+                            &recent,
+                        );
+                        qs.selected = qs.selected.min(qs.filtered.len().saturating_sub(1));
+                    }
                 }
             }
-        }
-        (KeyModifiers::NONE, KeyCode::Char(c)) | (KeyModifiers::SHIFT, KeyCode::Char(c)) => {
-            if let Some(qs) = &mut state.quick_switcher {
-                qs.query.push(c);
-                qs.debounce_ticks = 2;
-                if !state.page_title_cache.is_empty() {
-                    qs.filtered = filter_page_titles(
-                        &state.page_title_cache,
-                        &qs.query,
-                        QUICK_SWITCHER_LIMIT,
-                    );
-                    qs.selected = qs.selected.min(qs.filtered.len().saturating_sub(1));
+            (KeyModifiers::NONE, KeyCode::Char(c)) | (KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                let recent = state.recent_pages();
+                if let Some(qs) = &mut state.quick_switcher {
+                    qs.query.push(c);
+                    qs.debounce_ticks = 2;
+                    if !state.page_title_cache.is_empty() {
+                        qs.filtered = filter_page_titles(
+                            &state.page_title_cache,
+                            &qs.query,
+                            QUICK_SWITCHER_LIMIT,
+                            &recent,
+                        );
+                        qs.selected = qs.selected.min(qs.filtered.len().saturating_sub(1));
+                    }
                 }
             }
-        }
-        _ => {}
+            _ => {}
+        },
     }
     None
 }
@@ -178,16 +247,26 @@ pub fn handle_insert_key(state: &mut AppState, key: &KeyEvent) -> Option<WriteAc
         return handle_autocomplete_key(state, key);
     }
 
-    if key.code == KeyCode::Esc && key.modifiers == KeyModifiers::NONE {
-        return finalize_insert(state);
-    }
-
-    if key.code == KeyCode::Tab && key.modifiers == KeyModifiers::NONE {
-        return handle_indent(state);
+    if matches!(
+        state.input_mode,
+        InputMode::Insert {
+            vim_normal: Some(_),
+            ..
+        }
+    ) {
+        return super::vim::handle_vim_normal_key(state, key);
     }
 
-    if key.code == KeyCode::BackTab {
-        return handle_dedent(state);
+    let (action, selecting) = insert_action(state, key);
+    match action {
+        Some(InsertAction::Exit) if state.vim_editing => {
+            super::vim::enter_normal(state);
+            return None;
+        }
+        Some(InsertAction::Exit) => return finalize_insert(state),
+        Some(InsertAction::Indent) => return handle_indent(state),
+        Some(InsertAction::Unindent) => return handle_dedent(state),
+        _ => {}
     }
 
     let buffer = match &mut state.input_mode {
@@ -195,50 +274,69 @@ pub fn handle_insert_key(state: &mut AppState, key: &KeyEvent) -> Option<WriteAc
         InputMode::Normal => return None,
     };
 
-    match (key.modifiers, key.code) {
-        (KeyModifiers::NONE, KeyCode::Char(ch)) | (KeyModifiers::SHIFT, KeyCode::Char(ch)) => {
-            match ch {
-                '(' => buffer.insert_pair('(', ')'),
-                '[' => buffer.insert_pair('[', ']'),
-                '{' => buffer.insert_pair('{', '}'),
-                _ => buffer.insert_char(ch),
+    if let Some(motion) = action.and_then(editor_motion) {
+        buffer.set_selecting(selecting);
+        motion(buffer);
+    } else {
+        match action {
+            Some(InsertAction::DeleteBack) => buffer.delete_back(),
+            Some(InsertAction::DeleteForward) => buffer.delete_forward(),
+            Some(InsertAction::DeleteWordBack) => {
+                let start = buffer.prev_word_start();
+                buffer.delete_range(start, buffer.cursor);
+            }
+            Some(InsertAction::Copy) => {
+                if let Some(text) = buffer.selected_text() {
+                    state.pending_clipboard = Some(text.clone());
+                    state.text_register = text;
+                }
+            }
+            Some(InsertAction::Cut) => {
+                if let Some(text) = buffer.delete_selection() {
+                    state.pending_clipboard = Some(text.clone());
+                    state.text_register = text;
+                }
+            }
+            Some(InsertAction::Paste) => buffer.insert_str(&state.text_register),
+            Some(InsertAction::ToggleTodo) => buffer.toggle_todo(),
+            _ => {
+                if let (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(ch)) =
+                    (key.modifiers, key.code)
+                {
+                    match ch {
+                        '(' => buffer.insert_pair('(', ')'),
+                        '[' => buffer.insert_pair('[', ']'),
+                        '{' => buffer.insert_pair('{', '}'),
+                        _ => buffer.insert_char(ch),
+                    }
+                }
             }
         }
-        (KeyModifiers::NONE, KeyCode::Backspace) => buffer.delete_back(),
-        (KeyModifiers::NONE, KeyCode::Delete) => buffer.delete_forward(),
-        (KeyModifiers::NONE, KeyCode::Left) => buffer.move_left(),
-        (KeyModifiers::NONE, KeyCode::Right) => buffer.move_right(),
-        (KeyModifiers::NONE, KeyCode::Home) | (KeyModifiers::CONTROL, KeyCode::Char('a')) => {
-            buffer.move_home()
-        }
-        (KeyModifiers::NONE, KeyCode::End) | (KeyModifiers::CONTROL, KeyCode::Char('e')) => {
-            buffer.move_end()
-        }
-        (KeyModifiers::NONE, KeyCode::Up) => buffer.move_up(),
-        (KeyModifiers::NONE, KeyCode::Down) => buffer.move_down(),
-        (KeyModifiers::CONTROL, KeyCode::Left) => buffer.move_word_left(),
-        (KeyModifiers::CONTROL, KeyCode::Right) => buffer.move_word_right(),
-        (modifiers, KeyCode::Enter)
-            if modifiers.contains(KeyModifiers::ALT)
-                || modifiers.contains(KeyModifiers::CONTROL) =>
-        {
-            buffer.toggle_todo();
-        }
-        _ => {}
     }
 
-    // Check if (( was just typed — open autocomplete
+    // Check if ((, [[ or # was just typed — open autocomplete
     let buffer = match &state.input_mode {
         InputMode::Insert { buffer, .. } => buffer,
         InputMode::Normal => return None,
     };
-    if super::search::detect_block_ref_trigger(buffer) {
-        let results = filter_blocks(&state.days, &state.block_ref_cache, "", AUTOCOMPLETE_LIMIT);
+    let kind = if super::search::detect_block_ref_trigger(buffer) {
+        Some(AutocompleteKind::BlockRef)
+    } else if super::search::detect_page_link_trigger(buffer) {
+        Some(AutocompleteKind::PageLink)
+    } else if super::search::detect_tag_trigger(buffer) {
+        Some(AutocompleteKind::Tag)
+    } else {
+        None
+    };
+    if let Some(kind) = kind {
         state.autocomplete = Some(AutocompleteState {
+            kind,
             query: String::new(),
-            results,
+            results: Vec::new(),
             selected: 0,
+            fetching: false,
         });
+        refresh_autocomplete_results(state);
     } else if let Some(slash_pos) = super::slash::detect_trigger(buffer) {
         state.slash_menu = Some(super::slash::SlashMenuState {
             query: String::new(),
@@ -251,140 +349,241 @@ pub fn handle_insert_key(state: &mut AppState, key: &KeyEvent) -> Option<WriteAc
     None
 }
 
+/// The insert action bound to a key, and whether it extends the selection:
+/// a motion key pressed with an extra Shift does.
+fn insert_action(state: &AppState, key: &KeyEvent) -> (Option<InsertAction>, bool) {
+    if let Some(action) = state.insert_keys.resolve(key) {
+        return (Some(action), false);
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        let unshifted = KeyEvent::new(key.code, key.modifiers - KeyModifiers::SHIFT);
+        if let Some(action) = state.insert_keys.resolve(&unshifted) {
+            if action.is_motion() {
+                return (Some(action), true);
+            }
+        }
+    }
+    (None, false)
+}
+
+/// Cursor motion of an insert action.
+fn editor_motion(action: InsertAction) -> Option<fn(&mut EditBuffer)> {
+    let motion: fn(&mut EditBuffer) = match action {
+        InsertAction::CursorLeft => EditBuffer::move_left,
+        InsertAction::CursorRight => EditBuffer::move_right,
+        InsertAction::CursorUp => EditBuffer::move_up,
+        InsertAction::CursorDown => EditBuffer::move_down,
+        InsertAction::LineStart => EditBuffer::move_home,
+        InsertAction::LineEnd => EditBuffer::move_end,
+        InsertAction::WordLeft => EditBuffer::move_word_left,
+        InsertAction::WordRight => EditBuffer::move_word_right,
+        _ => return None,
+    };
+    Some(motion)
+}
+
 fn handle_autocomplete_key(state: &mut AppState, key: &KeyEvent) -> Option<WriteAction> {
-    match (key.modifiers, key.code) {
-        (KeyModifiers::NONE, KeyCode::Esc) => {
-            state.autocomplete = None;
+    let (kind, query_empty) = match &state.autocomplete {
+        Some(ac) => (ac.kind, ac.query.is_empty()),
+        None => return None,
+    };
+    match state.popup_keys.resolve(key) {
+        Some(PopupAction::Close) => {
+            // Keep typed page text in the buffer; a block ref query is discarded
+            let ac = state.autocomplete.take()?;
+            if kind != AutocompleteKind::BlockRef {
+                insert_into_buffer(state, &ac.query);
+            }
         }
-        (KeyModifiers::NONE, KeyCode::Up) => {
+        Some(PopupAction::SelectPrev) => {
             if let Some(ac) = &mut state.autocomplete {
                 ac.selected = ac.selected.saturating_sub(1);
             }
         }
-        (KeyModifiers::NONE, KeyCode::Down) => {
+        Some(PopupAction::SelectNext) => {
             if let Some(ac) = &mut state.autocomplete {
                 if !ac.results.is_empty() && ac.selected < ac.results.len() - 1 {
                     ac.selected += 1;
                 }
             }
         }
-        (KeyModifiers::NONE, KeyCode::Enter) => {
+        Some(PopupAction::Accept) => {
             return confirm_autocomplete(state);
         }
-        (KeyModifiers::NONE, KeyCode::Backspace) => {
-            let should_close = state
-                .autocomplete
-                .as_ref()
-                .is_some_and(|ac| ac.query.is_empty());
-            if should_close {
+        Some(PopupAction::Complete) if kind != AutocompleteKind::BlockRef => {
+            return confirm_autocomplete(state);
+        }
+        _ => match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Backspace) => {
+                if query_empty {
+                    state.autocomplete = None;
+                } else if let Some(ac) = &mut state.autocomplete {
+                    ac.query.pop();
+                    refresh_autocomplete_results(state);
+                }
+            }
+            // `#[` starts a bracketed tag: hand the bracket to the buffer so `#[[` triggers
+            (KeyModifiers::NONE, KeyCode::Char('['))
+                if kind == AutocompleteKind::Tag && query_empty =>
+            {
                 state.autocomplete = None;
-            } else if let Some(ac) = &mut state.autocomplete {
-                ac.query.pop();
-                let query = ac.query.clone();
-                ac.results = filter_blocks(
-                    &state.days,
-                    &state.block_ref_cache,
-                    &query,
-                    AUTOCOMPLETE_LIMIT,
-                );
-                ac.selected = ac.selected.min(ac.results.len().saturating_sub(1));
+                return handle_insert_key(state, key);
             }
-        }
-        (KeyModifiers::NONE, KeyCode::Char(ch)) | (KeyModifiers::SHIFT, KeyCode::Char(ch)) => {
-            if let Some(ac) = &mut state.autocomplete {
-                ac.query.push(ch);
-                let query = ac.query.clone();
-                ac.results = filter_blocks(
-                    &state.days,
-                    &state.block_ref_cache,
-                    &query,
-                    AUTOCOMPLETE_LIMIT,
-                );
-                ac.selected = ac.selected.min(ac.results.len().saturating_sub(1));
+            // A bare tag ends at whitespace: keep what was typed as plain text
+            (KeyModifiers::NONE, KeyCode::Char(ch)) | (KeyModifiers::SHIFT, KeyCode::Char(ch))
+                if kind == AutocompleteKind::Tag && ch.is_whitespace() =>
+            {
+                let ac = state.autocomplete.take()?;
+                insert_into_buffer(state, &format!("{}{}", ac.query, ch));
             }
-        }
-        _ => {}
+            (KeyModifiers::NONE, KeyCode::Char(ch)) | (KeyModifiers::SHIFT, KeyCode::Char(ch)) => {
+                if let Some(ac) = &mut state.autocomplete {
+                    ac.query.push(ch);
+                    refresh_autocomplete_results(state);
+                }
+            }
+            _ => {}
+        },
     }
     None
 }
 
+/// Recompute autocomplete results for the current query and kind.
+pub(super) fn refresh_autocomplete_results(state: &mut AppState) {
+    let recent = state.recent_pages();
+    let Some(ac) = &mut state.autocomplete else {
+        return;
+    };
+    ac.results = match ac.kind {
+        AutocompleteKind::BlockRef => filter_blocks(
+            &state.days,
+            &state.block_ref_cache,
+            &ac.query,
+            AUTOCOMPLETE_LIMIT,
+        ),
+        AutocompleteKind::PageLink | AutocompleteKind::Tag => page_autocomplete_results(
+            &state.page_title_cache,
+            &ac.query,
+            AUTOCOMPLETE_LIMIT,
+            &recent,
+        ),
+    };
+    ac.selected = ac.selected.min(ac.results.len().saturating_sub(1));
+}
+
+fn insert_into_buffer(state: &mut AppState, text: &str) {
+    if let InputMode::Insert { buffer, .. } = &mut state.input_mode {
+        for ch in text.chars() {
+            buffer.insert_char(ch);
+        }
+    }
+}
+
 fn confirm_autocomplete(state: &mut AppState) -> Option<WriteAction> {
     let ac = state.autocomplete.take()?;
-    let (uid, _) = ac.results.get(ac.selected)?.clone();
+    let (uid, text) = ac.results.get(ac.selected)?.clone();
 
     let buffer = match &mut state.input_mode {
         InputMode::Insert { buffer, .. } => buffer,
         InputMode::Normal => return None,
     };
 
-    // Find the (( before cursor and )) after, accounting for any query text typed
-    // Pattern in buffer: ((query|)) where | is cursor
-    // We need to find start of (( and end of ))
+    // Query chars go to ac.query, not the buffer, so the buffer still holds the
+    // bare trigger with the cursor inside it: ((|)), [[|]] or #|
     let cursor = buffer.cursor;
-    // Query chars go to ac.query, not the buffer — buffer still has (()) with cursor between
-    let start = cursor.checked_sub(2)?;
-    let end = if cursor + 1 < buffer.chars.len()
-        && buffer.chars[cursor] == ')'
-        && buffer.chars[cursor + 1] == ')'
-    {
-        cursor + 2
-    } else {
-        return None;
-    };
-
-    let replacement = format!("(({})) ", uid);
-    buffer.replace_range(start, end, &replacement);
+    match ac.kind {
+        AutocompleteKind::BlockRef | AutocompleteKind::PageLink => {
+            let (open, close) = if ac.kind == AutocompleteKind::BlockRef {
+                ('(', ')')
+            } else {
+                ('[', ']')
+            };
+            let start = cursor.checked_sub(2)?;
+            let end = if cursor + 1 < buffer.chars.len()
+                && buffer.chars[cursor] == close
+                && buffer.chars[cursor + 1] == close
+            {
+                cursor + 2
+            } else {
+                return None;
+            };
+            let replacement = if open == '(' {
+                format!("(({})) ", uid)
+            } else {
+                format!("[[{}]]", text)
+            };
+            buffer.replace_range(start, end, &replacement);
+        }
+        AutocompleteKind::Tag => {
+            let start = cursor.checked_sub(1)?;
+            if buffer.chars[start] != '#' {
+                return None;
+            }
+            buffer.replace_range(start, cursor, &markdown::tag_markup(&text));
+        }
+    }
     None
 }
 
 fn handle_slash_menu_key(state: &mut AppState, key: &KeyEvent) -> Option<WriteAction> {
-    match (key.modifiers, key.code) {
-        (KeyModifiers::NONE, KeyCode::Esc) => {
+    match state.popup_keys.resolve(key) {
+        Some(PopupAction::Close) => {
             state.slash_menu = None;
         }
-        (KeyModifiers::NONE, KeyCode::Up) => {
+        Some(PopupAction::SelectPrev) => {
             if let Some(sm) = &mut state.slash_menu {
                 sm.selected = sm.selected.saturating_sub(1);
             }
         }
-        (KeyModifiers::NONE, KeyCode::Down) => {
+        Some(PopupAction::SelectNext) => {
             if let Some(sm) = &mut state.slash_menu {
                 if !sm.commands.is_empty() && sm.selected < sm.commands.len() - 1 {
                     sm.selected += 1;
                 }
             }
         }
-        (KeyModifiers::NONE, KeyCode::Enter) => {
+        Some(PopupAction::Accept) => {
             return confirm_slash_command(state);
         }
-        (KeyModifiers::NONE, KeyCode::Backspace) => {
-            let should_close = state
-                .slash_menu
-                .as_ref()
-                .is_some_and(|sm| sm.query.is_empty());
-            if should_close {
-                state.slash_menu = None;
-            } else if let Some(sm) = &mut state.slash_menu {
-                sm.query.pop();
-                sm.commands = super::slash::filter(&sm.query);
-                sm.selected = sm.selected.min(sm.commands.len().saturating_sub(1));
+        _ => match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Backspace) => {
+                let should_close = state
+                    .slash_menu
+                    .as_ref()
+                    .is_some_and(|sm| sm.query.is_empty());
+                if should_close {
+                    state.slash_menu = None;
+                } else if let Some(sm) = &mut state.slash_menu {
+                    sm.query.pop();
+                    sm.commands = super::slash::filter(&sm.query);
+                    sm.selected = sm.selected.min(sm.commands.len().saturating_sub(1));
+                }
             }
-        }
-        (KeyModifiers::NONE, KeyCode::Char(ch)) | (KeyModifiers::SHIFT, KeyCode::Char(ch)) => {
-            if let Some(sm) = &mut state.slash_menu {
-                sm.query.push(ch);
-                sm.commands = super::slash::filter(&sm.query);
-                sm.selected = sm.selected.min(sm.commands.len().saturating_sub(1));
+            (KeyModifiers::NONE, KeyCode::Char(ch)) | (KeyModifiers::SHIFT, KeyCode::Char(ch)) => {
+                if let Some(sm) = &mut state.slash_menu {
+                    sm.query.push(ch);
+                    sm.commands = super::slash::filter(&sm.query);
+                    sm.selected = sm.selected.min(sm.commands.len().saturating_sub(1));
+                }
             }
-        }
-        _ => {}
+            _ => {}
+        },
     }
     None
 }
 
 fn confirm_slash_command(state: &mut AppState) -> Option<WriteAction> {
     let sm = state.slash_menu.take()?;
-    let cmd = sm.commands.get(sm.selected)?.clone();
+    let mut cmd = sm.commands.get(sm.selected)?.clone();
+    if let (SlashAction::InsertDate(_), (_, Some(arg))) = (&cmd.action, split_query(&sm.query)) {
+        let Some(date) = parse_natural_date(arg, Local::now().date_naive()) else {
+            // Keep the menu open so the date can be corrected
+            state.status_message = Some(format!("Unrecognized date: {}", arg));
+            state.slash_menu = Some(sm);
+            return None;
+        };
+        cmd.action = SlashAction::InsertDate(DateOffset::Date(date));
+    }
 
     let buffer = match &mut state.input_mode {
         InputMode::Insert { buffer, .. } => buffer,
@@ -393,6 +592,9 @@ fn confirm_slash_command(state: &mut AppState) -> Option<WriteAction> {
 
     // Query chars live in sm.query, not in the buffer — buffer still has just "/"
     super::slash::execute::execute(&cmd.action, buffer, sm.slash_pos, 0);
+    if cmd.action == SlashAction::PickDate {
+        open_calendar(state, Local::now().date_naive(), true);
+    }
     None
 }
 
@@ -405,6 +607,7 @@ pub(super) fn finalize_insert(state: &mut AppState) -> Option<WriteAction> {
                 block_uid,
                 original_text,
                 create_info,
+                ..
             } => (buffer, block_uid, original_text, create_info),
             InputMode::Normal => return None,
         };
@@ -423,6 +626,7 @@ pub(super) fn finalize_insert(state: &mut AppState) -> Option<WriteAction> {
                 block_uid: block_uid.clone(),
                 old_text: original_text.clone(),
             });
+            super::embeds::update_embedded_text(state, &block_uid, &new_text);
         }
         finalize_edit(&mut state.days, block_uid, &original_text, new_text)
     }
@@ -493,7 +697,9 @@ fn handle_indent(state: &mut AppState) -> Option<WriteAction> {
     let old_info = resolve_block_at_index(
         &state.days,
         &state.linked_refs,
-        find_block_index_by_uid(&state.days, &state.linked_refs, &block_uid).unwrap_or(0),
+        &state.embeds,
+        find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, &block_uid)
+            .unwrap_or(0),
     );
     let saved_selected = state.selected_block;
 
@@ -545,13 +751,17 @@ fn handle_dedent(state: &mut AppState) -> Option<WriteAction> {
     let old_info = resolve_block_at_index(
         &state.days,
         &state.linked_refs,
-        find_block_index_by_uid(&state.days, &state.linked_refs, &block_uid).unwrap_or(0),
+        &state.embeds,
+        find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, &block_uid)
+            .unwrap_or(0),
     );
     let saved_selected = state.selected_block;
 
     let (new_parent_uid, new_order) = dedent_block_in_days(&mut state.days, &block_uid)?;
 
-    if let Some(idx) = find_block_index_by_uid(&state.days, &state.linked_refs, &block_uid) {
+    if let Some(idx) =
+        find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, &block_uid)
+    {
         state.selected_block = idx;
     }
 
@@ -583,41 +793,3 @@ fn handle_dedent(state: &mut AppState) -> Option<WriteAction> {
         })
     }
 }
-
-// --- Delete block handler ---
-
-pub fn handle_delete_block(state: &mut AppState) -> Option<WriteAction> {
-    // Guard: no deleting in linked refs zone
-    if state
-        .resolve_linked_ref_item(state.selected_block)
-        .is_some()
-    {
-        return None;
-    }
-    state.redo_stack.clear();
-    let info = resolve_block_at_index(&state.days, &state.linked_refs, state.selected_block)?;
-    let block = find_block_in_days(&state.days, &info.block_uid)?;
-    let saved_selected = state.selected_block;
-
-    state.undo_stack.push(UndoEntry::DeleteBlock {
-        block,
-        parent_uid: info.parent_uid.clone(),
-        order: info.order,
-        selected_block: saved_selected,
-    });
-
-    remove_block_from_days(&mut state.days, &info.block_uid);
-
-    let total = state.flat_block_count();
-    if total == 0 {
-        state.selected_block = 0;
-    } else if state.selected_block >= total {
-        state.selected_block = total - 1;
-    }
-
-    Some(WriteAction::DeleteBlock {
-        block: BlockRef {
-            uid: info.block_uid,
-        },
-    })
-}
@@ -0,0 +1,73 @@
+use crate::api::types::{BlockLocation, BlockRef, OrderValue, WriteAction};
+use crate::markup;
+
+use super::blocks::{
+    child_uids, find_block_in_days, find_block_index_by_uid, find_block_parent_info,
+    move_block_in_days, resolve_block_at_index, set_block_open,
+};
+use super::state::{AppState, UndoEntry};
+use super::visual::push_undo;
+
+/// Move the selected kanban card to the end of the previous (or next) column.
+pub(super) fn move_card(state: &mut AppState, left: bool) -> Option<WriteAction> {
+    let info = resolve_block_at_index(
+        &state.days,
+        &state.linked_refs,
+        &state.embeds,
+        state.selected_block,
+    )?;
+    let column_uid = info.parent_uid;
+    let board_uid = find_block_parent_info(&state.days, &column_uid)
+        .map(|(uid, _)| uid)
+        .filter(|uid| {
+            find_block_in_days(&state.days, uid)
+                .is_some_and(|b| markup::block_macro(&b.string).as_deref() == Some("kanban"))
+        });
+    let Some(board_uid) = board_uid else {
+        state.status_message = Some("Not a kanban card".into());
+        return None;
+    };
+    let columns = child_uids(&state.days, &board_uid)?;
+    let idx = columns.iter().position(|uid| *uid == column_uid)?;
+    let target = if left {
+        idx.checked_sub(1)
+    } else {
+        Some(idx + 1).filter(|&i| i < columns.len())
+    };
+    let target = columns.get(target?)?.clone();
+    let order = child_uids(&state.days, &target)?.len() as i64;
+
+    let (_, old_order) = find_block_parent_info(&state.days, &info.block_uid)?;
+
+    let saved_selected = state.selected_block;
+    // A collapsed column would hide the card
+    set_block_open(&mut state.days, &target, true);
+    move_block_in_days(&mut state.days, &info.block_uid, &target, order);
+    push_undo(
+        state,
+        vec![UndoEntry::MoveBlock {
+            block_uid: info.block_uid.clone(),
+            old_parent_uid: column_uid,
+            old_order,
+            selected_block: saved_selected,
+        }],
+        saved_selected,
+    );
+    if let Some(idx) = find_block_index_by_uid(
+        &state.days,
+        &state.linked_refs,
+        &state.embeds,
+        &info.block_uid,
+    ) {
+        state.selected_block = idx;
+    }
+    Some(WriteAction::MoveBlock {
+        block: BlockRef {
+            uid: info.block_uid,
+        },
+        location: BlockLocation {
+            parent_uid: target,
+            order: OrderValue::Index(order),
+        },
+    })
+}
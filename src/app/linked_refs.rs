@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::api::types::{sort_linked_ref_groups, LinkedRefBlock, LinkedRefGroup};
+use crate::keys::mode::PopupAction;
+use crate::markdown;
+
+use super::state::{AppState, LinkedRefFilter, LinkedRefFilterState, LinkedRefsState, ViewMode};
+
+impl LinkedRefsState {
+    pub fn new_loading(filter: LinkedRefFilter) -> Self {
+        Self {
+            groups: vec![],
+            all_groups: vec![],
+            filter,
+            collapsed: false,
+            loading: true,
+        }
+    }
+
+    pub fn from_groups(all_groups: Vec<LinkedRefGroup>, filter: LinkedRefFilter) -> Self {
+        let mut lr = Self {
+            groups: vec![],
+            all_groups,
+            filter,
+            collapsed: false,
+            loading: false,
+        };
+        lr.apply_filter();
+        lr
+    }
+
+    /// Recompute the visible `groups` from `all_groups` and the current filter.
+    pub fn apply_filter(&mut self) {
+        self.groups = filter_groups(&self.all_groups, &self.filter);
+    }
+}
+
+/// Pages a linked ref block points at: its own page links plus the page it lives on.
+fn block_page_refs(block: &LinkedRefBlock) -> HashSet<String> {
+    let mut refs: HashSet<String> = markdown::extract_page_links(&block.string)
+        .into_iter()
+        .collect();
+    refs.insert(block.page_title.clone());
+    refs
+}
+
+/// Keep blocks that reference every included page and none of the excluded ones.
+/// Groups left without blocks are dropped; the rest are ordered by `filter.sort`.
+pub(super) fn filter_groups(
+    groups: &[LinkedRefGroup],
+    filter: &LinkedRefFilter,
+) -> Vec<LinkedRefGroup> {
+    let mut result: Vec<LinkedRefGroup> = groups
+        .iter()
+        .filter_map(|group| {
+            let blocks: Vec<LinkedRefBlock> = group
+                .blocks
+                .iter()
+                .filter(|block| {
+                    let refs = block_page_refs(block);
+                    filter.include.iter().all(|p| refs.contains(p))
+                        && !filter.exclude.iter().any(|p| refs.contains(p))
+                })
+                .cloned()
+                .collect();
+            if blocks.is_empty() {
+                None
+            } else {
+                Some(LinkedRefGroup {
+                    page_title: group.page_title.clone(),
+                    blocks,
+                })
+            }
+        })
+        .collect();
+    sort_linked_ref_groups(&mut result, filter.sort);
+    result
+}
+
+/// Pages referenced alongside `page_title`, with the number of blocks mentioning each.
+/// Sorted by count (descending), then title.
+pub(super) fn co_referenced_pages(
+    groups: &[LinkedRefGroup],
+    page_title: &str,
+) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for block in groups.iter().flat_map(|g| &g.blocks) {
+        for page in block_page_refs(block) {
+            if page != page_title {
+                *counts.entry(page).or_default() += 1;
+            }
+        }
+    }
+    let mut pages: Vec<(String, usize)> = counts.into_iter().collect();
+    pages.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    pages
+}
+
+/// Open the filter popup for the linked refs section under the cursor
+/// (or of the page being viewed).
+pub(super) fn open_linked_ref_filter(state: &mut AppState) {
+    let title = match &state.view_mode {
+        ViewMode::Page { title } => Some(title.clone()),
+        ViewMode::DailyNotes => state.day_title_at(state.selected_block),
+    };
+    let Some(title) = title else {
+        return;
+    };
+    let Some(lr) = state.linked_refs.get(&title) else {
+        return;
+    };
+    if lr.loading || lr.all_groups.is_empty() {
+        state.status_message = Some("No linked references to filter".into());
+        return;
+    }
+    let pages = co_referenced_pages(&lr.all_groups, &title);
+    state.linked_ref_filter = Some(LinkedRefFilterState {
+        page_title: title,
+        pages,
+        selected: 0,
+    });
+}
+
+/// Handle a key in the linked refs filter popup.
+/// Returns true when the filter changed and should be persisted.
+pub(super) fn handle_linked_ref_filter_key(state: &mut AppState, key: &KeyEvent) -> bool {
+    let action = state.popup_keys.resolve(key);
+    let Some(popup) = &mut state.linked_ref_filter else {
+        return false;
+    };
+    if action.is_none()
+        && key.modifiers != KeyModifiers::NONE
+        && key.modifiers != KeyModifiers::SHIFT
+    {
+        return false;
+    }
+    let selected_page = popup.pages.get(popup.selected).map(|(p, _)| p.clone());
+    let page_title = popup.page_title.clone();
+
+    let change: fn(&mut LinkedRefFilter, Option<&str>) = match (action, key.code) {
+        (Some(PopupAction::Close), _) | (None, KeyCode::Char('q')) => {
+            state.linked_ref_filter = None;
+            return false;
+        }
+        (Some(PopupAction::SelectPrev), _) | (None, KeyCode::Char('k')) => {
+            popup.selected = popup.selected.saturating_sub(1);
+            return false;
+        }
+        (Some(PopupAction::SelectNext), _) | (None, KeyCode::Char('j')) => {
+            if popup.selected + 1 < popup.pages.len() {
+                popup.selected += 1;
+            }
+            return false;
+        }
+        (Some(PopupAction::Accept), _) | (None, KeyCode::Char('i')) => |f, page| {
+            if let Some(page) = page {
+                toggle_page(&mut f.include, &mut f.exclude, page);
+            }
+        },
+        (Some(_), _) => return false,
+        (None, KeyCode::Char('x')) => |f, page| {
+            if let Some(page) = page {
+                toggle_page(&mut f.exclude, &mut f.include, page);
+            }
+        },
+        (None, KeyCode::Char('s')) => |f, _| f.sort = f.sort.next(),
+        (None, KeyCode::Char('c')) => |f, _| {
+            f.include.clear();
+            f.exclude.clear();
+        },
+        _ => return false,
+    };
+
+    let Some(lr) = state.linked_refs.get_mut(&page_title) else {
+        return false;
+    };
+    change(&mut lr.filter, selected_page.as_deref());
+    lr.apply_filter();
+    if lr.filter.is_default() {
+        state.linked_ref_filters.remove(&page_title);
+    } else {
+        state
+            .linked_ref_filters
+            .insert(page_title, lr.filter.clone());
+    }
+
+    let total = state.total_navigable_count();
+    state.selected_block = state.selected_block.min(total.saturating_sub(1));
+    true
+}
+
+/// Add `page` to `list` (removing it from `other`), or remove it if already present.
+fn toggle_page(list: &mut Vec<String>, other: &mut Vec<String>, page: &str) {
+    if let Some(pos) = list.iter().position(|p| p == page) {
+        list.remove(pos);
+    } else {
+        other.retain(|p| p != page);
+        list.push(page.to_string());
+    }
+}
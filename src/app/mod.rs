@@ -1,42 +1,68 @@
 mod actions;
+mod agenda;
+mod attributes;
 pub(crate) mod blocks;
+pub(crate) mod calendar;
+mod editor;
+mod embeds;
+pub(crate) mod help;
 mod input;
+mod kanban;
+mod linked_refs;
+mod mouse;
 mod nav;
+pub(crate) mod palette;
+mod query_blocks;
+mod rename;
 mod search;
+mod sequence;
 pub(crate) mod slash;
 mod state;
 mod tasks;
 mod undo;
+mod vim;
+mod visual;
+mod yank;
 pub use state::*;
 
 use actions::handle_action;
+use agenda::AgendaOutcome;
+use attributes::handle_attribute_table_key;
 use blocks::generate_uid;
 use input::{
-    handle_delete_block, handle_insert_key, handle_link_picker_key, handle_quick_switcher_key,
-    handle_search_key,
+    apply_remote_search_results, handle_insert_key, handle_link_picker_key,
+    handle_quick_switcher_key, handle_search_key, refresh_autocomplete_results,
 };
+use linked_refs::handle_linked_ref_filter_key;
+use mouse::MouseOutcome;
+use palette::{handle_command_palette_key, PaletteOutcome};
+use rename::{handle_rename_page_key, rename_refs_loaded};
 use tasks::{
-    collect_unresolved_refs, spawn_fetch_daily_note, spawn_fetch_linked_refs, spawn_fetch_page,
-    spawn_fetch_page_titles, spawn_refresh_daily_note, spawn_resolve_block_refs, spawn_write,
+    collect_unresolved_refs, spawn_fetch_block_page, spawn_fetch_daily_note,
+    spawn_fetch_linked_refs, spawn_fetch_page, spawn_fetch_page_titles, spawn_load_agenda,
+    spawn_load_calendar_month, spawn_load_embeds, spawn_refresh_daily_note,
+    spawn_resolve_block_refs, spawn_run_queries, spawn_search, spawn_write,
 };
 use undo::{apply_redo, apply_undo};
 
 #[cfg(test)]
 pub(crate) mod test_helpers;
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{Event, EventStream, KeyEvent, KeyEventKind, MouseButton, MouseEventKind};
 use futures::StreamExt;
 use ratatui::DefaultTerminal;
 use tokio::sync::mpsc;
 
 use crate::api::client::RoamClient;
 use crate::api::types::{Block, DailyNote};
+use crate::cache;
 use crate::config::AppConfig;
 use crate::error::{ErrorInfo, ErrorPopup, Result};
 use crate::keys::preset::Action;
 use crate::keys::KeybindingMap;
+use crate::ui::main_area::HitMap;
 
 fn dispatch_load_request(
     request: LoadRequest,
@@ -46,6 +72,8 @@ fn dispatch_load_request(
     match request {
         LoadRequest::DailyNote(date) => spawn_fetch_daily_note(client, date, tx),
         LoadRequest::Page(title) => spawn_fetch_page(client, &title, tx),
+        LoadRequest::LinkedRefs(title) => spawn_fetch_linked_refs(client, &title, tx),
+        LoadRequest::BlockPage(uid) => spawn_fetch_block_page(client, &uid, tx),
     }
 }
 
@@ -56,38 +84,48 @@ fn handle_normal_key(
     client: &RoamClient,
     tx: &mpsc::UnboundedSender<AppMessage>,
 ) {
-    if state.pending_key == Some('d')
-        && key.code == KeyCode::Char('d')
-        && key.modifiers == KeyModifiers::NONE
+    if state.visual_anchor.is_some()
+        && state.pending_keys.is_empty()
+        && keybindings.resolve(key).is_none()
     {
-        state.pending_key = None;
-        if let Some(write_action) = handle_delete_block(state) {
+        if let Some(action) = visual::visual_key_action(key) {
+            handle_normal_action(state, &action, client, tx);
+            return;
+        }
+    }
+    if let Some(action) = sequence::resolve_key(state, key, keybindings, Instant::now()) {
+        handle_normal_action(state, &action, client, tx);
+    }
+}
+
+fn handle_normal_action(
+    state: &mut AppState,
+    action: &Action,
+    client: &RoamClient,
+    tx: &mpsc::UnboundedSender<AppMessage>,
+) {
+    if action == &Action::Undo || action == &Action::Redo {
+        state.visual_anchor = None;
+        let write_action = if action == &Action::Undo {
+            apply_undo(state)
+        } else {
+            apply_redo(state)
+        };
+        if let Some(write_action) = write_action {
             spawn_write(client, write_action, tx);
         }
-    } else if state.pending_key == Some('d') {
-        state.pending_key = None;
-        if let Some(action) = keybindings.resolve(key) {
-            if let Some(req) = handle_action(state, action) {
-                dispatch_load_request(req, client, tx);
-            }
+    } else if visual::is_block_edit(action) {
+        if let Some(write_action) = visual::apply_block_edit(state, action) {
+            spawn_write(client, write_action, tx);
         }
-    } else if key.code == KeyCode::Char('d')
-        && key.modifiers == KeyModifiers::NONE
-        && keybindings.resolve(key).is_none()
-    {
-        state.pending_key = Some('d');
-    } else if let Some(action) = keybindings.resolve(key) {
-        if action == &Action::Undo {
-            if let Some(write_action) = apply_undo(state) {
-                spawn_write(client, write_action, tx);
-            }
-        } else if action == &Action::Redo {
-            if let Some(write_action) = apply_redo(state) {
-                spawn_write(client, write_action, tx);
-            }
-        } else if let Some(req) = handle_action(state, action) {
-            dispatch_load_request(req, client, tx);
+    } else if action == &Action::Agenda {
+        spawn_load_agenda(client, agenda::start_agenda(state), tx);
+    } else if matches!(action, Action::MoveCardLeft | Action::MoveCardRight) {
+        if let Some(write_action) = kanban::move_card(state, action == &Action::MoveCardLeft) {
+            spawn_write(client, write_action, tx);
         }
+    } else if let Some(req) = handle_action(state, action) {
+        dispatch_load_request(req, client, tx);
     }
 }
 
@@ -150,14 +188,22 @@ pub fn handle_page_loaded(state: &mut AppState, mut note: DailyNote) {
 pub fn handle_api_error(state: &mut AppState, error: ErrorInfo) {
     state.loading = false;
     state.loading_more = false;
+    rename::rename_refs_failed(state);
     state.error_popup = Some(ErrorPopup::from_error_info(&error));
 }
 
 pub async fn run(config: &AppConfig, terminal: &mut DefaultTerminal) -> Result<()> {
-    let keybindings =
-        KeybindingMap::from_preset(&config.keybindings.preset, &config.keybindings.bindings)?;
+    let keybindings = KeybindingMap::from_config(&config.keybindings)?;
 
     let mut state = AppState::new(&config.graph.name, keybindings.hints());
+    state.vim_editing = config.keybindings.preset == "vim";
+    state.key_labels = keybindings.key_labels();
+    state.help_sections = keybindings.sections();
+    state.sequence_timeout = Duration::from_millis(config.keybindings.timeout_ms);
+    state.insert_keys = keybindings.insert_keymap().clone();
+    state.popup_keys = keybindings.popup_keymap().clone();
+    state.light_theme = config.ui.theme == "light";
+    state.linked_ref_filters = cache::load_linked_ref_filters().unwrap_or_default();
 
     let (tx, mut rx) = mpsc::unbounded_channel::<AppMessage>();
 
@@ -166,64 +212,94 @@ pub async fn run(config: &AppConfig, terminal: &mut DefaultTerminal) -> Result<(
     // Fetch today's daily note
     spawn_fetch_daily_note(&client, state.current_date, &tx);
 
-    // Spawn event reader task
-    let event_tx = tx.clone();
-    tokio::spawn(async move {
-        let mut reader = EventStream::new();
-        loop {
-            match reader.next().await {
-                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
-                    if event_tx.send(AppMessage::Key(key)).is_err() {
-                        break;
-                    }
-                }
-                Some(Err(_)) => break,
-                None => break,
-                _ => {}
-            }
-        }
-    });
+    let mut event_reader = spawn_event_reader(&tx);
+    let mut hit_map = HitMap::default();
 
-    // Spawn tick timer
-    let tick_tx = tx.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_millis(250));
-        loop {
-            interval.tick().await;
-            if tick_tx.send(AppMessage::Tick).is_err() {
-                break;
-            }
-        }
-    });
+    let mut ticker = spawn_ticker(&tx);
 
     // Main loop
     loop {
-        terminal.draw(|frame| crate::ui::render(frame, &state))?;
+        terminal.draw(|frame| crate::ui::render(frame, &state, &mut hit_map))?;
 
         if let Some(msg) = rx.recv().await {
             match msg {
                 AppMessage::Key(key) => {
-                    // DEBUG: show raw key in header
-                    state.date_display = format!(
-                        "{:?}+{:?}",
-                        key.code, key.modifiers
-                    );
-
+                    // The view follows the selection again after any key
+                    state.scroll = None;
                     if state.error_popup.is_some() {
                         state.error_popup = None;
-                    } else if state.show_help {
-                        // Any key closes help
-                        state.show_help = false;
+                    } else if let Some(help) = &state.help {
+                        // The preset's help key closes it too, unless typing a query
+                        if !help.searching && keybindings.resolve(&key) == Some(&Action::Help) {
+                            state.help = None;
+                        } else {
+                            help::handle_help_key(&mut state, &key);
+                        }
+                    } else if state.command_palette.is_some() {
+                        match handle_command_palette_key(&mut state, &key) {
+                            Some(PaletteOutcome::Action(action)) => {
+                                handle_normal_action(&mut state, &action, &client, &tx);
+                            }
+                            Some(PaletteOutcome::Load(req)) => {
+                                dispatch_load_request(req, &client, &tx)
+                            }
+                            None => {}
+                        }
+                    } else if state.linked_ref_filter.is_some() {
+                        if handle_linked_ref_filter_key(&mut state, &key) {
+                            if let Err(e) =
+                                cache::save_linked_ref_filters(&state.linked_ref_filters)
+                            {
+                                state.status_message =
+                                    Some(format!("Failed to save linked refs filter: {}", e));
+                            }
+                        }
+                    } else if state.rename_page.is_some() {
+                        if let Some(write_action) = handle_rename_page_key(&mut state, &key) {
+                            spawn_write(&client, write_action, &tx);
+                            // Saved filters are keyed by title and follow the rename
+                            if let Err(e) =
+                                cache::save_linked_ref_filters(&state.linked_ref_filters)
+                            {
+                                state.status_message =
+                                    Some(format!("Failed to save linked refs filter: {}", e));
+                            }
+                        }
                     } else if state.link_picker.is_some() {
                         if let Some(req) = handle_link_picker_key(&mut state, &key) {
                             dispatch_load_request(req, &client, &tx);
                         }
+                    } else if state.attribute_table.is_some() {
+                        if let Some(req) = handle_attribute_table_key(&mut state, &key) {
+                            dispatch_load_request(req, &client, &tx);
+                        }
+                    } else if state.calendar.is_some() {
+                        let today = chrono::Local::now().date_naive();
+                        if let Some(req) = calendar::handle_calendar_key(&mut state, &key, today) {
+                            dispatch_load_request(req, &client, &tx);
+                        }
+                    } else if state.agenda.is_some() {
+                        let today = chrono::Local::now().date_naive();
+                        match agenda::handle_agenda_key(&mut state, &key, today) {
+                            Some(AgendaOutcome::Load(req)) => {
+                                dispatch_load_request(req, &client, &tx)
+                            }
+                            Some(AgendaOutcome::Write(write_action)) => {
+                                spawn_write(&client, write_action, &tx);
+                            }
+                            Some(AgendaOutcome::Reload) => {
+                                spawn_load_agenda(&client, agenda::start_agenda(&mut state), &tx);
+                            }
+                            None => {}
+                        }
                     } else if state.quick_switcher.is_some() {
                         if let Some(req) = handle_quick_switcher_key(&mut state, &key) {
                             dispatch_load_request(req, &client, &tx);
                         }
                     } else if state.search.is_some() {
-                        handle_search_key(&mut state, &key);
+                        if let Some(req) = handle_search_key(&mut state, &key) {
+                            dispatch_load_request(req, &client, &tx);
+                        }
                     } else if state.input_mode != InputMode::Normal {
                         if let Some(write_action) = handle_insert_key(&mut state, &key) {
                             spawn_write(&client, write_action, &tx);
@@ -232,6 +308,28 @@ pub async fn run(config: &AppConfig, terminal: &mut DefaultTerminal) -> Result<(
                         handle_normal_key(&mut state, &key, &keybindings, &client, &tx);
                     }
                 }
+                AppMessage::Mouse(event) => {
+                    if state.error_popup.is_some() || state.help.is_some() {
+                        // A click closes them like any key
+                        if event.kind == MouseEventKind::Down(MouseButton::Left) {
+                            state.error_popup = None;
+                            state.help = None;
+                        }
+                    } else if !state.has_popup() {
+                        match mouse::handle_mouse(&mut state, &event, &hit_map, Instant::now()) {
+                            Some(MouseOutcome::Action(action)) => {
+                                handle_normal_action(&mut state, &action, &client, &tx);
+                            }
+                            Some(MouseOutcome::Load(req)) => {
+                                dispatch_load_request(req, &client, &tx)
+                            }
+                            Some(MouseOutcome::Write(write_action)) => {
+                                spawn_write(&client, write_action, &tx);
+                            }
+                            None => {}
+                        }
+                    }
+                }
                 AppMessage::DailyNoteLoaded(note) => {
                     handle_daily_note_loaded(&mut state, note);
                     let unresolved = collect_unresolved_refs(&state);
@@ -240,45 +338,57 @@ pub async fn run(config: &AppConfig, terminal: &mut DefaultTerminal) -> Result<(
                     for day in &state.days {
                         let title = day.title.clone();
                         if !state.linked_refs.contains_key(&title) {
-                            state.linked_refs.insert(
-                                title.clone(),
-                                LinkedRefsState {
-                                    groups: vec![],
-                                    collapsed: false,
-                                    loading: true,
-                                },
-                            );
+                            let filter = state
+                                .linked_ref_filters
+                                .get(&title)
+                                .cloned()
+                                .unwrap_or_default();
+                            state
+                                .linked_refs
+                                .insert(title.clone(), LinkedRefsState::new_loading(filter));
                             spawn_fetch_linked_refs(&client, &title, &tx);
                         }
                     }
                 }
                 AppMessage::PageLoaded(note) => {
                     handle_page_loaded(&mut state, note);
+                    if let Some(uid) = state.pending_select_uid.take() {
+                        if let Some(idx) = blocks::find_block_index_by_uid(
+                            &state.days,
+                            &state.linked_refs,
+                            &state.embeds,
+                            &uid,
+                        ) {
+                            state.selected_block = idx;
+                        }
+                    }
                     let unresolved = collect_unresolved_refs(&state);
                     spawn_resolve_block_refs(&client, unresolved, &mut state, &tx);
                     // Fetch linked refs for page view
                     if let ViewMode::Page { ref title } = state.view_mode {
                         let title = title.clone();
-                        state.linked_refs.insert(
-                            title.clone(),
-                            LinkedRefsState {
-                                groups: vec![],
-                                collapsed: false,
-                                loading: true,
-                            },
-                        );
+                        let filter = state
+                            .linked_ref_filters
+                            .get(&title)
+                            .cloned()
+                            .unwrap_or_default();
+                        state
+                            .linked_refs
+                            .insert(title.clone(), LinkedRefsState::new_loading(filter));
                         spawn_fetch_linked_refs(&client, &title, &tx);
                     }
                 }
                 AppMessage::LinkedRefsLoaded(page_title, groups) => {
+                    let filter = state
+                        .linked_ref_filters
+                        .get(&page_title)
+                        .cloned()
+                        .unwrap_or_default();
                     state.linked_refs.insert(
-                        page_title,
-                        LinkedRefsState {
-                            groups,
-                            collapsed: false,
-                            loading: false,
-                        },
+                        page_title.clone(),
+                        LinkedRefsState::from_groups(groups, filter),
                     );
+                    rename_refs_loaded(&mut state, &page_title);
                 }
                 AppMessage::BlockRefResolved(uid, text) => {
                     state.pending_block_refs.remove(&uid);
@@ -292,6 +402,7 @@ pub async fn run(config: &AppConfig, terminal: &mut DefaultTerminal) -> Result<(
                 }
                 AppMessage::PageTitlesLoaded(titles) => {
                     state.page_title_cache = titles;
+                    let recent = state.recent_pages();
                     if let Some(qs) = &mut state.quick_switcher {
                         qs.fetching = false;
                         let query = qs.query.clone();
@@ -299,11 +410,64 @@ pub async fn run(config: &AppConfig, terminal: &mut DefaultTerminal) -> Result<(
                             &state.page_title_cache,
                             &query,
                             search::QUICK_SWITCHER_LIMIT,
+                            &recent,
                         );
                         qs.selected = qs.selected.min(qs.filtered.len().saturating_sub(1));
                     }
+                    if let Some(ac) = &mut state.autocomplete {
+                        ac.fetching = false;
+                        refresh_autocomplete_results(&mut state);
+                    }
+                }
+                AppMessage::SearchResultsLoaded(generation, hits) => {
+                    apply_remote_search_results(&mut state, generation, hits);
+                }
+                AppMessage::BlockPageLoaded(uid, title) => {
+                    if title.is_empty() {
+                        state.status_message = Some(format!("Block (({})) not found", uid));
+                    } else {
+                        state.pending_select_uid = Some(uid);
+                        let req = nav::navigate_to_page(&mut state, title);
+                        dispatch_load_request(req, &client, &tx);
+                    }
+                }
+                AppMessage::QueryResultsLoaded(uid, text, result) => {
+                    query_blocks::query_results_loaded(&mut state, &uid, &text, result);
+                }
+                AppMessage::CalendarLoaded(month, result) => {
+                    calendar::month_loaded(&mut state, month, result);
+                }
+                AppMessage::AgendaLoaded(result) => {
+                    agenda::agenda_loaded(&mut state, result);
+                }
+                AppMessage::EmbedLoaded(target, result) => {
+                    embeds::embed_loaded(&mut state, target, result);
                 }
                 AppMessage::Tick => {
+                    sequence::expire_pending(&mut state, Instant::now());
+
+                    // Graph-wide search debounce
+                    if let Some(s) = &mut state.search {
+                        if s.debounce_ticks > 0 {
+                            s.debounce_ticks -= 1;
+                            if s.debounce_ticks == 0 {
+                                s.pending_requests = 2;
+                                spawn_search(&client, &s.query, s.generation, &tx);
+                            }
+                        }
+                    }
+
+                    // Page autocomplete needs titles; fetch once if none are cached
+                    if let Some(ac) = &mut state.autocomplete {
+                        if ac.kind != AutocompleteKind::BlockRef
+                            && !ac.fetching
+                            && state.page_title_cache.is_empty()
+                        {
+                            ac.fetching = true;
+                            spawn_fetch_page_titles(&client, &tx);
+                        }
+                    }
+
                     // Quick Switcher debounce
                     if let Some(qs) = &mut state.quick_switcher {
                         if qs.debounce_ticks > 0 {
@@ -326,12 +490,64 @@ pub async fn run(config: &AppConfig, terminal: &mut DefaultTerminal) -> Result<(
                             for day in &state.days {
                                 spawn_refresh_daily_note(&client, day.date, &tx);
                             }
+                            let queries = query_blocks::collect_stale_queries(&state, true);
+                            spawn_run_queries(&client, queries, &mut state, &tx);
+                            let embeds = embeds::collect_stale_embeds(&state, true);
+                            spawn_load_embeds(&client, embeds, &mut state, &tx);
                         }
                     }
                 }
             }
         }
 
+        // Query blocks that were loaded, created or edited since the last message
+        let queries = query_blocks::collect_stale_queries(&state, false);
+        spawn_run_queries(&client, queries, &mut state, &tx);
+        let embeds = embeds::collect_stale_embeds(&state, false);
+        spawn_load_embeds(&client, embeds, &mut state, &tx);
+        // The calendar moved to a month it hasn't marked yet
+        if let Some(month) = calendar::start_month_load(&mut state) {
+            spawn_load_calendar_month(&client, month, &tx);
+        }
+
+        if let Some(text) = state.pending_clipboard.take() {
+            if let Err(e) = crate::clipboard::copy(&text) {
+                state.status_message = Some(format!("Failed to copy to clipboard: {}", e));
+            }
+        }
+
+        if let Some((path, text)) = state.pending_export.take() {
+            state.status_message = Some(match std::fs::write(&path, text) {
+                Ok(()) => format!("Exported to {}", path.display()),
+                Err(e) => format!("Failed to export: {}", e),
+            });
+        }
+
+        if let Some(edit) = state.pending_external_edit.take() {
+            // Stop reading keys so the editor gets them all, and stop ticking
+            // so the time spent editing doesn't queue up refreshes
+            event_reader.abort();
+            ticker.abort();
+            let _ = event_reader.await;
+            let _ = ticker.await;
+            let result = editor::run_external_editor(terminal, &edit.text, config.ui.mouse);
+            event_reader = spawn_event_reader(&tx);
+            ticker = spawn_ticker(&tx);
+            state.refresh_counter = 0;
+            match result {
+                Ok(text) => {
+                    if let Some(write_action) =
+                        editor::apply_external_edit(&mut state, &edit, &text)
+                    {
+                        spawn_write(&client, write_action, &tx);
+                    }
+                }
+                Err(e) => {
+                    state.status_message = Some(format!("Editor failed: {}", e));
+                }
+            }
+        }
+
         if state.should_quit {
             break;
         }
@@ -340,27 +556,71 @@ pub async fn run(config: &AppConfig, terminal: &mut DefaultTerminal) -> Result<(
     Ok(())
 }
 
+fn spawn_ticker(tx: &mpsc::UnboundedSender<AppMessage>) -> tokio::task::JoinHandle<()> {
+    let tick_tx = tx.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(250));
+        loop {
+            interval.tick().await;
+            if tick_tx.send(AppMessage::Tick).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+fn spawn_event_reader(tx: &mpsc::UnboundedSender<AppMessage>) -> tokio::task::JoinHandle<()> {
+    let event_tx = tx.clone();
+    tokio::spawn(async move {
+        let mut reader = EventStream::new();
+        loop {
+            let message = match reader.next().await {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    AppMessage::Key(key)
+                }
+                // Only clicks and the wheel; motion events would redraw constantly
+                Some(Ok(Event::Mouse(event)))
+                    if matches!(
+                        event.kind,
+                        MouseEventKind::Down(MouseButton::Left)
+                            | MouseEventKind::ScrollUp
+                            | MouseEventKind::ScrollDown
+                    ) =>
+                {
+                    AppMessage::Mouse(event)
+                }
+                Some(Err(_)) => break,
+                None => break,
+                _ => continue,
+            };
+            if event_tx.send(message).is_err() {
+                break;
+            }
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::actions::handle_action;
     use super::blocks::*;
     use super::input::{
-        finalize_insert, handle_delete_block, handle_insert_key, handle_quick_switcher_key,
-        handle_search_key,
+        finalize_insert, handle_insert_key, handle_quick_switcher_key, handle_search_key,
     };
     use super::nav::navigate_to_page;
-    use super::search::{
-        detect_block_ref_trigger, filter_blocks, filter_page_titles, AUTOCOMPLETE_LIMIT,
-        QUICK_SWITCHER_LIMIT, SEARCH_LIMIT,
-    };
+    use super::palette::{handle_command_palette_key, PaletteOutcome};
+    use super::search::{detect_block_ref_trigger, filter_blocks, filter_page_titles};
     use super::tasks::extract_uids_from_text;
     use super::test_helpers::*;
     use super::undo::{apply_redo, apply_undo};
+    use super::visual::{apply_block_edit, visual_key_action};
     use super::*;
-    use crate::api::types::{Block, WriteAction};
+    use crate::api::types::{Block, LinkedRefBlock, LinkedRefGroup, WriteAction};
     use crate::edit_buffer::EditBuffer;
     use crate::keys::preset::Action;
+    use crate::markup::EmbedTarget;
     use chrono::NaiveDate;
+    use crossterm::event::{KeyCode, KeyModifiers};
     use std::collections::{HashMap, HashSet};
 
     // --- resolve_block_at_index tests ---
@@ -368,7 +628,8 @@ mod tests {
     #[test]
     fn resolve_first_block() {
         let state = test_state_with_blocks();
-        let info = resolve_block_at_index(&state.days, &state.linked_refs, 0).unwrap();
+        let info =
+            resolve_block_at_index(&state.days, &state.linked_refs, &state.embeds, 0).unwrap();
         assert_eq!(info.block_uid, "b1");
         assert_eq!(info.parent_uid, "02-21-2026");
         assert_eq!(info.text, "Block one");
@@ -379,14 +640,17 @@ mod tests {
     #[test]
     fn resolve_last_block() {
         let state = test_state_with_blocks();
-        let info = resolve_block_at_index(&state.days, &state.linked_refs, 2).unwrap();
+        let info =
+            resolve_block_at_index(&state.days, &state.linked_refs, &state.embeds, 2).unwrap();
         assert_eq!(info.block_uid, "b3");
     }
 
     #[test]
     fn resolve_out_of_range() {
         let state = test_state_with_blocks();
-        assert!(resolve_block_at_index(&state.days, &state.linked_refs, 99).is_none());
+        assert!(
+            resolve_block_at_index(&state.days, &state.linked_refs, &state.embeds, 99).is_none()
+        );
     }
 
     #[test]
@@ -403,7 +667,7 @@ mod tests {
         let days = vec![day];
 
         // index 0 = Parent, index 1 = Child 1, index 2 = Other
-        let info = resolve_block_at_index(&days, &HashMap::new(), 1).unwrap();
+        let info = resolve_block_at_index(&days, &HashMap::new(), &HashMap::new(), 1).unwrap();
         assert_eq!(info.block_uid, "c1");
         assert_eq!(info.parent_uid, "p");
         assert_eq!(info.depth, 1);
@@ -415,7 +679,7 @@ mod tests {
         let day2 = make_daily_note(2026, 2, 20, vec![make_block("b", "B", 0)]);
         let days = vec![day1, day2];
 
-        let info = resolve_block_at_index(&days, &HashMap::new(), 1).unwrap();
+        let info = resolve_block_at_index(&days, &HashMap::new(), &HashMap::new(), 1).unwrap();
         assert_eq!(info.block_uid, "b");
         assert_eq!(info.parent_uid, "02-20-2026");
     }
@@ -542,6 +806,7 @@ mod tests {
                 block_uid,
                 original_text,
                 create_info,
+                ..
             } => {
                 assert_eq!(buffer.to_string(), "Block two");
                 assert_eq!(block_uid, "b2");
@@ -708,6 +973,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn insert_keymap_override_remaps_word_motion() {
+        let mut state = test_state();
+        state.insert_keys = crate::keys::mode::ModeKeymap::with_overrides(&HashMap::from([(
+            "word_left".to_string(),
+            "Alt+b".to_string(),
+        )]))
+        .unwrap();
+        enter_insert_mode(&mut state);
+        let alt_b = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::ALT);
+        handle_insert_key(&mut state, &alt_b);
+        // The default key no longer moves
+        handle_insert_key(
+            &mut state,
+            &KeyEvent::new(KeyCode::Left, KeyModifiers::CONTROL),
+        );
+        match &state.input_mode {
+            InputMode::Insert { buffer, .. } => assert_eq!(buffer.cursor, 6),
+            _ => panic!("Expected Insert mode"),
+        }
+    }
+
+    #[test]
+    fn insert_ctrl_w_deletes_word_back() {
+        let mut state = test_state();
+        enter_insert_mode(&mut state);
+        handle_insert_key(&mut state, &ctrl_key(KeyCode::Char('w')));
+        assert_eq!(insert_buffer_text(&state), "Block ");
+    }
+
     #[test]
     fn insert_esc_unchanged_returns_none() {
         let mut state = test_state();
@@ -777,13 +1072,13 @@ mod tests {
         }
     }
 
-    // --- handle_delete_block + chord tests ---
+    // --- Delete block and key sequence tests ---
 
     #[test]
     fn delete_block_removes_and_returns_action() {
         let mut state = test_state();
         state.selected_block = 1;
-        let action = handle_delete_block(&mut state);
+        let action = apply_block_edit(&mut state, &Action::DeleteBlock);
         assert!(action.is_some());
         match action.unwrap() {
             WriteAction::DeleteBlock { block } => {
@@ -799,7 +1094,7 @@ mod tests {
     fn delete_last_block_adjusts_selection() {
         let mut state = test_state();
         state.selected_block = 2;
-        handle_delete_block(&mut state);
+        apply_block_edit(&mut state, &Action::DeleteBlock);
         assert_eq!(state.selected_block, 1);
     }
 
@@ -807,27 +1102,87 @@ mod tests {
     fn delete_on_empty_days_returns_none() {
         let mut state = AppState::new("test", vec![]);
         state.loading = false;
-        let action = handle_delete_block(&mut state);
+        let action = apply_block_edit(&mut state, &Action::DeleteBlock);
         assert!(action.is_none());
     }
 
+    fn press(state: &mut AppState, keybindings: &KeybindingMap, keys: &[KeyEvent]) {
+        let client = RoamClient::new("test-graph", "test-token");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        for key in keys {
+            handle_normal_key(state, key, keybindings, &client, &tx);
+        }
+    }
+
+    #[test]
+    fn dd_sequence_deletes_block() {
+        let mut state = test_state();
+        let keybindings = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        let d = key_event(KeyCode::Char('d'));
+        let now = Instant::now();
+        assert!(sequence::resolve_key(&mut state, &d, &keybindings, now).is_none());
+        assert_eq!(state.pending_keys, vec![d]);
+
+        let action = sequence::resolve_key(&mut state, &d, &keybindings, now);
+        assert_eq!(action, Some(Action::DeleteBlock));
+        assert!(state.pending_keys.is_empty());
+        assert!(state.which_key.is_none());
+    }
+
+    #[test]
+    fn unmatched_key_restarts_sequence_on_its_own() {
+        let mut state = test_state();
+        let keybindings = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        press(
+            &mut state,
+            &keybindings,
+            &[key_event(KeyCode::Char('d')), key_event(KeyCode::Char('j'))],
+        );
+        assert!(state.pending_keys.is_empty());
+        assert_eq!(state.selected_block, 1);
+        assert_eq!(state.days[0].blocks.len(), 3);
+    }
+
+    #[test]
+    fn which_key_lists_continuations_until_timeout() {
+        let mut state = test_state();
+        let keybindings = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        let now = Instant::now();
+        let leader = key_event(KeyCode::Char(' '));
+        assert!(sequence::resolve_key(&mut state, &leader, &keybindings, now).is_none());
+        let which_key = state.which_key.clone().unwrap();
+        assert_eq!(which_key.prefix, "Space");
+        assert!(which_key
+            .entries
+            .contains(&("f".to_string(), "Quick switcher".to_string())));
+
+        sequence::expire_pending(&mut state, now + Duration::from_millis(500));
+        assert!(state.which_key.is_some());
+        sequence::expire_pending(&mut state, now + Duration::from_millis(1000));
+        assert!(state.which_key.is_none());
+        assert!(state.pending_keys.is_empty());
+    }
+
     #[test]
-    fn pending_key_d_then_d_triggers_delete() {
+    fn emacs_ctrl_x_sequence_quits() {
         let mut state = test_state();
-        // Simulate chord: first 'd' sets pending
-        state.pending_key = Some('d');
-        // Second 'd' would trigger delete in main loop
-        // Here we just verify the state is correct for it
-        assert_eq!(state.pending_key, Some('d'));
+        let keybindings = KeybindingMap::from_preset("emacs", &HashMap::new()).unwrap();
+        let x = ctrl_key(KeyCode::Char('x'));
+        press(&mut state, &keybindings, &[x]);
+        assert!(!state.should_quit);
+        assert_eq!(state.which_key.as_ref().unwrap().prefix, "Ctrl+x");
+        press(&mut state, &keybindings, &[ctrl_key(KeyCode::Char('c'))]);
+        assert!(state.should_quit);
     }
 
     #[test]
-    fn pending_key_d_then_other_clears_pending() {
+    fn visual_mode_keys_win_over_sequence_prefixes() {
         let mut state = test_state();
-        state.pending_key = Some('d');
-        // In main loop, pressing anything other than 'd' would clear pending
-        state.pending_key = None;
-        assert_eq!(state.pending_key, None);
+        let keybindings = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        handle_action(&mut state, &Action::VisualMode);
+        press(&mut state, &keybindings, &[key_event(KeyCode::Char('y'))]);
+        assert!(state.pending_keys.is_empty());
+        assert_eq!(state.pending_clipboard.as_deref(), Some("- Block one\n"));
     }
 
     // --- indent block tests ---
@@ -1182,6 +1537,7 @@ mod tests {
             block_uid: "b1".into(),
             original_text: "test".into(),
             create_info: None,
+            vim_normal: None,
         };
         state.refresh_counter = 119;
         state.refresh_counter += 1;
@@ -1260,6 +1616,26 @@ mod tests {
         assert_eq!(results[0].0, "b1");
     }
 
+    #[test]
+    fn filter_blocks_matches_substrings_best_first() {
+        let days = vec![make_daily_note(
+            2026,
+            2,
+            21,
+            vec![
+                make_block("b1", "meeting with team", 0),
+                make_block("b2", "team meeting notes", 1),
+                make_block("b3", "unrelated", 2),
+            ],
+        )];
+        let cache = HashMap::new();
+        let results = filter_blocks(&days, &cache, "meet", 10);
+        let uids: Vec<&str> = results.iter().map(|(uid, _)| uid.as_str()).collect();
+        assert_eq!(uids, ["b1", "b2"]);
+        // Scattered letters don't match block text
+        assert!(filter_blocks(&days, &cache, "mtn", 10).is_empty());
+    }
+
     #[test]
     fn filter_blocks_case_insensitive() {
         let days = vec![make_daily_note(
@@ -1469,85 +1845,244 @@ mod tests {
         assert!(selected < count);
     }
 
-    #[test]
-    fn new_state_starts_loading() {
-        let state = AppState::new("my-graph", vec![]);
-        assert!(state.loading);
-        assert_eq!(state.graph_name, "my-graph");
-        assert_eq!(state.selected_block, 0);
-        assert!(!state.should_quit);
-        assert!(state.days.is_empty());
+    // --- page autocomplete tests ---
+
+    fn type_chars(state: &mut AppState, text: &str) {
+        for ch in text.chars() {
+            handle_insert_key(state, &key_event(KeyCode::Char(ch)));
+        }
     }
 
-    #[test]
-    fn quit_action_sets_should_quit() {
-        let mut state = test_state();
-        handle_action(&mut state, &Action::Quit);
-        assert!(state.should_quit);
+    fn insert_buffer_text(state: &AppState) -> String {
+        match &state.input_mode {
+            InputMode::Insert { buffer, .. } => buffer.to_string(),
+            _ => panic!("Expected Insert mode"),
+        }
     }
 
-    #[test]
-    fn move_down_increments_selected_block() {
+    fn page_autocomplete_state() -> AppState {
         let mut state = test_state();
-        assert_eq!(state.selected_block, 0);
-        handle_action(&mut state, &Action::MoveDown);
-        assert_eq!(state.selected_block, 1);
-        handle_action(&mut state, &Action::MoveDown);
-        assert_eq!(state.selected_block, 2);
+        state.page_title_cache = sample_page_titles();
+        enter_insert_mode(&mut state);
+        state
     }
 
     #[test]
-    fn move_down_at_last_block_requests_more() {
-        let mut state = test_state();
-        state.selected_block = 2; // last block
-        let result = handle_action(&mut state, &Action::MoveDown);
-        assert!(result.is_some());
-        assert!(state.loading_more);
-        let expected_date = NaiveDate::from_ymd_opt(2026, 2, 20).unwrap();
-        assert_eq!(result.unwrap(), LoadRequest::DailyNote(expected_date));
+    fn typing_double_bracket_opens_page_autocomplete() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " [[");
+        let ac = state.autocomplete.as_ref().unwrap();
+        assert_eq!(ac.kind, AutocompleteKind::PageLink);
+        assert_eq!(ac.results.len(), 5);
+        assert_eq!(ac.results[0], ("uid1".to_string(), "Alpha".to_string()));
     }
 
     #[test]
-    fn move_down_while_loading_more_does_nothing() {
-        let mut state = test_state();
-        state.selected_block = 2;
-        state.loading_more = true;
-        let result = handle_action(&mut state, &Action::MoveDown);
-        assert!(result.is_none());
-        assert_eq!(state.selected_block, 2);
+    fn page_autocomplete_fuzzy_filters_titles() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " [[dn");
+        let ac = state.autocomplete.as_ref().unwrap();
+        assert_eq!(ac.results[0].1, "Daily Notes");
     }
 
     #[test]
-    fn move_up_decrements_selected_block() {
-        let mut state = test_state();
-        state.selected_block = 2;
-        handle_action(&mut state, &Action::MoveUp);
-        assert_eq!(state.selected_block, 1);
+    fn page_autocomplete_enter_completes_link() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " [[gam");
+        handle_insert_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(state.autocomplete.is_none());
+        assert_eq!(insert_buffer_text(&state), "Block one [[Gamma]]");
+        match &state.input_mode {
+            InputMode::Insert { buffer, .. } => assert_eq!(buffer.cursor, buffer.chars.len()),
+            _ => panic!("Expected Insert mode"),
+        }
     }
 
     #[test]
-    fn move_up_stops_at_zero() {
-        let mut state = test_state();
-        state.selected_block = 0;
-        handle_action(&mut state, &Action::MoveUp);
-        assert_eq!(state.selected_block, 0);
+    fn page_autocomplete_offers_new_page() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " [[Zeta Project");
+        let ac = state.autocomplete.as_ref().unwrap();
+        assert_eq!(
+            ac.results.last().unwrap(),
+            &(String::new(), "Zeta Project".to_string())
+        );
+        handle_insert_key(&mut state, &key_event(KeyCode::Enter));
+        assert_eq!(insert_buffer_text(&state), "Block one [[Zeta Project]]");
     }
 
     #[test]
-    fn move_down_on_empty_days_does_nothing() {
-        let mut state = AppState::new("test", vec![]);
-        state.loading = false;
-        handle_action(&mut state, &Action::MoveDown);
-        assert_eq!(state.selected_block, 0);
+    fn page_autocomplete_no_new_page_for_existing_title() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " [[Beta");
+        let ac = state.autocomplete.as_ref().unwrap();
+        assert!(ac.results.iter().all(|(uid, _)| !uid.is_empty()));
     }
 
     #[test]
-    fn daily_note_loaded_updates_state() {
-        let mut state = AppState::new("test", vec![]);
-        assert!(state.loading);
+    fn page_autocomplete_offers_new_page_differing_in_case() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " [[beta");
+        let ac = state.autocomplete.as_ref().unwrap();
+        assert_eq!(ac.results[0].1, "Beta");
+        assert_eq!(
+            ac.results.last().unwrap(),
+            &(String::new(), "beta".to_string())
+        );
+    }
 
-        let note = make_daily_note(2026, 2, 21, vec![make_block("b1", "Hello", 0)]);
-        handle_daily_note_loaded(&mut state, note);
+    #[test]
+    fn hash_bracket_completes_bracketed_tag() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " #");
+        assert_eq!(
+            state.autocomplete.as_ref().unwrap().kind,
+            AutocompleteKind::Tag
+        );
+        // `[` hands over to the bracket form
+        type_chars(&mut state, "[[");
+        assert_eq!(
+            state.autocomplete.as_ref().unwrap().kind,
+            AutocompleteKind::PageLink
+        );
+        type_chars(&mut state, "daily");
+        handle_insert_key(&mut state, &key_event(KeyCode::Enter));
+        assert_eq!(insert_buffer_text(&state), "Block one #[[Daily Notes]]");
+    }
+
+    #[test]
+    fn tag_autocomplete_inserts_bare_tag() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " #alg");
+        handle_insert_key(&mut state, &key_event(KeyCode::Tab));
+        assert_eq!(insert_buffer_text(&state), "Block one #Algorithms");
+    }
+
+    #[test]
+    fn tag_autocomplete_brackets_multiword_title() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " #notes");
+        handle_insert_key(&mut state, &key_event(KeyCode::Enter));
+        assert_eq!(insert_buffer_text(&state), "Block one #[[Daily Notes]]");
+    }
+
+    #[test]
+    fn tag_autocomplete_space_keeps_typed_text() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " #idea ");
+        assert!(state.autocomplete.is_none());
+        assert_eq!(insert_buffer_text(&state), "Block one #idea ");
+    }
+
+    #[test]
+    fn page_autocomplete_esc_keeps_query_in_brackets() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, " [[draft");
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        assert!(state.autocomplete.is_none());
+        assert_eq!(insert_buffer_text(&state), "Block one [[draft]]");
+    }
+
+    #[test]
+    fn hash_inside_word_does_not_open_tag_autocomplete() {
+        let mut state = page_autocomplete_state();
+        type_chars(&mut state, "#");
+        assert!(state.autocomplete.is_none());
+    }
+
+    #[test]
+    fn page_autocomplete_refreshes_when_titles_arrive() {
+        let mut state = test_state();
+        enter_insert_mode(&mut state);
+        type_chars(&mut state, " [[al");
+        let ac = state.autocomplete.as_ref().unwrap();
+        // Only the "create" entry until titles are loaded
+        assert_eq!(ac.results, vec![(String::new(), "al".to_string())]);
+        state.page_title_cache = sample_page_titles();
+        refresh_autocomplete_results(&mut state);
+        let ac = state.autocomplete.as_ref().unwrap();
+        assert_eq!(ac.results[0].1, "Alpha");
+    }
+
+    #[test]
+    fn new_state_starts_loading() {
+        let state = AppState::new("my-graph", vec![]);
+        assert!(state.loading);
+        assert_eq!(state.graph_name, "my-graph");
+        assert_eq!(state.selected_block, 0);
+        assert!(!state.should_quit);
+        assert!(state.days.is_empty());
+    }
+
+    #[test]
+    fn quit_action_sets_should_quit() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Quit);
+        assert!(state.should_quit);
+    }
+
+    #[test]
+    fn move_down_increments_selected_block() {
+        let mut state = test_state();
+        assert_eq!(state.selected_block, 0);
+        handle_action(&mut state, &Action::MoveDown);
+        assert_eq!(state.selected_block, 1);
+        handle_action(&mut state, &Action::MoveDown);
+        assert_eq!(state.selected_block, 2);
+    }
+
+    #[test]
+    fn move_down_at_last_block_requests_more() {
+        let mut state = test_state();
+        state.selected_block = 2; // last block
+        let result = handle_action(&mut state, &Action::MoveDown);
+        assert!(result.is_some());
+        assert!(state.loading_more);
+        let expected_date = NaiveDate::from_ymd_opt(2026, 2, 20).unwrap();
+        assert_eq!(result.unwrap(), LoadRequest::DailyNote(expected_date));
+    }
+
+    #[test]
+    fn move_down_while_loading_more_does_nothing() {
+        let mut state = test_state();
+        state.selected_block = 2;
+        state.loading_more = true;
+        let result = handle_action(&mut state, &Action::MoveDown);
+        assert!(result.is_none());
+        assert_eq!(state.selected_block, 2);
+    }
+
+    #[test]
+    fn move_up_decrements_selected_block() {
+        let mut state = test_state();
+        state.selected_block = 2;
+        handle_action(&mut state, &Action::MoveUp);
+        assert_eq!(state.selected_block, 1);
+    }
+
+    #[test]
+    fn move_up_stops_at_zero() {
+        let mut state = test_state();
+        state.selected_block = 0;
+        handle_action(&mut state, &Action::MoveUp);
+        assert_eq!(state.selected_block, 0);
+    }
+
+    #[test]
+    fn move_down_on_empty_days_does_nothing() {
+        let mut state = AppState::new("test", vec![]);
+        state.loading = false;
+        handle_action(&mut state, &Action::MoveDown);
+        assert_eq!(state.selected_block, 0);
+    }
+
+    #[test]
+    fn daily_note_loaded_updates_state() {
+        let mut state = AppState::new("test", vec![]);
+        assert!(state.loading);
+
+        let note = make_daily_note(2026, 2, 21, vec![make_block("b1", "Hello", 0)]);
+        handle_daily_note_loaded(&mut state, note);
 
         assert!(!state.loading);
         assert_eq!(state.days.len(), 1);
@@ -1950,7 +2485,8 @@ mod tests {
         state.selected_block = 1;
         let saved_selected = state.selected_block;
         // Indent b2 under b1
-        let info = resolve_block_at_index(&state.days, &state.linked_refs, 1).unwrap();
+        let info =
+            resolve_block_at_index(&state.days, &state.linked_refs, &state.embeds, 1).unwrap();
         indent_block_in_days(&mut state.days, "b2");
         state.undo_stack.push(UndoEntry::MoveBlock {
             block_uid: "b2".into(),
@@ -2040,7 +2576,7 @@ mod tests {
         let search = state.search.as_ref().unwrap();
         assert_eq!(search.query, "one");
         assert_eq!(search.results.len(), 1);
-        assert_eq!(search.results[0].0, "b1");
+        assert_eq!(search.results[0].uid, "b1");
     }
 
     #[test]
@@ -2126,6 +2662,167 @@ mod tests {
         assert_eq!(search.results.len(), 3);
     }
 
+    fn type_search(state: &mut AppState, text: &str) {
+        for ch in text.chars() {
+            handle_search_key(state, &key_event(KeyCode::Char(ch)));
+        }
+    }
+
+    fn search_generation(state: &AppState) -> u64 {
+        state.search.as_ref().unwrap().generation
+    }
+
+    fn remote_hit(
+        uid: &str,
+        text: &str,
+        page: &str,
+        is_page: bool,
+    ) -> crate::api::types::SearchHit {
+        crate::api::types::SearchHit {
+            uid: uid.into(),
+            string: text.into(),
+            page_title: page.into(),
+            is_page,
+        }
+    }
+
+    #[test]
+    fn search_local_results_carry_page_title() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Search);
+        let search = state.search.as_ref().unwrap();
+        assert_eq!(
+            search.results[0].page_title.as_deref(),
+            Some(TEST_DAY_TITLE)
+        );
+    }
+
+    #[test]
+    fn search_typing_starts_debounce_after_min_chars() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Search);
+        type_search(&mut state, "o");
+        assert_eq!(state.search.as_ref().unwrap().debounce_ticks, 0);
+        type_search(&mut state, "n");
+        assert!(state.search.as_ref().unwrap().debounce_ticks > 0);
+    }
+
+    #[test]
+    fn search_remote_results_merge_after_local() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Search);
+        type_search(&mut state, "one");
+        state.search.as_mut().unwrap().pending_requests = 2;
+        let generation = search_generation(&state);
+        apply_remote_search_results(
+            &mut state,
+            generation,
+            vec![
+                remote_hit("b1", "Block one", TEST_DAY_TITLE, false),
+                remote_hit("far", "someone else", "Other Page", false),
+            ],
+        );
+        let search = state.search.as_ref().unwrap();
+        let uids: Vec<&str> = search.results.iter().map(|r| r.uid.as_str()).collect();
+        assert_eq!(uids, vec!["b1", "far"]);
+        assert_eq!(search.results[1].page_title.as_deref(), Some("Other Page"));
+        assert_eq!(search.pending_requests, 1);
+    }
+
+    #[test]
+    fn search_ignores_stale_remote_results() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Search);
+        type_search(&mut state, "one");
+        let generation = search_generation(&state) - 1;
+        apply_remote_search_results(
+            &mut state,
+            generation,
+            vec![remote_hit("far", "someone", "Other", false)],
+        );
+        assert_eq!(state.search.as_ref().unwrap().results.len(), 1);
+    }
+
+    #[test]
+    fn search_query_change_drops_remote_state() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Search);
+        type_search(&mut state, "ab");
+        state.search.as_mut().unwrap().pending_requests = 2;
+        let generation = search_generation(&state);
+        apply_remote_search_results(
+            &mut state,
+            generation,
+            vec![remote_hit("x1", "abc", "P", false)],
+        );
+        assert_eq!(state.search.as_ref().unwrap().pending_requests, 1);
+
+        // Backspacing below the minimum cancels the graph search
+        handle_search_key(&mut state, &key_event(KeyCode::Backspace));
+        let search = state.search.as_ref().unwrap();
+        assert_eq!(search.pending_requests, 0);
+        assert_eq!(search.debounce_ticks, 0);
+        assert!(search.remote.is_empty());
+        assert!(search.results.iter().all(|r| r.uid != "x1"));
+
+        // The second response for the old query is ignored
+        apply_remote_search_results(
+            &mut state,
+            generation,
+            vec![remote_hit("x2", "abd", "P", false)],
+        );
+        assert!(state.search.as_ref().unwrap().remote.is_empty());
+    }
+
+    #[test]
+    fn search_drops_responses_to_an_earlier_identical_query() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Search);
+        type_search(&mut state, "ab");
+        let first = search_generation(&state);
+        type_search(&mut state, "c");
+        handle_search_key(&mut state, &key_event(KeyCode::Backspace));
+        state.search.as_mut().unwrap().pending_requests = 2;
+
+        // The query reads "ab" again, but this reply is to the first request
+        apply_remote_search_results(&mut state, first, vec![remote_hit("x1", "abc", "P", false)]);
+        let search = state.search.as_ref().unwrap();
+        assert_eq!(search.pending_requests, 2);
+        assert!(search.remote.is_empty());
+    }
+
+    #[test]
+    fn search_enter_on_remote_block_opens_containing_page() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Search);
+        type_search(&mut state, "zzz");
+        let generation = search_generation(&state);
+        apply_remote_search_results(
+            &mut state,
+            generation,
+            vec![remote_hit("far", "zzz here", "Other Page", false)],
+        );
+        let req = handle_search_key(&mut state, &key_event(KeyCode::Enter));
+        assert_eq!(req, Some(LoadRequest::Page("Other Page".into())));
+        assert_eq!(state.pending_select_uid.as_deref(), Some("far"));
+    }
+
+    #[test]
+    fn search_enter_on_page_hit_opens_page() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::Search);
+        type_search(&mut state, "proj");
+        let generation = search_generation(&state);
+        apply_remote_search_results(
+            &mut state,
+            generation,
+            vec![remote_hit("p1", "Project", "Project", true)],
+        );
+        let req = handle_search_key(&mut state, &key_event(KeyCode::Enter));
+        assert_eq!(req, Some(LoadRequest::Page("Project".into())));
+        assert!(state.pending_select_uid.is_none());
+    }
+
     // --- Collapse/Expand tests ---
 
     #[test]
@@ -2163,7 +2860,8 @@ mod tests {
         let mut state = test_state_with_children();
         set_block_open(&mut state.days, "p1", false);
         // index 0 = p1, index 1 = b2 (children hidden)
-        let info = resolve_block_at_index(&state.days, &state.linked_refs, 1).unwrap();
+        let info =
+            resolve_block_at_index(&state.days, &state.linked_refs, &state.embeds, 1).unwrap();
         assert_eq!(info.block_uid, "b2");
     }
 
@@ -2199,21 +2897,97 @@ mod tests {
     // --- Help tests ---
 
     #[test]
-    fn help_toggles_show_help() {
+    fn help_toggles_help_screen() {
         let mut state = test_state();
-        assert!(!state.show_help);
+        assert!(state.help.is_none());
         handle_action(&mut state, &Action::Help);
-        assert!(state.show_help);
+        assert!(state.help.is_some());
         handle_action(&mut state, &Action::Help);
-        assert!(!state.show_help);
+        assert!(state.help.is_none());
     }
 
     #[test]
     fn exit_closes_help() {
         let mut state = test_state();
-        state.show_help = true;
+        state.help = Some(HelpState::default());
         handle_action(&mut state, &Action::Exit);
-        assert!(!state.show_help);
+        assert!(state.help.is_none());
+    }
+
+    fn help_state() -> AppState {
+        let mut state = test_state();
+        state.help_sections = KeybindingMap::from_preset("vim", &HashMap::new())
+            .unwrap()
+            .sections();
+        handle_action(&mut state, &Action::Help);
+        state
+    }
+
+    #[test]
+    fn help_lists_every_binding_by_category() {
+        let state = help_state();
+        let lines = help::help_lines(&state.help_sections, "");
+        assert_eq!(lines[0], help::HelpLine::Header("Navigation"));
+        assert!(lines.contains(&help::HelpLine::Binding {
+            keys: "d d".into(),
+            label: "Delete block",
+        }));
+        assert!(lines.contains(&help::HelpLine::Header("Views")));
+        let bindings = lines
+            .iter()
+            .filter(|l| matches!(l, help::HelpLine::Binding { .. }))
+            .count();
+        let bound: usize = state.help_sections.iter().map(|s| s.bindings.len()).sum();
+        assert_eq!(bindings, bound);
+    }
+
+    #[test]
+    fn help_search_filters_by_label_and_key() {
+        let mut state = help_state();
+        for key in ['/', 'r', 'e', 'n', 'a', 'm'] {
+            help::handle_help_key(&mut state, &key_event(KeyCode::Char(key)));
+        }
+        let query = state.help.as_ref().unwrap().query.clone();
+        assert_eq!(query, "renam");
+        let lines = help::help_lines(&state.help_sections, &query);
+        assert_eq!(
+            lines,
+            vec![
+                help::HelpLine::Header("Views"),
+                help::HelpLine::Binding {
+                    keys: "Shift+R, Space r".into(),
+                    label: "Rename page",
+                },
+            ]
+        );
+        // Searching by key
+        let lines = help::help_lines(&state.help_sections, "ctrl+o");
+        assert!(lines.contains(&help::HelpLine::Binding {
+            keys: "Alt+[, Ctrl+o, Shift+←".into(),
+            label: "Navigate back",
+        }));
+
+        // Enter keeps the filter and returns to scrolling; q closes
+        help::handle_help_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(!state.help.as_ref().unwrap().searching);
+        help::handle_help_key(&mut state, &key_event(KeyCode::Char('q')));
+        assert!(state.help.is_none());
+    }
+
+    #[test]
+    fn help_scrolls_within_lines() {
+        let mut state = help_state();
+        let count = help::help_lines(&state.help_sections, "").len();
+        help::handle_help_key(&mut state, &key_event(KeyCode::Char('j')));
+        help::handle_help_key(&mut state, &key_event(KeyCode::Char('j')));
+        help::handle_help_key(&mut state, &key_event(KeyCode::Char('k')));
+        assert_eq!(state.help.as_ref().unwrap().scroll, 1);
+        help::handle_help_key(&mut state, &key_event(KeyCode::Char('G')));
+        assert_eq!(state.help.as_ref().unwrap().scroll, count - 1);
+        help::handle_help_key(&mut state, &key_event(KeyCode::PageDown));
+        assert_eq!(state.help.as_ref().unwrap().scroll, count - 1);
+        help::handle_help_key(&mut state, &key_event(KeyCode::Home));
+        assert_eq!(state.help.as_ref().unwrap().scroll, 0);
     }
 
     // --- Redo tests ---
@@ -2254,6 +3028,7 @@ mod tests {
             block_uid: "b1".into(),
             original_text: "Block one".into(),
             create_info: None,
+            vim_normal: None,
         };
         finalize_insert(&mut state);
         assert!(state.redo_stack.is_empty());
@@ -2700,81 +3475,704 @@ mod tests {
     }
 
     #[test]
-    fn nav_history_caps_at_50() {
+    fn attribute_table_lists_nested_attributes_and_opens_name() {
         let mut state = test_state();
-        for i in 0..55 {
-            state.days[0].blocks[0].string = format!("[[Page {}]]", i);
-            state.selected_block = 0;
-            handle_action(&mut state, &Action::Enter);
-            // Simulate page load to reset state
-            state.days = vec![make_daily_note(
-                2000,
-                1,
-                1,
-                vec![make_block("p", &format!("Content {}", i), 0)],
-            )];
-            state.loading = false;
+        state.days[0].blocks[0].string = "Status:: [[In Progress]]".into();
+        state.days[0].blocks[2]
+            .children
+            .push(make_block("c1", "Owner:: Ana", 0));
+        handle_action(&mut state, &Action::AttributeTable);
+        let table = state.attribute_table.as_ref().unwrap();
+        assert_eq!(table.page_title, "Test 2026-2-21");
+        assert_eq!(
+            table.rows,
+            vec![
+                ("Status".to_string(), "[[In Progress]]".to_string()),
+                ("Owner".to_string(), "Ana".to_string()),
+            ]
+        );
+        assert!(state.has_popup());
+
+        handle_attribute_table_key(&mut state, &key_event(KeyCode::Down));
+        let result = handle_attribute_table_key(&mut state, &key_event(KeyCode::Enter));
+        assert_eq!(result, Some(LoadRequest::Page("Owner".to_string())));
+        assert!(state.attribute_table.is_none());
+    }
+
+    fn query_hit(uid: &str, page_title: &str) -> crate::api::types::SearchHit {
+        crate::api::types::SearchHit {
+            uid: uid.into(),
+            string: format!("{} text", uid),
+            page_title: page_title.into(),
+            is_page: false,
         }
-        assert!(state.nav_history.len() <= 50);
     }
 
     #[test]
-    fn move_down_no_auto_load_in_page_view() {
+    fn query_blocks_are_run_once_until_edited_or_refreshed() {
         let mut state = test_state();
-        state.view_mode = ViewMode::Page {
-            title: "Test".to_string(),
-        };
-        state.selected_block = 2; // last block
-        let result = handle_action(&mut state, &Action::MoveDown);
-        assert!(result.is_none());
-        assert!(!state.loading_more);
-    }
+        let text = "{{query: {and: [[TODO]] [[Project X]]}}}";
+        state.days[0].blocks[1].string = text.into();
+        let stale = query_blocks::collect_stale_queries(&state, false);
+        assert_eq!(stale, vec![("b2".to_string(), text.to_string())]);
 
-    // --- Linked References tests ---
+        let datalog = query_blocks::start_query(&mut state, "b2", text).unwrap();
+        assert!(datalog.contains(r#"[?r1 :node/title "Project X"]"#));
+        assert!(state.query_results["b2"].loading);
+        assert!(query_blocks::collect_stale_queries(&state, false).is_empty());
+        // A refresh doesn't resend a query still in flight
+        assert!(query_blocks::collect_stale_queries(&state, true).is_empty());
 
-    #[test]
-    fn linked_ref_items_count_none() {
-        let state = test_state();
-        assert_eq!(state.total_navigable_count(), state.flat_block_count());
+        query_blocks::query_results_loaded(&mut state, "b2", text, Ok(vec![]));
+        assert_eq!(query_blocks::collect_stale_queries(&state, true).len(), 1);
+
+        state.days[0].blocks[1].string = "{{query: [[DONE]]}}".into();
+        assert_eq!(query_blocks::collect_stale_queries(&state, false).len(), 1);
     }
 
     #[test]
-    fn linked_ref_items_count_empty_groups() {
+    fn query_results_drop_the_query_block_and_stale_responses() {
         let mut state = test_state();
-        set_linked_refs(
+        let text = "{{[[query]]: [[TODO]]}}";
+        state.days[0].blocks[0].string = text.into();
+        query_blocks::start_query(&mut state, "b1", text);
+
+        query_blocks::query_results_loaded(
             &mut state,
-            LinkedRefsState {
-                groups: vec![],
-                collapsed: false,
-                loading: false,
-            },
+            "b1",
+            "{{query: [[old]]}}",
+            Ok(vec![query_hit("x", "A")]),
         );
-        assert_eq!(state.total_navigable_count(), state.flat_block_count());
+        assert!(state.query_results["b1"].loading);
+
+        let hits = vec![
+            query_hit("z", "Zeta"),
+            query_hit("b1", "Test"),
+            query_hit("a", "Alpha"),
+        ];
+        query_blocks::query_results_loaded(&mut state, "b1", text, Ok(hits));
+        let qr = &state.query_results["b1"];
+        assert!(!qr.loading);
+        let uids: Vec<&str> = qr.results.iter().map(|h| h.uid.as_str()).collect();
+        assert_eq!(uids, ["a", "z"]);
     }
 
     #[test]
-    fn linked_ref_items_count_expanded() {
+    fn invalid_query_keeps_error_without_request() {
         let mut state = test_state();
-        set_linked_refs(&mut state, make_linked_refs_state());
-        let flat = state.flat_block_count();
-        // 1 (header) + 1 (group A header) + 2 (blocks) + 1 (group B header) + 1 (block) = 6
-        assert_eq!(state.total_navigable_count(), flat + 6);
+        let text = "{{query: {xor: [[A]]}}}";
+        state.days[0].blocks[0].string = text.into();
+        assert!(query_blocks::start_query(&mut state, "b1", text).is_none());
+        let qr = &state.query_results["b1"];
+        assert!(qr.error.as_deref().unwrap().contains("xor"));
+        assert!(!qr.loading);
     }
 
     #[test]
-    fn linked_ref_items_count_collapsed() {
+    fn enter_on_query_block_toggles_results() {
         let mut state = test_state();
-        let mut lr = make_linked_refs_state();
-        lr.collapsed = true;
-        set_linked_refs(&mut state, lr);
-        let flat = state.flat_block_count();
-        assert_eq!(state.total_navigable_count(), flat + 1);
+        let text = "{{query: {and: [[TODO]] [[Project X]]}}}";
+        state.days[0].blocks[0].string = text.into();
+        query_blocks::start_query(&mut state, "b1", text);
+        assert_eq!(handle_action(&mut state, &Action::Enter), None);
+        assert!(state.query_results["b1"].collapsed);
+        assert!(state.link_picker.is_none());
+        handle_action(&mut state, &Action::Enter);
+        assert!(!state.query_results["b1"].collapsed);
     }
 
     #[test]
-    fn total_navigable_count_includes_linked_refs() {
+    fn embeds_are_fetched_once_until_refreshed() {
         let mut state = test_state();
-        set_linked_refs(&mut state, make_linked_refs_state());
+        state.days[0].blocks[0].string = "{{embed: ((e1))}}".into();
+        state.days[0].blocks[1].string = "{{[[embed]]: ((e1))}}".into();
+        state.days[0].blocks[2].string = "{{embed: [[Notes]]}}".into();
+        let stale = embeds::collect_stale_embeds(&state, false);
+        assert_eq!(
+            stale,
+            vec![
+                EmbedTarget::Block("e1".into()),
+                EmbedTarget::Page("Notes".into())
+            ]
+        );
+
+        for target in &stale {
+            embeds::start_embed(&mut state, target);
+        }
+        assert!(embeds::collect_stale_embeds(&state, true).is_empty());
+        let mut root = make_block("e1", "Embedded", 0);
+        root.children = vec![make_block("c1", "Child", 0)];
+        embeds::embed_loaded(&mut state, stale[0].clone(), Ok(vec![root]));
+        assert_eq!(state.block_ref_cache["e1"], "Embedded");
+        assert_eq!(
+            embeds::collect_stale_embeds(&state, true),
+            vec![EmbedTarget::Block("e1".into())]
+        );
+    }
+
+    #[test]
+    fn embedded_lines_are_rows_of_their_own() {
+        let mut state = test_state();
+        state.days[0].blocks[0].string = "{{embed: ((e1))}}".into();
+        let mut root = make_block("e1", "Embedded", 0);
+        root.children = vec![make_block("c1", "Child", 0)];
+        embeds::embed_loaded(&mut state, EmbedTarget::Block("e1".into()), Ok(vec![root]));
+
+        assert_eq!(state.flat_block_count(), 4);
+        let info = resolve_block_at_index(&state.days, &state.linked_refs, &state.embeds, 2);
+        assert_eq!(info.unwrap().block_uid, "b2");
+        assert!(
+            resolve_block_at_index(&state.days, &state.linked_refs, &state.embeds, 1).is_none()
+        );
+        assert_eq!(
+            blocks::find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, "b3"),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn editing_block_embed_writes_back_to_embedded_uid() {
+        let mut state = test_state();
+        state.days[0].blocks[0].string = "{{embed: ((e1))}}".into();
+        let target = EmbedTarget::Block("e1".into());
+        let mut root = make_block("e1", "Embedded", 0);
+        root.children = vec![make_block("c1", "Old", 0)];
+        embeds::embed_loaded(&mut state, target.clone(), Ok(vec![root]));
+
+        // The embedding block itself edits its own text
+        handle_action(&mut state, &Action::EditBlock);
+        match &state.input_mode {
+            InputMode::Insert {
+                buffer, block_uid, ..
+            } => {
+                assert_eq!(block_uid, "b1");
+                assert_eq!(buffer.to_string(), "{{embed: ((e1))}}");
+            }
+            InputMode::Normal => panic!("expected insert mode"),
+        }
+        assert!(finalize_insert(&mut state).is_none());
+
+        state.selected_block = 1;
+        handle_action(&mut state, &Action::EditBlock);
+        match &mut state.input_mode {
+            InputMode::Insert {
+                buffer, block_uid, ..
+            } => {
+                assert_eq!(block_uid, "c1");
+                assert_eq!(buffer.to_string(), "Old");
+                *buffer = EditBuffer::new("New");
+            }
+            InputMode::Normal => panic!("expected insert mode"),
+        }
+        let write = finalize_insert(&mut state);
+        assert!(matches!(
+            write,
+            Some(WriteAction::UpdateBlock { block }) if block.uid == "c1" && block.string == "New"
+        ));
+        // The embedding block is untouched; the embed shows the new text
+        assert_eq!(state.days[0].blocks[0].string, "{{embed: ((e1))}}");
+        assert_eq!(state.embeds[&target].blocks[0].children[0].string, "New");
+
+        let undo = apply_undo(&mut state);
+        assert!(matches!(
+            undo,
+            Some(WriteAction::UpdateBlock { block }) if block.uid == "c1" && block.string == "Old"
+        ));
+        assert_eq!(state.embeds[&target].blocks[0].children[0].string, "Old");
+    }
+
+    /// A board with columns "To Do" (cards c1, c2) and "Done" (empty).
+    fn kanban_state() -> AppState {
+        let mut state = test_state();
+        let mut todo = make_block("todo", "To Do", 0);
+        todo.children = vec![
+            make_block("c1", "Card one", 0),
+            make_block("c2", "Card two", 1),
+        ];
+        let mut board = make_block("board", "{{[[kanban]]}}", 0);
+        board.children = vec![todo, make_block("done", "Done", 1)];
+        state.days[0].blocks = vec![board];
+        state
+    }
+
+    #[test]
+    fn move_card_right_moves_to_end_of_next_column() {
+        let mut state = kanban_state();
+        state.selected_block = 3; // c2
+        let write = kanban::move_card(&mut state, false);
+        assert!(matches!(
+            write,
+            Some(WriteAction::MoveBlock { block, location })
+                if block.uid == "c2" && location.parent_uid == "done"
+        ));
+        let board = &state.days[0].blocks[0];
+        assert_eq!(board.children[0].children.len(), 1);
+        assert_eq!(board.children[1].children[0].uid, "c2");
+        // The selection follows the card: board, To Do, c1, Done, c2
+        assert_eq!(state.selected_block, 4);
+
+        // Already in the last column
+        assert!(kanban::move_card(&mut state, false).is_none());
+        apply_undo(&mut state);
+        assert_eq!(state.days[0].blocks[0].children[0].children[1].uid, "c2");
+    }
+
+    #[test]
+    fn move_card_outside_a_board_sets_status() {
+        let mut state = kanban_state();
+        state.selected_block = 1; // a column, whose parent is the board block
+        assert!(kanban::move_card(&mut state, true).is_none());
+        assert_eq!(state.status_message.as_deref(), Some("Not a kanban card"));
+    }
+
+    fn agenda_state() -> AppState {
+        let mut state = test_state();
+        agenda::start_agenda(&mut state);
+        let hit = |uid: &str, text: &str, page: &str| crate::api::types::SearchHit {
+            uid: uid.into(),
+            string: text.into(),
+            page_title: page.into(),
+            is_page: false,
+        };
+        agenda::agenda_loaded(
+            &mut state,
+            Ok(vec![
+                hit("late", "{{[[TODO]]}} late [[February 20th, 2026]]", "Work"),
+                hit("nodate", "{{[[TODO]]}} someday", "Home"),
+                hit("today", "{{[[TODO]]}} now [[February 21st, 2026]]", "Home"),
+                hit("week", "{{[[TODO]]}} soon [[February 22nd, 2026]]", "Work"),
+                hit("child", "under a task", "Work"),
+                hit("done", "{{[[DONE]]}} finished", "Work"),
+            ]),
+        );
+        state
+    }
+
+    fn agenda_uids(state: &AppState, today: NaiveDate) -> Vec<String> {
+        let agenda = state.agenda.as_ref().unwrap();
+        agenda
+            .groups(today)
+            .into_iter()
+            .flat_map(|(_, items)| items)
+            .map(|item| item.uid.clone())
+            .collect()
+    }
+
+    #[test]
+    fn agenda_lists_open_todos_grouped_by_due_date() {
+        let state = agenda_state();
+        let today = NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        let agenda = state.agenda.as_ref().unwrap();
+        assert!(!agenda.loading);
+        assert_eq!(
+            agenda_uids(&state, today),
+            ["late", "today", "week", "nodate"]
+        );
+        let groups: Vec<AgendaGroup> = agenda.groups(today).into_iter().map(|(g, _)| g).collect();
+        assert_eq!(
+            groups[0],
+            AgendaGroup::Due(NaiveDate::from_ymd_opt(2026, 2, 20))
+        );
+        assert_eq!(groups[3], AgendaGroup::Due(None));
+        assert!(state.has_popup());
+    }
+
+    #[test]
+    fn agenda_filters_and_groups_by_page() {
+        let mut state = agenda_state();
+        // Saturday; the week runs Monday 16th to Sunday 22nd
+        let today = NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        let f = key_event(KeyCode::Char('f'));
+        agenda::handle_agenda_key(&mut state, &f, today);
+        assert_eq!(agenda_uids(&state, today), ["late"]);
+        agenda::handle_agenda_key(&mut state, &f, today);
+        assert_eq!(agenda_uids(&state, today), ["today"]);
+        agenda::handle_agenda_key(&mut state, &f, today);
+        assert_eq!(agenda_uids(&state, today), ["late", "today", "week"]);
+        agenda::handle_agenda_key(&mut state, &f, today);
+        assert_eq!(agenda_uids(&state, today), ["nodate"]);
+        agenda::handle_agenda_key(&mut state, &f, today);
+        agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Char('g')), today);
+        assert_eq!(
+            agenda_uids(&state, today),
+            ["today", "nodate", "late", "week"]
+        );
+        let agenda = state.agenda.as_ref().unwrap();
+        assert_eq!(agenda.groups(today)[0].0, AgendaGroup::Page("Home".into()));
+    }
+
+    #[test]
+    fn agenda_toggles_reschedules_and_opens_tasks() {
+        let mut state = agenda_state();
+        let today = NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        let write = agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Char('x')), today);
+        match write {
+            Some(agenda::AgendaOutcome::Write(WriteAction::UpdateBlock { block })) => {
+                assert_eq!(block.uid, "late");
+                assert_eq!(block.string, "{{[[DONE]]}} late [[February 20th, 2026]]");
+            }
+            other => panic!("expected an update, got {:?}", other),
+        }
+        assert!(state.agenda.as_ref().unwrap().items[0].done);
+        // Toggling again reopens it
+        agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Char('x')), today);
+        assert!(!state.agenda.as_ref().unwrap().items[0].done);
+
+        let write = agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Char('>')), today);
+        match write {
+            Some(agenda::AgendaOutcome::Write(WriteAction::UpdateBlock { block })) => {
+                assert_eq!(block.string, "{{[[TODO]]}} late [[February 21st, 2026]]");
+            }
+            other => panic!("expected an update, got {:?}", other),
+        }
+        // Now due today, after the Home task; the selection follows it
+        assert_eq!(
+            agenda_uids(&state, today),
+            ["today", "late", "week", "nodate"]
+        );
+        assert_eq!(state.agenda.as_ref().unwrap().selected, 1);
+
+        agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Down), today);
+        let open = agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Enter), today);
+        assert!(matches!(
+            open,
+            Some(agenda::AgendaOutcome::Load(LoadRequest::BlockPage(ref uid))) if uid == "week"
+        ));
+        assert!(state.agenda.is_none());
+    }
+
+    #[test]
+    fn agenda_reschedules_undated_task_from_today() {
+        let mut state = agenda_state();
+        let today = NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        state.agenda.as_mut().unwrap().selected = 3;
+        agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Char('t')), today);
+        let item = state
+            .agenda
+            .as_ref()
+            .unwrap()
+            .items
+            .iter()
+            .find(|item| item.uid == "nodate")
+            .unwrap();
+        assert_eq!(item.text, "{{[[TODO]]}} someday [[February 21st, 2026]]");
+        assert_eq!(item.due, Some(today));
+    }
+
+    #[test]
+    fn agenda_edits_can_be_undone_after_closing() {
+        let mut state = agenda_state();
+        let today = NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Char('x')), today);
+        agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Char('>')), today);
+        agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Esc), today);
+        assert!(state.agenda.is_none());
+        assert_eq!(state.undo_stack.len(), 2);
+
+        let undo_text = |state: &mut AppState| match apply_undo(state) {
+            Some(WriteAction::UpdateBlock { block }) => block.string,
+            other => panic!("expected an update, got {:?}", other),
+        };
+        // The reschedule of the DONE task, then the toggle
+        assert_eq!(
+            undo_text(&mut state),
+            "{{[[DONE]]}} late [[February 20th, 2026]]"
+        );
+        assert_eq!(
+            undo_text(&mut state),
+            "{{[[TODO]]}} late [[February 20th, 2026]]"
+        );
+        match apply_redo(&mut state) {
+            Some(WriteAction::UpdateBlock { block }) => {
+                assert_eq!(block.string, "{{[[DONE]]}} late [[February 20th, 2026]]");
+            }
+            other => panic!("expected an update, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn agenda_and_calendar_follow_popup_keys() {
+        let mut state = agenda_state();
+        state.popup_keys = crate::keys::mode::ModeKeymap::with_overrides(&HashMap::from([
+            ("select_next".to_string(), "Ctrl+j".to_string()),
+            ("close".to_string(), "Ctrl+g".to_string()),
+        ]))
+        .unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 2, 21).unwrap();
+        agenda::handle_agenda_key(&mut state, &ctrl_key(KeyCode::Char('j')), today);
+        assert_eq!(state.agenda.as_ref().unwrap().selected, 1);
+        // Esc is no longer bound to close
+        agenda::handle_agenda_key(&mut state, &key_event(KeyCode::Esc), today);
+        assert!(state.agenda.is_some());
+        agenda::handle_agenda_key(&mut state, &ctrl_key(KeyCode::Char('g')), today);
+        assert!(state.agenda.is_none());
+
+        calendar::open_calendar(&mut state, today, false);
+        calendar::handle_calendar_key(&mut state, &key_event(KeyCode::Esc), today);
+        assert!(state.calendar.is_some());
+        calendar::handle_calendar_key(&mut state, &ctrl_key(KeyCode::Char('g')), today);
+        assert!(state.calendar.is_none());
+    }
+
+    #[test]
+    fn filter_rename_and_help_follow_popup_keys() {
+        let remap = |state: &mut AppState| {
+            state.popup_keys = crate::keys::mode::ModeKeymap::with_overrides(&HashMap::from([
+                ("select_next".to_string(), "Ctrl+j".to_string()),
+                ("close".to_string(), "Ctrl+g".to_string()),
+            ]))
+            .unwrap();
+        };
+
+        let mut state = test_state();
+        remap(&mut state);
+        open_filter_popup(&mut state);
+        handle_linked_ref_filter_key(&mut state, &ctrl_key(KeyCode::Char('j')));
+        assert_eq!(state.linked_ref_filter.as_ref().unwrap().selected, 1);
+        handle_linked_ref_filter_key(&mut state, &key_event(KeyCode::Esc));
+        assert!(state.linked_ref_filter.is_some());
+        handle_linked_ref_filter_key(&mut state, &ctrl_key(KeyCode::Char('g')));
+        assert!(state.linked_ref_filter.is_none());
+
+        let mut state = rename_test_state();
+        remap(&mut state);
+        open_rename_with_title(&mut state, "New");
+        handle_rename_page_key(&mut state, &ctrl_key(KeyCode::Char('j')));
+        assert_eq!(state.rename_page.as_ref().unwrap().selected, 1);
+        handle_rename_page_key(&mut state, &key_event(KeyCode::Esc));
+        assert!(state.rename_page.as_ref().unwrap().preview.is_some());
+        handle_rename_page_key(&mut state, &ctrl_key(KeyCode::Char('g')));
+        handle_rename_page_key(&mut state, &ctrl_key(KeyCode::Char('g')));
+        assert!(state.rename_page.is_none());
+
+        let mut state = help_state();
+        remap(&mut state);
+        help::handle_help_key(&mut state, &ctrl_key(KeyCode::Char('j')));
+        assert_eq!(state.help.as_ref().unwrap().scroll, 1);
+        help::handle_help_key(&mut state, &key_event(KeyCode::Esc));
+        assert!(state.help.is_some());
+        help::handle_help_key(&mut state, &ctrl_key(KeyCode::Char('g')));
+        assert!(state.help.is_none());
+    }
+
+    #[test]
+    fn calendar_opens_on_selected_day_and_moves() {
+        let mut state = test_state();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        calendar::open_calendar(&mut state, today, false);
+        let date = |m, d| NaiveDate::from_ymd_opt(2026, m, d).unwrap();
+        assert_eq!(state.calendar.as_ref().unwrap().selected, date(2, 21));
+        assert!(state.has_popup());
+
+        let mut press = |code| calendar::handle_calendar_key(&mut state, &key_event(code), today);
+        press(KeyCode::Char('l'));
+        press(KeyCode::Char('j'));
+        press(KeyCode::Char('j'));
+        assert!(press(KeyCode::Char('>')).is_none());
+        assert_eq!(state.calendar.as_ref().unwrap().selected, date(4, 8));
+        calendar::handle_calendar_key(&mut state, &key_event(KeyCode::Char('t')), today);
+        assert_eq!(state.calendar.as_ref().unwrap().selected, today);
+        calendar::handle_calendar_key(&mut state, &key_event(KeyCode::Esc), today);
+        assert!(state.calendar.is_none());
+    }
+
+    #[test]
+    fn calendar_loads_each_month_once() {
+        let mut state = test_state();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        let march = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        calendar::open_calendar(&mut state, today, true);
+        assert_eq!(calendar::start_month_load(&mut state), Some(march));
+        // Already in flight
+        assert_eq!(calendar::start_month_load(&mut state), None);
+        calendar::month_loaded(&mut state, march, Ok(vec![today]));
+        assert_eq!(calendar::start_month_load(&mut state), None);
+        let cal = state.calendar.as_ref().unwrap();
+        assert!(cal.with_content.contains(&today));
+        assert!(cal.loading.is_none());
+
+        calendar::handle_calendar_key(&mut state, &key_event(KeyCode::PageUp), today);
+        assert_eq!(
+            calendar::start_month_load(&mut state),
+            NaiveDate::from_ymd_opt(2026, 2, 1)
+        );
+    }
+
+    #[test]
+    fn calendar_enter_goes_to_daily_note() {
+        let mut state = test_state();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        calendar::open_calendar(&mut state, today, false);
+        calendar::handle_calendar_key(&mut state, &key_event(KeyCode::Char('h')), today);
+        let req = calendar::handle_calendar_key(&mut state, &key_event(KeyCode::Enter), today);
+        assert!(matches!(
+            req,
+            Some(LoadRequest::DailyNote(d)) if d == NaiveDate::from_ymd_opt(2026, 2, 20).unwrap()
+        ));
+        assert!(state.calendar.is_none());
+        assert!(state.days.is_empty());
+        assert!(state.loading);
+    }
+
+    #[test]
+    fn slash_date_with_argument_inserts_parsed_date() {
+        let mut state = test_state();
+        open_slash_menu(&mut state);
+        for c in "date in 3 days".chars() {
+            handle_insert_key(&mut state, &key_event(KeyCode::Char(c)));
+        }
+        let sm = state.slash_menu.as_ref().unwrap();
+        assert_eq!(sm.commands[0].name, "date");
+        handle_insert_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(state.slash_menu.is_none());
+
+        let date = chrono::Local::now().date_naive() + chrono::Days::new(3);
+        let expected = format!("Block one [[{}]]", format_roam_daily_title(date));
+        match &state.input_mode {
+            InputMode::Insert { buffer, .. } => assert_eq!(buffer.to_string(), expected),
+            _ => panic!("Expected Insert mode"),
+        }
+    }
+
+    #[test]
+    fn slash_date_with_bad_argument_keeps_menu_open() {
+        let mut state = test_state();
+        open_slash_menu(&mut state);
+        for c in "date someday".chars() {
+            handle_insert_key(&mut state, &key_event(KeyCode::Char(c)));
+        }
+        handle_insert_key(&mut state, &key_event(KeyCode::Enter));
+        assert_eq!(state.slash_menu.as_ref().unwrap().query, "date someday");
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("Unrecognized date: someday")
+        );
+        assert_eq!(insert_buffer_text(&state), "Block one /");
+    }
+
+    #[test]
+    fn go_to_date_action_opens_date_prompt() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::GoToDate);
+        let palette = state.command_palette.as_ref().unwrap();
+        assert!(palette.date_prompt);
+        assert!(palette.filtered.is_empty());
+    }
+
+    #[test]
+    fn slash_calendar_inserts_date_link() {
+        let mut state = test_state();
+        open_slash_menu(&mut state);
+        for c in "calendar".chars() {
+            handle_insert_key(&mut state, &key_event(KeyCode::Char(c)));
+        }
+        handle_insert_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(state.slash_menu.is_none());
+        assert!(state.calendar.as_ref().unwrap().insert);
+
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        calendar::handle_calendar_key(&mut state, &key_event(KeyCode::Char('t')), today);
+        let req = calendar::handle_calendar_key(&mut state, &key_event(KeyCode::Enter), today);
+        assert!(req.is_none());
+        match &state.input_mode {
+            InputMode::Insert { buffer, .. } => {
+                assert_eq!(buffer.to_string(), "Block one [[March 10th, 2026]]");
+            }
+            _ => panic!("Expected Insert mode"),
+        }
+    }
+
+    #[test]
+    fn calendar_month_weeks_start_on_monday() {
+        // March 2026 starts on a Sunday and spans six weeks
+        let weeks = calendar::month_weeks(NaiveDate::from_ymd_opt(2026, 3, 1).unwrap());
+        assert_eq!(weeks.len(), 6);
+        assert_eq!(weeks[0][..6], [None; 6]);
+        assert_eq!(weeks[0][6], NaiveDate::from_ymd_opt(2026, 3, 1));
+        assert_eq!(weeks[5][1], NaiveDate::from_ymd_opt(2026, 3, 31));
+        assert_eq!(weeks[5][2], None);
+    }
+
+    #[test]
+    fn attribute_table_without_attributes_sets_status() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::AttributeTable);
+        assert!(state.attribute_table.is_none());
+        assert!(state.status_message.is_some());
+    }
+
+    #[test]
+    fn nav_history_caps_at_50() {
+        let mut state = test_state();
+        for i in 0..55 {
+            state.days[0].blocks[0].string = format!("[[Page {}]]", i);
+            state.selected_block = 0;
+            handle_action(&mut state, &Action::Enter);
+            // Simulate page load to reset state
+            state.days = vec![make_daily_note(
+                2000,
+                1,
+                1,
+                vec![make_block("p", &format!("Content {}", i), 0)],
+            )];
+            state.loading = false;
+        }
+        assert!(state.nav_history.len() <= 50);
+    }
+
+    #[test]
+    fn move_down_no_auto_load_in_page_view() {
+        let mut state = test_state();
+        state.view_mode = ViewMode::Page {
+            title: "Test".to_string(),
+        };
+        state.selected_block = 2; // last block
+        let result = handle_action(&mut state, &Action::MoveDown);
+        assert!(result.is_none());
+        assert!(!state.loading_more);
+    }
+
+    // --- Linked References tests ---
+
+    #[test]
+    fn linked_ref_items_count_none() {
+        let state = test_state();
+        assert_eq!(state.total_navigable_count(), state.flat_block_count());
+    }
+
+    #[test]
+    fn linked_ref_items_count_empty_groups() {
+        let mut state = test_state();
+        set_linked_refs(
+            &mut state,
+            LinkedRefsState::from_groups(vec![], LinkedRefFilter::default()),
+        );
+        assert_eq!(state.total_navigable_count(), state.flat_block_count());
+    }
+
+    #[test]
+    fn linked_ref_items_count_expanded() {
+        let mut state = test_state();
+        set_linked_refs(&mut state, make_linked_refs_state());
+        let flat = state.flat_block_count();
+        // 1 (header) + 1 (group A header) + 2 (blocks) + 1 (group B header) + 1 (block) = 6
+        assert_eq!(state.total_navigable_count(), flat + 6);
+    }
+
+    #[test]
+    fn linked_ref_items_count_collapsed() {
+        let mut state = test_state();
+        let mut lr = make_linked_refs_state();
+        lr.collapsed = true;
+        set_linked_refs(&mut state, lr);
+        let flat = state.flat_block_count();
+        assert_eq!(state.total_navigable_count(), flat + 1);
+    }
+
+    #[test]
+    fn total_navigable_count_includes_linked_refs() {
+        let mut state = test_state();
+        set_linked_refs(&mut state, make_linked_refs_state());
         let flat = state.flat_block_count();
         assert_eq!(state.total_navigable_count(), flat + 6);
     }
@@ -2892,52 +4290,471 @@ mod tests {
         assert!(state.linked_refs.is_empty());
     }
 
-    #[test]
-    fn handle_page_loaded_sets_state() {
-        let mut state = test_state();
-        state.view_mode = ViewMode::Page {
-            title: "Target".into(),
+    // --- Linked refs filter tests ---
+
+    fn tagged_linked_refs_state() -> LinkedRefsState {
+        let block = |uid: &str, text: &str, page: &str| crate::api::types::LinkedRefBlock {
+            uid: uid.into(),
+            string: text.into(),
+            page_title: page.into(),
         };
-        let note = DailyNote {
-            date: chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
-            uid: "page-uid".into(),
-            title: "Target".into(),
-            blocks: vec![make_block("b1", "content", 0)],
+        LinkedRefsState::from_groups(
+            vec![
+                crate::api::types::LinkedRefGroup {
+                    page_title: "February 1st, 2026".into(),
+                    blocks: vec![
+                        block("r1", "[[Target]] and [[Project]]", "February 1st, 2026"),
+                        block("r2", "[[Target]] #[[Archive]]", "February 1st, 2026"),
+                    ],
+                },
+                crate::api::types::LinkedRefGroup {
+                    page_title: "Notes".into(),
+                    blocks: vec![block("r3", "[[Target]] [[Project]]", "Notes")],
+                },
+            ],
+            LinkedRefFilter::default(),
+        )
+    }
+
+    fn open_filter_popup(state: &mut AppState) {
+        state.view_mode = ViewMode::Page {
+            title: TEST_DAY_TITLE.into(),
         };
-        handle_page_loaded(&mut state, note);
-        assert!(!state.loading);
-        assert_eq!(state.days.len(), 1);
+        set_linked_refs(state, tagged_linked_refs_state());
+        handle_action(state, &Action::FilterLinkedRefs);
     }
 
-    // --- Slash menu tests ---
+    fn select_filter_page(state: &mut AppState, page: &str) {
+        let lf = state.linked_ref_filter.as_mut().unwrap();
+        lf.selected = lf.pages.iter().position(|(p, _)| p == page).unwrap();
+    }
 
-    /// Helper: enter insert mode and type " /" to open the slash menu.
-    /// The space ensures '/' triggers (trigger requires start-of-buffer or whitespace).
-    fn open_slash_menu(state: &mut AppState) {
-        enter_insert_mode(state);
-        handle_insert_key(state, &key_event(KeyCode::Char(' ')));
-        handle_insert_key(state, &key_event(KeyCode::Char('/')));
+    fn visible_uids(state: &AppState) -> Vec<String> {
+        state.linked_refs[TEST_DAY_TITLE]
+            .groups
+            .iter()
+            .flat_map(|g| g.blocks.iter().map(|b| b.uid.clone()))
+            .collect()
     }
 
     #[test]
-    fn typing_slash_opens_slash_menu() {
+    fn co_referenced_pages_counts_links_and_source_pages() {
+        let lr = tagged_linked_refs_state();
+        let pages = linked_refs::co_referenced_pages(&lr.all_groups, "Target");
+        assert_eq!(
+            pages,
+            vec![
+                ("February 1st, 2026".to_string(), 2),
+                ("Project".to_string(), 2),
+                ("Archive".to_string(), 1),
+                ("Notes".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_action_opens_popup_with_co_referenced_pages() {
         let mut state = test_state();
-        open_slash_menu(&mut state);
-        assert!(state.slash_menu.is_some());
-        let sm = state.slash_menu.as_ref().unwrap();
-        assert_eq!(sm.query, "");
-        assert_eq!(sm.selected, 0);
-        assert_eq!(sm.commands.len(), 18);
+        open_filter_popup(&mut state);
+        let lf = state.linked_ref_filter.as_ref().unwrap();
+        assert_eq!(lf.page_title, TEST_DAY_TITLE);
+        assert!(lf.pages.iter().any(|(p, _)| p == "Project"));
     }
 
     #[test]
-    fn slash_menu_filters_by_query() {
+    fn filter_action_without_linked_refs_does_nothing() {
         let mut state = test_state();
-        open_slash_menu(&mut state);
-        handle_insert_key(&mut state, &key_event(KeyCode::Char('t')));
-        handle_insert_key(&mut state, &key_event(KeyCode::Char('o')));
-        let sm = state.slash_menu.as_ref().unwrap();
-        assert_eq!(sm.query, "to");
+        handle_action(&mut state, &Action::FilterLinkedRefs);
+        assert!(state.linked_ref_filter.is_none());
+    }
+
+    #[test]
+    fn filter_include_keeps_only_matching_blocks() {
+        let mut state = test_state();
+        open_filter_popup(&mut state);
+        select_filter_page(&mut state, "Project");
+        assert!(handle_linked_ref_filter_key(
+            &mut state,
+            &key_event(KeyCode::Enter)
+        ));
+        assert_eq!(visible_uids(&state), vec!["r1", "r3"]);
+        assert_eq!(
+            state.linked_ref_filters[TEST_DAY_TITLE].include,
+            vec!["Project".to_string()]
+        );
+    }
+
+    #[test]
+    fn filter_exclude_hides_matching_blocks() {
+        let mut state = test_state();
+        open_filter_popup(&mut state);
+        select_filter_page(&mut state, "Notes");
+        handle_linked_ref_filter_key(&mut state, &key_event(KeyCode::Char('x')));
+        assert_eq!(visible_uids(&state), vec!["r1", "r2"]);
+    }
+
+    #[test]
+    fn filter_include_then_exclude_moves_page() {
+        let mut state = test_state();
+        open_filter_popup(&mut state);
+        select_filter_page(&mut state, "Archive");
+        handle_linked_ref_filter_key(&mut state, &key_event(KeyCode::Enter));
+        handle_linked_ref_filter_key(&mut state, &key_event(KeyCode::Char('x')));
+        let filter = &state.linked_refs[TEST_DAY_TITLE].filter;
+        assert!(filter.include.is_empty());
+        assert_eq!(filter.exclude, vec!["Archive".to_string()]);
+    }
+
+    #[test]
+    fn filter_clear_removes_persisted_entry() {
+        let mut state = test_state();
+        open_filter_popup(&mut state);
+        select_filter_page(&mut state, "Project");
+        handle_linked_ref_filter_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(state.linked_ref_filters.contains_key(TEST_DAY_TITLE));
+        handle_linked_ref_filter_key(&mut state, &key_event(KeyCode::Char('c')));
+        assert!(!state.linked_ref_filters.contains_key(TEST_DAY_TITLE));
+        assert_eq!(visible_uids(&state).len(), 3);
+    }
+
+    #[test]
+    fn filter_sort_cycles_group_order() {
+        let mut state = test_state();
+        open_filter_popup(&mut state);
+        handle_linked_ref_filter_key(&mut state, &key_event(KeyCode::Char('s')));
+        let lr = &state.linked_refs[TEST_DAY_TITLE];
+        assert_eq!(
+            lr.filter.sort,
+            crate::api::types::LinkedRefSort::NewestFirst
+        );
+        assert_eq!(lr.groups[0].page_title, "February 1st, 2026");
+    }
+
+    #[test]
+    fn filter_navigation_does_not_change_filter() {
+        let mut state = test_state();
+        open_filter_popup(&mut state);
+        assert!(!handle_linked_ref_filter_key(
+            &mut state,
+            &key_event(KeyCode::Char('j'))
+        ));
+        assert_eq!(state.linked_ref_filter.as_ref().unwrap().selected, 1);
+        assert!(!handle_linked_ref_filter_key(
+            &mut state,
+            &key_event(KeyCode::Esc)
+        ));
+        assert!(state.linked_ref_filter.is_none());
+    }
+
+    #[test]
+    fn filter_clamps_selection_when_results_shrink() {
+        let mut state = test_state();
+        open_filter_popup(&mut state);
+        state.selected_block = state.total_navigable_count() - 1;
+        select_filter_page(&mut state, "Archive");
+        handle_linked_ref_filter_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(state.selected_block < state.total_navigable_count());
+    }
+
+    #[test]
+    fn linked_refs_loaded_uses_saved_filter() {
+        let filter = LinkedRefFilter {
+            exclude: vec!["Notes".into()],
+            ..Default::default()
+        };
+        let lr = LinkedRefsState::from_groups(tagged_linked_refs_state().all_groups, filter);
+        assert_eq!(lr.groups.len(), 1);
+        assert_eq!(lr.all_groups.len(), 2);
+    }
+
+    #[test]
+    fn handle_page_loaded_sets_state() {
+        let mut state = test_state();
+        state.view_mode = ViewMode::Page {
+            title: "Target".into(),
+        };
+        let note = DailyNote {
+            date: chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+            uid: "page-uid".into(),
+            title: "Target".into(),
+            blocks: vec![make_block("b1", "content", 0)],
+        };
+        handle_page_loaded(&mut state, note);
+        assert!(!state.loading);
+        assert_eq!(state.days.len(), 1);
+    }
+
+    // --- Page rename tests ---
+
+    fn rename_test_state() -> AppState {
+        let mut state = test_state();
+        state.view_mode = ViewMode::Page {
+            title: "Old".into(),
+        };
+        state.days = vec![DailyNote {
+            date: chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+            uid: "page-uid".into(),
+            title: "Old".into(),
+            blocks: vec![
+                make_block("p1", "Intro", 0),
+                make_block("p2", "See also [[Old]]", 1),
+            ],
+        }];
+        let group = |page: &str, blocks: &[(&str, &str)]| LinkedRefGroup {
+            page_title: page.into(),
+            blocks: blocks
+                .iter()
+                .map(|(uid, text)| LinkedRefBlock {
+                    uid: uid.to_string(),
+                    string: text.to_string(),
+                    page_title: page.into(),
+                })
+                .collect(),
+        };
+        state.linked_refs.insert(
+            "Old".into(),
+            LinkedRefsState::from_groups(
+                vec![
+                    group(
+                        "Journal",
+                        &[("r1", "Read [[Old]] today"), ("r2", "#Old idea")],
+                    ),
+                    group("Work", &[("r3", "#[[Old]] with [[Other]]")]),
+                ],
+                LinkedRefFilter::default(),
+            ),
+        );
+        state
+    }
+
+    fn open_rename_with_title(state: &mut AppState, title: &str) {
+        assert_eq!(
+            rename::open_rename_page(state),
+            Some(LoadRequest::LinkedRefs("Old".into()))
+        );
+        rename::rename_refs_loaded(state, "Old");
+        state.rename_page.as_mut().unwrap().input = EditBuffer::new(title);
+        handle_rename_page_key(state, &key_event(KeyCode::Enter));
+    }
+
+    #[test]
+    fn rename_only_available_in_page_view() {
+        let mut state = test_state();
+        assert_eq!(rename::open_rename_page(&mut state), None);
+        assert!(state.rename_page.is_none());
+        assert!(state.status_message.is_some());
+    }
+
+    #[test]
+    fn rename_prefills_current_title() {
+        let mut state = rename_test_state();
+        handle_action(&mut state, &Action::RenamePage);
+        let rename = state.rename_page.as_ref().unwrap();
+        assert_eq!(rename.input.to_string(), "Old");
+        assert_eq!(rename.page_uid, "page-uid");
+        assert!(rename.preview.is_none());
+    }
+
+    #[test]
+    fn rename_typing_edits_title() {
+        let mut state = rename_test_state();
+        rename::open_rename_page(&mut state);
+        for _ in 0..3 {
+            handle_rename_page_key(&mut state, &key_event(KeyCode::Backspace));
+        }
+        for ch in "New".chars() {
+            handle_rename_page_key(&mut state, &key_event(KeyCode::Char(ch)));
+        }
+        assert_eq!(state.rename_page.as_ref().unwrap().input.to_string(), "New");
+    }
+
+    #[test]
+    fn rename_preview_lists_rewritten_blocks() {
+        let mut state = rename_test_state();
+        open_rename_with_title(&mut state, "New Name");
+        let preview = state.rename_page.as_ref().unwrap().preview.clone().unwrap();
+        let texts: Vec<(&str, &str)> = preview
+            .iter()
+            .map(|e| (e.uid.as_str(), e.new_text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("r1", "Read [[New Name]] today"),
+                ("r2", "#[[New Name]] idea"),
+                ("r3", "#[[New Name]] with [[Other]]"),
+                ("p2", "See also [[New Name]]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_waits_for_linked_refs() {
+        let mut state = rename_test_state();
+        rename::open_rename_page(&mut state);
+        state.rename_page.as_mut().unwrap().input = EditBuffer::new("New");
+        handle_rename_page_key(&mut state, &key_event(KeyCode::Enter));
+        let rename = state.rename_page.as_ref().unwrap();
+        assert!(rename.waiting_for_refs);
+        assert!(rename.preview.is_none());
+
+        rename::rename_refs_loaded(&mut state, "Old");
+        let rename = state.rename_page.as_ref().unwrap();
+        assert!(!rename.waiting_for_refs);
+        assert_eq!(rename.preview.as_ref().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn rename_stops_waiting_when_linked_refs_fail() {
+        let mut state = rename_test_state();
+        rename::open_rename_page(&mut state);
+        state.rename_page.as_mut().unwrap().input = EditBuffer::new("New");
+        handle_rename_page_key(&mut state, &key_event(KeyCode::Enter));
+        handle_api_error(
+            &mut state,
+            crate::error::ErrorInfo::Network("timeout".into()),
+        );
+        let rename = state.rename_page.as_ref().unwrap();
+        assert!(!rename.waiting_for_refs);
+        assert!(rename.error.as_ref().unwrap().contains("linked references"));
+
+        // Closing and reopening fetches them again
+        handle_rename_page_key(&mut state, &key_event(KeyCode::Esc));
+        assert_eq!(
+            rename::open_rename_page(&mut state),
+            Some(LoadRequest::LinkedRefs("Old".into()))
+        );
+    }
+
+    #[test]
+    fn rename_rejects_existing_title() {
+        let mut state = rename_test_state();
+        state.page_title_cache = vec![("Taken".into(), "uid9".into())];
+        open_rename_with_title(&mut state, "Taken");
+        let rename = state.rename_page.as_ref().unwrap();
+        assert!(rename.preview.is_none());
+        assert!(rename.error.as_ref().unwrap().contains("already exists"));
+    }
+
+    #[test]
+    fn rename_esc_in_preview_returns_to_title() {
+        let mut state = rename_test_state();
+        open_rename_with_title(&mut state, "New");
+        handle_rename_page_key(&mut state, &key_event(KeyCode::Esc));
+        let rename = state.rename_page.as_ref().unwrap();
+        assert!(rename.preview.is_none());
+        handle_rename_page_key(&mut state, &key_event(KeyCode::Esc));
+        assert!(state.rename_page.is_none());
+    }
+
+    #[test]
+    fn confirm_rename_sends_one_batch_and_updates_state() {
+        let mut state = rename_test_state();
+        open_rename_with_title(&mut state, "New");
+        let write = handle_rename_page_key(&mut state, &key_event(KeyCode::Enter)).unwrap();
+        let WriteAction::BatchActions { actions } = write else {
+            panic!("Expected a batch");
+        };
+        assert_eq!(actions.len(), 5);
+        assert!(matches!(
+            &actions[0],
+            WriteAction::UpdatePage { page } if page.uid == "page-uid" && page.title == "New"
+        ));
+        assert!(matches!(
+            &actions[1],
+            WriteAction::UpdateBlock { block } if block.uid == "r1" && block.string == "Read [[New]] today"
+        ));
+
+        assert!(state.rename_page.is_none());
+        assert_eq!(
+            state.view_mode,
+            ViewMode::Page {
+                title: "New".into()
+            }
+        );
+        assert_eq!(state.days[0].title, "New");
+        assert_eq!(state.days[0].blocks[1].string, "See also [[New]]");
+        assert!(!state.linked_refs.contains_key("Old"));
+        let lr = &state.linked_refs["New"];
+        assert_eq!(lr.all_groups[0].blocks[1].string, "#New idea");
+        assert_eq!(state.undo_stack.len(), 1);
+    }
+
+    #[test]
+    fn undo_rename_restores_title_and_blocks_in_one_step() {
+        let mut state = rename_test_state();
+        open_rename_with_title(&mut state, "New");
+        handle_rename_page_key(&mut state, &key_event(KeyCode::Enter));
+
+        let write = apply_undo(&mut state).unwrap();
+        let WriteAction::BatchActions { actions } = write else {
+            panic!("Expected a batch");
+        };
+        assert!(matches!(
+            &actions[0],
+            WriteAction::UpdatePage { page } if page.title == "Old"
+        ));
+        assert!(matches!(
+            &actions[2],
+            WriteAction::UpdateBlock { block } if block.uid == "r2" && block.string == "#Old idea"
+        ));
+        assert!(state.undo_stack.is_empty());
+        assert_eq!(state.days[0].title, "Old");
+        assert_eq!(
+            state.linked_refs["Old"].all_groups[0].blocks[0].string,
+            "Read [[Old]] today"
+        );
+
+        apply_redo(&mut state).unwrap();
+        assert_eq!(state.days[0].title, "New");
+        assert_eq!(state.days[0].blocks[1].string, "See also [[New]]");
+    }
+
+    #[test]
+    fn rename_moves_linked_ref_filter_to_new_title() {
+        let mut state = rename_test_state();
+        state.linked_ref_filters.insert(
+            "Old".into(),
+            LinkedRefFilter {
+                include: vec!["Work".into()],
+                ..Default::default()
+            },
+        );
+        open_rename_with_title(&mut state, "New");
+        handle_rename_page_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(!state.linked_ref_filters.contains_key("Old"));
+        assert!(state.linked_ref_filters.contains_key("New"));
+    }
+
+    // --- Slash menu tests ---
+
+    /// Helper: enter insert mode and type " /" to open the slash menu.
+    /// The space ensures '/' triggers (trigger requires start-of-buffer or whitespace).
+    fn open_slash_menu(state: &mut AppState) {
+        enter_insert_mode(state);
+        handle_insert_key(state, &key_event(KeyCode::Char(' ')));
+        handle_insert_key(state, &key_event(KeyCode::Char('/')));
+    }
+
+    #[test]
+    fn typing_slash_opens_slash_menu() {
+        let mut state = test_state();
+        open_slash_menu(&mut state);
+        assert!(state.slash_menu.is_some());
+        let sm = state.slash_menu.as_ref().unwrap();
+        assert_eq!(sm.query, "");
+        assert_eq!(sm.selected, 0);
+        assert_eq!(sm.commands.len(), 19);
+    }
+
+    #[test]
+    fn slash_menu_filters_by_query() {
+        let mut state = test_state();
+        open_slash_menu(&mut state);
+        handle_insert_key(&mut state, &key_event(KeyCode::Char('t')));
+        handle_insert_key(&mut state, &key_event(KeyCode::Char('o')));
+        let sm = state.slash_menu.as_ref().unwrap();
+        assert_eq!(sm.query, "to");
         let names: Vec<&str> = sm.commands.iter().map(|c| c.name).collect();
         assert!(names.contains(&"todo"));
         assert!(names.contains(&"tomorrow"));
@@ -3083,24 +4900,49 @@ mod tests {
     #[test]
     fn filter_page_titles_empty_query_returns_all() {
         let titles = sample_page_titles();
-        let result = filter_page_titles(&titles, "", 50);
+        let result = filter_page_titles(&titles, "", 50, &[]);
         assert_eq!(result.len(), 5);
     }
 
     #[test]
     fn filter_page_titles_prefix_match_ranks_first() {
         let titles = sample_page_titles();
-        let result = filter_page_titles(&titles, "al", 50);
-        // "Alpha" and "Algorithms" are prefix matches
-        assert_eq!(result.len(), 2);
+        let result = filter_page_titles(&titles, "al", 50, &[]);
+        // "Alpha" and "Algorithms" are prefix matches; "Daily Notes" matches fuzzily
+        assert_eq!(result.len(), 3);
         assert_eq!(result[0].0, "Alpha");
         assert_eq!(result[1].0, "Algorithms");
+        assert_eq!(result[2].0, "Daily Notes");
+    }
+
+    #[test]
+    fn filter_page_titles_fuzzy_word_starts() {
+        let titles = sample_page_titles();
+        let result = filter_page_titles(&titles, "dn", 50, &[]);
+        assert_eq!(result[0].0, "Daily Notes");
+    }
+
+    #[test]
+    fn filter_page_titles_boosts_recent_pages() {
+        let titles = sample_page_titles();
+        let recent = vec!["Gamma".to_string()];
+        let result = filter_page_titles(&titles, "", 50, &recent);
+        assert_eq!(result[0].0, "Gamma");
+    }
+
+    #[test]
+    fn recent_pages_lists_history_most_recent_first() {
+        let mut state = test_state();
+        navigate_to_page(&mut state, "First".into());
+        navigate_to_page(&mut state, "Second".into());
+        navigate_to_page(&mut state, "First".into());
+        assert_eq!(state.recent_pages(), vec!["First", "Second"]);
     }
 
     #[test]
     fn filter_page_titles_case_insensitive() {
         let titles = sample_page_titles();
-        let result = filter_page_titles(&titles, "BETA", 50);
+        let result = filter_page_titles(&titles, "BETA", 50, &[]);
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].0, "Beta");
     }
@@ -3108,14 +4950,14 @@ mod tests {
     #[test]
     fn filter_page_titles_no_match_returns_empty() {
         let titles = sample_page_titles();
-        let result = filter_page_titles(&titles, "zzz", 50);
+        let result = filter_page_titles(&titles, "zzz", 50, &[]);
         assert!(result.is_empty());
     }
 
     #[test]
     fn filter_page_titles_respects_limit() {
         let titles = sample_page_titles();
-        let result = filter_page_titles(&titles, "", 2);
+        let result = filter_page_titles(&titles, "", 2, &[]);
         assert_eq!(result.len(), 2);
     }
 
@@ -3195,6 +5037,26 @@ mod tests {
         assert_eq!(state.quick_switcher.as_ref().unwrap().selected, 1);
     }
 
+    #[test]
+    fn popup_keymap_override_applies_to_quick_switcher() {
+        let mut state = test_state();
+        state.popup_keys = crate::keys::mode::ModeKeymap::with_overrides(&HashMap::from([(
+            "select_next".to_string(),
+            "Ctrl+j".to_string(),
+        )]))
+        .unwrap();
+        state.page_title_cache = sample_page_titles();
+        handle_action(&mut state, &Action::QuickSwitcher);
+        handle_quick_switcher_key(&mut state, &ctrl_key(KeyCode::Char('j')));
+        assert_eq!(state.quick_switcher.as_ref().unwrap().selected, 1);
+        // Down was the default for select_next and is now unbound
+        handle_quick_switcher_key(&mut state, &key_event(KeyCode::Down));
+        assert_eq!(state.quick_switcher.as_ref().unwrap().selected, 1);
+        // Ctrl+p is still a default for select_prev
+        handle_quick_switcher_key(&mut state, &ctrl_key(KeyCode::Char('p')));
+        assert_eq!(state.quick_switcher.as_ref().unwrap().selected, 0);
+    }
+
     #[test]
     fn quick_switcher_up_at_zero_stays_zero() {
         let mut state = test_state();
@@ -3236,4 +5098,1241 @@ mod tests {
         // Popup closed after take()
         assert!(state.quick_switcher.is_none());
     }
+
+    // --- Visual mode tests ---
+
+    fn top_level_uids(state: &AppState) -> Vec<String> {
+        state.days[0].blocks.iter().map(|b| b.uid.clone()).collect()
+    }
+
+    fn select_range(state: &mut AppState, anchor: usize, cursor: usize) {
+        state.visual_anchor = Some(anchor);
+        state.selected_block = cursor;
+    }
+
+    fn test_state_four_blocks() -> AppState {
+        let mut state = test_state();
+        state.days[0].blocks.push(make_block("b4", "Block four", 3));
+        state
+    }
+
+    #[test]
+    fn visual_mode_extends_with_motions_and_exits() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::VisualMode);
+        assert_eq!(state.visual_range(), Some((0, 0)));
+        handle_action(&mut state, &Action::MoveDown);
+        handle_action(&mut state, &Action::MoveDown);
+        assert_eq!(state.visual_range(), Some((0, 2)));
+        handle_action(&mut state, &Action::Exit);
+        assert!(state.visual_range().is_none());
+        assert_eq!(state.selected_block, 2);
+    }
+
+    #[test]
+    fn select_up_starts_selection_from_cursor() {
+        let mut state = test_state();
+        state.selected_block = 2;
+        handle_action(&mut state, &Action::SelectUp);
+        assert_eq!(state.visual_range(), Some((1, 2)));
+    }
+
+    #[test]
+    fn select_down_stops_at_last_block() {
+        let mut state = test_state();
+        state.selected_block = 2;
+        handle_action(&mut state, &Action::SelectDown);
+        assert_eq!(state.visual_range(), Some((2, 2)));
+    }
+
+    #[test]
+    fn other_action_leaves_visual_mode() {
+        let mut state = test_state();
+        select_range(&mut state, 0, 1);
+        handle_action(&mut state, &Action::Search);
+        assert!(state.visual_anchor.is_none());
+    }
+
+    #[test]
+    fn visual_keys_map_to_operators() {
+        assert_eq!(
+            visual_key_action(&key_event(KeyCode::Char('d'))),
+            Some(Action::DeleteBlock)
+        );
+        assert_eq!(
+            visual_key_action(&key_event(KeyCode::Char('y'))),
+            Some(Action::Copy)
+        );
+        assert_eq!(
+            visual_key_action(&KeyEvent::new(KeyCode::Char('>'), KeyModifiers::SHIFT)),
+            Some(Action::Indent)
+        );
+        assert_eq!(visual_key_action(&ctrl_key(KeyCode::Char('d'))), None);
+    }
+
+    #[test]
+    fn bulk_delete_is_one_batch_and_one_undo() {
+        let mut state = test_state();
+        select_range(&mut state, 0, 1);
+        let write = apply_block_edit(&mut state, &Action::DeleteBlock).unwrap();
+        match write {
+            WriteAction::BatchActions { actions } => assert_eq!(actions.len(), 2),
+            other => panic!("expected batch, got {:?}", other),
+        }
+        assert_eq!(top_level_uids(&state), vec!["b3"]);
+        assert!(state.visual_anchor.is_none());
+        assert_eq!(state.selected_block, 0);
+        assert_eq!(state.undo_stack.len(), 1);
+
+        apply_undo(&mut state);
+        assert_eq!(top_level_uids(&state), vec!["b1", "b2", "b3"]);
+        apply_redo(&mut state);
+        assert_eq!(top_level_uids(&state), vec!["b3"]);
+        apply_undo(&mut state);
+        assert_eq!(top_level_uids(&state), vec!["b1", "b2", "b3"]);
+    }
+
+    #[test]
+    fn bulk_delete_takes_children_with_parent() {
+        let mut state = test_state_with_children();
+        select_range(&mut state, 0, 1); // p1 and c1
+        let write = apply_block_edit(&mut state, &Action::DeleteBlock).unwrap();
+        assert!(matches!(write, WriteAction::DeleteBlock { .. }));
+        assert_eq!(top_level_uids(&state), vec!["b2"]);
+    }
+
+    #[test]
+    fn delete_without_selection_deletes_selected_block() {
+        let mut state = test_state();
+        state.selected_block = 1;
+        let write = apply_block_edit(&mut state, &Action::DeleteBlock).unwrap();
+        assert!(matches!(write, WriteAction::DeleteBlock { .. }));
+        assert_eq!(top_level_uids(&state), vec!["b1", "b3"]);
+        assert!(matches!(
+            state.undo_stack.last(),
+            Some(UndoEntry::DeleteBlock { .. })
+        ));
+    }
+
+    #[test]
+    fn bulk_indent_keeps_order_and_selection() {
+        let mut state = test_state();
+        select_range(&mut state, 1, 2);
+        let write = apply_block_edit(&mut state, &Action::Indent).unwrap();
+        assert!(matches!(write, WriteAction::BatchActions { .. }));
+        let children: Vec<&str> = state.days[0].blocks[0]
+            .children
+            .iter()
+            .map(|b| b.uid.as_str())
+            .collect();
+        assert_eq!(children, vec!["b2", "b3"]);
+        assert_eq!(state.visual_range(), Some((1, 2)));
+
+        handle_action(&mut state, &Action::Exit);
+        apply_undo(&mut state);
+        assert_eq!(top_level_uids(&state), vec!["b1", "b2", "b3"]);
+        assert!(state.days[0].blocks[0].children.is_empty());
+        apply_redo(&mut state);
+        assert_eq!(state.days[0].blocks[0].children.len(), 2);
+        assert_eq!(state.days[0].blocks[0].children[0].uid, "b2");
+    }
+
+    #[test]
+    fn indent_without_selection_moves_selected_block() {
+        let mut state = test_state();
+        state.selected_block = 1;
+        let write = apply_block_edit(&mut state, &Action::Indent).unwrap();
+        assert!(matches!(write, WriteAction::MoveBlock { .. }));
+        assert_eq!(top_level_uids(&state), vec!["b1", "b3"]);
+        assert_eq!(state.selected_block, 1);
+    }
+
+    #[test]
+    fn bulk_dedent_keeps_order() {
+        let mut state = test_state_with_children();
+        select_range(&mut state, 1, 2); // c1 and c2
+        apply_block_edit(&mut state, &Action::Unindent).unwrap();
+        assert_eq!(top_level_uids(&state), vec!["p1", "c1", "c2", "b2"]);
+        assert_eq!(state.visual_range(), Some((1, 2)));
+
+        apply_undo(&mut state);
+        assert_eq!(top_level_uids(&state), vec!["p1", "b2"]);
+        let children: Vec<&str> = state.days[0].blocks[0]
+            .children
+            .iter()
+            .map(|b| b.uid.as_str())
+            .collect();
+        assert_eq!(children, vec!["c1", "c2"]);
+    }
+
+    #[test]
+    fn move_selection_down_swaps_with_next_sibling() {
+        let mut state = test_state_four_blocks();
+        select_range(&mut state, 0, 1);
+        let write = apply_block_edit(&mut state, &Action::MoveBlockDown).unwrap();
+        match write {
+            WriteAction::MoveBlock { block, location } => {
+                assert_eq!(block.uid, "b3");
+                assert!(matches!(
+                    location.order,
+                    crate::api::types::OrderValue::Index(0)
+                ));
+            }
+            other => panic!("expected move, got {:?}", other),
+        }
+        assert_eq!(top_level_uids(&state), vec!["b3", "b1", "b2", "b4"]);
+        assert_eq!(state.visual_range(), Some((1, 2)));
+
+        apply_undo(&mut state);
+        assert_eq!(top_level_uids(&state), vec!["b1", "b2", "b3", "b4"]);
+    }
+
+    #[test]
+    fn move_selection_up_swaps_with_previous_sibling() {
+        let mut state = test_state_four_blocks();
+        select_range(&mut state, 2, 1);
+        apply_block_edit(&mut state, &Action::MoveBlockUp).unwrap();
+        assert_eq!(top_level_uids(&state), vec!["b2", "b3", "b1", "b4"]);
+        // Cursor stays at the top of the range
+        assert_eq!(state.selected_block, 0);
+        assert_eq!(state.visual_anchor, Some(1));
+
+        apply_undo(&mut state);
+        assert_eq!(top_level_uids(&state), vec!["b1", "b2", "b3", "b4"]);
+    }
+
+    #[test]
+    fn move_selection_at_top_of_first_day_does_nothing() {
+        let mut state = test_state();
+        select_range(&mut state, 0, 1);
+        assert!(apply_block_edit(&mut state, &Action::MoveBlockUp).is_none());
+        assert!(state.undo_stack.is_empty());
+    }
+
+    fn child_uids_of(state: &AppState, uid: &str) -> Vec<String> {
+        find_block_in_days(&state.days, uid)
+            .unwrap()
+            .children
+            .iter()
+            .map(|b| b.uid.clone())
+            .collect()
+    }
+
+    #[test]
+    fn move_first_child_up_leaves_parent_without_previous_sibling() {
+        let mut state = test_state_with_children();
+        state.selected_block = 1; // c1
+        let write = apply_block_edit(&mut state, &Action::MoveBlockUp).unwrap();
+        match write {
+            WriteAction::MoveBlock { block, location } => {
+                assert_eq!(block.uid, "c1");
+                assert_eq!(location.parent_uid, "02-21-2026");
+                assert!(matches!(
+                    location.order,
+                    crate::api::types::OrderValue::Index(0)
+                ));
+            }
+            other => panic!("expected move, got {:?}", other),
+        }
+        assert_eq!(top_level_uids(&state), vec!["c1", "p1", "b2"]);
+        assert_eq!(child_uids_of(&state, "p1"), vec!["c2"]);
+        assert_eq!(state.selected_block, 0);
+
+        apply_undo(&mut state);
+        assert_eq!(top_level_uids(&state), vec!["p1", "b2"]);
+        assert_eq!(child_uids_of(&state, "p1"), vec!["c1", "c2"]);
+    }
+
+    #[test]
+    fn move_last_child_down_into_next_sibling_of_parent() {
+        let mut state = test_state_with_children();
+        state.days[0].blocks[1]
+            .children
+            .push(make_block("d1", "Nephew", 0));
+        select_range(&mut state, 1, 2); // c1 and c2
+        let write = apply_block_edit(&mut state, &Action::MoveBlockDown).unwrap();
+        assert!(matches!(write, WriteAction::BatchActions { .. }));
+        assert!(child_uids_of(&state, "p1").is_empty());
+        assert_eq!(child_uids_of(&state, "b2"), vec!["c1", "c2", "d1"]);
+        // p1, b2, c1, c2, d1
+        assert_eq!(state.visual_range(), Some((2, 3)));
+
+        apply_undo(&mut state);
+        assert_eq!(child_uids_of(&state, "p1"), vec!["c1", "c2"]);
+        assert_eq!(child_uids_of(&state, "b2"), vec!["d1"]);
+        apply_redo(&mut state);
+        assert_eq!(child_uids_of(&state, "b2"), vec!["c1", "c2", "d1"]);
+    }
+
+    #[test]
+    fn move_into_collapsed_parent_expands_it() {
+        let mut state = test_state_with_children();
+        state.days[0].blocks[1]
+            .children
+            .push(make_block("d1", "Nephew", 0));
+        set_block_open(&mut state.days, "p1", false);
+        state.selected_block = 2; // p1 (collapsed), b2, d1
+        apply_block_edit(&mut state, &Action::MoveBlockUp).unwrap();
+        assert_eq!(child_uids_of(&state, "p1"), vec!["c1", "c2", "d1"]);
+        assert!(state.days[0].blocks[0].open);
+        assert_eq!(state.selected_block, 3);
+    }
+
+    #[test]
+    fn move_top_level_block_across_days() {
+        let mut state = test_state_two_days();
+        state.selected_block = 1; // a2, last block of the newer day
+        apply_block_edit(&mut state, &Action::MoveBlockDown).unwrap();
+        let older: Vec<&str> = state.days[1]
+            .blocks
+            .iter()
+            .map(|b| b.uid.as_str())
+            .collect();
+        assert_eq!(older, vec!["a2", "b1", "b2"]);
+        assert_eq!(state.selected_block, 1);
+
+        apply_block_edit(&mut state, &Action::MoveBlockUp).unwrap();
+        let newer: Vec<&str> = state.days[0]
+            .blocks
+            .iter()
+            .map(|b| b.uid.as_str())
+            .collect();
+        assert_eq!(newer, vec!["a1", "a2"]);
+        assert_eq!(state.days[1].blocks.len(), 2);
+
+        // Moving down off the oldest loaded day does nothing
+        state.selected_block = 3;
+        assert!(apply_block_edit(&mut state, &Action::MoveBlockDown).is_none());
+    }
+
+    #[test]
+    fn move_selection_across_levels_is_refused() {
+        let mut state = test_state_with_children();
+        select_range(&mut state, 2, 3); // c2 and b2
+        assert!(apply_block_edit(&mut state, &Action::MoveBlockUp).is_none());
+        assert!(state.status_message.is_some());
+    }
+
+    #[test]
+    fn bulk_toggle_todo_and_undo() {
+        let mut state = test_state();
+        select_range(&mut state, 0, 1);
+        apply_block_edit(&mut state, &Action::ToggleTodo).unwrap();
+        assert_eq!(state.days[0].blocks[0].string, "{{[[TODO]]}} Block one");
+        assert_eq!(state.days[0].blocks[1].string, "{{[[TODO]]}} Block two");
+        assert_eq!(state.days[0].blocks[2].string, "Block three");
+
+        apply_undo(&mut state);
+        assert_eq!(state.days[0].blocks[0].string, "Block one");
+        assert_eq!(state.days[0].blocks[1].string, "Block two");
+    }
+
+    #[test]
+    fn bulk_toggle_todo_updates_embeds() {
+        let mut state = test_state();
+        let target = EmbedTarget::Page("Notes".into());
+        state.days[0].blocks[2].string = "{{embed: [[Notes]]}}".into();
+        embeds::embed_loaded(
+            &mut state,
+            target.clone(),
+            Ok(vec![make_block("b1", "Block one", 0)]),
+        );
+        select_range(&mut state, 0, 0);
+        apply_block_edit(&mut state, &Action::ToggleTodo).unwrap();
+        assert_eq!(
+            state.embeds[&target].blocks[0].string,
+            "{{[[TODO]]}} Block one"
+        );
+    }
+
+    #[test]
+    fn copy_fills_register_and_clipboard() {
+        let mut state = test_state_with_children();
+        select_range(&mut state, 0, 3);
+        assert!(apply_block_edit(&mut state, &Action::Copy).is_none());
+        let uids: Vec<&str> = state.register.iter().map(|b| b.uid.as_str()).collect();
+        assert_eq!(uids, vec!["p1", "b2"]);
+        assert_eq!(
+            state.pending_clipboard.as_deref(),
+            Some("- Parent\n  - Child 1\n  - Child 2\n- Sibling\n")
+        );
+        assert!(state.visual_anchor.is_none());
+        assert!(state.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn collapse_in_visual_mode_collapses_selected_parents() {
+        let mut state = test_state_with_children();
+        select_range(&mut state, 0, 3);
+        handle_action(&mut state, &Action::Collapse);
+        assert!(!state.days[0].blocks[0].open);
+        assert!(state.visual_anchor.is_none());
+        assert_eq!(state.selected_block, 0);
+        assert_eq!(state.flat_block_count(), 2);
+    }
+
+    // --- Yank and paste tests ---
+
+    fn created_uids(action: &WriteAction) -> Vec<(String, String)> {
+        let actions = match action {
+            WriteAction::BatchActions { actions } => actions.iter().collect(),
+            other => vec![other],
+        };
+        actions
+            .into_iter()
+            .filter_map(|a| match a {
+                WriteAction::CreateBlock { location, block } => {
+                    Some((location.parent_uid.clone(), block.uid.clone().unwrap()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paste_below_inserts_copies_with_fresh_uids() {
+        let mut state = test_state();
+        apply_block_edit(&mut state, &Action::Copy);
+        state.selected_block = 1;
+        let write = apply_block_edit(&mut state, &Action::Paste).unwrap();
+        let uids = top_level_uids(&state);
+        assert_eq!(uids.len(), 4);
+        assert_eq!(&uids[..2], &["b1", "b2"]);
+        assert!(!["b1", "b2", "b3"].contains(&uids[2].as_str()));
+        assert_eq!(state.days[0].blocks[2].string, "Block one");
+        assert_eq!(state.selected_block, 2);
+        assert_eq!(
+            created_uids(&write),
+            vec![("02-21-2026".to_string(), uids[2].clone())]
+        );
+        assert_eq!(state.status_message.as_deref(), Some("Pasted 1 block"));
+    }
+
+    #[test]
+    fn paste_above_inserts_before_selection() {
+        let mut state = test_state();
+        state.selected_block = 2;
+        apply_block_edit(&mut state, &Action::Copy);
+        state.selected_block = 0;
+        apply_block_edit(&mut state, &Action::PasteAbove).unwrap();
+        assert_eq!(state.days[0].blocks[0].string, "Block three");
+        assert_eq!(state.days[0].blocks[1].uid, "b1");
+        assert_eq!(state.selected_block, 0);
+    }
+
+    #[test]
+    fn paste_twice_gives_distinct_uids() {
+        let mut state = test_state();
+        apply_block_edit(&mut state, &Action::Copy);
+        apply_block_edit(&mut state, &Action::Paste);
+        apply_block_edit(&mut state, &Action::Paste);
+        let uids: HashSet<String> = top_level_uids(&state).into_iter().collect();
+        assert_eq!(uids.len(), 5);
+    }
+
+    #[test]
+    fn paste_subtree_creates_parents_before_children() {
+        let mut state = test_state_with_children();
+        apply_block_edit(&mut state, &Action::Copy);
+        state.selected_block = 3;
+        let write = apply_block_edit(&mut state, &Action::Paste).unwrap();
+        let pasted = &state.days[0].blocks[2];
+        assert_eq!(pasted.string, "Parent");
+        assert_eq!(pasted.children.len(), 2);
+        assert_eq!(
+            created_uids(&write),
+            vec![
+                ("02-21-2026".to_string(), pasted.uid.clone()),
+                (pasted.uid.clone(), pasted.children[0].uid.clone()),
+                (pasted.uid.clone(), pasted.children[1].uid.clone()),
+            ]
+        );
+    }
+
+    #[test]
+    fn undo_paste_removes_all_pasted_blocks() {
+        let mut state = test_state();
+        select_range(&mut state, 0, 1);
+        apply_block_edit(&mut state, &Action::Copy);
+        state.selected_block = 2;
+        apply_block_edit(&mut state, &Action::Paste).unwrap();
+        assert_eq!(top_level_uids(&state).len(), 5);
+        assert_eq!(state.undo_stack.len(), 1);
+
+        let undo = apply_undo(&mut state).unwrap();
+        assert!(matches!(undo, WriteAction::BatchActions { .. }));
+        assert_eq!(top_level_uids(&state), vec!["b1", "b2", "b3"]);
+
+        apply_redo(&mut state).unwrap();
+        let strings: Vec<&str> = state.days[0]
+            .blocks
+            .iter()
+            .map(|b| b.string.as_str())
+            .collect();
+        assert_eq!(
+            strings,
+            vec![
+                "Block one",
+                "Block two",
+                "Block three",
+                "Block one",
+                "Block two"
+            ]
+        );
+    }
+
+    #[test]
+    fn paste_with_empty_register_reports() {
+        let mut state = test_state();
+        assert!(apply_block_edit(&mut state, &Action::Paste).is_none());
+        assert_eq!(state.status_message.as_deref(), Some("Nothing to paste"));
+        assert_eq!(top_level_uids(&state).len(), 3);
+    }
+
+    #[test]
+    fn copy_block_refs_puts_refs_on_clipboard() {
+        let mut state = test_state();
+        apply_block_edit(&mut state, &Action::CopyBlockRef);
+        assert_eq!(state.pending_clipboard.as_deref(), Some("((b1))"));
+
+        select_range(&mut state, 1, 2);
+        apply_block_edit(&mut state, &Action::CopyBlockRef);
+        assert_eq!(state.pending_clipboard.as_deref(), Some("((b2))\n((b3))"));
+        assert_eq!(state.status_message.as_deref(), Some("Copied 2 block refs"));
+        assert!(state.register.is_empty());
+    }
+
+    #[test]
+    fn copy_markdown_converts_roam_syntax() {
+        let mut state = test_state();
+        state.days[0].blocks[0].string = "{{[[TODO]]}} see ((b2)) __now__".into();
+        select_range(&mut state, 0, 0);
+        apply_block_edit(&mut state, &Action::CopyMarkdown);
+        assert_eq!(
+            state.pending_clipboard.as_deref(),
+            Some("- [ ] see Block two *now*\n")
+        );
+    }
+
+    #[test]
+    fn yank_sequences_in_normal_mode() {
+        let mut state = test_state();
+        let keybindings = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        let client = RoamClient::new("test-graph", "test-token");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        handle_normal_key(
+            &mut state,
+            &key_event(KeyCode::Char('y')),
+            &keybindings,
+            &client,
+            &tx,
+        );
+        assert_eq!(state.pending_keys, vec![key_event(KeyCode::Char('y'))]);
+        handle_normal_key(
+            &mut state,
+            &key_event(KeyCode::Char('r')),
+            &keybindings,
+            &client,
+            &tx,
+        );
+        assert!(state.pending_keys.is_empty());
+        assert_eq!(state.pending_clipboard.as_deref(), Some("((b1))"));
+
+        handle_normal_key(
+            &mut state,
+            &key_event(KeyCode::Char('y')),
+            &keybindings,
+            &client,
+            &tx,
+        );
+        handle_normal_key(
+            &mut state,
+            &key_event(KeyCode::Char('y')),
+            &keybindings,
+            &client,
+            &tx,
+        );
+        assert_eq!(state.register.len(), 1);
+    }
+
+    #[test]
+    fn undo_delete_of_parent_recreates_children_remotely() {
+        let mut state = test_state_with_children();
+        apply_block_edit(&mut state, &Action::DeleteBlock);
+        let undo = apply_undo(&mut state).unwrap();
+        assert_eq!(
+            created_uids(&undo),
+            vec![
+                ("02-21-2026".to_string(), "p1".to_string()),
+                ("p1".to_string(), "c1".to_string()),
+                ("p1".to_string(), "c2".to_string()),
+            ]
+        );
+    }
+
+    // --- Editor selection and vim normal mode tests ---
+
+    fn editing(text: &str, cursor: usize, vim: bool) -> AppState {
+        let mut state = test_state();
+        state.vim_editing = vim;
+        let mut buffer = EditBuffer::new(text);
+        buffer.cursor = cursor;
+        state.input_mode = InputMode::Insert {
+            buffer,
+            block_uid: "b1".into(),
+            original_text: "Block one".into(),
+            create_info: None,
+            vim_normal: None,
+        };
+        state
+    }
+
+    fn edit_buffer(state: &AppState) -> &EditBuffer {
+        match &state.input_mode {
+            InputMode::Insert { buffer, .. } => buffer,
+            InputMode::Normal => panic!("not editing"),
+        }
+    }
+
+    fn in_vim_normal(state: &AppState) -> bool {
+        matches!(
+            state.input_mode,
+            InputMode::Insert {
+                vim_normal: Some(_),
+                ..
+            }
+        )
+    }
+
+    fn type_keys(state: &mut AppState, keys: &str) {
+        for ch in keys.chars() {
+            handle_insert_key(state, &key_event(KeyCode::Char(ch)));
+        }
+    }
+
+    fn shift_key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::SHIFT)
+    }
+
+    #[test]
+    fn shift_arrows_select_and_typing_replaces() {
+        let mut state = editing("hello world", 0, false);
+        for _ in 0..5 {
+            handle_insert_key(&mut state, &shift_key(KeyCode::Right));
+        }
+        assert_eq!(edit_buffer(&state).selection(), Some((0, 5)));
+        type_keys(&mut state, "J");
+        assert_eq!(edit_buffer(&state).to_string(), "J world");
+    }
+
+    #[test]
+    fn plain_motion_clears_selection() {
+        let mut state = editing("hello", 5, false);
+        handle_insert_key(&mut state, &shift_key(KeyCode::Home));
+        assert_eq!(edit_buffer(&state).selection(), Some((0, 5)));
+        handle_insert_key(&mut state, &key_event(KeyCode::Right));
+        assert_eq!(edit_buffer(&state).selection(), None);
+        assert_eq!(edit_buffer(&state).cursor, 1);
+    }
+
+    #[test]
+    fn cut_copy_and_paste_in_editor() {
+        let mut state = editing("abc def", 7, false);
+        handle_insert_key(
+            &mut state,
+            &KeyEvent::new(KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+        );
+        handle_insert_key(&mut state, &ctrl_key(KeyCode::Char('c')));
+        assert_eq!(state.text_register, "def");
+        assert_eq!(state.pending_clipboard.as_deref(), Some("def"));
+        assert_eq!(edit_buffer(&state).to_string(), "abc def");
+
+        handle_insert_key(&mut state, &ctrl_key(KeyCode::Char('x')));
+        assert_eq!(edit_buffer(&state).to_string(), "abc ");
+        handle_insert_key(&mut state, &ctrl_key(KeyCode::Char('a')));
+        handle_insert_key(&mut state, &ctrl_key(KeyCode::Char('v')));
+        assert_eq!(edit_buffer(&state).to_string(), "defabc ");
+    }
+
+    #[test]
+    fn bracket_around_selection_makes_link() {
+        let mut state = editing("see page", 8, false);
+        for _ in 0..4 {
+            handle_insert_key(&mut state, &shift_key(KeyCode::Left));
+        }
+        type_keys(&mut state, "[[");
+        assert_eq!(edit_buffer(&state).to_string(), "see [[page]]");
+    }
+
+    #[test]
+    fn esc_in_vim_preset_enters_editor_normal_mode() {
+        let mut state = editing("hello", 5, true);
+        assert!(handle_insert_key(&mut state, &key_event(KeyCode::Esc)).is_none());
+        assert!(in_vim_normal(&state));
+        assert_eq!(edit_buffer(&state).cursor, 4);
+
+        // Second Esc saves
+        let write = handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        assert!(matches!(write, Some(WriteAction::UpdateBlock { .. })));
+        assert_eq!(state.input_mode, InputMode::Normal);
+        assert_eq!(state.days[0].blocks[0].string, "hello");
+    }
+
+    #[test]
+    fn editor_normal_motions() {
+        let mut state = editing("one two three", 13, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        type_keys(&mut state, "0");
+        assert_eq!(edit_buffer(&state).cursor, 0);
+        type_keys(&mut state, "w");
+        assert_eq!(edit_buffer(&state).cursor, 4);
+        type_keys(&mut state, "e");
+        assert_eq!(edit_buffer(&state).cursor, 6);
+        type_keys(&mut state, "b");
+        assert_eq!(edit_buffer(&state).cursor, 4);
+        type_keys(&mut state, "$");
+        assert_eq!(edit_buffer(&state).cursor, 12);
+        // Letters are commands, not text
+        assert_eq!(edit_buffer(&state).to_string(), "one two three");
+    }
+
+    #[test]
+    fn editor_normal_delete_word_and_put() {
+        let mut state = editing("one two three", 0, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        type_keys(&mut state, "dw");
+        assert_eq!(edit_buffer(&state).to_string(), "two three");
+        assert_eq!(state.text_register, "one ");
+        type_keys(&mut state, "$p");
+        assert_eq!(edit_buffer(&state).to_string(), "two threeone ");
+        assert!(in_vim_normal(&state));
+    }
+
+    #[test]
+    fn editor_normal_change_word_enters_insert() {
+        let mut state = editing("one two", 4, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        type_keys(&mut state, "0cw");
+        assert!(!in_vim_normal(&state));
+        type_keys(&mut state, "ONE");
+        assert_eq!(edit_buffer(&state).to_string(), "ONE two");
+    }
+
+    #[test]
+    fn editor_normal_text_objects() {
+        let mut state = editing("call(a, b) now", 6, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        type_keys(&mut state, "di(");
+        assert_eq!(edit_buffer(&state).to_string(), "call() now");
+
+        let mut state = editing("see [[Old Page]] here", 8, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        type_keys(&mut state, "ci[");
+        type_keys(&mut state, "New");
+        assert_eq!(edit_buffer(&state).to_string(), "see [[New]] here");
+
+        let mut state = editing("see [[Page]] here", 8, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        type_keys(&mut state, "ya[");
+        assert_eq!(state.text_register, "[[Page]]");
+        type_keys(&mut state, "da[");
+        assert_eq!(edit_buffer(&state).to_string(), "see  here");
+
+        let mut state = editing("one two three", 5, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        type_keys(&mut state, "diw");
+        assert_eq!(edit_buffer(&state).to_string(), "one  three");
+    }
+
+    #[test]
+    fn editor_normal_visual_selection() {
+        let mut state = editing("one two three", 4, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc)); // steps back onto the space
+        type_keys(&mut state, "lvll");
+        assert_eq!(edit_buffer(&state).selection_inclusive(), Some((4, 7)));
+        type_keys(&mut state, "d");
+        assert_eq!(edit_buffer(&state).to_string(), "one  three");
+        assert_eq!(edit_buffer(&state).anchor, None);
+    }
+
+    #[test]
+    fn editor_normal_pending_operator_cancelled_by_esc() {
+        let mut state = editing("one", 3, true);
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        type_keys(&mut state, "d");
+        handle_insert_key(&mut state, &key_event(KeyCode::Esc));
+        assert!(in_vim_normal(&state));
+        type_keys(&mut state, "x");
+        assert_eq!(edit_buffer(&state).to_string(), "on");
+    }
+
+    fn page_edit(state: &mut AppState) -> super::editor::ExternalEdit {
+        super::editor::request_external_edit(state, true);
+        state.pending_external_edit.take().unwrap()
+    }
+
+    fn top_level_texts(state: &AppState) -> Vec<String> {
+        state.days[0]
+            .blocks
+            .iter()
+            .map(|b| b.string.clone())
+            .collect()
+    }
+
+    #[test]
+    fn external_edit_block_writes_its_subtree() {
+        let mut state = test_state_with_children();
+        super::editor::request_external_edit(&mut state, false);
+        let edit = state.pending_external_edit.take().unwrap();
+        assert_eq!(edit.parent_uid, "02-21-2026");
+        assert_eq!(edit.start_order, 0);
+        assert_eq!(
+            edit.text,
+            "- Parent <!-- uid:p1 -->\n  - Child 1 <!-- uid:c1 -->\n  - Child 2 <!-- uid:c2 -->\n"
+        );
+    }
+
+    #[test]
+    fn external_edit_page_writes_every_block() {
+        let mut state = test_state();
+        state.selected_block = 2;
+        let edit = page_edit(&mut state);
+        assert_eq!(edit.blocks.len(), 3);
+        assert!(edit.text.starts_with("- Block one <!-- uid:b1 -->\n"));
+    }
+
+    #[test]
+    fn external_edit_unchanged_text_does_nothing() {
+        let mut state = test_state();
+        let edit = page_edit(&mut state);
+        let text = edit.text.clone();
+        assert!(super::editor::apply_external_edit(&mut state, &edit, &text).is_none());
+        assert!(state.undo_stack.is_empty());
+        assert_eq!(state.status_message.as_deref(), Some("No changes"));
+    }
+
+    #[test]
+    fn external_edit_updates_text_and_creates_blocks() {
+        let mut state = test_state();
+        let edit = page_edit(&mut state);
+        let text = edit
+            .text
+            .replace(
+                "Block two <!-- uid:b2 -->",
+                "Block 2 <!-- uid:b2 -->\n  with a second line",
+            )
+            .replace("- Block three", "- Inserted\n- Block three");
+        let action = super::editor::apply_external_edit(&mut state, &edit, &text).unwrap();
+        assert_eq!(
+            top_level_texts(&state),
+            vec![
+                "Block one",
+                "Block 2\nwith a second line",
+                "Inserted",
+                "Block three"
+            ]
+        );
+        assert_eq!(created_uids(&action).len(), 1);
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("Applied 2 changes from editor")
+        );
+    }
+
+    #[test]
+    fn external_edit_deletes_removed_bullets() {
+        let mut state = test_state();
+        let edit = page_edit(&mut state);
+        let text = edit.text.replace("- Block two <!-- uid:b2 -->\n", "");
+        let action = super::editor::apply_external_edit(&mut state, &edit, &text).unwrap();
+        assert!(matches!(action, WriteAction::DeleteBlock { ref block } if block.uid == "b2"));
+        assert_eq!(top_level_texts(&state), vec!["Block one", "Block three"]);
+    }
+
+    #[test]
+    fn external_edit_reorders_and_reindents_as_moves() {
+        let mut state = test_state();
+        let edit = page_edit(&mut state);
+        let text = "- Block three <!-- uid:b3 -->\n  - Block one <!-- uid:b1 -->\n- Block two <!-- uid:b2 -->\n";
+        let action = super::editor::apply_external_edit(&mut state, &edit, text).unwrap();
+        let WriteAction::BatchActions { actions } = &action else {
+            panic!("expected a batch");
+        };
+        assert!(actions
+            .iter()
+            .all(|a| matches!(a, WriteAction::MoveBlock { .. })));
+        assert_eq!(top_level_texts(&state), vec!["Block three", "Block two"]);
+        assert_eq!(child_uids_of(&state, "b3"), vec!["b1"]);
+    }
+
+    #[test]
+    fn external_edit_deleting_parent_keeps_moved_child() {
+        let mut state = test_state_with_children();
+        let edit = page_edit(&mut state);
+        let text = "- Child 1 <!-- uid:c1 -->\n- Sibling <!-- uid:b2 -->\n";
+        super::editor::apply_external_edit(&mut state, &edit, text).unwrap();
+        assert_eq!(top_level_texts(&state), vec!["Child 1", "Sibling"]);
+        assert!(find_block_in_days(&state.days, "p1").is_none());
+        assert!(find_block_in_days(&state.days, "c2").is_none());
+    }
+
+    #[test]
+    fn external_edit_duplicate_uid_becomes_new_block() {
+        let mut state = test_state();
+        let edit = page_edit(&mut state);
+        let text = format!("{}- Block one <!-- uid:b1 -->\n", edit.text);
+        let action = super::editor::apply_external_edit(&mut state, &edit, &text).unwrap();
+        assert_eq!(created_uids(&action).len(), 1);
+        assert_eq!(state.days[0].blocks.len(), 4);
+        assert_ne!(state.days[0].blocks[3].uid, "b1");
+    }
+
+    #[test]
+    fn external_edit_undoes_in_one_step() {
+        let mut state = test_state_with_children();
+        let edit = page_edit(&mut state);
+        let text = "- Sibling <!-- uid:b2 -->\n- Parent again <!-- uid:p1 -->\n  - New child\n";
+        super::editor::apply_external_edit(&mut state, &edit, text).unwrap();
+        assert_eq!(state.undo_stack.len(), 1);
+        apply_undo(&mut state);
+        assert_eq!(top_level_texts(&state), vec!["Parent", "Sibling"]);
+        assert_eq!(child_uids_of(&state, "p1"), vec!["c1", "c2"]);
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> crossterm::event::MouseEvent {
+        crossterm::event::MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    const CHECKBOX_BLOCK: &str = "{{[[TODO]]}} a {{[[DONE]]}} b";
+
+    fn click_at(column: u16, row: u16) -> crossterm::event::MouseEvent {
+        mouse(MouseEventKind::Down(MouseButton::Left), column, row)
+    }
+
+    /// Rows 1..=3 hold blocks 0..=2; block 1 has a page link, a checkbox and a block ref.
+    /// The checkbox is the `{{[[DONE]]}}` of [`CHECKBOX_BLOCK`].
+    fn test_hit_map() -> HitMap {
+        use crate::markdown::LinkTarget;
+        use crate::ui::main_area::RowHit;
+        let row = |block_index: usize, targets| RowHit {
+            block_index: Some(block_index),
+            targets,
+            ..RowHit::default()
+        };
+        HitMap {
+            area: ratatui::layout::Rect::new(0, 1, 40, 5),
+            scroll_offset: 2,
+            total_rows: 12,
+            rows: vec![
+                row(0, vec![]),
+                row(
+                    1,
+                    vec![
+                        (4..6, LinkTarget::Checkbox(15..27)),
+                        (10..14, LinkTarget::Page("Page".into())),
+                        (20..24, LinkTarget::Block("b3".into())),
+                        (30..34, LinkTarget::Block("far".into())),
+                    ],
+                ),
+                row(2, vec![]),
+            ],
+        }
+    }
+
+    #[test]
+    fn click_selects_block() {
+        let mut state = test_state();
+        state.visual_anchor = Some(0);
+        let now = Instant::now();
+        let outcome = mouse::handle_mouse(&mut state, &click_at(8, 3), &test_hit_map(), now);
+        assert!(outcome.is_none());
+        assert_eq!(state.selected_block, 2);
+        assert!(state.visual_anchor.is_none());
+        // Outside any block
+        assert!(mouse::handle_mouse(&mut state, &click_at(8, 5), &test_hit_map(), now).is_none());
+        assert_eq!(state.selected_block, 2);
+    }
+
+    #[test]
+    fn double_click_edits_block() {
+        let mut state = test_state();
+        let now = Instant::now();
+        let hit_map = test_hit_map();
+        mouse::handle_mouse(&mut state, &click_at(8, 1), &hit_map, now);
+        let outcome = mouse::handle_mouse(
+            &mut state,
+            &click_at(8, 1),
+            &hit_map,
+            now + Duration::from_millis(100),
+        );
+        assert!(matches!(
+            outcome,
+            Some(MouseOutcome::Action(Action::EditBlock))
+        ));
+
+        // Too slow for a double click
+        let later = now + Duration::from_secs(5);
+        mouse::handle_mouse(&mut state, &click_at(8, 1), &hit_map, later);
+        let outcome = mouse::handle_mouse(
+            &mut state,
+            &click_at(8, 1),
+            &hit_map,
+            later + Duration::from_secs(1),
+        );
+        assert!(outcome.is_none());
+    }
+
+    #[test]
+    fn click_on_links_and_checkbox() {
+        let mut state = test_state();
+        state.days[0].blocks[1].string = CHECKBOX_BLOCK.into();
+        let hit_map = test_hit_map();
+        let now = Instant::now();
+
+        // Only the clicked checkbox flips
+        let outcome = mouse::handle_mouse(&mut state, &click_at(5, 2), &hit_map, now);
+        let expected = "{{[[TODO]]}} a {{[[TODO]]}} b";
+        assert!(matches!(
+            outcome,
+            Some(MouseOutcome::Write(WriteAction::UpdateBlock { ref block }))
+                if block.uid == "b2" && block.string == expected
+        ));
+        assert_eq!(state.days[0].blocks[1].string, expected);
+        assert_eq!(state.selected_block, 1);
+        assert!(matches!(
+            state.undo_stack.last(),
+            Some(UndoEntry::TextEdit { block_uid, old_text })
+                if block_uid == "b2" && old_text == CHECKBOX_BLOCK
+        ));
+        mouse::handle_mouse(&mut state, &click_at(5, 2), &hit_map, now);
+        assert_eq!(state.days[0].blocks[1].string, CHECKBOX_BLOCK);
+
+        // A loaded block ref selects the block
+        let outcome = mouse::handle_mouse(&mut state, &click_at(21, 2), &hit_map, now);
+        assert!(outcome.is_none());
+        assert_eq!(state.selected_block, 2);
+
+        // One that isn't loaded looks up its page
+        let outcome = mouse::handle_mouse(&mut state, &click_at(31, 2), &hit_map, now);
+        assert!(matches!(
+            outcome,
+            Some(MouseOutcome::Load(LoadRequest::BlockPage(ref uid))) if uid == "far"
+        ));
+
+        let outcome = mouse::handle_mouse(&mut state, &click_at(11, 2), &hit_map, now);
+        assert!(matches!(
+            outcome,
+            Some(MouseOutcome::Load(LoadRequest::Page(ref title))) if title == "Page"
+        ));
+        assert_eq!(
+            state.view_mode,
+            ViewMode::Page {
+                title: "Page".into()
+            }
+        );
+    }
+
+    #[test]
+    fn click_on_embedded_checkbox_toggles_embedded_block() {
+        let mut state = test_state();
+        let target = EmbedTarget::Page("Notes".into());
+        state.days[0].blocks[0].string = "{{embed: [[Notes]]}}".into();
+        embeds::embed_loaded(
+            &mut state,
+            target.clone(),
+            Ok(vec![make_block("n1", CHECKBOX_BLOCK, 0)]),
+        );
+
+        // Row 2 is the embedded line, block 1
+        let outcome =
+            mouse::handle_mouse(&mut state, &click_at(5, 2), &test_hit_map(), Instant::now());
+        let expected = "{{[[TODO]]}} a {{[[TODO]]}} b";
+        assert!(matches!(
+            outcome,
+            Some(MouseOutcome::Write(WriteAction::UpdateBlock { ref block }))
+                if block.uid == "n1" && block.string == expected
+        ));
+        assert_eq!(state.embeds[&target].blocks[0].string, expected);
+        assert_eq!(state.days[0].blocks[0].string, "{{embed: [[Notes]]}}");
+
+        apply_undo(&mut state);
+        assert_eq!(state.embeds[&target].blocks[0].string, CHECKBOX_BLOCK);
+    }
+
+    #[test]
+    fn click_elsewhere_while_editing_saves_block() {
+        let mut state = test_state();
+        handle_action(&mut state, &Action::EditBlock);
+        handle_insert_key(&mut state, &key_event(KeyCode::Char('!')));
+        let hit_map = test_hit_map();
+        let now = Instant::now();
+
+        // Clicks inside the edited block are ignored
+        assert!(mouse::handle_mouse(&mut state, &click_at(8, 1), &hit_map, now).is_none());
+        assert!(state.input_mode != InputMode::Normal);
+
+        let outcome = mouse::handle_mouse(&mut state, &click_at(8, 2), &hit_map, now);
+        assert!(matches!(
+            outcome,
+            Some(MouseOutcome::Write(WriteAction::UpdateBlock { .. }))
+        ));
+        assert_eq!(state.input_mode, InputMode::Normal);
+        assert_eq!(state.selected_block, 1);
+    }
+
+    #[test]
+    fn wheel_scrolls_from_rendered_offset() {
+        let mut state = test_state();
+        let hit_map = test_hit_map();
+        let now = Instant::now();
+        mouse::handle_mouse(
+            &mut state,
+            &mouse(MouseEventKind::ScrollUp, 0, 0),
+            &hit_map,
+            now,
+        );
+        assert_eq!(state.scroll, Some(0));
+        for _ in 0..5 {
+            mouse::handle_mouse(
+                &mut state,
+                &mouse(MouseEventKind::ScrollDown, 0, 0),
+                &hit_map,
+                now,
+            );
+        }
+        // 12 rows in a 5-row viewport
+        assert_eq!(state.scroll, Some(7));
+        assert_eq!(state.selected_block, 0);
+    }
+
+    // --- Command palette tests ---
+
+    fn palette_state() -> AppState {
+        let mut state = test_state();
+        state.key_labels = KeybindingMap::from_preset("vim", &HashMap::new())
+            .unwrap()
+            .key_labels();
+        handle_action(&mut state, &Action::CommandPalette);
+        state
+    }
+
+    fn type_palette(state: &mut AppState, text: &str) {
+        for c in text.chars() {
+            handle_command_palette_key(state, &key_event(KeyCode::Char(c)));
+        }
+    }
+
+    fn palette_labels(state: &AppState) -> Vec<String> {
+        state
+            .command_palette
+            .as_ref()
+            .unwrap()
+            .filtered
+            .iter()
+            .map(|e| e.label.clone())
+            .collect()
+    }
+
+    #[test]
+    fn command_palette_lists_actions_with_bound_keys() {
+        let state = palette_state();
+        let palette = state.command_palette.as_ref().unwrap();
+        let entry = |label: &str| palette.filtered.iter().find(|e| e.label == label).unwrap();
+        assert_eq!(entry("Quick switcher").keys, "Ctrl+k, Ctrl+t, Space f");
+        assert_eq!(entry("Delete block").keys, "d d");
+        assert_eq!(entry("Go to today").keys, "Shift+G");
+        assert_eq!(entry("Add TODO checkbox").keys, "/todo");
+        assert!(palette.filtered.iter().any(|e| e.label == "Go to date"));
+        // Every action but the palette itself is listed
+        let actions = palette
+            .filtered
+            .iter()
+            .filter(|e| matches!(e.command, palette::PaletteCommand::Action(_)))
+            .count();
+        assert_eq!(actions, Action::ALL.len() - 1);
+        assert!(state.has_popup());
+    }
+
+    #[test]
+    fn command_palette_fuzzy_filters_and_runs_action() {
+        let mut state = palette_state();
+        type_palette(&mut state, "renpg");
+        assert_eq!(palette_labels(&state)[0], "Rename page");
+
+        let outcome = handle_command_palette_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(matches!(
+            outcome,
+            Some(PaletteOutcome::Action(Action::RenamePage))
+        ));
+        assert!(state.command_palette.is_none());
+    }
+
+    #[test]
+    fn command_palette_backspace_and_esc_close() {
+        let mut state = palette_state();
+        type_palette(&mut state, "x");
+        handle_command_palette_key(&mut state, &key_event(KeyCode::Backspace));
+        assert!(state.command_palette.is_some());
+        handle_command_palette_key(&mut state, &key_event(KeyCode::Backspace));
+        assert!(state.command_palette.is_none());
+
+        handle_action(&mut state, &Action::CommandPalette);
+        handle_command_palette_key(&mut state, &key_event(KeyCode::Esc));
+        assert!(state.command_palette.is_none());
+    }
+
+    #[test]
+    fn command_palette_slash_command_edits_selected_block() {
+        let mut state = palette_state();
+        type_palette(&mut state, "Add TODO");
+        handle_command_palette_key(&mut state, &key_event(KeyCode::Enter));
+        assert_eq!(insert_buffer_text(&state), "{{[[TODO]]}} Block one");
+        assert!(state.command_palette.is_none());
+    }
+
+    #[test]
+    fn command_palette_go_to_date_loads_day() {
+        let mut state = palette_state();
+        type_palette(&mut state, "Go to date");
+        handle_command_palette_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(state.command_palette.as_ref().unwrap().date_prompt);
+
+        type_palette(&mut state, "bogus");
+        assert!(handle_command_palette_key(&mut state, &key_event(KeyCode::Enter)).is_none());
+        assert!(state.command_palette.as_ref().unwrap().error.is_some());
+
+        for _ in 0..5 {
+            handle_command_palette_key(&mut state, &key_event(KeyCode::Backspace));
+        }
+        type_palette(&mut state, "2026-01-05");
+        let outcome = handle_command_palette_key(&mut state, &key_event(KeyCode::Enter));
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        assert!(matches!(
+            outcome,
+            Some(PaletteOutcome::Load(LoadRequest::DailyNote(d))) if d == date
+        ));
+        assert!(state.command_palette.is_none());
+        assert!(state.days.is_empty());
+        assert!(state.loading);
+    }
+
+    #[test]
+    fn command_palette_toggles_theme_and_exports_page() {
+        let mut state = palette_state();
+        type_palette(&mut state, "toggle theme");
+        handle_command_palette_key(&mut state, &key_event(KeyCode::Enter));
+        assert!(state.light_theme);
+
+        handle_action(&mut state, &Action::CommandPalette);
+        type_palette(&mut state, "export");
+        handle_command_palette_key(&mut state, &key_event(KeyCode::Enter));
+        let (path, text) = state.pending_export.take().unwrap();
+        assert_eq!(path, std::path::PathBuf::from("Test 2026-2-21.md"));
+        assert!(text.starts_with("# Test 2026-2-21\n\n- Block one\n"));
+    }
 }
@@ -0,0 +1,147 @@
+//! Mouse input: clicking blocks, links and checkboxes, and wheel scrolling.
+//!
+//! Only reaches the app when `ui.mouse` is enabled. Clicks are mapped back to
+//! blocks through the [`HitMap`] recorded by the last render.
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+
+use crate::api::types::{BlockUpdate, WriteAction};
+use crate::keys::preset::Action;
+use crate::markdown::LinkTarget;
+use crate::markup::{self, NodeKind};
+use crate::ui::main_area::HitMap;
+
+use super::blocks::{find_block_index_by_uid, row_at_index, update_block_text_in_days, OutlineRow};
+use super::embeds::update_embedded_text;
+use super::input::finalize_insert;
+use super::nav::navigate_to_page;
+use super::state::{AppState, InputMode, LoadRequest, UndoEntry};
+use super::visual::push_undo;
+
+/// Rows moved per wheel notch.
+const SCROLL_ROWS: usize = 3;
+/// Longest gap between the clicks of a double click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// What the main loop should do after a mouse event.
+#[derive(Debug)]
+pub(super) enum MouseOutcome {
+    Action(Action),
+    Load(LoadRequest),
+    Write(WriteAction),
+}
+
+pub(super) fn handle_mouse(
+    state: &mut AppState,
+    event: &MouseEvent,
+    hit_map: &HitMap,
+    now: Instant,
+) -> Option<MouseOutcome> {
+    match event.kind {
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+            let current = state.scroll.unwrap_or(hit_map.scroll_offset);
+            let scroll = if event.kind == MouseEventKind::ScrollUp {
+                current.saturating_sub(SCROLL_ROWS)
+            } else {
+                (current + SCROLL_ROWS).min(hit_map.max_scroll())
+            };
+            state.scroll = Some(scroll);
+            None
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            let (block, target) = hit_map.hit(event.column, event.row)?;
+            click(state, block, target.cloned(), now)
+        }
+        _ => None,
+    }
+}
+
+fn click(
+    state: &mut AppState,
+    block: usize,
+    target: Option<LinkTarget>,
+    now: Instant,
+) -> Option<MouseOutcome> {
+    if state.input_mode != InputMode::Normal {
+        // Clicking another block saves the one being edited
+        if block == state.selected_block {
+            return None;
+        }
+        let write_action = finalize_insert(state);
+        select(state, block);
+        state.last_click = None;
+        return write_action.map(MouseOutcome::Write);
+    }
+
+    let double = state
+        .last_click
+        .is_some_and(|(at, clicked)| clicked == block && now.duration_since(at) < DOUBLE_CLICK);
+    state.last_click = (!double).then_some((now, block));
+    state.visual_anchor = None;
+    select(state, block);
+
+    match target {
+        Some(LinkTarget::Page(title)) => Some(MouseOutcome::Load(navigate_to_page(state, title))),
+        Some(LinkTarget::Block(uid)) => {
+            match find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, &uid) {
+                Some(idx) => {
+                    select(state, idx);
+                    None
+                }
+                None => Some(MouseOutcome::Load(LoadRequest::BlockPage(uid))),
+            }
+        }
+        Some(LinkTarget::Checkbox(span)) => {
+            toggle_checkbox(state, block, span).map(MouseOutcome::Write)
+        }
+        None if double => Some(MouseOutcome::Action(Action::EditBlock)),
+        None => None,
+    }
+}
+
+/// Swap the clicked `{{TODO}}` / `{{DONE}}` marker for the other one, leaving
+/// any other checkboxes in the block alone.
+fn toggle_checkbox(state: &mut AppState, block: usize, span: Range<usize>) -> Option<WriteAction> {
+    // Embedded lines toggle the embedded block in place
+    let (uid, old_text) = match row_at_index(&state.days, &state.linked_refs, &state.embeds, block)?
+    {
+        OutlineRow::Block { block, .. } | OutlineRow::Embedded(block) => {
+            (block.uid.clone(), block.string.clone())
+        }
+    };
+    let marker = markup::parse(old_text.get(span.clone())?);
+    let marker = match marker.as_slice() {
+        [node] if node.kind == (NodeKind::Todo { done: false }) => "{{[[DONE]]}}",
+        [node] if node.kind == (NodeKind::Todo { done: true }) => "{{[[TODO]]}}",
+        _ => return None,
+    };
+    let text = format!(
+        "{}{}{}",
+        &old_text[..span.start],
+        marker,
+        &old_text[span.end..]
+    );
+    update_block_text_in_days(&mut state.days, &uid, &text);
+    update_embedded_text(state, &uid, &text);
+    push_undo(
+        state,
+        vec![UndoEntry::TextEdit {
+            block_uid: uid.clone(),
+            old_text,
+        }],
+        block,
+    );
+    Some(WriteAction::UpdateBlock {
+        block: BlockUpdate { uid, string: text },
+    })
+}
+
+fn select(state: &mut AppState, block: usize) {
+    if state.selected_block != block {
+        state.selected_block = block;
+        state.cursor_col = 0;
+    }
+}
@@ -1,3 +1,5 @@
+use chrono::NaiveDate;
+
 use super::state::{AppState, LoadRequest, ViewMode, ViewSnapshot};
 
 /// Save current view as a snapshot and prepare for navigating to a new page.
@@ -16,6 +18,20 @@ pub(super) fn navigate_to_page(state: &mut AppState, title: String) -> LoadReque
     LoadRequest::Page(title)
 }
 
+/// Save current view and switch to the daily notes starting at `date`.
+/// Returns a LoadRequest::DailyNote for it.
+pub(super) fn go_to_date(state: &mut AppState, date: NaiveDate) -> LoadRequest {
+    push_nav_snapshot(state);
+    state.view_mode = ViewMode::DailyNotes;
+    state.days.clear();
+    state.selected_block = 0;
+    state.cursor_col = 0;
+    state.loading = true;
+    state.linked_refs.clear();
+    state.status_message = Some(format!("Loading {}...", date.format("%b %d, %Y")));
+    LoadRequest::DailyNote(date)
+}
+
 /// Push current state onto navigation history, truncating any forward history.
 pub(super) fn push_nav_snapshot(state: &mut AppState) {
     let snapshot = ViewSnapshot {
@@ -0,0 +1,245 @@
+//! Command palette: fuzzy-find any action, slash command or app command and run it.
+//!
+//! Each entry shows the keys bound to it in the active keymap, so the palette
+//! doubles as a way to learn the bindings.
+
+use std::path::PathBuf;
+
+use chrono::Local;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::api::queries::parse_natural_date;
+use crate::fuzzy;
+use crate::keys::mode::PopupAction;
+use crate::keys::preset::Action;
+use crate::markdown;
+
+use super::actions::handle_action;
+use super::calendar::open_calendar;
+use super::editor::selected_day;
+use super::nav;
+use super::slash::{self, SlashAction, SlashCommand};
+use super::state::{AppState, InputMode, LoadRequest};
+use super::yank::block_text_map;
+
+/// What a palette entry runs when chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteCommand {
+    Action(Action),
+    /// Edit the selected block and apply the slash command at the end of its text.
+    Slash(SlashCommand),
+    ExportPage,
+    ToggleTheme,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteEntry {
+    pub command: PaletteCommand,
+    pub label: String,
+    /// Bound keys, or the slash command typed in the editor.
+    pub keys: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandPaletteState {
+    pub query: String,
+    pub filtered: Vec<PaletteEntry>,
+    pub selected: usize,
+    /// "Go to date" was chosen; the query is now the date being typed.
+    pub date_prompt: bool,
+    pub error: Option<String>,
+}
+
+/// What the main loop should do after a palette key.
+#[derive(Debug)]
+pub(super) enum PaletteOutcome {
+    Action(Action),
+    Load(LoadRequest),
+}
+
+pub(super) fn open_command_palette(state: &mut AppState) {
+    state.command_palette = Some(CommandPaletteState {
+        query: String::new(),
+        filtered: filter_entries(state, ""),
+        selected: 0,
+        date_prompt: false,
+        error: None,
+    });
+}
+
+fn all_entries(state: &AppState) -> Vec<PaletteEntry> {
+    let commands = [
+        (PaletteCommand::ExportPage, "Export page as Markdown"),
+        (PaletteCommand::ToggleTheme, "Toggle light/dark theme"),
+    ];
+    let commands = commands.into_iter().map(|(command, label)| PaletteEntry {
+        command,
+        label: label.to_string(),
+        keys: String::new(),
+    });
+    let actions = Action::ALL
+        .iter()
+        .filter(|a| **a != Action::CommandPalette)
+        .map(|action| PaletteEntry {
+            command: PaletteCommand::Action(action.clone()),
+            label: action.label().to_string(),
+            keys: state.key_labels.get(action).cloned().unwrap_or_default(),
+        });
+    let slash_commands = slash::all_commands().into_iter().map(|cmd| PaletteEntry {
+        label: cmd.description.to_string(),
+        keys: format!("/{}", cmd.name),
+        command: PaletteCommand::Slash(cmd),
+    });
+    commands.chain(actions).chain(slash_commands).collect()
+}
+
+fn filter_entries(state: &AppState, query: &str) -> Vec<PaletteEntry> {
+    fuzzy::rank(all_entries(state), query, |e| &e.label, |_| 0, usize::MAX)
+}
+
+pub(super) fn handle_command_palette_key(
+    state: &mut AppState,
+    key: &KeyEvent,
+) -> Option<PaletteOutcome> {
+    let action = state.popup_keys.resolve(key);
+    let palette = state.command_palette.as_mut()?;
+    match action {
+        Some(PopupAction::Close) => {
+            state.command_palette = None;
+        }
+        Some(PopupAction::SelectPrev) => {
+            palette.selected = palette.selected.saturating_sub(1);
+        }
+        Some(PopupAction::SelectNext) if palette.selected + 1 < palette.filtered.len() => {
+            palette.selected += 1;
+        }
+        Some(PopupAction::Accept) => {
+            if palette.date_prompt {
+                return go_to_date(state);
+            }
+            let command = palette.filtered.get(palette.selected)?.command.clone();
+            state.command_palette = None;
+            return run_command(state, command);
+        }
+        Some(_) => {}
+        None => match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Backspace) => {
+                if palette.query.pop().is_none() {
+                    state.command_palette = None;
+                } else {
+                    refilter(state);
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char(c)) | (KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                palette.query.push(c);
+                refilter(state);
+            }
+            _ => {}
+        },
+    }
+    None
+}
+
+fn refilter(state: &mut AppState) {
+    let Some(palette) = &state.command_palette else {
+        return;
+    };
+    if palette.date_prompt {
+        if let Some(palette) = &mut state.command_palette {
+            palette.error = None;
+        }
+        return;
+    }
+    let filtered = filter_entries(state, &palette.query);
+    if let Some(palette) = &mut state.command_palette {
+        palette.filtered = filtered;
+        palette.selected = 0;
+    }
+}
+
+fn run_command(state: &mut AppState, command: PaletteCommand) -> Option<PaletteOutcome> {
+    match command {
+        // The prompt replaces the palette rather than closing it
+        PaletteCommand::Action(Action::GoToDate) => open_date_prompt(state),
+        PaletteCommand::Action(action) => return Some(PaletteOutcome::Action(action)),
+        PaletteCommand::Slash(cmd) => apply_slash_command(state, &cmd),
+        PaletteCommand::ExportPage => export_page(state),
+        PaletteCommand::ToggleTheme => {
+            state.light_theme = !state.light_theme;
+            state.status_message = Some(
+                if state.light_theme {
+                    "Light theme"
+                } else {
+                    "Dark theme"
+                }
+                .into(),
+            );
+        }
+    }
+    None
+}
+
+/// Open the palette straight into the "Go to date" prompt.
+pub(super) fn open_date_prompt(state: &mut AppState) {
+    state.command_palette = Some(CommandPaletteState {
+        query: String::new(),
+        filtered: Vec::new(),
+        selected: 0,
+        date_prompt: true,
+        error: None,
+    });
+}
+
+/// Open the selected block for editing and run the slash command as if it
+/// were typed at the end of the text; the block stays in insert mode.
+fn apply_slash_command(state: &mut AppState, cmd: &SlashCommand) {
+    handle_action(state, &Action::EditBlock);
+    match &mut state.input_mode {
+        InputMode::Insert { buffer, .. } => {
+            let slash_pos = buffer.cursor;
+            buffer.insert_char('/');
+            slash::execute::execute(&cmd.action, buffer, slash_pos, 0);
+            if cmd.action == SlashAction::PickDate {
+                open_calendar(state, Local::now().date_naive(), true);
+            }
+        }
+        InputMode::Normal => {
+            state.status_message = Some("Select a block to edit".into());
+        }
+    }
+}
+
+/// Queue the page or day the selection is on to be written as `<title>.md`.
+fn export_page(state: &mut AppState) {
+    let Some(day) = selected_day(state) else {
+        state.status_message = Some("Nothing to export".into());
+        return;
+    };
+    let text = format!(
+        "# {}\n\n{}",
+        day.title,
+        markdown::export_markdown(&day.blocks, &block_text_map(state))
+    );
+    let file_name: String = day
+        .title
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':') {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    state.pending_export = Some((PathBuf::from(format!("{}.md", file_name)), text));
+}
+
+fn go_to_date(state: &mut AppState) -> Option<PaletteOutcome> {
+    let palette = state.command_palette.as_mut()?;
+    let Some(date) = parse_natural_date(&palette.query, Local::now().date_naive()) else {
+        palette.error = Some(format!("Unrecognized date: {}", palette.query));
+        return None;
+    };
+    state.command_palette = None;
+    Some(PaletteOutcome::Load(nav::go_to_date(state, date)))
+}
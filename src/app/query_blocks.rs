@@ -0,0 +1,83 @@
+use crate::api::types::SearchHit;
+use crate::roam_query;
+
+use super::blocks::for_each_day_block;
+use super::state::{AppState, QueryResultsState};
+
+/// `(uid, text)` of query blocks whose results are missing or were fetched
+/// for different text. With `refresh`, every query block is returned.
+pub(super) fn collect_stale_queries(state: &AppState, refresh: bool) -> Vec<(String, String)> {
+    let mut stale = Vec::new();
+    for_each_day_block(&state.days, &mut |block| {
+        if roam_query::find_query(&block.string).is_none() {
+            return;
+        }
+        let current = state.query_results.get(&block.uid);
+        let changed = current.is_none_or(|qr| qr.text != block.string);
+        let idle = current.is_none_or(|qr| !qr.loading);
+        if changed || (refresh && idle) {
+            stale.push((block.uid.clone(), block.string.clone()));
+        }
+    });
+    stale
+}
+
+/// Record that `text` is being queried for `uid`. Returns the Datalog to
+/// run, or `None` when the query doesn't parse (the error is kept instead).
+pub(super) fn start_query(state: &mut AppState, uid: &str, text: &str) -> Option<String> {
+    let entry = state.query_results.entry(uid.to_string()).or_default();
+    entry.text = text.to_string();
+    match roam_query::find_query(text)? {
+        Ok(query) => {
+            entry.loading = true;
+            Some(query.expr.to_datalog())
+        }
+        Err(e) => {
+            entry.loading = false;
+            entry.results.clear();
+            entry.error = Some(e.to_string());
+            None
+        }
+    }
+}
+
+/// Store results of a query, unless the block changed since it was sent.
+pub(super) fn query_results_loaded(
+    state: &mut AppState,
+    uid: &str,
+    text: &str,
+    result: Result<Vec<SearchHit>, String>,
+) {
+    let Some(entry) = state.query_results.get_mut(uid) else {
+        return;
+    };
+    if entry.text != text {
+        return;
+    }
+    entry.loading = false;
+    match result {
+        Ok(mut hits) => {
+            // The query block references its own pages; it isn't a result
+            hits.retain(|hit| hit.uid != uid);
+            hits.sort_by(|a, b| a.page_title.cmp(&b.page_title));
+            entry.results = hits;
+            entry.error = None;
+        }
+        Err(e) => entry.error = Some(e),
+    }
+}
+
+/// Show or hide the results under a query block. Returns false for other blocks.
+pub(super) fn toggle_query_results(state: &mut AppState, uid: &str, text: &str) -> bool {
+    match state.query_results.get_mut(uid) {
+        Some(QueryResultsState {
+            text: queried,
+            collapsed,
+            ..
+        }) if queried == text => {
+            *collapsed = !*collapsed;
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::{HashMap, HashSet};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::api::types::{Block, BlockUpdate, PageUpdate, WriteAction};
+use crate::edit_buffer::EditBuffer;
+use crate::keys::mode::PopupAction;
+use crate::markdown;
+
+use super::blocks::update_block_text_in_days;
+use super::state::{AppState, LoadRequest, PageRenameEdit, RenamePageState, UndoEntry, ViewMode};
+
+/// Open the rename prompt for the current page and re-query its linked
+/// references, which are the blocks the rename has to rewrite.
+pub(super) fn open_rename_page(state: &mut AppState) -> Option<LoadRequest> {
+    let ViewMode::Page { title } = &state.view_mode else {
+        state.status_message = Some("Open a page to rename it".into());
+        return None;
+    };
+    let title = title.clone();
+    let page_uid = state
+        .days
+        .first()
+        .map(|d| d.uid.clone())
+        .unwrap_or_default();
+    if page_uid.is_empty() {
+        state.status_message = Some(format!("Page \"{}\" doesn't exist yet", title));
+        return None;
+    }
+    state.rename_page = Some(RenamePageState {
+        page_uid,
+        old_title: title.clone(),
+        input: EditBuffer::new(&title),
+        preview: None,
+        refs_loaded: false,
+        waiting_for_refs: false,
+        selected: 0,
+        error: None,
+    });
+    Some(LoadRequest::LinkedRefs(title))
+}
+
+/// Called when linked refs for `page_title` arrive; completes a pending preview.
+pub(super) fn rename_refs_loaded(state: &mut AppState, page_title: &str) {
+    let Some(rename) = &state.rename_page else {
+        return;
+    };
+    if rename.old_title != page_title {
+        return;
+    }
+    let waiting = rename.waiting_for_refs;
+    if let Some(rename) = &mut state.rename_page {
+        rename.refs_loaded = true;
+        rename.waiting_for_refs = false;
+    }
+    if waiting {
+        show_rename_preview(state);
+    }
+}
+
+/// Handle a key in the rename popup. Returns the batched write once the preview is confirmed.
+pub(super) fn handle_rename_page_key(state: &mut AppState, key: &KeyEvent) -> Option<WriteAction> {
+    let action = state.popup_keys.resolve(key);
+    let rename = state.rename_page.as_mut()?;
+
+    if let Some(preview) = &rename.preview {
+        match (action, key.modifiers, key.code) {
+            (Some(PopupAction::Close), ..) => {
+                rename.preview = None;
+                rename.selected = 0;
+            }
+            (Some(PopupAction::SelectPrev), ..)
+            | (None, KeyModifiers::NONE, KeyCode::Char('k')) => {
+                rename.selected = rename.selected.saturating_sub(1);
+            }
+            (Some(PopupAction::SelectNext), ..)
+            | (None, KeyModifiers::NONE, KeyCode::Char('j'))
+                if rename.selected + 1 < preview.len() =>
+            {
+                rename.selected += 1;
+            }
+            (Some(PopupAction::Accept), ..) | (None, KeyModifiers::NONE, KeyCode::Char('y')) => {
+                return confirm_rename(state);
+            }
+            _ => {}
+        }
+        return None;
+    }
+
+    let buffer = &mut rename.input;
+    match (action, key.modifiers, key.code) {
+        (Some(PopupAction::Close), ..) => {
+            state.rename_page = None;
+        }
+        (Some(PopupAction::Accept), ..) => {
+            submit_new_title(state);
+        }
+        (Some(_), ..) => {}
+        (None, KeyModifiers::NONE, KeyCode::Char(ch))
+        | (None, KeyModifiers::SHIFT, KeyCode::Char(ch)) => {
+            buffer.insert_char(ch);
+            rename.error = None;
+        }
+        (None, KeyModifiers::NONE, KeyCode::Backspace) => {
+            buffer.delete_back();
+            rename.error = None;
+        }
+        (None, KeyModifiers::NONE, KeyCode::Delete) => buffer.delete_forward(),
+        (None, KeyModifiers::NONE, KeyCode::Left) => buffer.move_left(),
+        (None, KeyModifiers::NONE, KeyCode::Right) => buffer.move_right(),
+        (None, KeyModifiers::NONE, KeyCode::Home)
+        | (None, KeyModifiers::CONTROL, KeyCode::Char('a')) => buffer.move_home(),
+        (None, KeyModifiers::NONE, KeyCode::End)
+        | (None, KeyModifiers::CONTROL, KeyCode::Char('e')) => buffer.move_end(),
+        _ => {}
+    }
+    None
+}
+
+/// Called on an API error; a rename waiting on its linked refs stops waiting,
+/// since the failed fetch won't answer.
+pub(super) fn rename_refs_failed(state: &mut AppState) {
+    if let Some(rename) = state.rename_page.as_mut().filter(|r| r.waiting_for_refs) {
+        rename.waiting_for_refs = false;
+        rename.error = Some("Couldn't load linked references; reopen to retry".into());
+    }
+}
+
+fn submit_new_title(state: &mut AppState) {
+    let Some(rename) = &mut state.rename_page else {
+        return;
+    };
+    let new_title = rename.input.to_string().trim().to_string();
+    if new_title.is_empty() {
+        rename.error = Some("Title can't be empty".into());
+        return;
+    }
+    if new_title == rename.old_title {
+        state.rename_page = None;
+        return;
+    }
+    if state
+        .page_title_cache
+        .iter()
+        .any(|(title, _)| *title == new_title)
+    {
+        rename.error = Some(format!("A page named \"{}\" already exists", new_title));
+        return;
+    }
+    if rename.refs_loaded {
+        show_rename_preview(state);
+    } else {
+        rename.waiting_for_refs = true;
+    }
+}
+
+fn show_rename_preview(state: &mut AppState) {
+    let Some(rename) = &state.rename_page else {
+        return;
+    };
+    let new_title = rename.input.to_string().trim().to_string();
+    let edits = rename_edits(state, &rename.old_title, &new_title);
+    if let Some(rename) = &mut state.rename_page {
+        rename.preview = Some(edits);
+        rename.selected = 0;
+    }
+}
+
+/// Every loaded or linked block whose text changes when `old` is renamed to `new`.
+pub(super) fn rename_edits(state: &AppState, old: &str, new: &str) -> Vec<PageRenameEdit> {
+    let mut edits = Vec::new();
+    let mut seen = HashSet::new();
+    if let Some(lr) = state.linked_refs.get(old) {
+        for group in &lr.all_groups {
+            for block in &group.blocks {
+                push_edit(
+                    &mut edits,
+                    &mut seen,
+                    &block.uid,
+                    &group.page_title,
+                    &block.string,
+                    old,
+                    new,
+                );
+            }
+        }
+    }
+    // Blocks on the loaded page itself can reference it too
+    for day in &state.days {
+        collect_block_edits(&day.blocks, &day.title, old, new, &mut edits, &mut seen);
+    }
+    edits
+}
+
+fn collect_block_edits(
+    blocks: &[Block],
+    page_title: &str,
+    old: &str,
+    new: &str,
+    edits: &mut Vec<PageRenameEdit>,
+    seen: &mut HashSet<String>,
+) {
+    for block in blocks {
+        push_edit(edits, seen, &block.uid, page_title, &block.string, old, new);
+        collect_block_edits(&block.children, page_title, old, new, edits, seen);
+    }
+}
+
+fn push_edit(
+    edits: &mut Vec<PageRenameEdit>,
+    seen: &mut HashSet<String>,
+    uid: &str,
+    page_title: &str,
+    text: &str,
+    old: &str,
+    new: &str,
+) {
+    let new_text = markdown::rename_page_refs(text, old, new);
+    if new_text != text && seen.insert(uid.to_string()) {
+        edits.push(PageRenameEdit {
+            uid: uid.to_string(),
+            page_title: page_title.to_string(),
+            old_text: text.to_string(),
+            new_text,
+        });
+    }
+}
+
+fn confirm_rename(state: &mut AppState) -> Option<WriteAction> {
+    let rename = state.rename_page.take()?;
+    let edits = rename.preview?;
+    let new_title = rename.input.to_string().trim().to_string();
+    let texts: Vec<(String, String)> = edits
+        .iter()
+        .map(|e| (e.uid.clone(), e.new_text.clone()))
+        .collect();
+
+    apply_page_rename(state, &rename.old_title, &new_title, &texts);
+    state.status_message = Some(format!(
+        "Renamed \"{}\" to \"{}\", {} block{} updated",
+        rename.old_title,
+        new_title,
+        edits.len(),
+        if edits.len() == 1 { "" } else { "s" }
+    ));
+    state.undo_stack.push(UndoEntry::RenamePage {
+        page_uid: rename.page_uid.clone(),
+        old_title: rename.old_title,
+        new_title: new_title.clone(),
+        edits,
+    });
+    state.redo_stack.clear();
+    Some(rename_write_action(&rename.page_uid, &new_title, texts))
+}
+
+/// One batched write: the title change followed by every rewritten block.
+pub(super) fn rename_write_action(
+    page_uid: &str,
+    title: &str,
+    texts: Vec<(String, String)>,
+) -> WriteAction {
+    let mut actions = vec![WriteAction::UpdatePage {
+        page: PageUpdate {
+            uid: page_uid.to_string(),
+            title: title.to_string(),
+        },
+    }];
+    actions.extend(
+        texts
+            .into_iter()
+            .map(|(uid, string)| WriteAction::UpdateBlock {
+                block: BlockUpdate { uid, string },
+            }),
+    );
+    WriteAction::BatchActions { actions }
+}
+
+/// Apply a rename locally: the page title everywhere it's held, plus the
+/// rewritten block texts in loaded pages, linked refs and caches.
+pub(super) fn apply_page_rename(
+    state: &mut AppState,
+    from: &str,
+    to: &str,
+    texts: &[(String, String)],
+) {
+    let texts_by_uid: HashMap<&str, &str> = texts
+        .iter()
+        .map(|(uid, text)| (uid.as_str(), text.as_str()))
+        .collect();
+
+    rename_in_view(&mut state.view_mode, &mut state.days, from, to);
+    for snapshot in &mut state.nav_history {
+        rename_in_view(&mut snapshot.view_mode, &mut snapshot.days, from, to);
+    }
+    for (uid, text) in texts {
+        update_block_text_in_days(&mut state.days, uid, text);
+        if let Some(cached) = state.block_ref_cache.get_mut(uid) {
+            *cached = text.clone();
+        }
+    }
+
+    if let Some(lr) = state.linked_refs.remove(from) {
+        state.linked_refs.insert(to.to_string(), lr);
+    }
+    for lr in state.linked_refs.values_mut() {
+        for group in &mut lr.all_groups {
+            if group.page_title == from {
+                group.page_title = to.to_string();
+            }
+            for block in &mut group.blocks {
+                if block.page_title == from {
+                    block.page_title = to.to_string();
+                }
+                if let Some(text) = texts_by_uid.get(block.uid.as_str()) {
+                    block.string = text.to_string();
+                }
+            }
+        }
+        for page in lr.filter.include.iter_mut().chain(&mut lr.filter.exclude) {
+            if page == from {
+                *page = to.to_string();
+            }
+        }
+        lr.apply_filter();
+    }
+
+    if let Some(filter) = state.linked_ref_filters.remove(from) {
+        state.linked_ref_filters.insert(to.to_string(), filter);
+    }
+    for filter in state.linked_ref_filters.values_mut() {
+        for page in filter.include.iter_mut().chain(&mut filter.exclude) {
+            if page == from {
+                *page = to.to_string();
+            }
+        }
+    }
+
+    for (title, _) in &mut state.page_title_cache {
+        if title == from {
+            *title = to.to_string();
+        }
+    }
+}
+
+fn rename_in_view(
+    view_mode: &mut ViewMode,
+    days: &mut [crate::api::types::DailyNote],
+    from: &str,
+    to: &str,
+) {
+    if let ViewMode::Page { title } = view_mode {
+        if title == from {
+            *title = to.to_string();
+        }
+    }
+    for day in days {
+        if day.title == from {
+            day.title = to.to_string();
+        }
+    }
+}
@@ -1,11 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::api::types::{Block, DailyNote};
+use crate::api::types::{Block, DailyNote, SearchHit};
 use crate::edit_buffer::EditBuffer;
+use crate::fuzzy;
+
+use super::state::SearchResult;
 
 pub(super) const AUTOCOMPLETE_LIMIT: usize = 20;
 pub(super) const SEARCH_LIMIT: usize = 50;
 pub(super) const QUICK_SWITCHER_LIMIT: usize = 50;
+/// Shortest query sent to the graph-wide search.
+pub(super) const REMOTE_SEARCH_MIN_CHARS: usize = 2;
+/// Most graph-wide hits kept per search query; Datalog has no row limit.
+pub(super) const REMOTE_SEARCH_LIMIT: usize = 100;
+/// Ticks (250ms each) to wait after the last keystroke before querying the graph.
+pub(super) const SEARCH_DEBOUNCE_TICKS: u8 = 2;
 
 pub fn detect_block_ref_trigger(buffer: &EditBuffer) -> bool {
     let c = buffer.cursor;
@@ -17,82 +26,152 @@ pub fn detect_block_ref_trigger(buffer: &EditBuffer) -> bool {
         && buffer.chars[c + 1] == ')'
 }
 
+/// `[[|]]` around the cursor — also covers `#[[|]]`, which completes the same way.
+pub fn detect_page_link_trigger(buffer: &EditBuffer) -> bool {
+    let c = buffer.cursor;
+    c >= 2
+        && c + 1 < buffer.chars.len()
+        && buffer.chars[c - 2] == '['
+        && buffer.chars[c - 1] == '['
+        && buffer.chars[c] == ']'
+        && buffer.chars[c + 1] == ']'
+}
+
+/// A `#` just typed at the start of a word, with nothing directly after it.
+pub fn detect_tag_trigger(buffer: &EditBuffer) -> bool {
+    let c = buffer.cursor;
+    c >= 1
+        && buffer.chars[c - 1] == '#'
+        && (c == 1 || buffer.chars[c - 2].is_whitespace())
+        && buffer.chars.get(c).is_none_or(|ch| ch.is_whitespace())
+}
+
+/// Page autocomplete candidates as `(uid, title)`, best first. When `query` isn't
+/// exactly an existing title, a "create new page" entry with an empty uid is appended.
+pub fn page_autocomplete_results(
+    titles: &[(String, String)],
+    query: &str,
+    limit: usize,
+    recent: &[String],
+) -> Vec<(String, String)> {
+    let mut results: Vec<(String, String)> = filter_page_titles(titles, query, limit, recent)
+        .into_iter()
+        .map(|(title, uid)| (uid, title))
+        .collect();
+    let query = query.trim();
+    // Roam titles are case-sensitive: "beta" is a different page from "Beta"
+    let exists = titles.iter().any(|(title, _)| title == query);
+    if !query.is_empty() && !exists {
+        results.push((String::new(), query.to_string()));
+    }
+    results
+}
+
+/// Loaded blocks and resolved block refs containing `query`, best first.
 pub fn filter_blocks(
     days: &[DailyNote],
     cache: &HashMap<String, String>,
     query: &str,
     limit: usize,
 ) -> Vec<(String, String)> {
-    let query_lower = query.to_lowercase();
-    let mut results = Vec::new();
+    let mut candidates = Vec::new();
     for day in days {
-        collect_matching_blocks(&day.blocks, &query_lower, &mut results, limit);
-        if results.len() >= limit {
-            break;
-        }
+        collect_blocks(&day.blocks, &mut candidates);
     }
-    // Also search resolved block refs from cache
-    if results.len() < limit {
-        for (uid, text) in cache {
-            if results.len() >= limit {
-                break;
-            }
-            if !text.is_empty()
-                && (query_lower.is_empty() || text.to_lowercase().contains(&query_lower))
-            {
-                // Avoid duplicates (block already found from days)
-                if !results.iter().any(|(u, _)| u == uid) {
-                    results.push((uid.clone(), text.clone()));
-                }
-            }
-        }
-    }
-    results.truncate(limit);
-    results
+    // Also search resolved block refs from cache, skipping blocks already loaded
+    let loaded: HashSet<&str> = candidates.iter().map(|(uid, _)| uid.as_str()).collect();
+    let mut cached: Vec<(String, String)> = cache
+        .iter()
+        .filter(|(uid, text)| !text.is_empty() && !loaded.contains(uid.as_str()))
+        .map(|(uid, text)| (uid.clone(), text.clone()))
+        .collect();
+    // HashMap order is random; keep the unranked (empty query) listing stable
+    cached.sort();
+    candidates.extend(cached);
+    fuzzy::rank_substring(candidates, query, |(_, text)| text, limit)
 }
 
+/// Fuzzy-match page titles, boosting recently visited pages (`recent`, most recent first).
 pub fn filter_page_titles(
     titles: &[(String, String)],
     query: &str,
     limit: usize,
+    recent: &[String],
 ) -> Vec<(String, String)> {
-    if query.is_empty() {
-        return titles.iter().take(limit).cloned().collect();
-    }
-    let query_lower = query.to_lowercase();
-    let mut prefix_matches: Vec<(String, String)> = Vec::new();
-    let mut contains_matches: Vec<(String, String)> = Vec::new();
-    for (title, uid) in titles {
-        let title_lower = title.to_lowercase();
-        if title_lower.starts_with(&query_lower) {
-            prefix_matches.push((title.clone(), uid.clone()));
-        } else if title_lower.contains(&query_lower) {
-            contains_matches.push((title.clone(), uid.clone()));
-        }
-        if prefix_matches.len() + contains_matches.len() >= limit {
+    fuzzy::rank(
+        titles.iter().cloned(),
+        query,
+        |(title, _)| title,
+        |(title, _)| fuzzy::recency_bonus(title, recent),
+        limit,
+    )
+}
+
+/// Local search hits, each tagged with the title of the day/page it was found on.
+pub fn local_search_results(
+    days: &[DailyNote],
+    cache: &HashMap<String, String>,
+    query: &str,
+    limit: usize,
+) -> Vec<SearchResult> {
+    filter_blocks(days, cache, query, limit)
+        .into_iter()
+        .map(|(uid, text)| {
+            let page_title = days
+                .iter()
+                .find(|d| contains_block(&d.blocks, &uid))
+                .map(|d| d.title.clone());
+            SearchResult {
+                uid,
+                text,
+                page_title,
+                is_page: false,
+            }
+        })
+        .collect()
+}
+
+/// Append graph-wide hits to local results, skipping blocks already found locally
+/// and hits that no longer match the (possibly longer) current query.
+pub fn merge_search_results(
+    mut local: Vec<SearchResult>,
+    remote: &[SearchHit],
+    query: &str,
+    limit: usize,
+) -> Vec<SearchResult> {
+    for hit in remote {
+        if local.len() >= limit {
             break;
         }
+        let matches = if hit.is_page {
+            fuzzy::fuzzy_match(query, &hit.string)
+        } else {
+            fuzzy::substring_match(query, &hit.string)
+        };
+        if matches.is_none() || local.iter().any(|r| r.uid == hit.uid) {
+            continue;
+        }
+        local.push(SearchResult {
+            uid: hit.uid.clone(),
+            text: hit.string.clone(),
+            page_title: Some(hit.page_title.clone()),
+            is_page: hit.is_page,
+        });
     }
-    prefix_matches.extend(contains_matches);
-    prefix_matches.truncate(limit);
-    prefix_matches
+    local
 }
 
-fn collect_matching_blocks(
-    blocks: &[Block],
-    query: &str,
-    results: &mut Vec<(String, String)>,
-    limit: usize,
-) {
+fn contains_block(blocks: &[Block], uid: &str) -> bool {
+    blocks
+        .iter()
+        .any(|b| b.uid == uid || contains_block(&b.children, uid))
+}
+
+fn collect_blocks(blocks: &[Block], out: &mut Vec<(String, String)>) {
     for block in blocks {
-        if results.len() >= limit {
-            return;
-        }
-        if !block.string.is_empty()
-            && (query.is_empty() || block.string.to_lowercase().contains(query))
-        {
-            results.push((block.uid.clone(), block.string.clone()));
+        if !block.string.is_empty() {
+            out.push((block.uid.clone(), block.string.clone()));
         }
-        collect_matching_blocks(&block.children, query, results, limit);
+        collect_blocks(&block.children, out);
     }
 }
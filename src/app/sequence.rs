@@ -0,0 +1,71 @@
+//! Multi-key sequences such as `dd`, `Leader f` or `Ctrl+x Ctrl+c`.
+//!
+//! Keys are collected in `pending_keys` until they match a binding. While a
+//! sequence is incomplete the which-key popup lists the possible next keys;
+//! it is abandoned after `sequence_timeout` without a key.
+
+use std::time::Instant;
+
+use crossterm::event::KeyEvent;
+
+use crate::keys::preset::Action;
+use crate::keys::{format_sequence, KeybindingMap, SequenceMatch};
+
+use super::state::{AppState, WhichKeyState};
+
+/// Add `key` to the pending sequence and return the action it completes.
+pub(super) fn resolve_key(
+    state: &mut AppState,
+    key: &KeyEvent,
+    keybindings: &KeybindingMap,
+    now: Instant,
+) -> Option<Action> {
+    state.pending_keys.push(*key);
+    match keybindings.resolve_sequence(&state.pending_keys) {
+        SequenceMatch::Action(action) => {
+            clear_pending(state);
+            Some(action.clone())
+        }
+        SequenceMatch::Prefix => {
+            state.pending_since = Some(now);
+            state.which_key = Some(WhichKeyState {
+                prefix: format_sequence(&state.pending_keys),
+                entries: keybindings
+                    .continuations(&state.pending_keys)
+                    .into_iter()
+                    .map(|(key, action)| {
+                        let label = action.map_or("+more", |a| a.label());
+                        (key, label.to_string())
+                    })
+                    .collect(),
+            });
+            None
+        }
+        SequenceMatch::None => {
+            let restart = state.pending_keys.len() > 1;
+            clear_pending(state);
+            // A key that doesn't continue the sequence counts on its own
+            if restart {
+                resolve_key(state, key, keybindings, now)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Drop a sequence whose next key didn't arrive in time.
+pub(super) fn expire_pending(state: &mut AppState, now: Instant) {
+    if state
+        .pending_since
+        .is_some_and(|since| now.duration_since(since) >= state.sequence_timeout)
+    {
+        clear_pending(state);
+    }
+}
+
+pub(super) fn clear_pending(state: &mut AppState) {
+    state.pending_keys.clear();
+    state.pending_since = None;
+    state.which_key = None;
+}
@@ -0,0 +1,7 @@
+use super::types::{SlashAction, SlashCommand};
+
+pub(super) const CMD: SlashCommand = SlashCommand {
+    name: "calendar",
+    description: "Pick a date from the calendar",
+    action: SlashAction::PickDate,
+};
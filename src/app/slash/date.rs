@@ -2,6 +2,6 @@ use super::types::{DateOffset, SlashAction, SlashCommand};
 
 pub(super) const CMD: SlashCommand = SlashCommand {
     name: "date",
-    description: "Insert today's date",
+    description: "Insert a date (today, or /date next friday)",
     action: SlashAction::InsertDate(DateOffset::Today),
 };
@@ -48,11 +48,15 @@ pub fn execute(action: &SlashAction, buffer: &mut EditBuffer, slash_pos: usize,
                 DateOffset::Today => today,
                 DateOffset::Yesterday => today.checked_sub_days(Days::new(1)).unwrap_or(today),
                 DateOffset::Tomorrow => today.checked_add_days(Days::new(1)).unwrap_or(today),
+                DateOffset::Date(date) => *date,
             };
             let title = format_roam_daily_title(date);
             let link = format!("[[{}]]", title);
             buffer.replace_range(slash_pos, replace_end, &link);
         }
+        SlashAction::PickDate => {
+            buffer.replace_range(slash_pos, replace_end, "");
+        }
         SlashAction::InsertTime => {
             let time_str = Local::now().format("%H:%M").to_string();
             buffer.replace_range(slash_pos, replace_end, &time_str);
@@ -165,6 +169,20 @@ mod tests {
         assert!(result.contains("2026") || result.contains("2025") || result.contains("2027"));
     }
 
+    #[test]
+    fn execute_insert_parsed_date() {
+        let mut buf = EditBuffer::new("due /");
+        buf.cursor = 5;
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 3, 6).unwrap();
+        execute(
+            &SlashAction::InsertDate(DateOffset::Date(date)),
+            &mut buf,
+            4,
+            0,
+        );
+        assert_eq!(buf.to_string(), "due [[March 6th, 2026]]");
+    }
+
     #[test]
     fn execute_insert_time() {
         let mut buf = EditBuffer::new("/");
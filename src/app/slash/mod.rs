@@ -3,6 +3,7 @@ mod types;
 
 mod blockquote;
 mod bold;
+mod calendar;
 mod code;
 mod date;
 mod done;
@@ -23,6 +24,7 @@ mod yesterday;
 pub use types::*;
 
 use crate::edit_buffer::EditBuffer;
+use crate::fuzzy;
 
 pub fn all_commands() -> Vec<SlashCommand> {
     vec![
@@ -31,6 +33,7 @@ pub fn all_commands() -> Vec<SlashCommand> {
         date::CMD,
         yesterday::CMD,
         tomorrow::CMD,
+        calendar::CMD,
         time::CMD,
         code::CMD,
         hr::CMD,
@@ -47,12 +50,26 @@ pub fn all_commands() -> Vec<SlashCommand> {
     ]
 }
 
+/// Rank commands by the name typed so far. Once the query has an argument
+/// after a space, only commands that take one are offered.
 pub fn filter(query: &str) -> Vec<SlashCommand> {
-    let q = query.to_lowercase();
-    all_commands()
-        .into_iter()
-        .filter(|c| c.name.contains(&q))
-        .collect()
+    let (name, arg) = split_query(query);
+    let mut commands = all_commands();
+    if arg.is_some() {
+        commands.retain(|c| c.action.takes_argument());
+    }
+    let limit = commands.len();
+    fuzzy::rank(commands, name, |c| c.name, |_| 0, limit)
+}
+
+/// Split a slash query into the command name and the argument typed after
+/// the first space, if any.
+pub fn split_query(query: &str) -> (&str, Option<&str>) {
+    match query.split_once(' ') {
+        Some((name, arg)) if !arg.trim().is_empty() => (name, Some(arg.trim())),
+        Some((name, _)) => (name, None),
+        None => (query, None),
+    }
 }
 
 /// Detects if the user just typed '/' at a position that should open the slash menu.
@@ -140,7 +157,7 @@ mod tests {
     #[test]
     fn filter_empty_returns_all() {
         let results = filter("");
-        assert_eq!(results.len(), 18);
+        assert_eq!(results.len(), 19);
     }
 
     #[test]
@@ -151,6 +168,25 @@ mod tests {
         assert!(names.contains(&"tomorrow"));
     }
 
+    #[test]
+    fn filter_with_argument_keeps_commands_that_take_one() {
+        let names: Vec<&str> = filter("date next friday").iter().map(|c| c.name).collect();
+        assert_eq!(names, ["date"]);
+        assert!(filter("bold text").is_empty());
+        // A trailing space alone is not an argument
+        assert!(filter("bo ").iter().any(|c| c.name == "bold"));
+    }
+
+    #[test]
+    fn split_query_separates_name_and_argument() {
+        assert_eq!(split_query("date"), ("date", None));
+        assert_eq!(split_query("date "), ("date", None));
+        assert_eq!(
+            split_query("date  next friday "),
+            ("date", Some("next friday"))
+        );
+    }
+
     #[test]
     fn filter_case_insensitive() {
         let results = filter("TODO");
@@ -163,6 +199,12 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn filter_fuzzy_ranks_best_match_first() {
+        let results = filter("tmr");
+        assert_eq!(results[0].name, "tomorrow");
+    }
+
     #[test]
     fn filter_partial_match() {
         let results = filter("ode");
@@ -172,8 +214,8 @@ mod tests {
     // --- all_commands tests ---
 
     #[test]
-    fn all_commands_has_18() {
-        assert_eq!(all_commands().len(), 18);
+    fn all_commands_has_19() {
+        assert_eq!(all_commands().len(), 19);
     }
 
     #[test]
@@ -1,3 +1,5 @@
+use chrono::NaiveDate;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct SlashMenuState {
     pub query: String,
@@ -22,13 +24,25 @@ pub enum SlashAction {
         close: &'static str,
     },
     InsertDate(DateOffset),
+    /// Remove the command and open the calendar to pick a date to link.
+    PickDate,
     InsertTime,
     InsertCodeBlock,
 }
 
+impl SlashAction {
+    /// Whether text typed after the command name, as in `/date next friday`,
+    /// is passed to the command.
+    pub fn takes_argument(&self) -> bool {
+        matches!(self, Self::InsertDate(_))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DateOffset {
     Today,
     Yesterday,
     Tomorrow,
+    /// A date typed after the command, as in `/date in 3 days`.
+    Date(NaiveDate),
 }
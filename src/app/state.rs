@@ -1,10 +1,17 @@
 use std::collections::{HashMap, HashSet};
 
-use chrono::{Local, NaiveDate};
+use chrono::{Datelike, Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
 
-use crate::api::types::{Block, DailyNote, LinkedRefBlock, LinkedRefGroup};
+use crate::api::types::{
+    Block, DailyNote, LinkedRefBlock, LinkedRefGroup, LinkedRefSort, SearchHit,
+};
 use crate::edit_buffer::EditBuffer;
 use crate::error::ErrorPopup;
+use crate::keys::mode::{InsertAction, ModeKeymap, PopupAction};
+use crate::markup::EmbedTarget;
+
+use super::blocks::walk_rows;
 
 #[derive(Debug, Clone)]
 pub enum UndoEntry {
@@ -27,20 +34,90 @@ pub enum UndoEntry {
         old_order: i64,
         selected_block: usize,
     },
+    /// Restores `old_title` and each edit's `old_text`; the page currently has `new_title`.
+    RenamePage {
+        page_uid: String,
+        old_title: String,
+        new_title: String,
+        edits: Vec<PageRenameEdit>,
+    },
+    /// Several steps undone together; `entries` are in the order the steps ran.
+    Batch {
+        entries: Vec<UndoEntry>,
+        selected_block: usize,
+    },
+}
+
+/// A block whose references change when a page is renamed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRenameEdit {
+    pub uid: String,
+    /// Page the block lives on, for the preview.
+    pub page_title: String,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenamePageState {
+    pub page_uid: String,
+    pub old_title: String,
+    pub input: EditBuffer,
+    /// Blocks to rewrite once the new title is entered; `None` while editing it.
+    pub preview: Option<Vec<PageRenameEdit>>,
+    /// Linked references were re-queried since the prompt opened.
+    pub refs_loaded: bool,
+    /// The title was entered but linked references are still loading.
+    pub waiting_for_refs: bool,
+    pub selected: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutocompleteKind {
+    /// `((` — insert a block reference
+    BlockRef,
+    /// `[[` or `#[[` — complete the bracketed page title
+    PageLink,
+    /// `#` at a word start — insert `#tag`, or `#[[tag]]` when the title needs brackets
+    Tag,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AutocompleteState {
+    pub kind: AutocompleteKind,
     pub query: String,
-    pub results: Vec<(String, String)>, // (uid, text)
+    /// (uid, text); for page kinds the text is the page title and an empty
+    /// uid marks the "create new page" entry.
+    pub results: Vec<(String, String)>,
     pub selected: usize,
+    /// Page titles are being fetched because the cache was empty.
+    pub fetching: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub uid: String,
+    pub text: String,
+    /// Containing page, when known (resolved block refs have none).
+    pub page_title: Option<String>,
+    pub is_page: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SearchState {
     pub query: String,
-    pub results: Vec<(String, String)>, // (uid, text)
+    /// Local hits followed by graph-wide hits not already loaded.
+    pub results: Vec<SearchResult>,
     pub selected: usize,
+    /// Graph-wide hits for the last query sent to the API.
+    pub remote: Vec<SearchHit>,
+    pub debounce_ticks: u8,
+    /// Graph queries in flight for the current query.
+    pub pending_requests: u8,
+    /// Bumped on every query change. Graph searches carry the generation
+    /// they were sent for; responses from an older one are dropped.
+    pub generation: u64,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -58,12 +135,212 @@ pub struct QuickSwitcherState {
     pub fetching: bool,
 }
 
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HelpState {
+    pub query: String,
+    /// Keys go to the query instead of scrolling.
+    pub searching: bool,
+    /// First line shown.
+    pub scroll: usize,
+}
+
+/// Continuations of a partly typed key sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhichKeyState {
+    /// The keys typed so far, formatted.
+    pub prefix: String,
+    /// (next key, what it does)
+    pub entries: Vec<(String, String)>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinkPickerState {
     pub links: Vec<String>,
     pub selected: usize,
 }
 
+/// Attributes (`Name:: value`) set anywhere on a page, as a two-column table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeTableState {
+    pub page_title: String,
+    /// (name, value) in document order.
+    pub rows: Vec<(String, String)>,
+    pub selected: usize,
+}
+
+/// Which tasks the agenda lists, by due date.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AgendaFilter {
+    #[default]
+    All,
+    /// Due before today.
+    Overdue,
+    Today,
+    /// Due between Monday and Sunday of the current week.
+    ThisWeek,
+    NoDate,
+}
+
+impl AgendaFilter {
+    pub fn next(self) -> Self {
+        match self {
+            Self::All => Self::Overdue,
+            Self::Overdue => Self::Today,
+            Self::Today => Self::ThisWeek,
+            Self::ThisWeek => Self::NoDate,
+            Self::NoDate => Self::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Overdue => "overdue",
+            Self::Today => "today",
+            Self::ThisWeek => "this week",
+            Self::NoDate => "no date",
+        }
+    }
+
+    pub fn matches(self, due: Option<NaiveDate>, today: NaiveDate) -> bool {
+        match (self, due) {
+            (Self::All, _) => true,
+            (Self::NoDate, due) => due.is_none(),
+            (_, None) => false,
+            (Self::Overdue, Some(due)) => due < today,
+            (Self::Today, Some(due)) => due == today,
+            (Self::ThisWeek, Some(due)) => {
+                let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+                due >= monday && due < monday + Days::new(7)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AgendaGrouping {
+    /// By the daily note the task links to; undated tasks come last.
+    #[default]
+    DueDate,
+    Page,
+}
+
+/// A `{{[[TODO]]}}` block found anywhere in the graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgendaItem {
+    pub uid: String,
+    pub text: String,
+    pub page_title: String,
+    /// First daily-note link in the text, e.g. `[[February 24th, 2026]]`.
+    pub due: Option<NaiveDate>,
+    /// Checked off from the agenda; kept in the list so it can be unchecked.
+    pub done: bool,
+}
+
+/// Graph-wide TODO list opened with the agenda action.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgendaState {
+    pub items: Vec<AgendaItem>,
+    pub filter: AgendaFilter,
+    pub grouping: AgendaGrouping,
+    /// Index into the items of [`AgendaState::groups`], flattened.
+    pub selected: usize,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl AgendaState {
+    /// Items passing the filter, grouped and sorted for display.
+    pub fn groups(&self, today: NaiveDate) -> Vec<(AgendaGroup, Vec<&AgendaItem>)> {
+        let mut items: Vec<&AgendaItem> = self
+            .items
+            .iter()
+            .filter(|item| self.filter.matches(item.due, today))
+            .collect();
+        // Undated tasks sort after dated ones
+        let due_key = |item: &AgendaItem| (item.due.is_none(), item.due);
+        match self.grouping {
+            AgendaGrouping::DueDate => items.sort_by(|a, b| {
+                due_key(a)
+                    .cmp(&due_key(b))
+                    .then_with(|| a.page_title.cmp(&b.page_title))
+            }),
+            AgendaGrouping::Page => items.sort_by(|a, b| {
+                a.page_title
+                    .cmp(&b.page_title)
+                    .then_with(|| due_key(a).cmp(&due_key(b)))
+            }),
+        }
+        let mut groups: Vec<(AgendaGroup, Vec<&AgendaItem>)> = Vec::new();
+        for item in items {
+            let group = match self.grouping {
+                AgendaGrouping::DueDate => AgendaGroup::Due(item.due),
+                AgendaGrouping::Page => AgendaGroup::Page(item.page_title.clone()),
+            };
+            match groups.last_mut() {
+                Some((last, members)) if *last == group => members.push(item),
+                _ => groups.push((group, vec![item])),
+            }
+        }
+        groups
+    }
+
+    /// Number of items passing the filter.
+    pub fn visible_count(&self, today: NaiveDate) -> usize {
+        self.items
+            .iter()
+            .filter(|item| self.filter.matches(item.due, today))
+            .count()
+    }
+
+    /// The selected item, as shown by [`AgendaState::groups`].
+    pub fn selected_item(&self, today: NaiveDate) -> Option<&AgendaItem> {
+        self.groups(today)
+            .into_iter()
+            .flat_map(|(_, items)| items)
+            .nth(self.selected)
+    }
+}
+
+/// Heading of an agenda group.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgendaGroup {
+    Due(Option<NaiveDate>),
+    Page(String),
+}
+
+/// Month grid for jumping to a day, or for picking a date to link while editing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarState {
+    /// Highlighted day; the grid shows its month.
+    pub selected: NaiveDate,
+    /// Days whose daily note has blocks, across the months loaded so far.
+    pub with_content: HashSet<NaiveDate>,
+    /// First days of the months `with_content` covers.
+    pub loaded_months: HashSet<NaiveDate>,
+    /// Month being fetched.
+    pub loading: Option<NaiveDate>,
+    /// Opened from insert mode: choosing a day inserts its link.
+    pub insert: bool,
+}
+
+impl CalendarState {
+    pub fn new(selected: NaiveDate, insert: bool) -> Self {
+        Self {
+            selected,
+            with_content: HashSet::new(),
+            loaded_months: HashSet::new(),
+            loading: None,
+            insert,
+        }
+    }
+
+    /// First day of the month shown.
+    pub fn month(&self) -> NaiveDate {
+        self.selected.with_day(1).unwrap_or(self.selected)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(super) struct ViewSnapshot {
     pub(super) view_mode: ViewMode,
@@ -75,13 +352,106 @@ pub(super) struct ViewSnapshot {
 pub enum LoadRequest {
     DailyNote(NaiveDate),
     Page(String),
+    /// Re-query the linked references of a page.
+    LinkedRefs(String),
+    /// Open the page a block lives on, with the block selected.
+    BlockPage(String),
+}
+
+/// Per-page linked references filter. Persisted in the local cache.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinkedRefFilter {
+    /// Only show blocks that also reference every one of these pages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Hide blocks that reference any of these pages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub sort: LinkedRefSort,
+}
+
+impl LinkedRefFilter {
+    pub fn is_active(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinkedRefsState {
+    /// Groups visible after applying `filter` (what navigation and rendering use).
     pub groups: Vec<LinkedRefGroup>,
+    /// Unfiltered groups as returned by the API.
+    pub all_groups: Vec<LinkedRefGroup>,
+    pub filter: LinkedRefFilter,
+    pub collapsed: bool,
+    pub loading: bool,
+}
+
+/// Results of a `{{query: ...}}` block, keyed by the block's uid in `AppState`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResultsState {
+    /// Block text the results were requested for; an edit re-runs the query.
+    pub text: String,
+    pub results: Vec<SearchHit>,
     pub collapsed: bool,
     pub loading: bool,
+    /// Parse or API error, shown instead of the results.
+    pub error: Option<String>,
+}
+
+/// Fetched content of an embed, keyed by its target in `AppState`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmbedState {
+    /// The embedded block with its children, or the embedded page's top-level blocks.
+    pub blocks: Vec<Block>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl EmbedState {
+    /// Blocks to draw under the embedding block: a block embed's children
+    /// (the block itself is the embed line) or a page's top-level blocks.
+    pub fn nested_blocks(&self, target: &EmbedTarget) -> &[Block] {
+        match target {
+            EmbedTarget::Block(_) => self
+                .blocks
+                .first()
+                .map(|root| root.children.as_slice())
+                .unwrap_or_default(),
+            EmbedTarget::Page(_) => &self.blocks,
+        }
+    }
+
+    /// Nested blocks that get a line of their own, with their level in the
+    /// embed. Blocks past `MAX_EMBED_DEPTH` stay folded under their parent.
+    pub fn visible_blocks(&self, target: &EmbedTarget) -> Vec<(usize, &Block)> {
+        fn visit<'a>(blocks: &'a [Block], level: usize, out: &mut Vec<(usize, &'a Block)>) {
+            for block in blocks {
+                out.push((level, block));
+                if block.open && level + 1 < MAX_EMBED_DEPTH {
+                    visit(&block.children, level + 1, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        visit(self.nested_blocks(target), 0, &mut out);
+        out
+    }
+}
+
+/// Levels of an embedded tree shown before the rest is folded.
+pub const MAX_EMBED_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkedRefFilterState {
+    pub page_title: String,
+    pub pages: Vec<(String, usize)>, // (co-referenced page, block count)
+    pub selected: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -94,12 +464,23 @@ pub enum LinkedRefItem {
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMessage {
     Key(crossterm::event::KeyEvent),
+    Mouse(crossterm::event::MouseEvent),
     DailyNoteLoaded(DailyNote),
     PageLoaded(DailyNote),
     RefreshLoaded(DailyNote),
     BlockRefResolved(String, String),              // (uid, text)
     LinkedRefsLoaded(String, Vec<LinkedRefGroup>), // (page_title, groups)
     PageTitlesLoaded(Vec<(String, String)>),       // (title, uid)
+    SearchResultsLoaded(u64, Vec<SearchHit>),      // (search generation, hits)
+    BlockPageLoaded(String, String),               // (uid, page title; empty if not found)
+    /// (query block uid, block text queried, hits or error)
+    QueryResultsLoaded(String, String, Result<Vec<SearchHit>, String>),
+    /// (embed target, embedded blocks or error)
+    EmbedLoaded(EmbedTarget, Result<Vec<Block>, String>),
+    /// TODO blocks for the agenda, or the query error
+    AgendaLoaded(Result<Vec<SearchHit>, String>),
+    /// (first day of a month, its days with daily-note content or error)
+    CalendarLoaded(NaiveDate, Result<Vec<NaiveDate>, String>),
     ApiError(crate::error::ErrorInfo),
     Tick,
 }
@@ -118,23 +499,68 @@ pub struct AppState {
     pub should_quit: bool,
     pub refresh_counter: u32,
     pub input_mode: InputMode,
-    pub pending_key: Option<char>,
+    /// Keys typed so far of a multi-key sequence.
+    pub pending_keys: Vec<crossterm::event::KeyEvent>,
+    /// When the last key of `pending_keys` was typed.
+    pub(super) pending_since: Option<std::time::Instant>,
+    /// How long a sequence waits for its next key.
+    pub(super) sequence_timeout: std::time::Duration,
+    pub which_key: Option<WhichKeyState>,
+    /// Keys of the block editor.
+    pub(super) insert_keys: ModeKeymap<InsertAction>,
+    /// Keys of the list popups.
+    pub(super) popup_keys: ModeKeymap<PopupAction>,
     pub block_ref_cache: HashMap<String, String>,
     pub(super) pending_block_refs: HashSet<String>,
     pub autocomplete: Option<AutocompleteState>,
     pub search: Option<SearchState>,
     pub undo_stack: Vec<UndoEntry>,
     pub redo_stack: Vec<UndoEntry>,
-    pub show_help: bool,
+    pub help: Option<HelpState>,
+    /// Bindings of the active keymap, shown on the help screen.
+    pub help_sections: Vec<crate::keys::KeySection>,
     pub view_mode: ViewMode,
     pub(super) nav_history: Vec<ViewSnapshot>,
     pub(super) nav_index: usize,
     pub link_picker: Option<LinkPickerState>,
+    pub attribute_table: Option<AttributeTableState>,
+    pub agenda: Option<AgendaState>,
+    pub calendar: Option<CalendarState>,
     pub error_popup: Option<ErrorPopup>,
     pub linked_refs: HashMap<String, LinkedRefsState>,
+    pub query_results: HashMap<String, QueryResultsState>,
+    pub embeds: HashMap<EmbedTarget, EmbedState>,
     pub slash_menu: Option<super::slash::SlashMenuState>,
     pub quick_switcher: Option<QuickSwitcherState>,
     pub(super) page_title_cache: Vec<(String, String)>,
+    pub linked_ref_filter: Option<LinkedRefFilterState>,
+    pub rename_page: Option<RenamePageState>,
+    pub(super) linked_ref_filters: HashMap<String, LinkedRefFilter>,
+    /// Block to select once the page being loaded arrives (set by search jumps).
+    pub(super) pending_select_uid: Option<String>,
+    /// Index where visual selection started; the selection runs to `selected_block`.
+    pub visual_anchor: Option<usize>,
+    /// Last copied subtrees, outermost blocks in document order.
+    pub(super) register: Vec<Block>,
+    /// Text to put on the system clipboard on the next loop iteration.
+    pub(super) pending_clipboard: Option<String>,
+    /// Last text cut or yanked inside the block editor.
+    pub(super) text_register: String,
+    /// Esc in the block editor enters vim normal mode instead of saving.
+    pub vim_editing: bool,
+    /// Blocks to open in `$EDITOR` on the next loop iteration.
+    pub(super) pending_external_edit: Option<super::editor::ExternalEdit>,
+    /// Rows of the main area scrolled off the top by the mouse wheel; cleared
+    /// by any key so the view follows the selection again.
+    pub scroll: Option<usize>,
+    /// Time and block of the last left click, to detect double clicks.
+    pub(super) last_click: Option<(std::time::Instant, usize)>,
+    pub command_palette: Option<super::palette::CommandPaletteState>,
+    /// Formatted keys bound to each action, shown in the command palette.
+    pub(super) key_labels: HashMap<crate::keys::preset::Action, String>,
+    pub light_theme: bool,
+    /// Markdown export to write on the next loop iteration.
+    pub(super) pending_export: Option<(std::path::PathBuf, String)>,
 }
 
 impl AppState {
@@ -154,37 +580,64 @@ impl AppState {
             should_quit: false,
             refresh_counter: 0,
             input_mode: InputMode::Normal,
-            pending_key: None,
+            pending_keys: Vec::new(),
+            pending_since: None,
+            sequence_timeout: std::time::Duration::from_millis(1000),
+            which_key: None,
+            insert_keys: ModeKeymap::default(),
+            popup_keys: ModeKeymap::default(),
             block_ref_cache: HashMap::new(),
             pending_block_refs: HashSet::new(),
             autocomplete: None,
             search: None,
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
-            show_help: false,
+            help: None,
+            help_sections: Vec::new(),
             view_mode: ViewMode::DailyNotes,
             nav_history: Vec::new(),
             nav_index: 0,
             link_picker: None,
+            attribute_table: None,
+            agenda: None,
+            calendar: None,
             error_popup: None,
             linked_refs: HashMap::new(),
+            query_results: HashMap::new(),
+            embeds: HashMap::new(),
             slash_menu: None,
             quick_switcher: None,
             page_title_cache: Vec::new(),
+            linked_ref_filter: None,
+            rename_page: None,
+            linked_ref_filters: HashMap::new(),
+            pending_select_uid: None,
+            visual_anchor: None,
+            register: Vec::new(),
+            pending_clipboard: None,
+            text_register: String::new(),
+            vim_editing: false,
+            pending_external_edit: None,
+            scroll: None,
+            last_click: None,
+            command_palette: None,
+            key_labels: HashMap::new(),
+            light_theme: false,
+            pending_export: None,
         }
     }
 
     pub fn flat_block_count(&self) -> usize {
         self.days
             .iter()
-            .map(|d| count_blocks_recursive(&d.blocks))
+            .map(|d| count_blocks_recursive(&d.blocks, &self.embeds))
             .sum()
     }
 
     pub fn total_navigable_count(&self) -> usize {
         let mut total = 0;
         for day in &self.days {
-            total += count_blocks_recursive(&day.blocks);
+            total += count_blocks_recursive(&day.blocks, &self.embeds);
             if let Some(lr) = self.linked_refs.get(&day.title) {
                 total += linked_ref_section_count(lr);
             }
@@ -195,7 +648,7 @@ impl AppState {
     pub fn resolve_linked_ref_item(&self, index: usize) -> Option<LinkedRefItem> {
         let mut pos = 0;
         for day in &self.days {
-            let block_count = count_blocks_recursive(&day.blocks);
+            let block_count = count_blocks_recursive(&day.blocks, &self.embeds);
             if index < pos + block_count {
                 return None; // regular block in this day
             }
@@ -212,12 +665,74 @@ impl AppState {
         None
     }
 
+    /// Inclusive range of the visual selection, if visual mode is active.
+    pub fn visual_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.visual_anchor?;
+        Some((
+            anchor.min(self.selected_block),
+            anchor.max(self.selected_block),
+        ))
+    }
+
+    /// Whether a popup that takes all input is open.
+    pub fn has_popup(&self) -> bool {
+        self.command_palette.is_some()
+            || self.linked_ref_filter.is_some()
+            || self.rename_page.is_some()
+            || self.link_picker.is_some()
+            || self.attribute_table.is_some()
+            || self.agenda.is_some()
+            || self.calendar.is_some()
+            || self.quick_switcher.is_some()
+            || self.search.is_some()
+            || self.autocomplete.is_some()
+            || self.slash_menu.is_some()
+    }
+
+    /// Page titles from navigation history, most recently visited first.
+    pub fn recent_pages(&self) -> Vec<String> {
+        let current = match &self.view_mode {
+            ViewMode::Page { title } => Some(title),
+            ViewMode::DailyNotes => None,
+        };
+        let history = self
+            .nav_history
+            .iter()
+            .rev()
+            .filter_map(|s| match &s.view_mode {
+                ViewMode::Page { title } => Some(title),
+                ViewMode::DailyNotes => None,
+            });
+        let mut recent: Vec<String> = Vec::new();
+        for title in current.into_iter().chain(history) {
+            if !recent.contains(title) {
+                recent.push(title.clone());
+            }
+        }
+        recent
+    }
+
+    /// Title of the day whose blocks or linked refs contain this index.
+    pub fn day_title_at(&self, index: usize) -> Option<String> {
+        let mut pos = 0;
+        for day in &self.days {
+            pos += count_blocks_recursive(&day.blocks, &self.embeds);
+            if let Some(lr) = self.linked_refs.get(&day.title) {
+                pos += linked_ref_section_count(lr);
+            }
+            if index < pos {
+                return Some(day.title.clone());
+            }
+        }
+        None
+    }
+
     /// Find which day's linked refs section contains this index.
     /// Returns the day title if the index is a linked ref SectionHeader.
     pub fn linked_ref_day_at(&self, index: usize) -> Option<String> {
         let mut pos = 0;
         for day in &self.days {
-            pos += count_blocks_recursive(&day.blocks);
+            pos += count_blocks_recursive(&day.blocks, &self.embeds);
             if let Some(lr) = self.linked_refs.get(&day.title) {
                 let lr_count = linked_ref_section_count(lr);
                 if lr_count > 0 && index < pos + lr_count {
@@ -240,9 +755,18 @@ pub enum InputMode {
         block_uid: String,
         original_text: String,
         create_info: Option<CreateInfo>,
+        /// Set while in vim normal mode inside the editor.
+        vim_normal: Option<VimNormal>,
     },
 }
 
+/// Vim normal mode inside the block editor.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VimNormal {
+    /// Keys of an unfinished command, like `d` or `ci`.
+    pub pending: String,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CreateInfo {
     pub parent_uid: String,
@@ -260,8 +784,15 @@ pub struct BlockInfo {
     pub depth: usize,
 }
 
+/// A linked refs section is hidden only when there is nothing to show and no
+/// filter is hiding results; a filtered-out section keeps its header so the
+/// filter can be changed again.
+fn linked_refs_hidden(lr: &LinkedRefsState) -> bool {
+    lr.groups.is_empty() && !lr.filter.is_active()
+}
+
 pub(crate) fn linked_ref_section_count(lr: &LinkedRefsState) -> usize {
-    if lr.groups.is_empty() {
+    if linked_refs_hidden(lr) {
         return 0;
     }
     if lr.collapsed {
@@ -273,7 +804,7 @@ pub(crate) fn linked_ref_section_count(lr: &LinkedRefsState) -> usize {
 }
 
 fn resolve_within_linked_refs(lr: &LinkedRefsState, offset: usize) -> Option<LinkedRefItem> {
-    if lr.groups.is_empty() {
+    if linked_refs_hidden(lr) {
         return None;
     }
     if offset == 0 {
@@ -298,15 +829,15 @@ fn resolve_within_linked_refs(lr: &LinkedRefsState, offset: usize) -> Option<Lin
     None
 }
 
-pub(crate) fn count_blocks_recursive(blocks: &[Block]) -> usize {
-    blocks
-        .iter()
-        .map(|b| {
-            if b.open {
-                1 + count_blocks_recursive(&b.children)
-            } else {
-                1
-            }
-        })
-        .sum()
+/// Navigable rows of `blocks`: open children and the lines of embeds included.
+pub(crate) fn count_blocks_recursive(
+    blocks: &[Block],
+    embeds: &HashMap<EmbedTarget, EmbedState>,
+) -> usize {
+    let mut count = 0;
+    walk_rows(blocks, "", 0, embeds, &mut |_| {
+        count += 1;
+        None::<()>
+    });
+    count
 }
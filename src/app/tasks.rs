@@ -5,10 +5,17 @@ use tokio::sync::mpsc;
 
 use crate::api::client::RoamClient;
 use crate::api::queries;
-use crate::api::types::{Block, DailyNote, WriteAction};
+use crate::api::types::{
+    parse_block_search_results, parse_daily_note_dates, parse_page_search_results, Block,
+    DailyNote, SearchHit, WriteAction,
+};
 use crate::error::ErrorInfo;
 use crate::markdown;
+use crate::markup::EmbedTarget;
 
+use super::embeds::start_embed;
+use super::query_blocks::start_query;
+use super::search::REMOTE_SEARCH_LIMIT;
 use super::state::{AppMessage, AppState};
 
 pub(super) fn spawn_fetch_daily_note(
@@ -68,6 +75,35 @@ pub(super) fn spawn_fetch_page(
     });
 }
 
+/// Find the page a block ref points into, to open it with the block selected.
+pub(super) fn spawn_fetch_block_page(
+    client: &RoamClient,
+    uid: &str,
+    tx: &mpsc::UnboundedSender<AppMessage>,
+) {
+    let (eid, selector) = queries::pull_block_page(uid);
+    let client_clone = client.clone();
+    let tx_clone = tx.clone();
+    let uid_owned = uid.to_string();
+    tokio::spawn(async move {
+        match client_clone.pull(eid, &selector).await {
+            Ok(resp) => {
+                let title = resp
+                    .result
+                    .get(":block/page")
+                    .and_then(|p| p.get(":node/title"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let _ = tx_clone.send(AppMessage::BlockPageLoaded(uid_owned, title));
+            }
+            Err(e) => {
+                let _ = tx_clone.send(AppMessage::ApiError(ErrorInfo::from_roam_error(&e)));
+            }
+        }
+    });
+}
+
 pub(super) fn spawn_fetch_linked_refs(
     client: &RoamClient,
     page_title: &str,
@@ -237,7 +273,7 @@ pub(super) fn spawn_fetch_page_titles(client: &RoamClient, tx: &mpsc::UnboundedS
                         }
                     }
                 }
-                titles.sort_by(|a, b| a.0.to_lowercase().cmp(&b.0.to_lowercase()));
+                titles.sort_by_key(|a| a.0.to_lowercase());
                 let _ = tx_clone.send(AppMessage::PageTitlesLoaded(titles));
             }
             Err(e) => {
@@ -247,6 +283,131 @@ pub(super) fn spawn_fetch_page_titles(client: &RoamClient, tx: &mpsc::UnboundedS
     });
 }
 
+/// Run the query of each `(uid, text)` block; results arrive as `QueryResultsLoaded`.
+pub(super) fn spawn_run_queries(
+    client: &RoamClient,
+    blocks: Vec<(String, String)>,
+    state: &mut AppState,
+    tx: &mpsc::UnboundedSender<AppMessage>,
+) {
+    for (uid, text) in blocks {
+        let Some(datalog) = start_query(state, &uid, &text) else {
+            continue;
+        };
+        let client = client.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let result = match client.query(datalog, vec![]).await {
+                Ok(resp) => Ok(parse_block_search_results(&resp.result)),
+                Err(e) => Err(e.to_string()),
+            };
+            let _ = tx.send(AppMessage::QueryResultsLoaded(uid, text, result));
+        });
+    }
+}
+
+/// Find every TODO block in the graph for the agenda.
+pub(super) fn spawn_load_agenda(
+    client: &RoamClient,
+    datalog: String,
+    tx: &mpsc::UnboundedSender<AppMessage>,
+) {
+    let client = client.clone();
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let result = match client.query(datalog, vec![]).await {
+            Ok(resp) => Ok(parse_block_search_results(&resp.result)),
+            Err(e) => Err(e.to_string()),
+        };
+        let _ = tx.send(AppMessage::AgendaLoaded(result));
+    });
+}
+
+/// Find which daily notes of `month` have blocks, to mark them in the calendar.
+pub(super) fn spawn_load_calendar_month(
+    client: &RoamClient,
+    month: NaiveDate,
+    tx: &mpsc::UnboundedSender<AppMessage>,
+) {
+    let query = queries::daily_notes_with_content_query(month.year(), month.month());
+    let client = client.clone();
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let result = match client.query(query, vec![]).await {
+            Ok(resp) => Ok(parse_daily_note_dates(&resp.result)),
+            Err(e) => Err(e.to_string()),
+        };
+        let _ = tx.send(AppMessage::CalendarLoaded(month, result));
+    });
+}
+
+/// Pull the content of each embed, with children.
+pub(super) fn spawn_load_embeds(
+    client: &RoamClient,
+    targets: Vec<EmbedTarget>,
+    state: &mut AppState,
+    tx: &mpsc::UnboundedSender<AppMessage>,
+) {
+    for target in targets {
+        start_embed(state, &target);
+        let (eid, selector) = match &target {
+            EmbedTarget::Block(uid) => queries::pull_block_tree(uid),
+            EmbedTarget::Page(title) => queries::pull_page_by_title(title),
+        };
+        let client = client.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let result = match client.pull(eid, &selector).await {
+                Ok(resp) if resp.result.is_null() => Err("not found".to_string()),
+                Ok(resp) => {
+                    let root = Block::from_pull_response(&resp.result);
+                    Ok(match target {
+                        EmbedTarget::Block(_) => vec![root],
+                        EmbedTarget::Page(_) => root.children,
+                    })
+                }
+                Err(e) => Err(e.to_string()),
+            };
+            let _ = tx.send(AppMessage::EmbedLoaded(target, result));
+        });
+    }
+}
+
+type SearchParser = fn(&[Vec<serde_json::Value>]) -> Vec<SearchHit>;
+
+/// Run the graph-wide block and page title searches in parallel.
+/// Each sends its own `SearchResultsLoaded`, so results stream in as they arrive.
+pub(super) fn spawn_search(
+    client: &RoamClient,
+    query: &str,
+    generation: u64,
+    tx: &mpsc::UnboundedSender<AppMessage>,
+) {
+    let searches: [(String, SearchParser); 2] = [
+        (
+            queries::search_page_titles_query(query),
+            parse_page_search_results,
+        ),
+        (
+            queries::search_blocks_query(query),
+            parse_block_search_results,
+        ),
+    ];
+    for (datalog, parse) in searches {
+        let client = client.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            // Search errors are silent — local results are still shown
+            let mut hits = match client.query(datalog, vec![]).await {
+                Ok(resp) => parse(&resp.result),
+                Err(_) => Vec::new(),
+            };
+            hits.truncate(REMOTE_SEARCH_LIMIT);
+            let _ = tx.send(AppMessage::SearchResultsLoaded(generation, hits));
+        });
+    }
+}
+
 pub(super) fn spawn_refresh_daily_note(
     client: &RoamClient,
     date: NaiveDate,
@@ -3,7 +3,7 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::api::types::{Block, DailyNote, LinkedRefBlock, LinkedRefGroup};
 
-use super::{AppState, LinkedRefsState};
+use super::{AppState, LinkedRefFilter, LinkedRefsState};
 
 pub fn make_block(uid: &str, text: &str, order: i64) -> Block {
     Block {
@@ -119,8 +119,8 @@ pub fn enter_insert_mode(state: &mut AppState) {
 }
 
 pub fn make_linked_refs_state() -> LinkedRefsState {
-    LinkedRefsState {
-        groups: vec![
+    LinkedRefsState::from_groups(
+        vec![
             LinkedRefGroup {
                 page_title: "Page A".into(),
                 blocks: vec![
@@ -145,9 +145,8 @@ pub fn make_linked_refs_state() -> LinkedRefsState {
                 }],
             },
         ],
-        collapsed: false,
-        loading: false,
-    }
+        LinkedRefFilter::default(),
+    )
 }
 
 /// Day title used by test_state() for the single day
@@ -1,10 +1,13 @@
-use crate::api::types::{BlockLocation, BlockRef, BlockUpdate, NewBlock, OrderValue, WriteAction};
+use crate::api::types::{BlockLocation, BlockRef, BlockUpdate, OrderValue, WriteAction};
 
 use super::blocks::{
     find_block_in_days, find_block_parent_info, insert_block_in_days, move_block_in_days,
     remove_block_from_days, resolve_block_at_index, update_block_text_in_days,
 };
-use super::state::{AppState, UndoEntry};
+use super::embeds::{embedded_text, update_embedded_text};
+use super::rename::{apply_page_rename, rename_write_action};
+use super::state::{AppState, PageRenameEdit, UndoEntry};
+use super::yank::create_subtree_actions;
 
 pub fn apply_undo(state: &mut AppState) -> Option<WriteAction> {
     let entry = state.undo_stack.pop()?;
@@ -27,15 +30,20 @@ fn apply_undo_entry(state: &mut AppState, entry: UndoEntry) -> (UndoEntry, Write
             old_text,
         } => {
             // Save current text as redo entry
-            let current_text =
-                resolve_block_at_index(&state.days, &state.linked_refs, state.selected_block)
-                    .filter(|info| info.block_uid == block_uid)
-                    .map(|info| info.text.clone())
-                    .or_else(|| {
-                        find_block_in_days(&state.days, &block_uid).map(|b| b.string.clone())
-                    })
-                    .unwrap_or_default();
+            let current_text = resolve_block_at_index(
+                &state.days,
+                &state.linked_refs,
+                &state.embeds,
+                state.selected_block,
+            )
+            .filter(|info| info.block_uid == block_uid)
+            .map(|info| info.text.clone())
+            .or_else(|| find_block_in_days(&state.days, &block_uid).map(|b| b.string.clone()))
+            .or_else(|| embedded_text(state, &block_uid))
+            .or_else(|| state.block_ref_cache.get(&block_uid).cloned())
+            .unwrap_or_default();
             update_block_text_in_days(&mut state.days, &block_uid, &old_text);
+            update_embedded_text(state, &block_uid, &old_text);
             let redo = UndoEntry::TextEdit {
                 block_uid: block_uid.clone(),
                 old_text: current_text,
@@ -83,23 +91,16 @@ fn apply_undo_entry(state: &mut AppState, entry: UndoEntry) -> (UndoEntry, Write
             selected_block,
         } => {
             let uid = block.uid.clone();
-            let text = block.string.clone();
+            // Recreate the children too, not just the block itself
+            let mut actions = create_subtree_actions(&parent_uid, order, &block);
             insert_block_in_days(&mut state.days, &parent_uid, order, block);
             state.selected_block = selected_block;
             state.cursor_col = 0;
-            let redo = UndoEntry::CreateBlock {
-                block_uid: uid.clone(),
-            };
-            let write = WriteAction::CreateBlock {
-                location: BlockLocation {
-                    parent_uid,
-                    order: OrderValue::Index(order),
-                },
-                block: NewBlock {
-                    string: text,
-                    uid: Some(uid),
-                    open: None,
-                },
+            let redo = UndoEntry::CreateBlock { block_uid: uid };
+            let write = if actions.len() == 1 {
+                actions.remove(0)
+            } else {
+                WriteAction::BatchActions { actions }
             };
             (redo, write)
         }
@@ -138,5 +139,55 @@ fn apply_undo_entry(state: &mut AppState, entry: UndoEntry) -> (UndoEntry, Write
             };
             (redo, write)
         }
+        UndoEntry::RenamePage {
+            page_uid,
+            old_title,
+            new_title,
+            edits,
+        } => {
+            let texts: Vec<(String, String)> = edits
+                .iter()
+                .map(|e| (e.uid.clone(), e.old_text.clone()))
+                .collect();
+            apply_page_rename(state, &new_title, &old_title, &texts);
+            let write = rename_write_action(&page_uid, &old_title, texts);
+            let redo = UndoEntry::RenamePage {
+                page_uid,
+                old_title: new_title,
+                new_title: old_title,
+                edits: edits
+                    .into_iter()
+                    .map(|e| PageRenameEdit {
+                        old_text: e.new_text,
+                        new_text: e.old_text,
+                        ..e
+                    })
+                    .collect(),
+            };
+            (redo, write)
+        }
+        UndoEntry::Batch {
+            entries,
+            selected_block,
+        } => {
+            let current_selected = state.selected_block;
+            let mut redo_entries = Vec::with_capacity(entries.len());
+            let mut actions = Vec::with_capacity(entries.len());
+            // Entries are stored in the order the steps ran, so undo walks them
+            // backwards; the redo entries come out reversed, ready for the same walk.
+            for entry in entries.into_iter().rev() {
+                let (redo, write) = apply_undo_entry(state, entry);
+                redo_entries.push(redo);
+                actions.push(write);
+            }
+            let total = state.flat_block_count();
+            state.selected_block = selected_block.min(total.saturating_sub(1));
+            state.cursor_col = 0;
+            let redo = UndoEntry::Batch {
+                entries: redo_entries,
+                selected_block: current_selected,
+            };
+            (redo, WriteAction::BatchActions { actions })
+        }
     }
 }
@@ -0,0 +1,257 @@
+//! Vim normal mode inside the block editor: motions, operators and text objects.
+//!
+//! Only used with the vim preset, where Esc in the editor enters this mode
+//! and a second Esc saves the block.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::api::types::WriteAction;
+use crate::edit_buffer::EditBuffer;
+
+use super::input::finalize_insert;
+use super::state::{AppState, InputMode, VimNormal};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    /// The keys so far start a longer command.
+    Pending,
+    Move(usize),
+    /// Apply an operator to `start..end`.
+    Operate(Operator, usize, usize),
+    /// Back to insert mode with the cursor here.
+    Insert(usize),
+    Put {
+        before: bool,
+    },
+    ToggleVisual,
+    Nothing,
+}
+
+/// Leave insert mode for normal mode, stepping back onto the last typed character.
+pub(super) fn enter_normal(state: &mut AppState) {
+    if let InputMode::Insert {
+        buffer, vim_normal, ..
+    } = &mut state.input_mode
+    {
+        buffer.anchor = None;
+        if buffer.cursor > buffer.line_start() {
+            buffer.cursor -= 1;
+        }
+        *vim_normal = Some(VimNormal::default());
+    }
+}
+
+pub(super) fn handle_vim_normal_key(state: &mut AppState, key: &KeyEvent) -> Option<WriteAction> {
+    let InputMode::Insert {
+        buffer, vim_normal, ..
+    } = &mut state.input_mode
+    else {
+        return None;
+    };
+    let normal = vim_normal.as_mut()?;
+
+    if key.code == KeyCode::Esc {
+        if !normal.pending.is_empty() {
+            normal.pending.clear();
+        } else if buffer.anchor.is_some() {
+            buffer.anchor = None;
+        } else {
+            return finalize_insert(state);
+        }
+        return None;
+    }
+
+    let ch = match (key.modifiers, key.code) {
+        (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => c,
+        (KeyModifiers::NONE, KeyCode::Left) => 'h',
+        (KeyModifiers::NONE, KeyCode::Right) => 'l',
+        (KeyModifiers::NONE, KeyCode::Up) => 'k',
+        (KeyModifiers::NONE, KeyCode::Down) => 'j',
+        (KeyModifiers::NONE, KeyCode::Home) => '0',
+        (KeyModifiers::NONE, KeyCode::End) => '$',
+        _ => {
+            normal.pending.clear();
+            return None;
+        }
+    };
+    normal.pending.push(ch);
+    let keys = std::mem::take(&mut normal.pending);
+
+    match parse_command(&keys, buffer) {
+        Command::Pending => normal.pending = keys,
+        Command::Nothing => {}
+        Command::Move(pos) => {
+            buffer.cursor = pos;
+            clamp_cursor(buffer);
+        }
+        Command::Operate(op, start, end) => {
+            let text = if op == Operator::Yank {
+                buffer.cursor = start;
+                buffer.chars[start..end].iter().collect()
+            } else {
+                buffer.delete_range(start, end)
+            };
+            buffer.anchor = None;
+            if op == Operator::Change {
+                *vim_normal = None;
+            } else {
+                clamp_cursor(buffer);
+            }
+            state.pending_clipboard = Some(text.clone());
+            state.text_register = text;
+        }
+        Command::Insert(pos) => {
+            buffer.cursor = pos;
+            buffer.anchor = None;
+            *vim_normal = None;
+        }
+        Command::Put { before } => {
+            let text = &state.text_register;
+            if !text.is_empty() {
+                buffer.anchor = None;
+                if !before && buffer.cursor < buffer.chars.len() {
+                    buffer.cursor += 1;
+                }
+                buffer.insert_str(text);
+                buffer.cursor -= 1;
+            }
+        }
+        Command::ToggleVisual => {
+            buffer.anchor = match buffer.anchor {
+                Some(_) => None,
+                None => Some(buffer.cursor),
+            };
+        }
+    }
+    None
+}
+
+fn parse_command(keys: &str, buffer: &EditBuffer) -> Command {
+    let mut chars = keys.chars();
+    let Some(first) = chars.next() else {
+        return Command::Nothing;
+    };
+    let rest = chars.as_str();
+    let cursor = buffer.cursor;
+    let len = buffer.chars.len();
+    let selection = buffer.selection_inclusive();
+
+    let operator = match first {
+        'd' => Some(Operator::Delete),
+        'c' => Some(Operator::Change),
+        'y' => Some(Operator::Yank),
+        _ => None,
+    };
+    if let Some(op) = operator {
+        return match selection {
+            Some((start, end)) => Command::Operate(op, start, end),
+            None => operator_command(op, first, rest, buffer),
+        };
+    }
+
+    match first {
+        'x' => match selection {
+            Some((start, end)) => Command::Operate(Operator::Delete, start, end),
+            None if cursor < len => Command::Operate(Operator::Delete, cursor, cursor + 1),
+            None => Command::Nothing,
+        },
+        'D' => Command::Operate(Operator::Delete, cursor, buffer.line_end()),
+        'C' => Command::Operate(Operator::Change, cursor, buffer.line_end()),
+        'i' => Command::Insert(cursor),
+        'a' => Command::Insert((cursor + 1).min(len)),
+        'I' => Command::Insert(buffer.line_start()),
+        'A' => Command::Insert(buffer.line_end()),
+        'p' => Command::Put { before: false },
+        'P' => Command::Put { before: true },
+        'v' => Command::ToggleVisual,
+        _ => match motion(first, buffer) {
+            Some(pos) => Command::Move(pos),
+            None => Command::Nothing,
+        },
+    }
+}
+
+/// An operator waiting for its motion or text object: `dw`, `ci(`, `yy`.
+fn operator_command(op: Operator, key: char, rest: &str, buffer: &EditBuffer) -> Command {
+    let mut chars = rest.chars();
+    let Some(target) = chars.next() else {
+        return Command::Pending;
+    };
+    let cursor = buffer.cursor;
+    let range = match target {
+        // Doubled operator: the whole line, with its line break for `dd`
+        c if c == key => {
+            let (start, end) = (buffer.line_start(), buffer.line_end());
+            if op != Operator::Delete {
+                Some((start, end))
+            } else if end < buffer.chars.len() {
+                Some((start, end + 1))
+            } else {
+                Some((start.saturating_sub(1), end))
+            }
+        }
+        'i' | 'a' => match chars.next() {
+            None => return Command::Pending,
+            Some(object) => text_object(object, target == 'a', buffer),
+        },
+        // `e` includes the character it lands on, and `cw` changes to the
+        // end of the word like `ce`
+        'e' | 'w' if target == 'e' || op == Operator::Change => {
+            Some((cursor, (buffer.word_end() + 1).min(buffer.chars.len())))
+        }
+        m => motion(m, buffer).map(|pos| (pos.min(cursor), pos.max(cursor))),
+    };
+    match range {
+        Some((start, end)) if start < end => Command::Operate(op, start, end),
+        _ => Command::Nothing,
+    }
+}
+
+fn motion(key: char, buffer: &EditBuffer) -> Option<usize> {
+    let cursor = buffer.cursor;
+    Some(match key {
+        'h' => cursor.saturating_sub(1).max(buffer.line_start()),
+        'l' => (cursor + 1).min(buffer.line_end()),
+        'j' | 'k' => {
+            let mut moved = buffer.clone();
+            if key == 'j' {
+                moved.move_down();
+            } else {
+                moved.move_up();
+            }
+            moved.cursor
+        }
+        'w' => buffer.next_word_start(),
+        'b' => buffer.prev_word_start(),
+        'e' => buffer.word_end(),
+        '0' => buffer.line_start(),
+        '$' => buffer.line_end(),
+        _ => return None,
+    })
+}
+
+fn text_object(key: char, around: bool, buffer: &EditBuffer) -> Option<(usize, usize)> {
+    match key {
+        'w' => buffer.word_object(around),
+        '(' | ')' | 'b' => buffer.bracket_object('(', ')', around),
+        '[' | ']' => buffer.bracket_object('[', ']', around),
+        '{' | '}' | 'B' => buffer.bracket_object('{', '}', around),
+        _ => None,
+    }
+}
+
+/// In normal mode the cursor sits on a character, never past the end of a line.
+fn clamp_cursor(buffer: &mut EditBuffer) {
+    let len = buffer.chars.len();
+    buffer.cursor = buffer.cursor.min(len.saturating_sub(1));
+    if buffer.chars.get(buffer.cursor) == Some(&'\n') && buffer.cursor > buffer.line_start() {
+        buffer.cursor -= 1;
+    }
+}
@@ -0,0 +1,464 @@
+//! Visual mode: a contiguous range of blocks that block edits apply to at once.
+//!
+//! Every edit here also works without a selection, on the selected block
+//! alone, so the single-block actions share the same code path.
+
+use std::collections::HashSet;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::api::types::{BlockLocation, BlockRef, BlockUpdate, OrderValue, WriteAction};
+use crate::edit_buffer::EditBuffer;
+use crate::keys::preset::Action;
+
+use super::blocks::{
+    child_uids, dedent_block_in_days, find_block_in_days, find_block_index_by_uid,
+    find_block_parent_info, indent_block_in_days, move_block_in_days, remove_block_from_days,
+    resolve_block_at_index, set_block_open, update_block_text_in_days,
+};
+use super::embeds::update_embedded_text;
+use super::state::{count_blocks_recursive, AppState, BlockInfo, UndoEntry};
+use super::yank::{copy_block_refs, copy_markdown, paste, yank_selection};
+
+/// Actions that edit blocks and therefore apply to the whole visual selection.
+pub(super) fn is_block_edit(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::DeleteBlock
+            | Action::Indent
+            | Action::Unindent
+            | Action::MoveBlockUp
+            | Action::MoveBlockDown
+            | Action::ToggleTodo
+            | Action::Copy
+            | Action::CopyBlockRef
+            | Action::CopyMarkdown
+            | Action::Paste
+            | Action::PasteAbove
+    )
+}
+
+/// Vim operators available on a selection when the key has no other binding.
+pub(super) fn visual_key_action(key: &KeyEvent) -> Option<Action> {
+    if !matches!(key.modifiers, KeyModifiers::NONE | KeyModifiers::SHIFT) {
+        return None;
+    }
+    match key.code {
+        KeyCode::Char('d') | KeyCode::Char('x') => Some(Action::DeleteBlock),
+        KeyCode::Char('y') => Some(Action::Copy),
+        KeyCode::Char('>') => Some(Action::Indent),
+        KeyCode::Char('<') => Some(Action::Unindent),
+        _ => None,
+    }
+}
+
+pub(super) fn toggle_visual_mode(state: &mut AppState) {
+    state.visual_anchor = match state.visual_anchor {
+        Some(_) => None,
+        None => Some(state.selected_block),
+    };
+}
+
+/// Shift+arrow: start a selection at the current block if needed, then move.
+pub(super) fn extend_selection(state: &mut AppState, up: bool) {
+    if state.visual_anchor.is_none() {
+        state.visual_anchor = Some(state.selected_block);
+    }
+    if up {
+        state.selected_block = state.selected_block.saturating_sub(1);
+    } else if state.selected_block + 1 < state.total_navigable_count() {
+        state.selected_block += 1;
+    }
+    state.cursor_col = 0;
+}
+
+/// Collapse or expand every selected block that has children, then leave visual mode.
+pub(super) fn set_selection_open(state: &mut AppState, open: bool) {
+    let (start, _) = selection_bounds(state);
+    for info in selected_blocks(state) {
+        let has_children = find_block_in_days(&state.days, &info.block_uid)
+            .is_some_and(|b| !b.children.is_empty());
+        if has_children {
+            set_block_open(&mut state.days, &info.block_uid, open);
+        }
+    }
+    // Blocks above the first selected one are untouched, so its index still holds
+    state.visual_anchor = None;
+    state.selected_block = start;
+    state.cursor_col = 0;
+}
+
+/// Apply a block edit to the selection (or the selected block). Returns the
+/// write to send; bulk edits become one batch and one undo entry.
+pub(super) fn apply_block_edit(state: &mut AppState, action: &Action) -> Option<WriteAction> {
+    match action {
+        Action::DeleteBlock => delete_selection(state),
+        Action::Indent => indent_selection(state),
+        Action::Unindent => dedent_selection(state),
+        Action::MoveBlockUp => move_selection(state, true),
+        Action::MoveBlockDown => move_selection(state, false),
+        Action::ToggleTodo => toggle_todo_selection(state),
+        Action::Copy => {
+            yank_selection(state);
+            None
+        }
+        Action::CopyBlockRef => {
+            copy_block_refs(state);
+            None
+        }
+        Action::CopyMarkdown => {
+            copy_markdown(state);
+            None
+        }
+        Action::Paste => paste(state, true),
+        Action::PasteAbove => paste(state, false),
+        _ => None,
+    }
+}
+
+fn selection_bounds(state: &AppState) -> (usize, usize) {
+    state
+        .visual_range()
+        .unwrap_or((state.selected_block, state.selected_block))
+}
+
+/// Every selected block in document order; linked reference rows are skipped.
+fn selected_blocks(state: &AppState) -> Vec<BlockInfo> {
+    let (start, end) = selection_bounds(state);
+    (start..=end)
+        .filter_map(|idx| {
+            resolve_block_at_index(&state.days, &state.linked_refs, &state.embeds, idx)
+        })
+        .collect()
+}
+
+/// Selected blocks whose parent isn't selected: the subtrees an edit moves as a whole.
+pub(super) fn selection_roots(state: &AppState) -> Vec<BlockInfo> {
+    let blocks = selected_blocks(state);
+    let uids: HashSet<&str> = blocks.iter().map(|b| b.block_uid.as_str()).collect();
+    blocks
+        .iter()
+        .filter(|b| !uids.contains(b.parent_uid.as_str()))
+        .cloned()
+        .collect()
+}
+
+pub(super) fn push_undo(state: &mut AppState, mut entries: Vec<UndoEntry>, selected_block: usize) {
+    let entry = match entries.len() {
+        0 => return,
+        1 => entries.pop().unwrap(),
+        _ => UndoEntry::Batch {
+            entries,
+            selected_block,
+        },
+    };
+    state.undo_stack.push(entry);
+    state.redo_stack.clear();
+}
+
+pub(super) fn batch(mut actions: Vec<WriteAction>) -> Option<WriteAction> {
+    match actions.len() {
+        0 => None,
+        1 => actions.pop(),
+        _ => Some(WriteAction::BatchActions { actions }),
+    }
+}
+
+pub(super) fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Reselect the blocks spanning `first_uid` to the end of `last_uid`'s visible
+/// subtree after they moved, keeping the cursor at the same end of the range.
+fn reselect(state: &mut AppState, first_uid: &str, last_uid: &str) {
+    let Some(first) =
+        find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, first_uid)
+    else {
+        return;
+    };
+    let last_start =
+        find_block_index_by_uid(&state.days, &state.linked_refs, &state.embeds, last_uid)
+            .unwrap_or(first);
+    let hidden = find_block_in_days(&state.days, last_uid)
+        .map(|b| count_blocks_recursive(std::slice::from_ref(&b), &state.embeds) - 1)
+        .unwrap_or(0);
+    let last = last_start + hidden;
+    match state.visual_anchor {
+        Some(anchor) if anchor > state.selected_block => {
+            state.visual_anchor = Some(last);
+            state.selected_block = first;
+        }
+        Some(_) => {
+            state.visual_anchor = Some(first);
+            state.selected_block = last;
+        }
+        None => state.selected_block = first,
+    }
+    state.cursor_col = 0;
+}
+
+fn delete_selection(state: &mut AppState) -> Option<WriteAction> {
+    let roots = selection_roots(state);
+    if roots.is_empty() {
+        return None;
+    }
+    let (start, _) = selection_bounds(state);
+    let saved_selected = state.selected_block;
+    let mut entries = Vec::new();
+    let mut actions = Vec::new();
+    for info in roots {
+        let uid = info.block_uid;
+        let (Some(block), Some((parent_uid, order))) = (
+            find_block_in_days(&state.days, &uid),
+            find_block_parent_info(&state.days, &uid),
+        ) else {
+            continue;
+        };
+        remove_block_from_days(&mut state.days, &uid);
+        entries.push(UndoEntry::DeleteBlock {
+            block,
+            parent_uid,
+            order,
+            selected_block: saved_selected,
+        });
+        actions.push(WriteAction::DeleteBlock {
+            block: BlockRef { uid },
+        });
+    }
+
+    let was_visual = state.visual_anchor.take().is_some();
+    let total = state.flat_block_count();
+    state.selected_block = start.min(total.saturating_sub(1));
+    state.cursor_col = 0;
+    if was_visual {
+        state.status_message = Some(format!(
+            "Deleted {} block{}",
+            entries.len(),
+            plural(entries.len())
+        ));
+    }
+    push_undo(state, entries, saved_selected);
+    batch(actions)
+}
+
+fn indent_selection(state: &mut AppState) -> Option<WriteAction> {
+    let roots = selection_roots(state);
+    let (first, last) = (roots.first()?.clone(), roots.last()?.clone());
+    let saved_selected = state.selected_block;
+    let mut entries = Vec::new();
+    let mut actions = Vec::new();
+    for info in &roots {
+        let uid = &info.block_uid;
+        let Some((old_parent_uid, old_order)) = find_block_parent_info(&state.days, uid) else {
+            continue;
+        };
+        let Some((new_parent_uid, _)) = indent_block_in_days(&mut state.days, uid) else {
+            continue;
+        };
+        entries.push(UndoEntry::MoveBlock {
+            block_uid: uid.clone(),
+            old_parent_uid,
+            old_order,
+            selected_block: saved_selected,
+        });
+        actions.push(WriteAction::MoveBlock {
+            block: BlockRef { uid: uid.clone() },
+            location: BlockLocation {
+                parent_uid: new_parent_uid,
+                order: OrderValue::Position("last".into()),
+            },
+        });
+    }
+    reselect(state, &first.block_uid, &last.block_uid);
+    push_undo(state, entries, saved_selected);
+    batch(actions)
+}
+
+fn dedent_selection(state: &mut AppState) -> Option<WriteAction> {
+    let roots = selection_roots(state);
+    let (first, last) = (roots.first()?.clone(), roots.last()?.clone());
+    let saved_selected = state.selected_block;
+    let mut entries = Vec::new();
+    let mut actions = Vec::new();
+    // Bottom-up, so each block lands right after its old parent and above
+    // the blocks dedented before it
+    for info in roots.iter().rev() {
+        let uid = &info.block_uid;
+        let Some((old_parent_uid, old_order)) = find_block_parent_info(&state.days, uid) else {
+            continue;
+        };
+        let Some((new_parent_uid, new_order)) = dedent_block_in_days(&mut state.days, uid) else {
+            continue;
+        };
+        entries.push(UndoEntry::MoveBlock {
+            block_uid: uid.clone(),
+            old_parent_uid,
+            old_order,
+            selected_block: saved_selected,
+        });
+        actions.push(WriteAction::MoveBlock {
+            block: BlockRef { uid: uid.clone() },
+            location: BlockLocation {
+                parent_uid: new_parent_uid,
+                order: OrderValue::Index(new_order),
+            },
+        });
+    }
+    reselect(state, &first.block_uid, &last.block_uid);
+    push_undo(state, entries, saved_selected);
+    batch(actions)
+}
+
+/// Move the selected sibling run past its neighbour by moving the neighbour
+/// to the other side, which is a single write however many blocks are selected.
+/// At the first or last sibling the run crosses into the adjacent parent instead.
+fn move_selection(state: &mut AppState, up: bool) -> Option<WriteAction> {
+    let roots = selection_roots(state);
+    let (first, last) = (roots.first()?.clone(), roots.last()?.clone());
+    let parent_uid = first.parent_uid.clone();
+    if roots.iter().any(|r| r.parent_uid != parent_uid) {
+        state.status_message = Some("Can't move blocks from different levels".into());
+        return None;
+    }
+    let siblings = child_uids(&state.days, &parent_uid)?;
+    let start = siblings.iter().position(|uid| *uid == first.block_uid)?;
+    let end = start + roots.len() - 1;
+    let (neighbour_idx, target) = if up {
+        match start.checked_sub(1) {
+            Some(idx) => (idx, end),
+            None => return move_selection_across(state, &roots, up),
+        }
+    } else if end + 1 < siblings.len() {
+        (end + 1, start)
+    } else {
+        return move_selection_across(state, &roots, up);
+    };
+    let neighbour = siblings[neighbour_idx].clone();
+    let saved_selected = state.selected_block;
+
+    move_block_in_days(&mut state.days, &neighbour, &parent_uid, target as i64);
+    reselect(state, &first.block_uid, &last.block_uid);
+    push_undo(
+        state,
+        vec![UndoEntry::MoveBlock {
+            block_uid: neighbour.clone(),
+            old_parent_uid: parent_uid.clone(),
+            old_order: neighbour_idx as i64,
+            selected_block: saved_selected,
+        }],
+        saved_selected,
+    );
+    Some(WriteAction::MoveBlock {
+        block: BlockRef { uid: neighbour },
+        location: BlockLocation {
+            parent_uid,
+            order: OrderValue::Index(target as i64),
+        },
+    })
+}
+
+/// Move a run that is already at the edge of its parent into the neighbouring
+/// parent, keeping the blocks where they appear on screen.
+fn move_selection_across(
+    state: &mut AppState,
+    roots: &[BlockInfo],
+    up: bool,
+) -> Option<WriteAction> {
+    let (first, last) = (roots.first()?, roots.last()?);
+    let (target_parent, target_order) = edge_target(state, &first.parent_uid, up)?;
+    let saved_selected = state.selected_block;
+    // Moving into a collapsed block would hide the selection
+    set_block_open(&mut state.days, &target_parent, true);
+    let mut entries = Vec::new();
+    let mut actions = Vec::new();
+    for (i, info) in roots.iter().enumerate() {
+        let uid = &info.block_uid;
+        let Some((old_parent_uid, old_order)) = find_block_parent_info(&state.days, uid) else {
+            continue;
+        };
+        let order = target_order + i as i64;
+        move_block_in_days(&mut state.days, uid, &target_parent, order);
+        entries.push(UndoEntry::MoveBlock {
+            block_uid: uid.clone(),
+            old_parent_uid,
+            old_order,
+            selected_block: saved_selected,
+        });
+        actions.push(WriteAction::MoveBlock {
+            block: BlockRef { uid: uid.clone() },
+            location: BlockLocation {
+                parent_uid: target_parent.clone(),
+                order: OrderValue::Index(order),
+            },
+        });
+    }
+    reselect(state, &first.block_uid, &last.block_uid);
+    push_undo(state, entries, saved_selected);
+    batch(actions)
+}
+
+/// Where a run leaving the top (or bottom) of `parent_uid` goes: the end of the
+/// parent's previous sibling (or start of its next one), out of the parent when
+/// it has no such sibling, and for top-level blocks the adjacent day.
+fn edge_target(state: &AppState, parent_uid: &str, up: bool) -> Option<(String, i64)> {
+    if let Some(day_idx) = state.days.iter().position(|d| d.uid == parent_uid) {
+        // Days are listed newest first, so the day above is the newer one
+        let day = if up {
+            state.days.get(day_idx.checked_sub(1)?)?
+        } else {
+            state.days.get(day_idx + 1)?
+        };
+        let order = if up { day.blocks.len() as i64 } else { 0 };
+        return Some((day.uid.clone(), order));
+    }
+    let (grandparent_uid, _) = find_block_parent_info(&state.days, parent_uid)?;
+    let uncles = child_uids(&state.days, &grandparent_uid)?;
+    let idx = uncles.iter().position(|uid| uid == parent_uid)?;
+    let neighbour = if up {
+        idx.checked_sub(1)
+    } else {
+        Some(idx + 1).filter(|&i| i < uncles.len())
+    };
+    match neighbour {
+        Some(n) => {
+            let order = if up {
+                child_uids(&state.days, &uncles[n])?.len() as i64
+            } else {
+                0
+            };
+            Some((uncles[n].clone(), order))
+        }
+        None => Some((grandparent_uid, (idx + !up as usize) as i64)),
+    }
+}
+
+/// Cycle each selected block through TODO → DONE → plain.
+fn toggle_todo_selection(state: &mut AppState) -> Option<WriteAction> {
+    let blocks = selected_blocks(state);
+    let saved_selected = state.selected_block;
+    let mut entries = Vec::new();
+    let mut actions = Vec::new();
+    for info in blocks {
+        let mut buffer = EditBuffer::new(&info.text);
+        buffer.toggle_todo();
+        let new_text = buffer.to_string();
+        update_block_text_in_days(&mut state.days, &info.block_uid, &new_text);
+        update_embedded_text(state, &info.block_uid, &new_text);
+        entries.push(UndoEntry::TextEdit {
+            block_uid: info.block_uid.clone(),
+            old_text: info.text,
+        });
+        actions.push(WriteAction::UpdateBlock {
+            block: BlockUpdate {
+                uid: info.block_uid,
+                string: new_text,
+            },
+        });
+    }
+    push_undo(state, entries, saved_selected);
+    batch(actions)
+}
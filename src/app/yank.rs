@@ -0,0 +1,163 @@
+//! Yank and paste of block subtrees, and copying blocks to the system clipboard.
+
+use std::collections::HashMap;
+
+use crate::api::types::{Block, BlockLocation, NewBlock, OrderValue, WriteAction};
+use crate::markdown;
+
+use super::blocks::{
+    find_block_in_days, find_block_index_by_uid, generate_uid, insert_block_in_days,
+};
+use super::state::{AppState, UndoEntry};
+use super::visual::{batch, plural, push_undo, selection_roots};
+
+/// The selected subtrees, outermost blocks in document order.
+fn selected_subtrees(state: &AppState) -> Vec<Block> {
+    selection_roots(state)
+        .iter()
+        .filter_map(|info| find_block_in_days(&state.days, &info.block_uid))
+        .collect()
+}
+
+/// Yank the selected subtrees into the register and put their text on the
+/// clipboard as an outline that Roam pastes back as blocks.
+pub(super) fn yank_selection(state: &mut AppState) {
+    let blocks = selected_subtrees(state);
+    if blocks.is_empty() {
+        return;
+    }
+    let count = blocks.len();
+    state.pending_clipboard = Some(markdown::blocks_to_markdown(&blocks));
+    state.register = blocks;
+    state.visual_anchor = None;
+    state.status_message = Some(format!("Copied {} block{}", count, plural(count)));
+}
+
+/// Copy `((uid))` references to the selected blocks, one per line.
+pub(super) fn copy_block_refs(state: &mut AppState) {
+    let refs: Vec<String> = selection_roots(state)
+        .iter()
+        .map(|info| format!("(({}))", info.block_uid))
+        .collect();
+    if refs.is_empty() {
+        return;
+    }
+    state.status_message = Some(match refs.as_slice() {
+        [single] => format!("Copied block ref {}", single),
+        _ => format!("Copied {} block refs", refs.len()),
+    });
+    state.pending_clipboard = Some(refs.join("\n"));
+    state.visual_anchor = None;
+}
+
+/// Text of every loaded or resolved block by uid, for resolving block refs on export.
+pub(super) fn block_text_map(state: &AppState) -> HashMap<String, String> {
+    let mut block_map = markdown::build_block_text_map(&state.days);
+    block_map.extend(
+        state
+            .block_ref_cache
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone())),
+    );
+    block_map
+}
+
+/// Copy the selected subtrees as standard Markdown, with block refs resolved.
+pub(super) fn copy_markdown(state: &mut AppState) {
+    let blocks = selected_subtrees(state);
+    if blocks.is_empty() {
+        return;
+    }
+    let block_map = block_text_map(state);
+    let count = blocks.len();
+    state.pending_clipboard = Some(markdown::export_markdown(&blocks, &block_map));
+    state.visual_anchor = None;
+    state.status_message = Some(format!(
+        "Copied {} block{} as Markdown",
+        count,
+        plural(count)
+    ));
+}
+
+/// Paste the register below (or above) the selection as new blocks with fresh uids.
+pub(super) fn paste(state: &mut AppState, below: bool) -> Option<WriteAction> {
+    if state.register.is_empty() {
+        state.status_message = Some("Nothing to paste".into());
+        return None;
+    }
+    let roots = selection_roots(state);
+    let (parent_uid, order) = match if below { roots.last() } else { roots.first() } {
+        Some(info) => (info.parent_uid.clone(), info.order + below as i64),
+        // Empty page: paste as its first blocks
+        None if state.flat_block_count() == 0 => {
+            let day = state.days.first().filter(|d| !d.uid.is_empty())?;
+            (day.uid.clone(), 0)
+        }
+        None => return None,
+    };
+    state.visual_anchor = None;
+
+    let saved_selected = state.selected_block;
+    let blocks: Vec<Block> = state.register.iter().map(with_fresh_uids).collect();
+    let mut entries = Vec::new();
+    let mut actions = Vec::new();
+    for (i, block) in blocks.iter().enumerate() {
+        let block_order = order + i as i64;
+        insert_block_in_days(&mut state.days, &parent_uid, block_order, block.clone());
+        actions.extend(create_subtree_actions(&parent_uid, block_order, block));
+        entries.push(UndoEntry::CreateBlock {
+            block_uid: block.uid.clone(),
+        });
+    }
+    if let Some(idx) = find_block_index_by_uid(
+        &state.days,
+        &state.linked_refs,
+        &state.embeds,
+        &blocks[0].uid,
+    ) {
+        state.selected_block = idx;
+        state.cursor_col = 0;
+    }
+    state.status_message = Some(format!(
+        "Pasted {} block{}",
+        blocks.len(),
+        plural(blocks.len())
+    ));
+    push_undo(state, entries, saved_selected);
+    batch(actions)
+}
+
+fn with_fresh_uids(block: &Block) -> Block {
+    Block {
+        uid: generate_uid(),
+        string: block.string.clone(),
+        order: block.order,
+        children: block.children.iter().map(with_fresh_uids).collect(),
+        open: block.open,
+        refs: block.refs.clone(),
+    }
+}
+
+/// Writes that create `block` at `order` under `parent_uid` and then its
+/// descendants, parents first so every child has somewhere to go.
+pub(super) fn create_subtree_actions(
+    parent_uid: &str,
+    order: i64,
+    block: &Block,
+) -> Vec<WriteAction> {
+    let mut actions = vec![WriteAction::CreateBlock {
+        location: BlockLocation {
+            parent_uid: parent_uid.to_string(),
+            order: OrderValue::Index(order),
+        },
+        block: NewBlock {
+            string: block.string.clone(),
+            uid: Some(block.uid.clone()),
+            open: (!block.open).then_some(false),
+        },
+    }];
+    for (i, child) in block.children.iter().enumerate() {
+        actions.extend(create_subtree_actions(&block.uid, i as i64, child));
+    }
+    actions
+}
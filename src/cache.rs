@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::LinkedRefFilter;
+use crate::config::AppConfig;
+use crate::error::{Result, RoamError};
+
+/// Local UI state persisted between sessions, stored next to config.toml.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct LinkedRefsCache {
+    #[serde(default)]
+    pages: HashMap<String, LinkedRefFilter>,
+}
+
+fn linked_refs_path() -> Option<PathBuf> {
+    AppConfig::config_dir().map(|dir| dir.join("linked_refs.toml"))
+}
+
+pub fn load_linked_ref_filters() -> Result<HashMap<String, LinkedRefFilter>> {
+    match linked_refs_path() {
+        Some(path) => load_linked_ref_filters_from(&path),
+        None => Ok(HashMap::new()),
+    }
+}
+
+pub fn save_linked_ref_filters(filters: &HashMap<String, LinkedRefFilter>) -> Result<()> {
+    match linked_refs_path() {
+        Some(path) => save_linked_ref_filters_to(&path, filters),
+        None => Ok(()),
+    }
+}
+
+fn load_linked_ref_filters_from(path: &Path) -> Result<HashMap<String, LinkedRefFilter>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    let cache: LinkedRefsCache = toml::from_str(&content)?;
+    Ok(cache.pages)
+}
+
+fn save_linked_ref_filters_to(
+    path: &Path,
+    filters: &HashMap<String, LinkedRefFilter>,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let cache = LinkedRefsCache {
+        pages: filters.clone(),
+    };
+    let content = toml::to_string(&cache).map_err(|e| RoamError::Config(e.to_string()))?;
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::LinkedRefSort;
+
+    #[test]
+    fn missing_file_loads_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let filters = load_linked_ref_filters_from(&dir.path().join("none.toml")).unwrap();
+        assert!(filters.is_empty());
+    }
+
+    #[test]
+    fn filters_roundtrip_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("linked_refs.toml");
+        let mut filters = HashMap::new();
+        filters.insert(
+            "My Page".to_string(),
+            LinkedRefFilter {
+                include: vec!["Project".into()],
+                exclude: vec!["Archive".into()],
+                sort: LinkedRefSort::NewestFirst,
+            },
+        );
+        filters.insert(
+            "February 21st, 2026".to_string(),
+            LinkedRefFilter {
+                sort: LinkedRefSort::OldestFirst,
+                ..Default::default()
+            },
+        );
+
+        save_linked_ref_filters_to(&path, &filters).unwrap();
+        let loaded = load_linked_ref_filters_from(&path).unwrap();
+        assert_eq!(loaded, filters);
+    }
+
+    #[test]
+    fn invalid_file_returns_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("linked_refs.toml");
+        std::fs::write(&path, "pages = 3").unwrap();
+        assert!(load_linked_ref_filters_from(&path).is_err());
+    }
+}
@@ -31,6 +31,9 @@ pub struct UiConfig {
     pub sidebar_default: bool,
     #[serde(default = "default_sidebar_width")]
     pub sidebar_width_percent: u8,
+    /// Capture the mouse for clicking and scrolling (disables terminal text selection).
+    #[serde(default)]
+    pub mouse: bool,
 }
 
 impl Default for UiConfig {
@@ -39,6 +42,7 @@ impl Default for UiConfig {
             theme: default_theme(),
             sidebar_default: default_sidebar(),
             sidebar_width_percent: default_sidebar_width(),
+            mouse: false,
         }
     }
 }
@@ -49,6 +53,18 @@ pub struct KeybindingsConfig {
     pub preset: String,
     #[serde(default)]
     pub bindings: HashMap<String, String>,
+    /// Block editor keys, by insert action name.
+    #[serde(default)]
+    pub insert: HashMap<String, String>,
+    /// Keys of the search, switcher, picker and menu popups.
+    #[serde(default)]
+    pub popup: HashMap<String, String>,
+    /// Key that `Leader` stands for in key sequences.
+    #[serde(default = "default_leader")]
+    pub leader: String,
+    /// How long to wait for the next key of a sequence, in milliseconds.
+    #[serde(default = "default_sequence_timeout")]
+    pub timeout_ms: u64,
 }
 
 impl Default for KeybindingsConfig {
@@ -56,6 +72,10 @@ impl Default for KeybindingsConfig {
         Self {
             preset: default_preset(),
             bindings: HashMap::new(),
+            insert: HashMap::new(),
+            popup: HashMap::new(),
+            leader: default_leader(),
+            timeout_ms: default_sequence_timeout(),
         }
     }
 }
@@ -76,6 +96,14 @@ fn default_preset() -> String {
     "vim".into()
 }
 
+fn default_leader() -> String {
+    crate::keys::DEFAULT_LEADER.into()
+}
+
+fn default_sequence_timeout() -> u64 {
+    1000
+}
+
 impl AppConfig {
     pub fn load_from_path(config_path: &Path) -> Result<Self> {
         let config: AppConfig = Figment::new()
@@ -124,14 +152,27 @@ api_token = ""  # or set ROAM_API_TOKEN env var
 theme = "dark"
 sidebar_default = true
 sidebar_width_percent = 35
+mouse = false  # click to select, double-click to edit, wheel to scroll
 
 [keybindings]
 preset = "vim"  # vim | emacs | vscode
+leader = "Space"  # key that "Leader" stands for in sequences
+timeout_ms = 1000  # wait for the next key of a sequence
 
-# Override specific keys:
+# Override specific keys; separate the keys of a sequence with spaces:
 # [keybindings.bindings]
 # quit = "Ctrl+q"
 # search = "Ctrl+f"
+# go_daily = "g g"
+# quick_switcher = "Leader f p"
+
+# Keys of the block editor and of list popups are single keys with a modifier:
+# [keybindings.insert]
+# word_left = "Alt+b"
+# word_right = "Alt+f"
+# [keybindings.popup]
+# select_next = "Ctrl+j"
+# select_prev = "Ctrl+k"
 "#;
 
         std::fs::write(path, content)?;
@@ -174,9 +215,12 @@ api_token = "token-123"
 
 [ui]
 theme = "light"
+mouse = true
 
 [keybindings]
 preset = "emacs"
+leader = ","
+timeout_ms = 500
 "#,
         );
 
@@ -184,7 +228,10 @@ preset = "emacs"
         assert_eq!(config.graph.name, "test-graph");
         assert_eq!(config.graph.api_token, "token-123");
         assert_eq!(config.ui.theme, "light");
+        assert!(config.ui.mouse);
         assert_eq!(config.keybindings.preset, "emacs");
+        assert_eq!(config.keybindings.leader, ",");
+        assert_eq!(config.keybindings.timeout_ms, 500);
     }
 
     #[test]
@@ -203,7 +250,10 @@ api_token = "token-123"
         assert_eq!(config.ui.theme, "dark");
         assert!(config.ui.sidebar_default);
         assert_eq!(config.ui.sidebar_width_percent, 35);
+        assert!(!config.ui.mouse);
         assert_eq!(config.keybindings.preset, "vim");
+        assert_eq!(config.keybindings.leader, "Space");
+        assert_eq!(config.keybindings.timeout_ms, 1000);
     }
 
     #[test]
@@ -293,12 +343,23 @@ preset = "vim"
 [keybindings.bindings]
 quit = "Ctrl+q"
 search = "Ctrl+f"
+
+[keybindings.insert]
+word_left = "Alt+b"
+
+[keybindings.popup]
+select_next = "Ctrl+j"
 "#,
         );
 
         let config = AppConfig::load_from_path(&path).unwrap();
         assert_eq!(config.keybindings.bindings.get("quit").unwrap(), "Ctrl+q");
         assert_eq!(config.keybindings.bindings.get("search").unwrap(), "Ctrl+f");
+        assert_eq!(config.keybindings.insert.get("word_left").unwrap(), "Alt+b");
+        assert_eq!(
+            config.keybindings.popup.get("select_next").unwrap(),
+            "Ctrl+j"
+        );
     }
 
     #[test]
@@ -2,34 +2,67 @@
 pub struct EditBuffer {
     pub chars: Vec<char>,
     pub cursor: usize,
+    /// Where the selection started; it runs from here to the cursor.
+    pub anchor: Option<usize>,
 }
 
 impl EditBuffer {
     pub fn new(text: &str) -> Self {
         let chars: Vec<char> = text.chars().collect();
         let cursor = chars.len();
-        Self { chars, cursor }
+        Self {
+            chars,
+            cursor,
+            anchor: None,
+        }
     }
 
     pub fn new_empty() -> Self {
         Self {
             chars: Vec::new(),
             cursor: 0,
+            anchor: None,
         }
     }
 
     pub fn insert_char(&mut self, ch: char) {
+        self.delete_selection();
         self.chars.insert(self.cursor, ch);
         self.cursor += 1;
     }
 
+    /// Insert a bracket pair around the cursor, or around the selection,
+    /// which stays selected so `[` twice makes a `[[link]]`.
     pub fn insert_pair(&mut self, open: char, close: char) {
+        if let Some((start, end)) = self.selection() {
+            self.chars.insert(end, close);
+            self.chars.insert(start, open);
+            let forward = self.cursor == end;
+            let (start, end) = (start + 1, end + 1);
+            (self.anchor, self.cursor) = if forward {
+                (Some(start), end)
+            } else {
+                (Some(end), start)
+            };
+            return;
+        }
         self.chars.insert(self.cursor, open);
         self.chars.insert(self.cursor + 1, close);
         self.cursor += 1; // cursor between open and close
     }
 
+    pub fn insert_str(&mut self, text: &str) {
+        self.delete_selection();
+        let new_chars: Vec<char> = text.chars().collect();
+        let len = new_chars.len();
+        self.chars.splice(self.cursor..self.cursor, new_chars);
+        self.cursor += len;
+    }
+
     pub fn delete_back(&mut self) {
+        if self.delete_selection().is_some() {
+            return;
+        }
         if self.cursor > 0 {
             self.cursor -= 1;
             self.chars.remove(self.cursor);
@@ -37,11 +70,60 @@ impl EditBuffer {
     }
 
     pub fn delete_forward(&mut self) {
+        if self.delete_selection().is_some() {
+            return;
+        }
         if self.cursor < self.chars.len() {
             self.chars.remove(self.cursor);
         }
     }
 
+    /// Start a selection at the cursor when `selecting`, otherwise drop it.
+    /// Call before a motion so Shift+motion extends and a plain motion clears.
+    pub fn set_selecting(&mut self, selecting: bool) {
+        if !selecting {
+            self.anchor = None;
+        } else if self.anchor.is_none() {
+            self.anchor = Some(self.cursor);
+        }
+    }
+
+    /// The selected range, start inclusive and end exclusive, if not empty.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?.min(self.chars.len());
+        let range = (anchor.min(self.cursor), anchor.max(self.cursor));
+        (range.0 < range.1).then_some(range)
+    }
+
+    /// The selection with the character under the cursor included, as in
+    /// vim's visual mode where the cursor sits on a character.
+    pub fn selection_inclusive(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?.min(self.chars.len());
+        let end = (anchor.max(self.cursor) + 1).min(self.chars.len());
+        let start = anchor.min(self.cursor);
+        (start < end).then_some((start, end))
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        Some(self.chars[start..end].iter().collect())
+    }
+
+    /// Remove the selection, leaving the cursor where it started.
+    pub fn delete_selection(&mut self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        self.anchor = None;
+        Some(self.delete_range(start, end))
+    }
+
+    /// Remove `start..end` and return it, leaving the cursor at `start`.
+    pub fn delete_range(&mut self, start: usize, end: usize) -> String {
+        let end = end.min(self.chars.len());
+        let start = start.min(end);
+        self.cursor = start;
+        self.chars.drain(start..end).collect()
+    }
+
     pub fn move_left(&mut self) {
         if self.cursor > 0 {
             self.cursor -= 1;
@@ -138,6 +220,174 @@ impl EditBuffer {
         let new_len = new_chars.len();
         self.chars.splice(start..end, new_chars);
         self.cursor = start + new_len;
+        self.anchor = None;
+    }
+
+    pub fn line_start(&self) -> usize {
+        self.chars[..self.cursor.min(self.chars.len())]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map(|p| p + 1)
+            .unwrap_or(0)
+    }
+
+    /// Position of the newline ending the cursor's line, or the end of the text.
+    pub fn line_end(&self) -> usize {
+        let from = self.cursor.min(self.chars.len());
+        self.chars[from..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map(|p| from + p)
+            .unwrap_or(self.chars.len())
+    }
+
+    /// Start of the next word (vim `w`): words are runs of letters, digits
+    /// and `_`, or runs of other non-blank characters.
+    pub fn next_word_start(&self) -> usize {
+        let len = self.chars.len();
+        let mut pos = self.cursor;
+        if pos < len {
+            let class = char_class(self.chars[pos]);
+            while pos < len && class != CharClass::Blank && char_class(self.chars[pos]) == class {
+                pos += 1;
+            }
+        }
+        while pos < len && char_class(self.chars[pos]) == CharClass::Blank {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// Start of the current or previous word (vim `b`).
+    pub fn prev_word_start(&self) -> usize {
+        let mut pos = self.cursor.min(self.chars.len());
+        while pos > 0 && char_class(self.chars[pos - 1]) == CharClass::Blank {
+            pos -= 1;
+        }
+        if pos > 0 {
+            let class = char_class(self.chars[pos - 1]);
+            while pos > 0 && char_class(self.chars[pos - 1]) == class {
+                pos -= 1;
+            }
+        }
+        pos
+    }
+
+    /// Last character of the current or next word (vim `e`).
+    pub fn word_end(&self) -> usize {
+        let len = self.chars.len();
+        let mut pos = self.cursor + 1;
+        while pos < len && char_class(self.chars[pos]) == CharClass::Blank {
+            pos += 1;
+        }
+        if pos >= len {
+            return len.saturating_sub(1);
+        }
+        let class = char_class(self.chars[pos]);
+        while pos + 1 < len && char_class(self.chars[pos + 1]) == class {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// The word under the cursor (vim `iw`), or with the blanks after it
+    /// (before it at the end of a line) for `aw`.
+    pub fn word_object(&self, around: bool) -> Option<(usize, usize)> {
+        let len = self.chars.len();
+        if self.cursor >= len {
+            return None;
+        }
+        let class = char_class(self.chars[self.cursor]);
+        let same = |i: usize| char_class(self.chars[i]) == class;
+        let mut start = self.cursor;
+        while start > 0 && same(start - 1) {
+            start -= 1;
+        }
+        let mut end = self.cursor + 1;
+        while end < len && same(end) {
+            end += 1;
+        }
+        if around && class != CharClass::Blank {
+            let blank = |i: usize| char_class(self.chars[i]) == CharClass::Blank;
+            if end < len && blank(end) {
+                while end < len && blank(end) {
+                    end += 1;
+                }
+            } else {
+                while start > 0 && blank(start - 1) {
+                    start -= 1;
+                }
+            }
+        }
+        Some((start, end))
+    }
+
+    /// The innermost `open`…`close` pair around the cursor (vim `i(`, `a[`).
+    /// For square brackets inside or around a `[[link]]` the pair is the whole
+    /// link, so `i[` is the page title and `a[` includes both bracket pairs.
+    pub fn bracket_object(&self, open: char, close: char, around: bool) -> Option<(usize, usize)> {
+        let (mut start, mut end) = self.enclosing_pair(open, close)?;
+        if open == '[' {
+            let at = |i: usize, c: char| self.chars.get(i) == Some(&c);
+            if start > 0 && at(start - 1, '[') && at(end + 1, ']') {
+                (start, end) = (start - 1, end + 1);
+            }
+            if at(start + 1, '[') && end > 0 && at(end - 1, ']') {
+                return Some(if around {
+                    (start, end + 1)
+                } else {
+                    (start + 2, end - 1)
+                });
+            }
+        }
+        Some(if around {
+            (start, end + 1)
+        } else {
+            (start + 1, end)
+        })
+    }
+
+    /// Positions of the nearest unmatched `open` at or before the cursor and
+    /// the `close` matching it.
+    fn enclosing_pair(&self, open: char, close: char) -> Option<(usize, usize)> {
+        if self.chars.is_empty() {
+            return None;
+        }
+        let len = self.chars.len();
+        let cursor = self.cursor.min(len.saturating_sub(1));
+        let mut depth = 0;
+        let mut start = None;
+        let first = if self.chars.get(cursor) == Some(&close) {
+            cursor.checked_sub(1)?
+        } else {
+            cursor
+        };
+        for i in (0..=first).rev() {
+            let c = self.chars[i];
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                if depth == 0 {
+                    start = Some(i);
+                    break;
+                }
+                depth -= 1;
+            }
+        }
+        let start = start?;
+        let mut depth = 0;
+        for i in start + 1..len {
+            let c = self.chars[i];
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                if depth == 0 {
+                    return Some((start, i));
+                }
+                depth -= 1;
+            }
+        }
+        None
     }
 
     pub fn toggle_todo(&mut self) {
@@ -165,6 +415,23 @@ impl EditBuffer {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Blank
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
 impl std::fmt::Display for EditBuffer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s: String = self.chars.iter().collect();
@@ -540,4 +807,132 @@ mod tests {
         buf.move_up();
         assert_eq!(buf.cursor, 0); // col 0 in "hello"
     }
+
+    #[test]
+    fn selection_runs_between_anchor_and_cursor() {
+        let mut buf = EditBuffer::new("hello world");
+        buf.cursor = 6;
+        buf.set_selecting(true);
+        buf.move_end();
+        assert_eq!(buf.selection(), Some((6, 11)));
+        assert_eq!(buf.selected_text().as_deref(), Some("world"));
+        buf.set_selecting(false);
+        assert_eq!(buf.selection(), None);
+    }
+
+    #[test]
+    fn typing_replaces_selection() {
+        let mut buf = EditBuffer::new("hello world");
+        buf.anchor = Some(0);
+        buf.cursor = 5;
+        buf.insert_char('J');
+        assert_eq!(buf.to_string(), "J world");
+        assert_eq!(buf.cursor, 1);
+        assert_eq!(buf.anchor, None);
+    }
+
+    #[test]
+    fn backspace_deletes_selection() {
+        let mut buf = EditBuffer::new("hello world");
+        buf.anchor = Some(11);
+        buf.cursor = 5;
+        buf.delete_back();
+        assert_eq!(buf.to_string(), "hello");
+        assert_eq!(buf.cursor, 5);
+    }
+
+    #[test]
+    fn insert_pair_wraps_selection_twice_into_link() {
+        let mut buf = EditBuffer::new("see page");
+        buf.anchor = Some(4);
+        buf.cursor = 8;
+        buf.insert_pair('[', ']');
+        buf.insert_pair('[', ']');
+        assert_eq!(buf.to_string(), "see [[page]]");
+        assert_eq!(buf.selected_text().as_deref(), Some("page"));
+    }
+
+    #[test]
+    fn insert_str_at_cursor() {
+        let mut buf = EditBuffer::new("ac");
+        buf.cursor = 1;
+        buf.insert_str("bb");
+        assert_eq!(buf.to_string(), "abbc");
+        assert_eq!(buf.cursor, 3);
+    }
+
+    #[test]
+    fn vim_word_motions() {
+        let mut buf = EditBuffer::new("foo.bar  baz");
+        buf.cursor = 0;
+        assert_eq!(buf.next_word_start(), 3); // "."
+        assert_eq!(buf.word_end(), 2);
+        buf.cursor = 4;
+        assert_eq!(buf.next_word_start(), 9);
+        assert_eq!(buf.word_end(), 6);
+        buf.cursor = 9;
+        assert_eq!(buf.prev_word_start(), 4);
+        buf.cursor = 11;
+        assert_eq!(buf.word_end(), 11);
+    }
+
+    #[test]
+    fn line_bounds() {
+        let mut buf = EditBuffer::new("one\ntwo");
+        buf.cursor = 5;
+        assert_eq!(buf.line_start(), 4);
+        assert_eq!(buf.line_end(), 7);
+        buf.cursor = 1;
+        assert_eq!(buf.line_end(), 3);
+    }
+
+    #[test]
+    fn word_object_inner_and_around() {
+        let mut buf = EditBuffer::new("one two three");
+        buf.cursor = 5;
+        assert_eq!(buf.word_object(false), Some((4, 7)));
+        assert_eq!(buf.word_object(true), Some((4, 8)));
+        // Last word takes the blanks before it
+        buf.cursor = 10;
+        assert_eq!(buf.word_object(true), Some((7, 13)));
+    }
+
+    #[test]
+    fn bracket_object_finds_innermost_pair() {
+        let mut buf = EditBuffer::new("f(a, (b), c)");
+        buf.cursor = 6;
+        assert_eq!(buf.bracket_object('(', ')', false), Some((6, 7)));
+        buf.cursor = 3;
+        assert_eq!(buf.bracket_object('(', ')', false), Some((2, 11)));
+        assert_eq!(buf.bracket_object('(', ')', true), Some((1, 12)));
+        // On the closing bracket
+        buf.cursor = 11;
+        assert_eq!(buf.bracket_object('(', ')', false), Some((2, 11)));
+        buf.cursor = 0;
+        assert_eq!(buf.bracket_object('(', ')', false), None);
+    }
+
+    #[test]
+    fn bracket_object_covers_whole_link() {
+        let mut buf = EditBuffer::new("see [[My Page]] now");
+        for cursor in [4, 5, 8, 14] {
+            buf.cursor = cursor;
+            assert_eq!(buf.bracket_object('[', ']', false), Some((6, 13)));
+            assert_eq!(buf.bracket_object('[', ']', true), Some((4, 15)));
+        }
+        let mut buf = EditBuffer::new("[a] b");
+        buf.cursor = 1;
+        assert_eq!(buf.bracket_object('[', ']', true), Some((0, 3)));
+    }
+
+    #[test]
+    fn text_objects_on_empty_buffer() {
+        let buf = EditBuffer::new("");
+        assert_eq!(buf.word_object(false), None);
+        assert_eq!(buf.word_object(true), None);
+        for (open, close) in [('(', ')'), ('[', ']'), ('{', '}')] {
+            assert_eq!(buf.bracket_object(open, close, false), None);
+            assert_eq!(buf.bracket_object(open, close, true), None);
+        }
+    }
 }
@@ -0,0 +1,348 @@
+//! Fuzzy subsequence matching shared by the quick switcher, search,
+//! autocomplete popups and slash menu, plus substring matching for block
+//! text, where a subsequence of a short query matches almost every long block.
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 15;
+const BONUS_WORD_BOUNDARY: i64 = 10;
+const BONUS_CASE: i64 = 1;
+const PENALTY_GAP: i64 = 1;
+const PENALTY_LEADING_MAX: i64 = 10;
+
+/// Score added per step of recency: the most recently visited page gets
+/// `RECENCY_STEPS * RECENCY_BONUS`, older pages progressively less.
+const RECENCY_BONUS: i64 = 4;
+const RECENCY_STEPS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices in the candidate that matched the query.
+    pub positions: Vec<usize>,
+}
+
+/// Match `query` as a case-insensitive subsequence of `candidate`.
+///
+/// Returns `None` when some query character can't be matched in order. An empty
+/// query matches everything with score 0. Among all possible alignments the
+/// best-scoring one is chosen: consecutive runs and matches at word starts
+/// score higher, gaps between matches cost a little.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: vec![],
+        });
+    }
+    let text: Vec<char> = candidate.chars().collect();
+    let (m, n) = (query.len(), text.len());
+    if m > n {
+        return None;
+    }
+    let query_lower: Vec<char> = query.iter().map(|c| fold(*c)).collect();
+    let text_lower: Vec<char> = text.iter().map(|c| fold(*c)).collect();
+
+    // Cheap rejection before the O(m*n) pass
+    let mut qi = 0;
+    for c in &text_lower {
+        if qi < m && *c == query_lower[qi] {
+            qi += 1;
+        }
+    }
+    if qi < m {
+        return None;
+    }
+
+    const NONE: i64 = i64::MIN / 2;
+    // score[j][i]: best score with query[j] matched at text[i]
+    let mut score = vec![vec![NONE; n]; m];
+    let mut from = vec![vec![usize::MAX; n]; m];
+
+    for j in 0..m {
+        // Best (score[j-1][k] + k) over k < i - 1, for the linear gap penalty
+        let mut best_prev = NONE;
+        let mut best_prev_idx = usize::MAX;
+        for i in j..n {
+            if j > 0 && i >= 2 && score[j - 1][i - 2] != NONE {
+                let candidate = score[j - 1][i - 2] + (i - 2) as i64 * PENALTY_GAP;
+                if candidate > best_prev {
+                    best_prev = candidate;
+                    best_prev_idx = i - 2;
+                }
+            }
+            if text_lower[i] != query_lower[j] {
+                continue;
+            }
+            let mut base = SCORE_MATCH;
+            if is_word_boundary(&text, i) {
+                base += BONUS_WORD_BOUNDARY;
+            }
+            if text[i] == query[j] {
+                base += BONUS_CASE;
+            }
+            if j == 0 {
+                score[j][i] = base - (i as i64 * PENALTY_GAP).min(PENALTY_LEADING_MAX);
+                continue;
+            }
+            // Consecutive with the previous match
+            let mut best = NONE;
+            if i >= 1 && score[j - 1][i - 1] != NONE {
+                best = score[j - 1][i - 1] + BONUS_CONSECUTIVE;
+                from[j][i] = i - 1;
+            }
+            // After a gap of (i - k - 1) chars
+            if best_prev != NONE {
+                let gapped = best_prev - (i - 1) as i64 * PENALTY_GAP;
+                if gapped > best {
+                    best = gapped;
+                    from[j][i] = best_prev_idx;
+                }
+            }
+            if best != NONE {
+                score[j][i] = best + base;
+            }
+        }
+    }
+
+    let (mut end, best) = score[m - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| **s != NONE)
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(&a.0)))?;
+    let best = *best;
+
+    let mut positions = vec![0; m];
+    for j in (0..m).rev() {
+        positions[j] = end;
+        if j > 0 {
+            end = from[j][end];
+        }
+    }
+    Some(FuzzyMatch {
+        score: best,
+        positions,
+    })
+}
+
+/// Match `query` as a case-insensitive substring of `candidate`.
+///
+/// Scored like a fully consecutive fuzzy match, so occurrences at a word start
+/// and near the beginning rank first. An empty query matches everything.
+pub fn substring_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.trim().chars().map(fold).collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: vec![],
+        });
+    }
+    let text: Vec<char> = candidate.chars().collect();
+    let text_lower: Vec<char> = text.iter().map(|c| fold(*c)).collect();
+    let m = query.len();
+    let base = m as i64 * SCORE_MATCH + (m as i64 - 1) * BONUS_CONSECUTIVE;
+    let (start, score) = text_lower
+        .windows(m)
+        .enumerate()
+        .filter(|(_, window)| *window == query.as_slice())
+        .map(|(i, _)| {
+            let boundary = if is_word_boundary(&text, i) {
+                BONUS_WORD_BOUNDARY
+            } else {
+                0
+            };
+            (i, base + boundary - (i as i64).min(PENALTY_LEADING_MAX))
+        })
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))?;
+    Some(FuzzyMatch {
+        score,
+        positions: (start..start + m).collect(),
+    })
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn is_word_boundary(text: &[char], i: usize) -> bool {
+    if i == 0 {
+        return true;
+    }
+    let prev = text[i - 1];
+    let cur = text[i];
+    !prev.is_alphanumeric() || (prev.is_lowercase() && cur.is_uppercase())
+}
+
+/// Bonus for a page title based on its position in `recent` (most recent first).
+pub fn recency_bonus(title: &str, recent: &[String]) -> i64 {
+    recent
+        .iter()
+        .take(RECENCY_STEPS)
+        .position(|r| r == title)
+        .map(|rank| (RECENCY_STEPS - rank) as i64 * RECENCY_BONUS)
+        .unwrap_or(0)
+}
+
+/// Filter and rank `items` by fuzzy score plus `boost`, best first, keeping at most `limit`.
+///
+/// With an empty query every item matches; items are then ordered by boost alone,
+/// preserving the input order among ties.
+pub fn rank<T>(
+    items: impl IntoIterator<Item = T>,
+    query: &str,
+    key: impl Fn(&T) -> &str,
+    boost: impl Fn(&T) -> i64,
+    limit: usize,
+) -> Vec<T> {
+    rank_with(fuzzy_match, items, query, key, boost, limit)
+}
+
+/// [`rank`] with [`substring_match`], for block text.
+pub fn rank_substring<T>(
+    items: impl IntoIterator<Item = T>,
+    query: &str,
+    key: impl Fn(&T) -> &str,
+    limit: usize,
+) -> Vec<T> {
+    rank_with(substring_match, items, query, key, |_| 0, limit)
+}
+
+fn rank_with<T>(
+    matcher: fn(&str, &str) -> Option<FuzzyMatch>,
+    items: impl IntoIterator<Item = T>,
+    query: &str,
+    key: impl Fn(&T) -> &str,
+    boost: impl Fn(&T) -> i64,
+    limit: usize,
+) -> Vec<T> {
+    let mut scored: Vec<(i64, T)> = items
+        .into_iter()
+        .filter_map(|item| {
+            let m = matcher(query, key(&item))?;
+            Some((m.score + boost(&item), item))
+        })
+        .collect();
+    // Stable sort keeps input order among equal scores
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.truncate(limit);
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Char positions in `text` to highlight for `query`, empty when nothing matches.
+pub fn match_positions(query: &str, text: &str) -> Vec<usize> {
+    fuzzy_match(query, text)
+        .map(|m| m.positions)
+        .unwrap_or_default()
+}
+
+/// [`match_positions`] for block text ranked with [`substring_match`].
+pub fn substring_positions(query: &str, text: &str) -> Vec<usize> {
+    substring_match(query, text)
+        .map(|m| m.positions)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_query_matches_everything() {
+        let m = fuzzy_match("", "anything").unwrap();
+        assert_eq!(m.score, 0);
+        assert!(m.positions.is_empty());
+    }
+
+    #[test]
+    fn substring_match_prefers_word_starts() {
+        assert!(substring_match("a_c", "a_b_c").is_none());
+        let m = substring_match("Note", "denote notes").unwrap();
+        assert_eq!(m.positions, vec![7, 8, 9, 10]);
+        assert!(substring_match("eno", "denote").is_some());
+        assert_eq!(substring_match("  ", "x").unwrap().score, 0);
+    }
+
+    #[test]
+    fn subsequence_required_in_order() {
+        assert!(fuzzy_match("abc", "a_b_c").is_some());
+        assert!(fuzzy_match("acb", "abc").is_none());
+        assert!(fuzzy_match("abcd", "abc").is_none());
+    }
+
+    #[test]
+    fn matching_is_case_insensitive() {
+        let m = fuzzy_match("ROAM", "roam research").unwrap();
+        assert_eq!(m.positions, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn prefers_word_boundaries() {
+        let m = fuzzy_match("dn", "daily notes").unwrap();
+        assert_eq!(m.positions, vec![0, 6]);
+    }
+
+    #[test]
+    fn prefers_consecutive_run_over_scattered() {
+        let m = fuzzy_match("note", "n o t e notes").unwrap();
+        assert_eq!(m.positions, vec![8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn camel_case_counts_as_boundary() {
+        let m = fuzzy_match("qs", "QuickSwitcher").unwrap();
+        assert_eq!(m.positions, vec![0, 5]);
+    }
+
+    #[test]
+    fn prefix_scores_higher_than_middle() {
+        let prefix = fuzzy_match("proj", "Project ideas").unwrap();
+        let middle = fuzzy_match("proj", "Old improj notes").unwrap();
+        assert!(prefix.score > middle.score);
+    }
+
+    #[test]
+    fn whitespace_in_query_is_ignored() {
+        let m = fuzzy_match("da no", "daily notes").unwrap();
+        assert_eq!(m.positions.len(), 4);
+    }
+
+    #[test]
+    fn handles_multibyte_chars() {
+        let m = fuzzy_match("café", "Le café noir").unwrap();
+        assert_eq!(m.positions, vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn rank_orders_by_score_and_limits() {
+        let items = vec!["Improv notes", "Project", "Projects archive", "Unrelated"];
+        let ranked = rank(items, "proj", |s| s, |_| 0, 2);
+        assert_eq!(ranked, vec!["Project", "Projects archive"]);
+    }
+
+    #[test]
+    fn rank_with_empty_query_keeps_order() {
+        let items = vec!["b", "a", "c"];
+        assert_eq!(rank(items, "", |s| s, |_| 0, 10), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn recency_boost_reorders_ties() {
+        let recent = vec!["Beta".to_string()];
+        let items = vec!["Alpha", "Beta"];
+        let ranked = rank(items, "", |s| s, |s| recency_bonus(s, &recent), 10);
+        assert_eq!(ranked, vec!["Beta", "Alpha"]);
+    }
+
+    #[test]
+    fn recency_bonus_decays_with_rank() {
+        let recent = vec!["A".to_string(), "B".to_string()];
+        assert!(recency_bonus("A", &recent) > recency_bonus("B", &recent));
+        assert_eq!(recency_bonus("C", &recent), 0);
+    }
+
+    #[test]
+    fn match_positions_empty_when_no_match() {
+        assert!(match_positions("xyz", "abc").is_empty());
+        assert_eq!(match_positions("ac", "abc"), vec![0, 2]);
+    }
+}
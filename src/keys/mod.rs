@@ -1,47 +1,145 @@
+pub mod mode;
 pub mod parser;
 pub mod preset;
 
 use std::collections::HashMap;
 
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+use crate::config::KeybindingsConfig;
 use crate::error::{Result, RoamError};
-use preset::{get_preset, Action};
+use mode::{InsertAction, ModeAction, ModeKeymap, PopupAction};
+use preset::{get_preset, preset_sequences, Action, Category};
+
+/// Leader key used when the config doesn't set one.
+pub const DEFAULT_LEADER: &str = "Space";
 
 pub struct KeybindingMap {
-    bindings: HashMap<KeyEvent, Action>,
+    /// Key sequences to actions; most are a single key. No sequence is a
+    /// prefix of another, so a sequence is complete as soon as it matches.
+    bindings: HashMap<Vec<KeyEvent>, Action>,
+    insert: ModeKeymap<InsertAction>,
+    popup: ModeKeymap<PopupAction>,
+}
+
+/// How far a sequence of keys got towards a binding.
+#[derive(Debug, PartialEq)]
+pub enum SequenceMatch<'a> {
+    Action(&'a Action),
+    /// The keys start one or more longer bindings.
+    Prefix,
+    None,
 }
 
 impl KeybindingMap {
+    pub fn from_config(config: &KeybindingsConfig) -> Result<Self> {
+        let mut map = Self::build(&config.preset, &config.bindings, &config.leader)?;
+        map.insert = ModeKeymap::with_overrides(&config.insert)?;
+        map.popup = ModeKeymap::with_overrides(&config.popup)?;
+        Ok(map)
+    }
+
     pub fn from_preset(name: &str, overrides: &HashMap<String, String>) -> Result<Self> {
-        let mut bindings = get_preset(name)
-            .ok_or_else(|| RoamError::Config(format!("Unknown keybinding preset: {}", name)))?;
+        Self::build(name, overrides, DEFAULT_LEADER)
+    }
 
+    /// Keys of the block editor.
+    pub fn insert_keymap(&self) -> &ModeKeymap<InsertAction> {
+        &self.insert
+    }
+
+    /// Keys of the list popups: search, switchers, pickers and menus.
+    pub fn popup_keymap(&self) -> &ModeKeymap<PopupAction> {
+        &self.popup
+    }
+
+    fn build(name: &str, overrides: &HashMap<String, String>, leader: &str) -> Result<Self> {
+        let leader = parser::parse_key(leader)?;
+        let mut bindings: HashMap<Vec<KeyEvent>, Action> = get_preset(name)
+            .ok_or_else(|| RoamError::Config(format!("Unknown keybinding preset: {}", name)))?
+            .into_iter()
+            .map(|(key, action)| (vec![normalize(&key)], action))
+            .collect();
+        bindings.extend(
+            preset_sequences(name, leader)
+                .into_iter()
+                .map(|(keys, action)| (keys.iter().map(normalize).collect(), action)),
+        );
+
+        let mut overridden: Vec<Vec<KeyEvent>> = Vec::new();
         for (action_name, key_str) in overrides {
             let action = Action::from_str(action_name)
                 .ok_or_else(|| RoamError::Config(format!("Unknown action: {}", action_name)))?;
-            let key_event = parser::parse_key(key_str)?;
+            let keys: Vec<KeyEvent> = parser::parse_sequence(key_str, leader)?
+                .iter()
+                .map(normalize)
+                .collect();
 
             bindings.retain(|_, v| v != &action);
-            bindings.insert(key_event, action);
+            bindings.insert(keys.clone(), action);
+            overridden.push(keys);
+        }
+
+        // An override replaces preset bindings it would shadow or be shadowed by
+        for keys in &overridden {
+            if let Some(other) = overridden.iter().find(|o| is_strict_prefix_pair(o, keys)) {
+                return Err(RoamError::Config(format!(
+                    "Key sequence '{}' conflicts with '{}'",
+                    format_sequence(keys),
+                    format_sequence(other)
+                )));
+            }
+            bindings.retain(|k, _| !is_strict_prefix_pair(k, keys));
         }
 
-        Ok(Self { bindings })
+        Ok(Self {
+            bindings,
+            insert: ModeKeymap::default(),
+            popup: ModeKeymap::default(),
+        })
     }
 
     pub fn resolve(&self, key: &KeyEvent) -> Option<&Action> {
-        self.bindings.get(key).or_else(|| {
-            // On terminals with enhanced keyboard protocol (macOS), the received
-            // KeyEvent may carry extra kind/state fields that prevent a direct
-            // HashMap match. Fall back to matching only code + modifiers.
-            self.bindings.iter().find_map(|(k, v)| {
-                if k.code == key.code && k.modifiers == key.modifiers {
-                    Some(v)
-                } else {
-                    None
-                }
-            })
-        })
+        // On terminals with enhanced keyboard protocol (macOS), the received
+        // KeyEvent may carry extra kind/state fields that prevent a direct
+        // HashMap match. Match only code + modifiers.
+        self.bindings.get([normalize(key)].as_slice())
+    }
+
+    /// Match keys typed so far against every binding.
+    pub fn resolve_sequence(&self, keys: &[KeyEvent]) -> SequenceMatch<'_> {
+        let keys: Vec<KeyEvent> = keys.iter().map(normalize).collect();
+        if let Some(action) = self.bindings.get(&keys) {
+            return SequenceMatch::Action(action);
+        }
+        if self
+            .bindings
+            .keys()
+            .any(|seq| seq.len() > keys.len() && seq.starts_with(&keys))
+        {
+            SequenceMatch::Prefix
+        } else {
+            SequenceMatch::None
+        }
+    }
+
+    /// Keys that can follow `prefix`, each with the action it completes or
+    /// `None` when it only leads to longer sequences. Sorted by key.
+    pub fn continuations(&self, prefix: &[KeyEvent]) -> Vec<(String, Option<&Action>)> {
+        let prefix: Vec<KeyEvent> = prefix.iter().map(normalize).collect();
+        let mut next: HashMap<KeyEvent, Option<&Action>> = HashMap::new();
+        for (seq, action) in &self.bindings {
+            if seq.len() > prefix.len() && seq.starts_with(&prefix) {
+                let complete = seq.len() == prefix.len() + 1;
+                next.insert(seq[prefix.len()], complete.then_some(action));
+            }
+        }
+        let mut next: Vec<(String, Option<&Action>)> = next
+            .into_iter()
+            .map(|(key, action)| (format_key_event(&key), action))
+            .collect();
+        next.sort_by(|a, b| a.0.cmp(&b.0));
+        next
     }
 
     pub fn hints(&self) -> Vec<(String, &'static str)> {
@@ -55,17 +153,126 @@ impl KeybindingMap {
 
         let mut hints = Vec::new();
         for action in &important {
-            if let Some((key_event, _)) = self.bindings.iter().find(|(_, a)| *a == action) {
-                hints.push((format_key_event(key_event), action.hint_text()));
+            if let Some((keys, _)) = self
+                .bindings
+                .iter()
+                .filter(|(_, a)| *a == action)
+                .min_by_key(|(keys, _)| keys.len())
+            {
+                hints.push((format_sequence(keys), action.hint_text()));
             }
         }
         hints
     }
+
+    /// Every bound key per action, formatted and joined with `, `.
+    pub fn key_labels(&self) -> HashMap<Action, String> {
+        self.keys_by_action()
+            .into_iter()
+            .map(|(action, labels)| (action, labels.join(", ")))
+            .collect()
+    }
+
+    /// Bound actions grouped by category, in [`Action::ALL`] order, each with
+    /// its formatted keys; insert mode and popup keys come last.
+    pub fn sections(&self) -> Vec<KeySection> {
+        let mut keys = self.keys_by_action();
+        let mut sections: Vec<KeySection> = Category::ALL
+            .iter()
+            .map(|category| KeySection {
+                title: category.label(),
+                bindings: Action::ALL
+                    .iter()
+                    .filter(|a| a.category() == *category)
+                    .filter_map(|a| Some((a.label(), keys.remove(a)?)))
+                    .collect(),
+            })
+            .collect();
+        sections.push(mode_section("Insert mode", &self.insert));
+        sections.push(mode_section("Popups", &self.popup));
+        sections.retain(|section| !section.bindings.is_empty());
+        sections
+    }
+
+    /// The bindings as Markdown tables, one per category.
+    pub fn cheat_sheet(&self, title: &str) -> String {
+        let mut out = format!("# {}\n", title);
+        for section in self.sections() {
+            out.push_str(&format!(
+                "\n## {}\n\n| Action | Keys |\n|---|---|\n",
+                section.title
+            ));
+            for (label, keys) in &section.bindings {
+                let keys: Vec<String> = keys
+                    .iter()
+                    .map(|k| format!("`{}`", k.replace('|', "\\|")))
+                    .collect();
+                out.push_str(&format!("| {} | {} |\n", label, keys.join(" / ")));
+            }
+        }
+        out
+    }
+
+    /// Formatted keys of each bound action, sorted.
+    fn keys_by_action(&self) -> HashMap<Action, Vec<String>> {
+        let mut keys: HashMap<Action, Vec<String>> = HashMap::new();
+        for (sequence, action) in &self.bindings {
+            keys.entry(action.clone())
+                .or_default()
+                .push(format_sequence(sequence));
+        }
+        for labels in keys.values_mut() {
+            labels.sort();
+        }
+        keys
+    }
 }
 
-fn format_key_event(key: &KeyEvent) -> String {
-    use crossterm::event::{KeyCode, KeyModifiers};
+/// Bound actions of one category or mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySection {
+    pub title: &'static str,
+    /// (action label, formatted keys)
+    pub bindings: Vec<(&'static str, Vec<String>)>,
+}
 
+fn mode_section<A: ModeAction>(title: &'static str, keymap: &ModeKeymap<A>) -> KeySection {
+    KeySection {
+        title,
+        bindings: keymap
+            .labels()
+            .into_iter()
+            .map(|(action, keys)| (action.label(), keys))
+            .collect(),
+    }
+}
+
+/// Match on code and modifiers only. Terminals report Shift+Tab as BackTab,
+/// with or without Shift, so both spellings are folded into a bare BackTab.
+fn normalize(key: &KeyEvent) -> KeyEvent {
+    match key.code {
+        KeyCode::BackTab => KeyEvent::new(KeyCode::BackTab, key.modifiers - KeyModifiers::SHIFT),
+        KeyCode::Tab if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyEvent::new(KeyCode::BackTab, key.modifiers - KeyModifiers::SHIFT)
+        }
+        _ => KeyEvent::new(key.code, key.modifiers),
+    }
+}
+
+/// Whether one sequence is a strict prefix of the other.
+fn is_strict_prefix_pair(a: &[KeyEvent], b: &[KeyEvent]) -> bool {
+    a.len() != b.len() && (a.starts_with(b) || b.starts_with(a))
+}
+
+/// Keys of a sequence separated by spaces, e.g. `Ctrl+x Ctrl+c`.
+pub fn format_sequence(keys: &[KeyEvent]) -> String {
+    keys.iter()
+        .map(format_key_event)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_key_event(key: &KeyEvent) -> String {
     let mut parts = Vec::new();
 
     if key.modifiers.contains(KeyModifiers::CONTROL) {
@@ -79,6 +286,7 @@ fn format_key_event(key: &KeyEvent) -> String {
     }
 
     let key_str = match key.code {
+        KeyCode::Char(' ') => "Space".to_string(),
         KeyCode::Char(c) => c.to_string(),
         KeyCode::Enter => "Enter".to_string(),
         KeyCode::Esc => "Esc".to_string(),
@@ -105,7 +313,6 @@ fn format_key_event(key: &KeyEvent) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
     #[test]
     fn from_preset_vim_resolves_j_to_move_down() {
@@ -121,6 +328,27 @@ mod tests {
         assert_eq!(map.resolve(&key), Some(&Action::Quit));
     }
 
+    #[test]
+    fn shift_tab_spellings_resolve_to_unindent() {
+        let map = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        for key in [
+            KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT),
+            KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Tab, KeyModifiers::SHIFT),
+        ] {
+            assert_eq!(map.resolve(&key), Some(&Action::Unindent));
+        }
+
+        let mut overrides = HashMap::new();
+        overrides.insert("unindent".into(), "Ctrl+Shift+Tab".into());
+        let map = KeybindingMap::from_preset("vim", &overrides).unwrap();
+        let key = KeyEvent::new(
+            KeyCode::BackTab,
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+        assert_eq!(map.resolve(&key), Some(&Action::Unindent));
+    }
+
     #[test]
     fn override_replaces_existing_binding() {
         let mut overrides = HashMap::new();
@@ -166,6 +394,179 @@ mod tests {
         assert!(hint_labels.contains(&"search"));
     }
 
+    #[test]
+    fn key_labels_join_every_binding() {
+        let map = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        let labels = map.key_labels();
+        assert_eq!(labels[&Action::QuickSwitcher], "Ctrl+k, Ctrl+t, Space f");
+        assert_eq!(labels[&Action::Quit], "q");
+        assert_eq!(labels[&Action::DeleteBlock], "d d");
+    }
+
+    #[test]
+    fn resolve_sequence_reports_prefixes_and_completions() {
+        let map = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        let d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE);
+        let x = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert_eq!(map.resolve_sequence(&[d]), SequenceMatch::Prefix);
+        assert_eq!(
+            map.resolve_sequence(&[d, d]),
+            SequenceMatch::Action(&Action::DeleteBlock)
+        );
+        assert_eq!(map.resolve_sequence(&[d, x]), SequenceMatch::None);
+        let j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(
+            map.resolve_sequence(&[j]),
+            SequenceMatch::Action(&Action::MoveDown)
+        );
+    }
+
+    #[test]
+    fn continuations_list_next_keys() {
+        let map = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        let y = KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE);
+        let next = map.continuations(&[y]);
+        assert_eq!(
+            next,
+            vec![
+                ("m".to_string(), Some(&Action::CopyMarkdown)),
+                ("r".to_string(), Some(&Action::CopyBlockRef)),
+                ("y".to_string(), Some(&Action::Copy)),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_config_applies_and_validates_mode_keymaps() {
+        let mut config = KeybindingsConfig::default();
+        config.insert.insert("word_right".into(), "Alt+f".into());
+        let map = KeybindingMap::from_config(&config).unwrap();
+        let alt_f = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::ALT);
+        assert_eq!(
+            map.insert_keymap().resolve(&alt_f),
+            Some(mode::InsertAction::WordRight)
+        );
+
+        config.popup.insert("accept".into(), "Space".into());
+        assert!(KeybindingMap::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn sequence_overrides_with_leader() {
+        let mut overrides = HashMap::new();
+        overrides.insert("go_daily".into(), "g g".into());
+        overrides.insert("command_palette".into(), "Leader Leader".into());
+        let config = KeybindingsConfig {
+            bindings: overrides,
+            leader: ",".into(),
+            ..KeybindingsConfig::default()
+        };
+        let map = KeybindingMap::from_config(&config).unwrap();
+
+        let g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        assert_eq!(
+            map.resolve_sequence(&[g, g]),
+            SequenceMatch::Action(&Action::GoDaily)
+        );
+        // The preset's single-key binding for the action is replaced
+        let shift_g = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(map.resolve(&shift_g), None);
+
+        let comma = KeyEvent::new(KeyCode::Char(','), KeyModifiers::NONE);
+        assert_eq!(
+            map.resolve_sequence(&[comma, comma]),
+            SequenceMatch::Action(&Action::CommandPalette)
+        );
+        // Leader bindings from the preset follow the configured leader
+        let f = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE);
+        assert_eq!(
+            map.resolve_sequence(&[comma, f]),
+            SequenceMatch::Action(&Action::QuickSwitcher)
+        );
+    }
+
+    #[test]
+    fn override_replaces_shadowing_preset_bindings() {
+        let mut overrides = HashMap::new();
+        overrides.insert("paste".into(), "y".into());
+        let map = KeybindingMap::from_preset("vim", &overrides).unwrap();
+        let y = KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE);
+        assert_eq!(map.resolve(&y), Some(&Action::Paste));
+        assert_eq!(map.resolve_sequence(&[y, y]), SequenceMatch::None);
+    }
+
+    #[test]
+    fn conflicting_override_sequences_return_error() {
+        let mut overrides = HashMap::new();
+        overrides.insert("quit".into(), "g".into());
+        overrides.insert("go_daily".into(), "g g".into());
+        assert!(KeybindingMap::from_preset("vim", &overrides).is_err());
+    }
+
+    #[test]
+    fn presets_have_no_prefix_conflicts() {
+        for preset in ["vim", "emacs", "vscode"] {
+            let map = KeybindingMap::from_preset(preset, &HashMap::new()).unwrap();
+            for a in map.bindings.keys() {
+                assert!(
+                    !map.bindings.keys().any(|b| is_strict_prefix_pair(a, b)),
+                    "{} binds a prefix of {}",
+                    preset,
+                    format_sequence(a)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sections_group_bound_actions() {
+        let map = KeybindingMap::from_preset("vim", &HashMap::new()).unwrap();
+        let sections = map.sections();
+        let titles: Vec<&str> = sections.iter().map(|s| s.title).collect();
+        assert_eq!(
+            titles,
+            [
+                "Navigation",
+                "Editing",
+                "Structure",
+                "Views",
+                "Insert mode",
+                "Popups"
+            ]
+        );
+        assert_eq!(
+            sections[0].bindings[0],
+            ("Move up", vec!["k".to_string(), "↑".to_string()])
+        );
+        // Every bound action is listed once, and only bound ones
+        let listed: usize = sections[..Category::ALL.len()]
+            .iter()
+            .map(|s| s.bindings.len())
+            .sum();
+        assert_eq!(listed, map.key_labels().len());
+    }
+
+    #[test]
+    fn cheat_sheet_renders_markdown_tables() {
+        let map = KeybindingMap::from_preset("emacs", &HashMap::new()).unwrap();
+        let sheet = map.cheat_sheet("Emacs keybindings");
+        assert!(sheet
+            .starts_with("# Emacs keybindings\n\n## Navigation\n\n| Action | Keys |\n|---|---|\n"));
+        assert!(sheet.contains("| Quit | `Ctrl+q` / `Ctrl+x Ctrl+c` |\n"));
+        assert!(sheet.contains("\n## Views\n"));
+        assert!(sheet.contains("\n## Insert mode\n"));
+        assert!(sheet.contains("| Previous word | `Alt+←` / `Ctrl+←` |\n"));
+    }
+
+    #[test]
+    fn format_sequence_joins_with_spaces() {
+        let keys = [
+            KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+        ];
+        assert_eq!(format_sequence(&keys), "Ctrl+x Space");
+    }
+
     #[test]
     fn format_key_event_simple_char() {
         let key = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
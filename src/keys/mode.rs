@@ -0,0 +1,376 @@
+//! Keymaps for insert mode and popups, configured from `[keybindings.insert]`
+//! and `[keybindings.popup]`.
+//!
+//! Unlike normal mode these are single keys, and characters typed without a
+//! modifier are always text: they can't be bound.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::parser::parse_key;
+use super::{format_key_event, normalize};
+use crate::error::{Result, RoamError};
+
+/// An action of a mode keymap.
+pub trait ModeAction: Copy + Eq + Hash + 'static {
+    /// Config table the actions are read from, e.g. `insert`.
+    const TABLE: &'static str;
+    const ALL: &'static [Self];
+
+    fn name(&self) -> &'static str;
+    fn label(&self) -> &'static str;
+    fn default_keys(&self) -> Vec<KeyEvent>;
+
+    fn from_str(s: &str) -> Option<Self> {
+        let s = s.to_lowercase();
+        Self::ALL.iter().copied().find(|a| a.name() == s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InsertAction {
+    /// Save and leave the editor (vim: go to normal mode).
+    Exit,
+    Indent,
+    Unindent,
+    CursorLeft,
+    CursorRight,
+    CursorUp,
+    CursorDown,
+    LineStart,
+    LineEnd,
+    WordLeft,
+    WordRight,
+    DeleteBack,
+    DeleteForward,
+    DeleteWordBack,
+    Copy,
+    Cut,
+    Paste,
+    ToggleTodo,
+}
+
+impl InsertAction {
+    /// Cursor motions; with Shift held they extend the selection.
+    pub fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            Self::CursorLeft
+                | Self::CursorRight
+                | Self::CursorUp
+                | Self::CursorDown
+                | Self::LineStart
+                | Self::LineEnd
+                | Self::WordLeft
+                | Self::WordRight
+        )
+    }
+}
+
+impl ModeAction for InsertAction {
+    const TABLE: &'static str = "insert";
+    const ALL: &'static [Self] = &[
+        Self::Exit,
+        Self::Indent,
+        Self::Unindent,
+        Self::CursorLeft,
+        Self::CursorRight,
+        Self::CursorUp,
+        Self::CursorDown,
+        Self::LineStart,
+        Self::LineEnd,
+        Self::WordLeft,
+        Self::WordRight,
+        Self::DeleteBack,
+        Self::DeleteForward,
+        Self::DeleteWordBack,
+        Self::Copy,
+        Self::Cut,
+        Self::Paste,
+        Self::ToggleTodo,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Exit => "exit",
+            Self::Indent => "indent",
+            Self::Unindent => "unindent",
+            Self::CursorLeft => "cursor_left",
+            Self::CursorRight => "cursor_right",
+            Self::CursorUp => "cursor_up",
+            Self::CursorDown => "cursor_down",
+            Self::LineStart => "line_start",
+            Self::LineEnd => "line_end",
+            Self::WordLeft => "word_left",
+            Self::WordRight => "word_right",
+            Self::DeleteBack => "delete_back",
+            Self::DeleteForward => "delete_forward",
+            Self::DeleteWordBack => "delete_word_back",
+            Self::Copy => "copy",
+            Self::Cut => "cut",
+            Self::Paste => "paste",
+            Self::ToggleTodo => "toggle_todo",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Exit => "Save and leave editor",
+            Self::Indent => "Indent block",
+            Self::Unindent => "Unindent block",
+            Self::CursorLeft => "Cursor left",
+            Self::CursorRight => "Cursor right",
+            Self::CursorUp => "Cursor up",
+            Self::CursorDown => "Cursor down",
+            Self::LineStart => "Start of line",
+            Self::LineEnd => "End of line",
+            Self::WordLeft => "Previous word",
+            Self::WordRight => "Next word",
+            Self::DeleteBack => "Delete backward",
+            Self::DeleteForward => "Delete forward",
+            Self::DeleteWordBack => "Delete word backward",
+            Self::Copy => "Copy selection",
+            Self::Cut => "Cut selection",
+            Self::Paste => "Paste",
+            Self::ToggleTodo => "Toggle TODO",
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyEvent> {
+        match self {
+            Self::Exit => vec![key(KeyCode::Esc)],
+            Self::Indent => vec![key(KeyCode::Tab)],
+            Self::Unindent => vec![key(KeyCode::BackTab)],
+            Self::CursorLeft => vec![key(KeyCode::Left)],
+            Self::CursorRight => vec![key(KeyCode::Right)],
+            Self::CursorUp => vec![key(KeyCode::Up)],
+            Self::CursorDown => vec![key(KeyCode::Down)],
+            Self::LineStart => vec![key(KeyCode::Home), ctrl(KeyCode::Char('a'))],
+            Self::LineEnd => vec![key(KeyCode::End), ctrl(KeyCode::Char('e'))],
+            Self::WordLeft => vec![ctrl(KeyCode::Left), alt(KeyCode::Left)],
+            Self::WordRight => vec![ctrl(KeyCode::Right), alt(KeyCode::Right)],
+            Self::DeleteBack => vec![key(KeyCode::Backspace)],
+            Self::DeleteForward => vec![key(KeyCode::Delete)],
+            Self::DeleteWordBack => vec![ctrl(KeyCode::Char('w')), alt(KeyCode::Backspace)],
+            Self::Copy => vec![ctrl(KeyCode::Char('c'))],
+            Self::Cut => vec![ctrl(KeyCode::Char('x'))],
+            Self::Paste => vec![ctrl(KeyCode::Char('v'))],
+            Self::ToggleTodo => vec![ctrl(KeyCode::Enter), alt(KeyCode::Enter)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PopupAction {
+    SelectPrev,
+    SelectNext,
+    Accept,
+    Close,
+    /// Accept a page or tag completion and keep typing.
+    Complete,
+}
+
+impl ModeAction for PopupAction {
+    const TABLE: &'static str = "popup";
+    const ALL: &'static [Self] = &[
+        Self::SelectPrev,
+        Self::SelectNext,
+        Self::Accept,
+        Self::Close,
+        Self::Complete,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::SelectPrev => "select_prev",
+            Self::SelectNext => "select_next",
+            Self::Accept => "accept",
+            Self::Close => "close",
+            Self::Complete => "complete",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::SelectPrev => "Previous item",
+            Self::SelectNext => "Next item",
+            Self::Accept => "Choose item",
+            Self::Close => "Close popup",
+            Self::Complete => "Accept page or tag completion",
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyEvent> {
+        match self {
+            Self::SelectPrev => vec![key(KeyCode::Up), ctrl(KeyCode::Char('p'))],
+            Self::SelectNext => vec![key(KeyCode::Down), ctrl(KeyCode::Char('n'))],
+            Self::Accept => vec![key(KeyCode::Enter)],
+            Self::Close => vec![key(KeyCode::Esc)],
+            Self::Complete => vec![key(KeyCode::Tab)],
+        }
+    }
+}
+
+/// Single keys to the actions of one mode.
+#[derive(Debug, Clone)]
+pub struct ModeKeymap<A> {
+    bindings: HashMap<KeyEvent, A>,
+}
+
+impl<A: ModeAction> Default for ModeKeymap<A> {
+    fn default() -> Self {
+        let bindings = A::ALL
+            .iter()
+            .flat_map(|action| action.default_keys().into_iter().map(|k| (k, *action)))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl<A: ModeAction> ModeKeymap<A> {
+    /// The default keymap with `overrides` (action name to key) applied. An
+    /// override replaces every default key of its action.
+    pub fn with_overrides(overrides: &HashMap<String, String>) -> Result<Self> {
+        let mut map = Self::default();
+        let mut overridden: HashMap<KeyEvent, A> = HashMap::new();
+        for (action_name, key_str) in overrides {
+            let action = A::from_str(action_name).ok_or_else(|| {
+                RoamError::Config(format!(
+                    "Unknown action in [keybindings.{}]: {}",
+                    A::TABLE,
+                    action_name
+                ))
+            })?;
+            let key = normalize(&parse_key(key_str)?);
+            if is_text_input(&key) {
+                return Err(RoamError::Config(format!(
+                    "Key '{}' for {} in [keybindings.{}] would shadow typing; add a modifier",
+                    key_str,
+                    action.name(),
+                    A::TABLE
+                )));
+            }
+            if let Some(other) = overridden.insert(key, action) {
+                return Err(RoamError::Config(format!(
+                    "Key '{}' is bound to both {} and {} in [keybindings.{}]",
+                    format_key_event(&key),
+                    other.name(),
+                    action.name(),
+                    A::TABLE
+                )));
+            }
+            map.bindings.retain(|_, a| *a != action);
+        }
+        map.bindings.extend(overridden);
+        Ok(map)
+    }
+
+    pub fn resolve(&self, key: &KeyEvent) -> Option<A> {
+        self.bindings.get(&normalize(key)).copied()
+    }
+
+    /// Formatted keys of each action in [`ModeAction::ALL`] order, sorted.
+    pub fn labels(&self) -> Vec<(A, Vec<String>)> {
+        A::ALL
+            .iter()
+            .filter_map(|action| {
+                let mut keys: Vec<String> = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, a)| *a == action)
+                    .map(|(k, _)| format_key_event(k))
+                    .collect();
+                keys.sort();
+                (!keys.is_empty()).then_some((*action, keys))
+            })
+            .collect()
+    }
+}
+
+/// A character typed with no modifier but Shift.
+fn is_text_input(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char(_)) && (key.modifiers - KeyModifiers::SHIFT).is_empty()
+}
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+fn ctrl(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::CONTROL)
+}
+
+fn alt(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::ALT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(a, k)| (a.to_string(), k.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn defaults_cover_every_action() {
+        let insert = ModeKeymap::<InsertAction>::default();
+        assert_eq!(insert.labels().len(), InsertAction::ALL.len());
+        let popup = ModeKeymap::<PopupAction>::default();
+        assert_eq!(popup.labels().len(), PopupAction::ALL.len());
+        assert_eq!(
+            popup.resolve(&ctrl(KeyCode::Char('n'))),
+            Some(PopupAction::SelectNext)
+        );
+    }
+
+    #[test]
+    fn shift_tab_resolves_as_backtab() {
+        let insert = ModeKeymap::<InsertAction>::default();
+        let shift_backtab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(insert.resolve(&shift_backtab), Some(InsertAction::Unindent));
+    }
+
+    #[test]
+    fn override_replaces_default_keys() {
+        let insert =
+            ModeKeymap::<InsertAction>::with_overrides(&overrides(&[("word_left", "Alt+b")]))
+                .unwrap();
+        assert_eq!(
+            insert.resolve(&alt(KeyCode::Char('b'))),
+            Some(InsertAction::WordLeft)
+        );
+        assert_eq!(insert.resolve(&ctrl(KeyCode::Left)), None);
+    }
+
+    #[test]
+    fn override_takes_key_from_other_action() {
+        let popup =
+            ModeKeymap::<PopupAction>::with_overrides(&overrides(&[("accept", "Tab")])).unwrap();
+        assert_eq!(popup.resolve(&key(KeyCode::Tab)), Some(PopupAction::Accept));
+        assert_eq!(popup.resolve(&key(KeyCode::Enter)), None);
+    }
+
+    #[test]
+    fn invalid_overrides_return_errors() {
+        let unknown = ModeKeymap::<PopupAction>::with_overrides(&overrides(&[("jump", "Ctrl+j")]));
+        assert!(unknown
+            .unwrap_err()
+            .to_string()
+            .contains("[keybindings.popup]"));
+
+        let typing = ModeKeymap::<PopupAction>::with_overrides(&overrides(&[("select_next", "j")]));
+        assert!(typing.unwrap_err().to_string().contains("shadow typing"));
+
+        let duplicate = ModeKeymap::<InsertAction>::with_overrides(&overrides(&[
+            ("copy", "Ctrl+y"),
+            ("paste", "Ctrl+y"),
+        ]));
+        assert!(duplicate.unwrap_err().to_string().contains("bound to both"));
+    }
+}
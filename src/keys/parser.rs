@@ -34,11 +34,34 @@ pub fn parse_key(input: &str) -> Result<KeyEvent> {
     Ok(KeyEvent::new(code, modifiers))
 }
 
+/// Parse a space-separated key sequence such as `g g` or `Ctrl+x Ctrl+s`.
+/// `Leader` stands for the `leader` key.
+pub fn parse_sequence(input: &str, leader: KeyEvent) -> Result<Vec<KeyEvent>> {
+    let keys = input
+        .split_whitespace()
+        .map(|part| {
+            if part.eq_ignore_ascii_case("leader") {
+                Ok(leader)
+            } else {
+                parse_key(part)
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if keys.is_empty() {
+        return Err(RoamError::Config(format!(
+            "No key code found in '{}'",
+            input
+        )));
+    }
+    Ok(keys)
+}
+
 fn parse_key_code(s: &str) -> Result<KeyCode> {
     match s.to_lowercase().as_str() {
         "enter" | "return" => Ok(KeyCode::Enter),
         "esc" | "escape" => Ok(KeyCode::Esc),
         "tab" => Ok(KeyCode::Tab),
+        "backtab" => Ok(KeyCode::BackTab),
         "backspace" | "bs" => Ok(KeyCode::Backspace),
         "delete" | "del" => Ok(KeyCode::Delete),
         "insert" | "ins" => Ok(KeyCode::Insert),
@@ -167,4 +190,28 @@ mod tests {
         assert_eq!(key.code, KeyCode::Char('k'));
         assert_eq!(key.modifiers, KeyModifiers::CONTROL | KeyModifiers::SHIFT);
     }
+
+    #[test]
+    fn parse_sequence_splits_on_whitespace() {
+        let leader = KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        let keys = parse_sequence("Ctrl+x  Ctrl+s", leader).unwrap();
+        assert_eq!(
+            keys,
+            vec![
+                KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL),
+                KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+            ]
+        );
+        assert_eq!(parse_sequence("q", leader).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn parse_sequence_expands_leader() {
+        let leader = KeyEvent::new(KeyCode::Char(','), KeyModifiers::NONE);
+        let keys = parse_sequence("leader f p", leader).unwrap();
+        assert_eq!(keys[0], leader);
+        assert_eq!(keys.len(), 3);
+        assert!(parse_sequence("  ", leader).is_err());
+        assert!(parse_sequence("g Hyper+x", leader).is_err());
+    }
 }
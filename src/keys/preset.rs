@@ -28,6 +28,29 @@ pub enum Action {
     CursorRight,
     NavBack,
     NavForward,
+    FilterLinkedRefs,
+    RenamePage,
+    AttributeTable,
+    Agenda,
+    Calendar,
+    GoToDate,
+    VisualMode,
+    SelectUp,
+    SelectDown,
+    MoveBlockUp,
+    MoveBlockDown,
+    MoveCardLeft,
+    MoveCardRight,
+    DeleteBlock,
+    ToggleTodo,
+    Copy,
+    CopyBlockRef,
+    CopyMarkdown,
+    Paste,
+    PasteAbove,
+    OpenInEditor,
+    OpenPageInEditor,
+    CommandPalette,
 }
 
 impl Action {
@@ -57,6 +80,29 @@ impl Action {
             "cursor_right" => Some(Self::CursorRight),
             "nav_back" => Some(Self::NavBack),
             "nav_forward" => Some(Self::NavForward),
+            "filter_linked_refs" => Some(Self::FilterLinkedRefs),
+            "rename_page" => Some(Self::RenamePage),
+            "attribute_table" => Some(Self::AttributeTable),
+            "agenda" => Some(Self::Agenda),
+            "calendar" => Some(Self::Calendar),
+            "go_to_date" => Some(Self::GoToDate),
+            "visual_mode" => Some(Self::VisualMode),
+            "select_up" => Some(Self::SelectUp),
+            "select_down" => Some(Self::SelectDown),
+            "move_block_up" => Some(Self::MoveBlockUp),
+            "move_block_down" => Some(Self::MoveBlockDown),
+            "move_card_left" => Some(Self::MoveCardLeft),
+            "move_card_right" => Some(Self::MoveCardRight),
+            "delete_block" => Some(Self::DeleteBlock),
+            "toggle_todo" => Some(Self::ToggleTodo),
+            "copy" => Some(Self::Copy),
+            "copy_block_ref" => Some(Self::CopyBlockRef),
+            "copy_markdown" => Some(Self::CopyMarkdown),
+            "paste" => Some(Self::Paste),
+            "paste_above" => Some(Self::PasteAbove),
+            "open_in_editor" => Some(Self::OpenInEditor),
+            "open_page_in_editor" => Some(Self::OpenPageInEditor),
+            "command_palette" => Some(Self::CommandPalette),
             _ => None,
         }
     }
@@ -87,6 +133,213 @@ impl Action {
             Self::CursorRight => "cursor →",
             Self::NavBack => "back",
             Self::NavForward => "forward",
+            Self::FilterLinkedRefs => "filter refs",
+            Self::RenamePage => "rename page",
+            Self::AttributeTable => "attributes",
+            Self::Agenda => "agenda",
+            Self::Calendar => "calendar",
+            Self::GoToDate => "go to date",
+            Self::VisualMode => "visual",
+            Self::SelectUp => "select ↑",
+            Self::SelectDown => "select ↓",
+            Self::MoveBlockUp => "move block ↑",
+            Self::MoveBlockDown => "move block ↓",
+            Self::MoveCardLeft => "card ←",
+            Self::MoveCardRight => "card →",
+            Self::DeleteBlock => "delete",
+            Self::ToggleTodo => "todo",
+            Self::Copy => "copy",
+            Self::CopyBlockRef => "copy ref",
+            Self::CopyMarkdown => "copy markdown",
+            Self::Paste => "paste",
+            Self::PasteAbove => "paste above",
+            Self::OpenInEditor => "$EDITOR",
+            Self::OpenPageInEditor => "page in $EDITOR",
+            Self::CommandPalette => "commands",
+        }
+    }
+
+    /// Name shown in the command palette.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::MoveUp => "Move up",
+            Self::MoveDown => "Move down",
+            Self::Collapse => "Collapse block",
+            Self::Expand => "Expand block",
+            Self::Enter => "Open link or toggle section",
+            Self::Exit => "Exit visual mode or close overlay",
+            Self::Search => "Search graph",
+            Self::Quit => "Quit",
+            Self::ToggleSidebar => "Toggle sidebar",
+            Self::Help => "Show help",
+            Self::GoDaily => "Go to today",
+            Self::NextDay => "Next day",
+            Self::PrevDay => "Previous day",
+            Self::QuickSwitcher => "Quick switcher",
+            Self::Indent => "Indent block",
+            Self::Unindent => "Unindent block",
+            Self::EditBlock => "Edit block",
+            Self::CreateBlock => "New block",
+            Self::Undo => "Undo",
+            Self::Redo => "Redo",
+            Self::CursorLeft => "Cursor left",
+            Self::CursorRight => "Cursor right",
+            Self::NavBack => "Navigate back",
+            Self::NavForward => "Navigate forward",
+            Self::FilterLinkedRefs => "Filter linked references",
+            Self::RenamePage => "Rename page",
+            Self::AttributeTable => "Show page attributes",
+            Self::Agenda => "TODO agenda",
+            Self::Calendar => "Calendar",
+            Self::GoToDate => "Go to date",
+            Self::VisualMode => "Visual mode",
+            Self::SelectUp => "Extend selection up",
+            Self::SelectDown => "Extend selection down",
+            Self::MoveBlockUp => "Move block up",
+            Self::MoveBlockDown => "Move block down",
+            Self::MoveCardLeft => "Move card to previous column",
+            Self::MoveCardRight => "Move card to next column",
+            Self::DeleteBlock => "Delete block",
+            Self::ToggleTodo => "Toggle TODO",
+            Self::Copy => "Copy block",
+            Self::CopyBlockRef => "Copy block reference",
+            Self::CopyMarkdown => "Copy as Markdown",
+            Self::Paste => "Paste below",
+            Self::PasteAbove => "Paste above",
+            Self::OpenInEditor => "Edit block in $EDITOR",
+            Self::OpenPageInEditor => "Edit page in $EDITOR",
+            Self::CommandPalette => "Command palette",
+        }
+    }
+
+    /// Section of the help screen and cheat sheet the action is listed under.
+    pub fn category(&self) -> Category {
+        match self {
+            Self::MoveUp
+            | Self::MoveDown
+            | Self::CursorLeft
+            | Self::CursorRight
+            | Self::Enter
+            | Self::GoDaily
+            | Self::NextDay
+            | Self::PrevDay
+            | Self::NavBack
+            | Self::NavForward => Category::Navigation,
+            Self::EditBlock
+            | Self::CreateBlock
+            | Self::DeleteBlock
+            | Self::ToggleTodo
+            | Self::Undo
+            | Self::Redo
+            | Self::Copy
+            | Self::CopyBlockRef
+            | Self::CopyMarkdown
+            | Self::Paste
+            | Self::PasteAbove
+            | Self::OpenInEditor
+            | Self::OpenPageInEditor => Category::Editing,
+            Self::Collapse
+            | Self::Expand
+            | Self::Indent
+            | Self::Unindent
+            | Self::VisualMode
+            | Self::SelectUp
+            | Self::SelectDown
+            | Self::MoveBlockUp
+            | Self::MoveBlockDown
+            | Self::MoveCardLeft
+            | Self::MoveCardRight => Category::Structure,
+            Self::Exit
+            | Self::Search
+            | Self::Quit
+            | Self::ToggleSidebar
+            | Self::Help
+            | Self::QuickSwitcher
+            | Self::FilterLinkedRefs
+            | Self::RenamePage
+            | Self::AttributeTable
+            | Self::Agenda
+            | Self::Calendar
+            | Self::GoToDate
+            | Self::CommandPalette => Category::Views,
+        }
+    }
+
+    /// Every action, in the order the command palette lists them.
+    pub const ALL: [Action; 47] = [
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::Collapse,
+        Self::Expand,
+        Self::Enter,
+        Self::Exit,
+        Self::Search,
+        Self::Quit,
+        Self::ToggleSidebar,
+        Self::Help,
+        Self::GoDaily,
+        Self::NextDay,
+        Self::PrevDay,
+        Self::QuickSwitcher,
+        Self::Indent,
+        Self::Unindent,
+        Self::EditBlock,
+        Self::CreateBlock,
+        Self::Undo,
+        Self::Redo,
+        Self::CursorLeft,
+        Self::CursorRight,
+        Self::NavBack,
+        Self::NavForward,
+        Self::FilterLinkedRefs,
+        Self::RenamePage,
+        Self::AttributeTable,
+        Self::Agenda,
+        Self::Calendar,
+        Self::GoToDate,
+        Self::VisualMode,
+        Self::SelectUp,
+        Self::SelectDown,
+        Self::MoveBlockUp,
+        Self::MoveBlockDown,
+        Self::MoveCardLeft,
+        Self::MoveCardRight,
+        Self::DeleteBlock,
+        Self::ToggleTodo,
+        Self::Copy,
+        Self::CopyBlockRef,
+        Self::CopyMarkdown,
+        Self::Paste,
+        Self::PasteAbove,
+        Self::OpenInEditor,
+        Self::OpenPageInEditor,
+        Self::CommandPalette,
+    ];
+}
+
+/// Groups of actions on the help screen and in the cheat sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Navigation,
+    Editing,
+    Structure,
+    Views,
+}
+
+impl Category {
+    pub const ALL: [Category; 4] = [
+        Self::Navigation,
+        Self::Editing,
+        Self::Structure,
+        Self::Views,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Navigation => "Navigation",
+            Self::Editing => "Editing",
+            Self::Structure => "Structure",
+            Self::Views => "Views",
         }
     }
 }
@@ -126,6 +379,7 @@ pub fn vim_preset() -> HashMap<KeyEvent, Action> {
     m.insert(key(KeyCode::Char('b')), Action::ToggleSidebar);
     m.insert(key(KeyCode::Char('?')), Action::Help);
     m.insert(ctrl(KeyCode::Char('k')), Action::QuickSwitcher);
+    m.insert(ctrl(KeyCode::Char('t')), Action::QuickSwitcher);
     m.insert(key(KeyCode::Tab), Action::Indent);
     m.insert(shift(KeyCode::BackTab), Action::Unindent);
     m.insert(key(KeyCode::Char('i')), Action::EditBlock);
@@ -133,7 +387,8 @@ pub fn vim_preset() -> HashMap<KeyEvent, Action> {
     m.insert(key(KeyCode::Char('u')), Action::Undo);
     m.insert(ctrl(KeyCode::Char('r')), Action::Redo);
     m.insert(shift(KeyCode::Char('N')), Action::NextDay);
-    m.insert(shift(KeyCode::Char('P')), Action::PrevDay);
+    m.insert(key(KeyCode::Char(']')), Action::NextDay);
+    m.insert(key(KeyCode::Char('[')), Action::PrevDay);
     m.insert(shift(KeyCode::Char('G')), Action::GoDaily);
     m.insert(key(KeyCode::PageDown), Action::NextDay);
     m.insert(key(KeyCode::PageUp), Action::PrevDay);
@@ -144,6 +399,23 @@ pub fn vim_preset() -> HashMap<KeyEvent, Action> {
     m.insert(shift(KeyCode::Right), Action::NavForward);
     m.insert(alt(KeyCode::Char('[')), Action::NavBack);
     m.insert(alt(KeyCode::Char(']')), Action::NavForward);
+    m.insert(key(KeyCode::Char('f')), Action::FilterLinkedRefs);
+    m.insert(shift(KeyCode::Char('R')), Action::RenamePage);
+    m.insert(shift(KeyCode::Char('A')), Action::AttributeTable);
+    m.insert(shift(KeyCode::Char('V')), Action::VisualMode);
+    m.insert(shift(KeyCode::Up), Action::SelectUp);
+    m.insert(shift(KeyCode::Down), Action::SelectDown);
+    m.insert(alt(KeyCode::Char('k')), Action::MoveBlockUp);
+    m.insert(alt(KeyCode::Char('j')), Action::MoveBlockDown);
+    m.insert(alt(KeyCode::Char('h')), Action::MoveCardLeft);
+    m.insert(alt(KeyCode::Char('l')), Action::MoveCardRight);
+    m.insert(key(KeyCode::Char('t')), Action::ToggleTodo);
+    m.insert(key(KeyCode::Char('p')), Action::Paste);
+    m.insert(shift(KeyCode::Char('P')), Action::PasteAbove);
+    m.insert(shift(KeyCode::Char('E')), Action::OpenInEditor);
+    m.insert(ctrl(KeyCode::Char('e')), Action::OpenPageInEditor);
+    m.insert(ctrl(KeyCode::Char('p')), Action::CommandPalette);
+    m.insert(key(KeyCode::Char(':')), Action::CommandPalette);
     m
 }
 
@@ -178,6 +450,29 @@ pub fn emacs_preset() -> HashMap<KeyEvent, Action> {
     m.insert(alt(KeyCode::Char('[')), Action::NavBack);
     m.insert(alt(KeyCode::Char(']')), Action::NavForward);
     m.insert(alt(KeyCode::Char('u')), Action::QuickSwitcher);
+    m.insert(alt(KeyCode::Char('f')), Action::FilterLinkedRefs);
+    m.insert(alt(KeyCode::Char('r')), Action::RenamePage);
+    m.insert(alt(KeyCode::Char('a')), Action::AttributeTable);
+    m.insert(ctrl(KeyCode::Char(' ')), Action::VisualMode);
+    m.insert(shift(KeyCode::Up), Action::SelectUp);
+    m.insert(shift(KeyCode::Down), Action::SelectDown);
+    m.insert(alt(KeyCode::Up), Action::MoveBlockUp);
+    m.insert(alt(KeyCode::Down), Action::MoveBlockDown);
+    m.insert(alt(KeyCode::Left), Action::MoveCardLeft);
+    m.insert(alt(KeyCode::Right), Action::MoveCardRight);
+    m.insert(ctrl(KeyCode::Char('k')), Action::DeleteBlock);
+    m.insert(alt(KeyCode::Char('t')), Action::ToggleTodo);
+    m.insert(alt(KeyCode::Char('w')), Action::Copy);
+    m.insert(alt(KeyCode::Char('c')), Action::CopyBlockRef);
+    m.insert(alt(KeyCode::Char('m')), Action::CopyMarkdown);
+    m.insert(ctrl(KeyCode::Char('y')), Action::Paste);
+    m.insert(alt(KeyCode::Char('y')), Action::PasteAbove);
+    m.insert(alt(KeyCode::Char('e')), Action::OpenInEditor);
+    m.insert(
+        KeyEvent::new(KeyCode::Char('E'), KeyModifiers::ALT | KeyModifiers::SHIFT),
+        Action::OpenPageInEditor,
+    );
+    m.insert(alt(KeyCode::Char('x')), Action::CommandPalette);
     m
 }
 
@@ -197,6 +492,7 @@ pub fn vscode_preset() -> HashMap<KeyEvent, Action> {
     m.insert(key(KeyCode::F(1)), Action::Help);
     m.insert(ctrl(KeyCode::Char('p')), Action::QuickSwitcher);
     m.insert(ctrl(KeyCode::Char('k')), Action::QuickSwitcher);
+    m.insert(ctrl(KeyCode::Char('t')), Action::QuickSwitcher);
     m.insert(ctrl(KeyCode::Char('d')), Action::GoDaily);
     m.insert(key(KeyCode::Tab), Action::Indent);
     m.insert(shift(KeyCode::BackTab), Action::Unindent);
@@ -212,9 +508,106 @@ pub fn vscode_preset() -> HashMap<KeyEvent, Action> {
     m.insert(shift(KeyCode::Right), Action::NavForward);
     m.insert(alt(KeyCode::Char('[')), Action::NavBack);
     m.insert(alt(KeyCode::Char(']')), Action::NavForward);
+    m.insert(alt(KeyCode::Char('f')), Action::FilterLinkedRefs);
+    m.insert(key(KeyCode::F(2)), Action::RenamePage);
+    m.insert(alt(KeyCode::Char('a')), Action::AttributeTable);
+    m.insert(ctrl_shift(KeyCode::Char('a')), Action::Agenda);
+    m.insert(ctrl_shift(KeyCode::Char('d')), Action::Calendar);
+    m.insert(ctrl(KeyCode::Char('g')), Action::GoToDate);
+    m.insert(shift(KeyCode::Up), Action::SelectUp);
+    m.insert(shift(KeyCode::Down), Action::SelectDown);
+    m.insert(
+        KeyEvent::new(KeyCode::Up, KeyModifiers::ALT | KeyModifiers::SHIFT),
+        Action::MoveBlockUp,
+    );
+    m.insert(
+        KeyEvent::new(KeyCode::Down, KeyModifiers::ALT | KeyModifiers::SHIFT),
+        Action::MoveBlockDown,
+    );
+    m.insert(
+        KeyEvent::new(KeyCode::Left, KeyModifiers::ALT | KeyModifiers::SHIFT),
+        Action::MoveCardLeft,
+    );
+    m.insert(
+        KeyEvent::new(KeyCode::Right, KeyModifiers::ALT | KeyModifiers::SHIFT),
+        Action::MoveCardRight,
+    );
+    m.insert(key(KeyCode::Delete), Action::DeleteBlock);
+    m.insert(alt(KeyCode::Enter), Action::ToggleTodo);
+    m.insert(ctrl(KeyCode::Char('c')), Action::Copy);
+    m.insert(ctrl_shift(KeyCode::Char('c')), Action::CopyBlockRef);
+    m.insert(
+        KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL | KeyModifiers::ALT,
+        ),
+        Action::CopyMarkdown,
+    );
+    m.insert(ctrl(KeyCode::Char('v')), Action::Paste);
+    m.insert(ctrl_shift(KeyCode::Char('v')), Action::PasteAbove);
+    m.insert(ctrl(KeyCode::Char('e')), Action::OpenInEditor);
+    m.insert(ctrl_shift(KeyCode::Char('e')), Action::OpenPageInEditor);
+    m.insert(ctrl_shift(KeyCode::Char('p')), Action::CommandPalette);
     m
 }
 
+/// Multi-key bindings for a preset; `leader` stands in for the leader key.
+pub fn preset_sequences(name: &str, leader: KeyEvent) -> Vec<(Vec<KeyEvent>, Action)> {
+    match name.to_lowercase().as_str() {
+        "vim" => vec![
+            (
+                vec![key(KeyCode::Char('d')), key(KeyCode::Char('d'))],
+                Action::DeleteBlock,
+            ),
+            (
+                vec![key(KeyCode::Char('y')), key(KeyCode::Char('y'))],
+                Action::Copy,
+            ),
+            (
+                vec![key(KeyCode::Char('y')), key(KeyCode::Char('r'))],
+                Action::CopyBlockRef,
+            ),
+            (
+                vec![key(KeyCode::Char('y')), key(KeyCode::Char('m'))],
+                Action::CopyMarkdown,
+            ),
+            (vec![leader, key(KeyCode::Char('f'))], Action::QuickSwitcher),
+            (vec![leader, key(KeyCode::Char('s'))], Action::Search),
+            (vec![leader, key(KeyCode::Char('r'))], Action::RenamePage),
+            (vec![leader, key(KeyCode::Char('a'))], Action::Agenda),
+            (vec![leader, key(KeyCode::Char('c'))], Action::Calendar),
+            (vec![leader, key(KeyCode::Char('d'))], Action::GoToDate),
+        ],
+        "emacs" => vec![
+            (
+                vec![ctrl(KeyCode::Char('x')), ctrl(KeyCode::Char('c'))],
+                Action::Quit,
+            ),
+            (
+                vec![ctrl(KeyCode::Char('x')), ctrl(KeyCode::Char('f'))],
+                Action::QuickSwitcher,
+            ),
+            (
+                vec![ctrl(KeyCode::Char('x')), key(KeyCode::Char('u'))],
+                Action::Undo,
+            ),
+            (
+                vec![ctrl(KeyCode::Char('x')), key(KeyCode::Char('a'))],
+                Action::Agenda,
+            ),
+            (
+                vec![ctrl(KeyCode::Char('x')), key(KeyCode::Char('d'))],
+                Action::Calendar,
+            ),
+            (
+                vec![ctrl(KeyCode::Char('x')), key(KeyCode::Char('g'))],
+                Action::GoToDate,
+            ),
+        ],
+        _ => Vec::new(),
+    }
+}
+
 pub fn get_preset(name: &str) -> Option<HashMap<KeyEvent, Action>> {
     match name.to_lowercase().as_str() {
         "vim" => Some(vim_preset()),
@@ -381,6 +774,21 @@ mod tests {
         assert_eq!(preset.get(&key(KeyCode::PageUp)), Some(&Action::PrevDay));
     }
 
+    #[test]
+    fn vim_brackets_map_to_days_and_shift_p_to_paste_above() {
+        let preset = vim_preset();
+        assert_eq!(
+            preset.get(&shift(KeyCode::Char('N'))),
+            Some(&Action::NextDay)
+        );
+        assert_eq!(preset.get(&key(KeyCode::Char(']'))), Some(&Action::NextDay));
+        assert_eq!(preset.get(&key(KeyCode::Char('['))), Some(&Action::PrevDay));
+        assert_eq!(
+            preset.get(&shift(KeyCode::Char('P'))),
+            Some(&Action::PasteAbove)
+        );
+    }
+
     #[test]
     fn emacs_pagedown_maps_to_next_day() {
         let preset = emacs_preset();
@@ -572,4 +980,322 @@ mod tests {
             Some(&Action::QuickSwitcher)
         );
     }
+
+    // --- Linked refs filter tests ---
+
+    #[test]
+    fn action_from_str_filter_linked_refs() {
+        assert_eq!(
+            Action::from_str("filter_linked_refs"),
+            Some(Action::FilterLinkedRefs)
+        );
+    }
+
+    #[test]
+    fn presets_bind_filter_linked_refs() {
+        assert_eq!(
+            vim_preset().get(&key(KeyCode::Char('f'))),
+            Some(&Action::FilterLinkedRefs)
+        );
+        assert_eq!(
+            emacs_preset().get(&alt(KeyCode::Char('f'))),
+            Some(&Action::FilterLinkedRefs)
+        );
+        assert_eq!(
+            vscode_preset().get(&alt(KeyCode::Char('f'))),
+            Some(&Action::FilterLinkedRefs)
+        );
+    }
+
+    #[test]
+    fn action_from_str_rename_page() {
+        assert_eq!(Action::from_str("rename_page"), Some(Action::RenamePage));
+    }
+
+    #[test]
+    fn presets_bind_rename_page() {
+        assert_eq!(
+            vim_preset().get(&shift(KeyCode::Char('R'))),
+            Some(&Action::RenamePage)
+        );
+        assert_eq!(
+            emacs_preset().get(&alt(KeyCode::Char('r'))),
+            Some(&Action::RenamePage)
+        );
+        assert_eq!(
+            vscode_preset().get(&key(KeyCode::F(2))),
+            Some(&Action::RenamePage)
+        );
+    }
+
+    #[test]
+    fn presets_bind_open_in_editor() {
+        assert_eq!(
+            Action::from_str("open_page_in_editor"),
+            Some(Action::OpenPageInEditor)
+        );
+        assert_eq!(
+            vim_preset().get(&shift(KeyCode::Char('E'))),
+            Some(&Action::OpenInEditor)
+        );
+        assert_eq!(
+            vim_preset().get(&ctrl(KeyCode::Char('e'))),
+            Some(&Action::OpenPageInEditor)
+        );
+        assert_eq!(
+            emacs_preset().get(&alt(KeyCode::Char('e'))),
+            Some(&Action::OpenInEditor)
+        );
+        assert_eq!(
+            vscode_preset().get(&ctrl_shift(KeyCode::Char('e'))),
+            Some(&Action::OpenPageInEditor)
+        );
+    }
+
+    // --- Visual mode and bulk block edit tests ---
+
+    #[test]
+    fn action_from_str_visual_and_block_edits() {
+        assert_eq!(Action::from_str("visual_mode"), Some(Action::VisualMode));
+        assert_eq!(Action::from_str("select_up"), Some(Action::SelectUp));
+        assert_eq!(Action::from_str("select_down"), Some(Action::SelectDown));
+        assert_eq!(Action::from_str("move_block_up"), Some(Action::MoveBlockUp));
+        assert_eq!(
+            Action::from_str("move_block_down"),
+            Some(Action::MoveBlockDown)
+        );
+        assert_eq!(Action::from_str("delete_block"), Some(Action::DeleteBlock));
+        assert_eq!(Action::from_str("toggle_todo"), Some(Action::ToggleTodo));
+        assert_eq!(Action::from_str("copy"), Some(Action::Copy));
+    }
+
+    #[test]
+    fn presets_bind_shift_arrows_to_selection() {
+        for preset in [vim_preset(), emacs_preset(), vscode_preset()] {
+            assert_eq!(preset.get(&shift(KeyCode::Up)), Some(&Action::SelectUp));
+            assert_eq!(preset.get(&shift(KeyCode::Down)), Some(&Action::SelectDown));
+        }
+    }
+
+    #[test]
+    fn vim_shift_v_maps_to_visual_mode() {
+        assert_eq!(
+            vim_preset().get(&shift(KeyCode::Char('V'))),
+            Some(&Action::VisualMode)
+        );
+    }
+
+    #[test]
+    fn presets_bind_move_block() {
+        assert_eq!(
+            vim_preset().get(&alt(KeyCode::Char('k'))),
+            Some(&Action::MoveBlockUp)
+        );
+        assert_eq!(
+            vim_preset().get(&alt(KeyCode::Char('j'))),
+            Some(&Action::MoveBlockDown)
+        );
+        assert_eq!(
+            emacs_preset().get(&alt(KeyCode::Up)),
+            Some(&Action::MoveBlockUp)
+        );
+        let alt_shift = |code| KeyEvent::new(code, KeyModifiers::ALT | KeyModifiers::SHIFT);
+        assert_eq!(
+            vscode_preset().get(&alt_shift(KeyCode::Down)),
+            Some(&Action::MoveBlockDown)
+        );
+    }
+
+    #[test]
+    fn vscode_ctrl_c_maps_to_copy() {
+        assert_eq!(
+            vscode_preset().get(&ctrl(KeyCode::Char('c'))),
+            Some(&Action::Copy)
+        );
+    }
+
+    // --- Yank and paste tests ---
+
+    #[test]
+    fn action_from_str_yank_and_paste() {
+        assert_eq!(
+            Action::from_str("copy_block_ref"),
+            Some(Action::CopyBlockRef)
+        );
+        assert_eq!(
+            Action::from_str("copy_markdown"),
+            Some(Action::CopyMarkdown)
+        );
+        assert_eq!(Action::from_str("paste"), Some(Action::Paste));
+        assert_eq!(Action::from_str("paste_above"), Some(Action::PasteAbove));
+    }
+
+    #[test]
+    fn vim_p_pastes_below_and_shift_p_above() {
+        let preset = vim_preset();
+        assert_eq!(preset.get(&key(KeyCode::Char('p'))), Some(&Action::Paste));
+        assert_eq!(
+            preset.get(&shift(KeyCode::Char('P'))),
+            Some(&Action::PasteAbove)
+        );
+    }
+
+    #[test]
+    fn vscode_ctrl_v_maps_to_paste() {
+        let preset = vscode_preset();
+        assert_eq!(preset.get(&ctrl(KeyCode::Char('v'))), Some(&Action::Paste));
+        assert_eq!(
+            preset.get(&ctrl_shift(KeyCode::Char('c'))),
+            Some(&Action::CopyBlockRef)
+        );
+    }
+
+    #[test]
+    fn emacs_ctrl_y_maps_to_paste() {
+        assert_eq!(
+            emacs_preset().get(&ctrl(KeyCode::Char('y'))),
+            Some(&Action::Paste)
+        );
+    }
+
+    // --- Command palette tests ---
+
+    #[test]
+    fn all_lists_every_action_once() {
+        for action in &Action::ALL {
+            assert_eq!(
+                Action::ALL.iter().filter(|a| *a == action).count(),
+                1,
+                "{:?} listed twice",
+                action
+            );
+        }
+        assert_eq!(
+            Action::from_str("command_palette"),
+            Some(Action::CommandPalette)
+        );
+    }
+
+    #[test]
+    fn presets_bind_kanban_card_moves() {
+        assert_eq!(
+            Action::from_str("move_card_left"),
+            Some(Action::MoveCardLeft)
+        );
+        assert_eq!(
+            vim_preset().get(&alt(KeyCode::Char('l'))),
+            Some(&Action::MoveCardRight)
+        );
+        assert_eq!(
+            emacs_preset().get(&alt(KeyCode::Left)),
+            Some(&Action::MoveCardLeft)
+        );
+        assert_eq!(
+            vscode_preset().get(&KeyEvent::new(
+                KeyCode::Right,
+                KeyModifiers::ALT | KeyModifiers::SHIFT
+            )),
+            Some(&Action::MoveCardRight)
+        );
+    }
+
+    #[test]
+    fn presets_bind_agenda() {
+        assert_eq!(Action::from_str("agenda"), Some(Action::Agenda));
+        assert_eq!(
+            vscode_preset().get(&ctrl_shift(KeyCode::Char('a'))),
+            Some(&Action::Agenda)
+        );
+        let leader = key(KeyCode::Char(' '));
+        assert!(preset_sequences("vim", leader)
+            .contains(&(vec![leader, key(KeyCode::Char('a'))], Action::Agenda)));
+    }
+
+    #[test]
+    fn presets_bind_calendar() {
+        assert_eq!(Action::from_str("calendar"), Some(Action::Calendar));
+        assert_eq!(
+            vscode_preset().get(&ctrl_shift(KeyCode::Char('d'))),
+            Some(&Action::Calendar)
+        );
+        let leader = key(KeyCode::Char(' '));
+        assert!(preset_sequences("vim", leader)
+            .contains(&(vec![leader, key(KeyCode::Char('c'))], Action::Calendar)));
+        let cx = ctrl(KeyCode::Char('x'));
+        assert!(preset_sequences("emacs", leader)
+            .contains(&(vec![cx, key(KeyCode::Char('d'))], Action::Calendar)));
+    }
+
+    #[test]
+    fn presets_bind_go_to_date() {
+        assert_eq!(Action::from_str("go_to_date"), Some(Action::GoToDate));
+        assert_eq!(
+            vscode_preset().get(&ctrl(KeyCode::Char('g'))),
+            Some(&Action::GoToDate)
+        );
+        let leader = key(KeyCode::Char(' '));
+        assert!(preset_sequences("vim", leader)
+            .contains(&(vec![leader, key(KeyCode::Char('d'))], Action::GoToDate)));
+        let cx = ctrl(KeyCode::Char('x'));
+        assert!(preset_sequences("emacs", leader)
+            .contains(&(vec![cx, key(KeyCode::Char('g'))], Action::GoToDate)));
+    }
+
+    #[test]
+    fn presets_bind_command_palette() {
+        assert_eq!(
+            vim_preset().get(&ctrl(KeyCode::Char('p'))),
+            Some(&Action::CommandPalette)
+        );
+        assert_eq!(
+            vim_preset().get(&key(KeyCode::Char(':'))),
+            Some(&Action::CommandPalette)
+        );
+        assert_eq!(
+            emacs_preset().get(&alt(KeyCode::Char('x'))),
+            Some(&Action::CommandPalette)
+        );
+        assert_eq!(
+            vscode_preset().get(&ctrl_shift(KeyCode::Char('p'))),
+            Some(&Action::CommandPalette)
+        );
+    }
+
+    // --- Key sequence tests ---
+
+    #[test]
+    fn vim_sequences_cover_operators_and_leader() {
+        let leader = key(KeyCode::Char(' '));
+        let sequences = preset_sequences("vim", leader);
+        let find = |keys: &[KeyEvent]| {
+            sequences
+                .iter()
+                .find(|(seq, _)| seq.as_slice() == keys)
+                .map(|(_, a)| a.clone())
+        };
+        let d = key(KeyCode::Char('d'));
+        assert_eq!(find(&[d, d]), Some(Action::DeleteBlock));
+        assert_eq!(
+            find(&[key(KeyCode::Char('y')), key(KeyCode::Char('m'))]),
+            Some(Action::CopyMarkdown)
+        );
+        assert_eq!(
+            find(&[leader, key(KeyCode::Char('f'))]),
+            Some(Action::QuickSwitcher)
+        );
+        // Operator keys stay free to start sequences
+        assert!(!vim_preset().contains_key(&d));
+        assert!(!vim_preset().contains_key(&leader));
+    }
+
+    #[test]
+    fn emacs_ctrl_x_sequences() {
+        let sequences = preset_sequences("emacs", key(KeyCode::Char(' ')));
+        assert!(sequences.contains(&(
+            vec![ctrl(KeyCode::Char('x')), ctrl(KeyCode::Char('c'))],
+            Action::Quit
+        )));
+        assert!(!emacs_preset().contains_key(&ctrl(KeyCode::Char('x'))));
+        assert!(preset_sequences("vscode", key(KeyCode::Char(' '))).is_empty());
+    }
 }
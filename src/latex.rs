@@ -0,0 +1,501 @@
+//! A small LaTeX-to-Unicode renderer for `$$...$$` in block text.
+//!
+//! [`to_unicode`] covers the math people write inline: Greek letters, sub-
+//! and superscripts, `\frac`, `\sqrt`, big operators and common relations.
+//! Anything else returns `None`, so callers can show the source instead of
+//! a half-converted formula.
+//!
+//! ```
+//! use roam_sdk::latex::to_unicode;
+//!
+//! assert_eq!(to_unicode(r"\alpha^2 + \beta_i").as_deref(), Some("α² + βᵢ"));
+//! assert_eq!(to_unicode(r"\frac{1}{2}").as_deref(), Some("1⁄2"));
+//! assert_eq!(to_unicode(r"\begin{matrix} a \end{matrix}"), None);
+//! ```
+
+/// Render LaTeX math as Unicode text, or `None` when it uses a construct
+/// without a plain-text equivalent.
+pub fn to_unicode(src: &str) -> Option<String> {
+    let mut parser = Parser {
+        chars: src.trim().chars().collect(),
+        pos: 0,
+    };
+    let out = parser.sequence()?;
+    (parser.pos == parser.chars.len()).then_some(out)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Atoms up to the end of input or a closing brace, which is left for
+    /// the caller.
+    fn sequence(&mut self) -> Option<String> {
+        let mut out = String::new();
+        while self.peek().is_some_and(|c| c != '}') {
+            out.push_str(&self.atom()?);
+        }
+        Some(out)
+    }
+
+    /// One character, `{group}`, command with its arguments, or script.
+    fn atom(&mut self) -> Option<String> {
+        match self.next()? {
+            '{' => self.group_rest(),
+            '}' => None,
+            '\\' => self.command(),
+            '^' => scripted(&self.arg()?, superscript),
+            '_' => scripted(&self.arg()?, subscript),
+            '\'' => Some("′".to_string()),
+            c if c.is_whitespace() => {
+                self.skip_space();
+                Some(" ".to_string())
+            }
+            c => Some(c.to_string()),
+        }
+    }
+
+    /// The rest of a group after its opening brace.
+    fn group_rest(&mut self) -> Option<String> {
+        let inner = self.sequence()?;
+        (self.next()? == '}').then_some(inner)
+    }
+
+    /// A command or script argument: `{group}` or a single atom.
+    fn arg(&mut self) -> Option<String> {
+        self.skip_space();
+        self.atom()
+    }
+
+    /// A `{...}` argument taken as plain text, for `\text` and friends.
+    fn text_arg(&mut self) -> Option<String> {
+        self.skip_space();
+        if self.next()? != '{' {
+            return None;
+        }
+        let mut depth = 0;
+        let mut out = String::new();
+        loop {
+            match self.next()? {
+                '{' => depth += 1,
+                '}' if depth == 0 => return Some(out),
+                '}' => depth -= 1,
+                c => out.push(c),
+            }
+        }
+    }
+
+    /// `[n]` after `\sqrt`, when present.
+    fn optional_arg(&mut self) -> Option<Option<String>> {
+        self.skip_space();
+        if self.peek() != Some('[') {
+            return Some(None);
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        while self.peek().is_some_and(|c| c != ']') {
+            out.push_str(&self.atom()?);
+        }
+        self.pos += 1;
+        Some(Some(out))
+    }
+
+    /// Everything after a backslash.
+    fn command(&mut self) -> Option<String> {
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_alphabetic) {
+            name.push(c);
+            self.pos += 1;
+        }
+        if name.is_empty() {
+            // Escaped symbol or spacing command: `\{`, `\%`, `\,`
+            return match self.next()? {
+                ',' | ';' | ':' | ' ' => Some(" ".to_string()),
+                '!' => Some(String::new()),
+                c @ ('{' | '}' | '%' | '$' | '&' | '#' | '_' | '|') => Some(c.to_string()),
+                _ => None,
+            };
+        }
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let num = self.arg()?;
+                let den = self.arg()?;
+                Some(format!("{}⁄{}", parenthesize(&num), parenthesize(&den)))
+            }
+            "sqrt" => {
+                let index = self.optional_arg()?;
+                let radicand = parenthesize(&self.arg()?);
+                match index.as_deref() {
+                    None | Some("2") => Some(format!("√{}", radicand)),
+                    Some("3") => Some(format!("∛{}", radicand)),
+                    Some("4") => Some(format!("∜{}", radicand)),
+                    Some(n) => Some(format!("{}√{}", scripted(n, superscript)?, radicand)),
+                }
+            }
+            "text" | "textrm" | "textit" | "textbf" | "operatorname" | "mbox" => self.text_arg(),
+            "mathrm" | "mathit" | "mathbf" | "mathsf" | "mathtt" | "boldsymbol" => self.arg(),
+            "mathbb" => self.arg()?.chars().map(double_struck).collect(),
+            "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "displaystyle" | "limits" => {
+                Some(String::new())
+            }
+            "quad" | "qquad" => Some(" ".to_string()),
+            _ => symbol(&name)
+                .map(str::to_string)
+                .or_else(|| FUNCTIONS.contains(&name.as_str()).then_some(name)),
+        }
+    }
+}
+
+/// Function names LaTeX sets upright: `\sin x` renders as `sin x`.
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "log", "ln", "lg", "exp", "lim", "max", "min", "sup", "inf", "det", "gcd", "deg", "dim", "ker",
+    "arg", "mod",
+];
+
+/// Wrap a fraction part or radicand in parentheses unless it is a single
+/// term, so `\frac{a+b}{2}` reads `(a+b)⁄2`.
+fn parenthesize(s: &str) -> String {
+    let s = s.trim();
+    if s.chars().count() <= 1 || s.chars().all(char::is_alphanumeric) {
+        s.to_string()
+    } else {
+        format!("({})", s)
+    }
+}
+
+/// Map every character of a script, failing when one has no Unicode form.
+fn scripted(s: &str, map: fn(char) -> Option<char>) -> Option<String> {
+    s.chars().filter(|c| !c.is_whitespace()).map(map).collect()
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'A' => 'ᴬ',
+        'B' => 'ᴮ',
+        'D' => 'ᴰ',
+        'E' => 'ᴱ',
+        'G' => 'ᴳ',
+        'H' => 'ᴴ',
+        'I' => 'ᴵ',
+        'J' => 'ᴶ',
+        'K' => 'ᴷ',
+        'L' => 'ᴸ',
+        'M' => 'ᴹ',
+        'N' => 'ᴺ',
+        'O' => 'ᴼ',
+        'P' => 'ᴾ',
+        'R' => 'ᴿ',
+        'T' => 'ᵀ',
+        'U' => 'ᵁ',
+        'W' => 'ᵂ',
+        'V' => 'ⱽ',
+        '′' | '*' | '∗' | '∘' | '°' => c,
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        'β' => 'ᵦ',
+        'γ' => 'ᵧ',
+        'ρ' => 'ᵨ',
+        'φ' => 'ᵩ',
+        'χ' => 'ᵪ',
+        _ => return None,
+    })
+}
+
+fn double_struck(c: char) -> Option<char> {
+    Some(match c {
+        'N' => 'ℕ',
+        'Z' => 'ℤ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'C' => 'ℂ',
+        'P' => 'ℙ',
+        'H' => 'ℍ',
+        _ => return None,
+    })
+}
+
+/// Greek letters, operators, relations and arrows.
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" => "ϵ",
+        "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "omicron" => "ο",
+        "pi" => "π",
+        "varpi" => "ϖ",
+        "rho" => "ρ",
+        "varrho" => "ϱ",
+        "sigma" => "σ",
+        "varsigma" => "ς",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        "bigcup" => "⋃",
+        "bigcap" => "⋂",
+        "times" => "×",
+        "cdot" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "•",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "wedge" | "land" => "∧",
+        "vee" | "lor" => "∨",
+        "neg" | "lnot" => "¬",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "mid" => "∣",
+        "parallel" => "∥",
+        "perp" => "⊥",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "emptyset" | "varnothing" => "∅",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "angle" => "∠",
+        "degree" => "°",
+        "prime" => "′",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "vert" => "|",
+        "Vert" => "‖",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(src: &str) -> String {
+        to_unicode(src).unwrap_or_else(|| panic!("unsupported: {}", src))
+    }
+
+    #[test]
+    fn greek_letters_and_operators() {
+        assert_eq!(render(r"\alpha + \beta \times \Gamma"), "α + β × Γ");
+        assert_eq!(render(r"a \leq b \neq c"), "a ≤ b ≠ c");
+        assert_eq!(render(r"x \to \infty"), "x → ∞");
+        assert_eq!(render(r"\sin \theta"), "sin θ");
+    }
+
+    #[test]
+    fn scripts_map_to_unicode_forms() {
+        assert_eq!(render("x^2"), "x²");
+        assert_eq!(render("x^{n+1}"), "xⁿ⁺¹");
+        assert_eq!(render("a_i"), "aᵢ");
+        assert_eq!(render("x_{10}^{2}"), "x₁₀²");
+    }
+
+    #[test]
+    fn fractions_and_roots() {
+        assert_eq!(render(r"\frac{1}{2}"), "1⁄2");
+        assert_eq!(render(r"\frac12"), "1⁄2");
+        assert_eq!(render(r"\frac{a+b}{c}"), "(a+b)⁄c");
+        assert_eq!(render(r"\sqrt{x}"), "√x");
+        assert_eq!(render(r"\sqrt{x^2 + y^2}"), "√(x² + y²)");
+        assert_eq!(render(r"\sqrt[3]{8}"), "∛8");
+    }
+
+    #[test]
+    fn big_operators_take_limits_as_scripts() {
+        assert_eq!(render(r"\sum_{i=1}^{n} i"), "∑ᵢ₌₁ⁿ i");
+        assert_eq!(render(r"\int_0^1 f(x)\,dx"), "∫₀¹ f(x) dx");
+    }
+
+    #[test]
+    fn text_and_fonts() {
+        assert_eq!(render(r"\text{if} x \in \mathbb{R}"), "if x ∈ ℝ");
+        assert_eq!(render(r"\mathbf{v}"), "v");
+        assert_eq!(render(r"\left( a \right)"), "( a )");
+    }
+
+    #[test]
+    fn unsupported_constructs_return_none() {
+        assert_eq!(to_unicode(r"\begin{pmatrix} a \end{pmatrix}"), None);
+        assert_eq!(to_unicode(r"\unknown"), None);
+        // No Unicode superscript for `q`
+        assert_eq!(to_unicode("x^q"), None);
+        assert_eq!(to_unicode(r"e^{i\pi}"), None);
+        assert_eq!(to_unicode("{a"), None);
+        assert_eq!(to_unicode("a}"), None);
+        assert_eq!(to_unicode(r"a \\ b"), None);
+    }
+}
@@ -1,5 +1,8 @@
 pub mod api;
 pub mod error;
+pub mod latex;
+pub mod markup;
+pub mod roam_query;
 
 // Convenience re-exports
 pub use api::client::RoamClient;
@@ -1,17 +1,22 @@
-// Re-export SDK modules so binary-internal modules can use crate::api:: and crate::error::
-pub(crate) use roam_sdk::{api, error};
+// Re-export SDK modules so binary-internal modules can use crate::api::, crate::error::, crate::markup:: and crate::roam_query::
+pub(crate) use roam_sdk::{api, error, latex, markup, roam_query};
 
 mod app;
+mod cache;
+mod clipboard;
 mod config;
 mod edit_buffer;
+mod fuzzy;
 mod highlight;
 mod keys;
 mod markdown;
 mod ui;
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use config::AppConfig;
+use keys::KeybindingMap;
 
 fn config_path() -> PathBuf {
     AppConfig::config_dir()
@@ -19,8 +24,43 @@ fn config_path() -> PathBuf {
         .join("config.toml")
 }
 
+/// `roam keys [--preset NAME]`: print the keybindings as a Markdown cheat
+/// sheet. Without `--preset` the configured keymap is used, overrides included.
+fn print_keys(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (map, title) = match args {
+        [] => {
+            let path = config_path();
+            let keybindings = if path.exists() {
+                AppConfig::load_from_path(&path)?.keybindings
+            } else {
+                config::KeybindingsConfig::default()
+            };
+            (
+                KeybindingMap::from_config(&keybindings)?,
+                "Keybindings".to_string(),
+            )
+        }
+        [flag, preset] if flag == "--preset" => (
+            KeybindingMap::from_preset(preset, &HashMap::new())?,
+            format!("{} keybindings", preset),
+        ),
+        _ => return Err("usage: roam keys [--preset vim|emacs|vscode]".into()),
+    };
+    print!("{}", map.cheat_sheet(&title));
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("keys") {
+        if let Err(e) = print_keys(&args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let path = config_path();
 
     if !path.exists() {
@@ -51,12 +91,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
     );
 
+    let mouse = config.ui.mouse;
+    if mouse {
+        let _ = crossterm::execute!(std::io::stdout(), crossterm::event::EnableMouseCapture);
+    }
+
     let hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
         let _ = crossterm::execute!(
             std::io::stdout(),
             crossterm::event::PopKeyboardEnhancementFlags
         );
+        if mouse {
+            let _ = crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture);
+        }
         ratatui::restore();
         hook(info);
     }));
@@ -67,6 +115,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::io::stdout(),
         crossterm::event::PopKeyboardEnhancementFlags
     );
+    if mouse {
+        let _ = crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture);
+    }
     ratatui::restore();
 
     if let Err(e) = result {
@@ -1,8 +1,13 @@
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::Span;
 
+use crate::latex;
+use crate::markup::{self, EmbedTarget, Node, NodeKind};
+use crate::roam_query::{self, Query};
+
 /// Count the number of rendered characters after markdown processing.
 ///
 /// This computes how many visible characters a line of text produces
@@ -15,11 +20,14 @@ pub fn rendered_char_count(text: &str) -> usize {
         .sum()
 }
 
-/// Parse Roam-flavored inline markdown into styled ratatui Spans.
+/// Render Roam block markup, parsed by [`markup::parse`], into styled
+/// ratatui Spans.
 ///
-/// Supports: **bold**, __italic__, [[page links]], ((block refs)), #tags,
-/// `inline code`, ~~strikethrough~~, ^^highlight^^, {{TODO}}, {{DONE}},
-/// {{embed: ((uid))}}, {{[[embed]]: ((uid))}}.
+/// Styles: **bold**, __italic__, [[page links]] (nested too), ((block refs)),
+/// #tags, `inline code`, ~~strikethrough~~, ^^highlight^^, {{TODO}}, {{DONE}},
+/// {{embed: ((uid))}}, {{[[embed]]: ((uid))}}, [links](url) and $$math$$,
+/// rendered as Unicode by [`latex::to_unicode`]. Formatting nests, so
+/// `**bold [[link]]**` is a bold link.
 ///
 /// When `block_map` is provided, ((block-uid)) references are resolved
 /// to show the referenced block's text instead of the raw UID.
@@ -32,370 +40,460 @@ pub fn render_spans_with_refs(
     base_style: Style,
     block_map: Option<&HashMap<String, String>>,
 ) -> Vec<Span<'static>> {
-    if text.is_empty() {
-        return vec![];
-    }
-
-    let chars: Vec<char> = text.chars().collect();
-    let len = chars.len();
-    let mut spans: Vec<Span<'static>> = Vec::new();
-    let mut plain = String::new();
-    let mut i = 0;
-
-    while i < len {
-        // {{TODO}} / {{DONE}} / {{[[TODO]]}} / {{[[DONE]]}} / {{embed: ...}} / {{[[embed]]: ...}}
-        if chars[i] == '{' && i + 1 < len && chars[i + 1] == '{' {
-            if let Some(end) = find_closing_double_brace(&chars, i + 2) {
-                let inner: String = chars[i + 2..end].iter().collect();
-                let normalized = inner
-                    .strip_prefix("[[")
-                    .and_then(|s| s.strip_suffix("]]"))
-                    .unwrap_or(&inner);
-                if normalized == "TODO" || normalized == "DONE" {
-                    flush_plain(&mut plain, base_style, &mut spans);
-                    if normalized == "TODO" {
-                        spans.push(Span::styled("☐ ".to_string(), base_style.fg(Color::Red)));
-                    } else {
-                        spans.push(Span::styled("✓ ".to_string(), base_style.fg(Color::Green)));
-                    }
-                    i = end + 2; // skip past }}
-                    continue;
-                }
-                // {{embed: ((uid))}} / {{[[embed]]: ((uid))}} / {{embed: [[page]]}}
-                if let Some(ref_text) = parse_embed_inner(&inner) {
-                    flush_plain(&mut plain, base_style, &mut spans);
-                    let resolved = resolve_ref(&ref_text, block_map);
-                    spans.push(Span::styled(
-                        format!("▸ {}", resolved),
-                        base_style.fg(Color::Magenta).add_modifier(Modifier::ITALIC),
-                    ));
-                    i = end + 2;
-                    continue;
-                }
-            }
-        }
+    render_spans_with_targets(text, base_style, block_map).0
+}
 
-        // `inline code`
-        if chars[i] == '`' {
-            if let Some(end) = find_single_delimiter(&chars, i + 1, '`') {
-                flush_plain(&mut plain, base_style, &mut spans);
-                let content: String = chars[i + 1..end].iter().collect();
-                spans.push(Span::styled(
-                    content,
-                    base_style.fg(Color::Green).bg(Color::DarkGray),
-                ));
-                i = end + 1;
-                continue;
-            }
-        }
+/// What a click on part of a rendered line points at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    /// A `[[page]]`, `#tag` or page embed.
+    Page(String),
+    /// A `((block ref))` or block embed.
+    Block(String),
+    /// The `{{TODO}}` / `{{DONE}}` checkbox, with the byte range of its marker
+    /// in the rendered string.
+    Checkbox(Range<usize>),
+}
 
-        // **bold**
-        if chars[i] == '*' && i + 1 < len && chars[i + 1] == '*' {
-            if let Some(end) = find_double_delimiter(&chars, i + 2, '*') {
-                flush_plain(&mut plain, base_style, &mut spans);
-                let content: String = chars[i + 2..end].iter().collect();
-                spans.push(Span::styled(
-                    content,
-                    base_style.fg(Color::White).add_modifier(Modifier::BOLD),
-                ));
-                i = end + 2;
-                continue;
-            }
-        }
+/// Like [`render_spans_with_refs`], also returning the clickable parts of the
+/// line as ranges of rendered characters. A link nested in another comes
+/// before it, so the first range containing a position is the innermost.
+pub fn render_spans_with_targets(
+    text: &str,
+    base_style: Style,
+    block_map: Option<&HashMap<String, String>>,
+) -> (Vec<Span<'static>>, Vec<(Range<usize>, LinkTarget)>) {
+    let mut renderer = Renderer {
+        src: text,
+        block_map,
+        spans: Vec::new(),
+        targets: Vec::new(),
+        len: 0,
+    };
+    renderer.nodes(&markup::parse(text), base_style);
+    (renderer.spans, renderer.targets)
+}
 
-        // __italic__
-        if chars[i] == '_' && i + 1 < len && chars[i + 1] == '_' {
-            if let Some(end) = find_double_delimiter(&chars, i + 2, '_') {
-                flush_plain(&mut plain, base_style, &mut spans);
-                let content: String = chars[i + 2..end].iter().collect();
-                spans.push(Span::styled(
-                    content,
-                    base_style.add_modifier(Modifier::ITALIC),
-                ));
-                i = end + 2;
-                continue;
-            }
-        }
+struct Renderer<'a> {
+    src: &'a str,
+    block_map: Option<&'a HashMap<String, String>>,
+    spans: Vec<Span<'static>>,
+    targets: Vec<(Range<usize>, LinkTarget)>,
+    /// Rendered characters so far.
+    len: usize,
+}
 
-        // ^^highlight^^
-        if chars[i] == '^' && i + 1 < len && chars[i + 1] == '^' {
-            if let Some(end) = find_double_delimiter(&chars, i + 2, '^') {
-                flush_plain(&mut plain, base_style, &mut spans);
-                let content: String = chars[i + 2..end].iter().collect();
-                spans.push(Span::styled(content, base_style.bg(Color::Yellow)));
-                i = end + 2;
-                continue;
-            }
+impl Renderer<'_> {
+    fn nodes(&mut self, nodes: &[Node], style: Style) {
+        for node in nodes {
+            self.node(node, style);
         }
+    }
 
-        // ~~strikethrough~~
-        if chars[i] == '~' && i + 1 < len && chars[i + 1] == '~' {
-            if let Some(end) = find_double_delimiter(&chars, i + 2, '~') {
-                flush_plain(&mut plain, base_style, &mut spans);
-                let content: String = chars[i + 2..end].iter().collect();
-                spans.push(Span::styled(
-                    content,
-                    base_style
-                        .fg(Color::DarkGray)
-                        .add_modifier(Modifier::CROSSED_OUT),
-                ));
-                i = end + 2;
-                continue;
+    fn node(&mut self, node: &Node, style: Style) {
+        let start = self.len;
+        match &node.kind {
+            NodeKind::Text(text) => self.push(text.clone(), style),
+            NodeKind::Bold(children) => self.nodes(
+                children,
+                style.fg(Color::White).add_modifier(Modifier::BOLD),
+            ),
+            NodeKind::Italic(children) => {
+                self.nodes(children, style.add_modifier(Modifier::ITALIC))
             }
-        }
-
-        // [[page link]]
-        if chars[i] == '[' && i + 1 < len && chars[i + 1] == '[' {
-            if let Some(end) = find_double_delimiter(&chars, i + 2, ']') {
-                flush_plain(&mut plain, base_style, &mut spans);
-                let content: String = chars[i + 2..end].iter().collect();
-                spans.push(Span::styled(content, base_style.fg(Color::Cyan)));
-                i = end + 2;
-                continue;
+            NodeKind::Highlight(children) => self.nodes(children, style.bg(Color::Yellow)),
+            NodeKind::Strike(children) => self.nodes(
+                children,
+                style
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT),
+            ),
+            NodeKind::Code(code) => {
+                self.push(code.clone(), style.fg(Color::Green).bg(Color::DarkGray))
             }
-        }
-
-        // [text](url) markdown link
-        if chars[i] == '[' && (i + 1 >= len || chars[i + 1] != '[') {
-            if let Some((text, link_end)) = parse_markdown_link(&chars, i) {
-                flush_plain(&mut plain, base_style, &mut spans);
-                spans.push(Span::styled(
-                    text,
-                    base_style
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::UNDERLINED),
-                ));
-                i = link_end;
-                continue;
+            NodeKind::PageRef { title, children } => {
+                self.nodes(children, style.fg(Color::Cyan));
+                self.target(start, LinkTarget::Page(title.clone()));
             }
-        }
-
-        // ((block ref))
-        if chars[i] == '(' && i + 1 < len && chars[i + 1] == '(' {
-            if let Some(end) = find_double_delimiter(&chars, i + 2, ')') {
-                flush_plain(&mut plain, base_style, &mut spans);
-                let uid: String = chars[i + 2..end].iter().collect();
-                let display = resolve_ref(&RefText::BlockUid(uid), block_map);
-                spans.push(Span::styled(display, base_style.fg(Color::Magenta)));
-                i = end + 2;
-                continue;
+            NodeKind::Tag { title, .. } => {
+                self.push(
+                    self.src[node.span.clone()].to_string(),
+                    style.fg(Color::Cyan),
+                );
+                self.target(start, LinkTarget::Page(title.clone()));
             }
-        }
-
-        // #tag (until space or end)
-        if chars[i] == '#' && i + 1 < len && !chars[i + 1].is_whitespace() {
-            flush_plain(&mut plain, base_style, &mut spans);
-            let start = i;
-            // Handle #[[page tag]] syntax
-            if i + 2 < len && chars[i + 1] == '[' && chars[i + 2] == '[' {
-                if let Some(end) = find_double_delimiter(&chars, i + 3, ']') {
-                    let content: String = chars[start..end + 2].iter().collect();
-                    spans.push(Span::styled(content, base_style.fg(Color::Cyan)));
-                    i = end + 2;
-                    continue;
-                }
+            NodeKind::BlockRef(uid) => {
+                self.push(self.resolve_block(uid), style.fg(Color::Magenta));
+                self.target(start, LinkTarget::Block(uid.clone()));
+            }
+            NodeKind::Embed(target) => {
+                let (display, target) = match target {
+                    EmbedTarget::Block(uid) => {
+                        (self.resolve_block(uid), LinkTarget::Block(uid.clone()))
+                    }
+                    EmbedTarget::Page(title) => (title.clone(), LinkTarget::Page(title.clone())),
+                };
+                self.push(
+                    format!("▸ {}", display),
+                    style.fg(Color::Magenta).add_modifier(Modifier::ITALIC),
+                );
+                self.target(start, target);
+            }
+            NodeKind::Todo { done: false } => {
+                self.push("☐ ".to_string(), style.fg(Color::Red));
+                self.target(start, LinkTarget::Checkbox(node.span.clone()));
             }
-            // Regular #tag
-            i += 1;
-            while i < len && !chars[i].is_whitespace() {
-                i += 1;
+            NodeKind::Todo { done: true } => {
+                self.push("✓ ".to_string(), style.fg(Color::Green));
+                self.target(start, LinkTarget::Checkbox(node.span.clone()));
+            }
+            NodeKind::Link { text, .. } => self.nodes(
+                text,
+                style.fg(Color::Cyan).add_modifier(Modifier::UNDERLINED),
+            ),
+            NodeKind::Image { alt, .. } => self.push(
+                alt.clone(),
+                style.fg(Color::Cyan).add_modifier(Modifier::UNDERLINED),
+            ),
+            NodeKind::Attribute(name) => {
+                let style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+                self.push(name.clone(), style);
+                self.target(start, LinkTarget::Page(name.clone()));
+                self.push(":".to_string(), style);
+            }
+            NodeKind::Macro { name, args } if name == "query" => {
+                // Named queries show their name; results are drawn under the block
+                let label = match roam_query::parse(args) {
+                    Ok(Query {
+                        title: Some(title), ..
+                    }) => title,
+                    _ => args.clone(),
+                };
+                self.push(
+                    format!("⌕ {}", label),
+                    style.fg(Color::Cyan).add_modifier(Modifier::ITALIC),
+                );
+            }
+            NodeKind::Latex(tex) => match latex::to_unicode(tex) {
+                Some(math) => self.push(math, style.fg(Color::Yellow)),
+                // Unsupported constructs show the source as written
+                None => self.push(
+                    self.src[node.span.clone()].to_string(),
+                    style.fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ),
+            },
+            NodeKind::Url(_) | NodeKind::Macro { .. } => {
+                self.push(self.src[node.span.clone()].to_string(), style)
             }
-            let tag: String = chars[start..i].iter().collect();
-            spans.push(Span::styled(tag, base_style.fg(Color::Cyan)));
-            continue;
         }
+    }
 
-        plain.push(chars[i]);
-        i += 1;
+    /// Append text, extending the last span when the style is the same.
+    fn push(&mut self, content: String, style: Style) {
+        if content.is_empty() {
+            return;
+        }
+        self.len += content.chars().count();
+        match self.spans.last_mut() {
+            Some(last) if last.style == style => last.content.to_mut().push_str(&content),
+            _ => self.spans.push(Span::styled(content, style)),
+        }
     }
 
-    flush_plain(&mut plain, base_style, &mut spans);
-    spans
+    /// Record everything rendered since `start` as pointing at `target`.
+    fn target(&mut self, start: usize, target: LinkTarget) {
+        self.targets.push((start..self.len, target));
+    }
+
+    /// The referenced block's text, or the uid when it isn't loaded.
+    fn resolve_block(&self, uid: &str) -> String {
+        self.block_map
+            .and_then(|map| map.get(uid))
+            .cloned()
+            .unwrap_or_else(|| uid.to_string())
+    }
 }
 
-/// Represents a reference extracted from embed syntax.
-enum RefText {
-    BlockUid(String),
-    PageName(String),
+/// Extract page names from `[[...]]`, `#[[...]]`, `#tag` links and a leading
+/// `Name::` attribute in block text, nested ones included.
+///
+/// Returns a deduplicated list of page names in order of first appearance.
+pub fn extract_page_links(text: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut seen = HashSet::new();
+    markup::walk(&markup::parse(text), &mut |node| {
+        if let NodeKind::PageRef { title, .. }
+        | NodeKind::Tag { title, .. }
+        | NodeKind::Attribute(title) = &node.kind
+        {
+            if seen.insert(title.clone()) {
+                links.push(title.clone());
+            }
+        }
+    });
+    links
 }
 
-/// Parse the inner content of {{...}} to extract embed references.
-/// Handles: `embed: ((uid))`, `[[embed]]: ((uid))`, `embed: [[page]]`, `[[embed]]: [[page]]`
-fn parse_embed_inner(inner: &str) -> Option<RefText> {
-    let trimmed = inner
-        .strip_prefix("[[embed]]:")
-        .or_else(|| inner.strip_prefix("embed:"))?
-        .trim();
-
-    if let Some(uid) = trimmed
-        .strip_prefix("((")
-        .and_then(|s| s.strip_suffix("))"))
-    {
-        Some(RefText::BlockUid(uid.to_string()))
-    } else if let Some(page) = trimmed
-        .strip_prefix("[[")
-        .and_then(|s| s.strip_suffix("]]"))
+/// Markup for tagging `title`: `#title`, or `#[[title]]` when a bare tag would end early.
+pub fn tag_markup(title: &str) -> String {
+    if title
+        .chars()
+        .any(|c| c.is_whitespace() || c == '[' || c == ']')
     {
-        Some(RefText::PageName(page.to_string()))
+        format!("#[[{}]]", title)
     } else {
-        Some(RefText::PageName(trimmed.to_string()))
+        format!("#{}", title)
     }
 }
 
-/// Resolve a reference to display text using the block map.
-fn resolve_ref(ref_text: &RefText, block_map: Option<&HashMap<String, String>>) -> String {
-    match ref_text {
-        RefText::BlockUid(uid) => {
-            if let Some(map) = block_map {
-                if let Some(text) = map.get(uid.as_str()) {
-                    return text.clone();
-                }
+/// Rewrite references to page `old` as references to `new`.
+///
+/// Covers `[[old]]`, `#[[old]]` and bare `#old` tags at the start of a word,
+/// including ones followed by punctuation such as `#old,`; a bare tag becomes
+/// `#[[new]]` when `new` contains spaces. Text inside `inline code` is left
+/// alone.
+pub fn rename_page_refs(text: &str, old: &str, new: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut replace = |span: &Range<usize>, with: String| {
+        out.push_str(&text[copied..span.start]);
+        out.push_str(&with);
+        copied = span.end;
+    };
+    rewrite_refs(&markup::parse(text), &mut |node| match &node.kind {
+        NodeKind::PageRef { title, .. } if title == old => {
+            replace(&node.span, format!("[[{}]]", new));
+            true
+        }
+        NodeKind::Tag {
+            title,
+            bracketed: true,
+        } if title == old => {
+            replace(&node.span, format!("#[[{}]]", new));
+            true
+        }
+        NodeKind::Tag {
+            title,
+            bracketed: false,
+        } if title == old
+            && text[..node.span.start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !c.is_alphanumeric()) =>
+        {
+            replace(&node.span, tag_markup(new));
+            true
+        }
+        _ => false,
+    });
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Visit nodes in source order; children are skipped when `f` returns true.
+fn rewrite_refs(nodes: &[Node], f: &mut impl FnMut(&Node) -> bool) {
+    for node in nodes {
+        if !f(node) {
+            if let Some(children) = node.children() {
+                rewrite_refs(children, f);
             }
-            uid.clone()
         }
-        RefText::PageName(name) => name.clone(),
     }
 }
 
-/// Parse `[text](url)` starting at position `start` (which points to the `[`).
-/// Returns `(text, end_position)` where end_position is past the closing `)`.
-fn parse_markdown_link(chars: &[char], start: usize) -> Option<(String, usize)> {
-    // Find closing ]
-    let mut i = start + 1;
-    while i < chars.len() && chars[i] != ']' {
-        i += 1;
-    }
-    if i >= chars.len() {
-        return None;
+/// Build a uid → text lookup map from loaded daily notes.
+pub fn build_block_text_map(days: &[crate::api::types::DailyNote]) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for day in days {
+        collect_block_texts(&day.blocks, &mut map);
     }
-    let text: String = chars[start + 1..i].iter().collect();
-    i += 1; // skip ]
+    map
+}
 
-    // Expect (
-    if i >= chars.len() || chars[i] != '(' {
-        return None;
+fn collect_block_texts(blocks: &[crate::api::types::Block], map: &mut HashMap<String, String>) {
+    for block in blocks {
+        if !block.string.is_empty() {
+            map.insert(block.uid.clone(), block.string.clone());
+        }
+        collect_block_texts(&block.children, map);
     }
-    i += 1; // skip (
+}
 
-    // Find closing )
-    let url_start = i;
-    while i < chars.len() && chars[i] != ')' {
-        i += 1;
-    }
-    if i >= chars.len() {
-        return None;
-    }
-    let _url: String = chars[url_start..i].iter().collect();
-    i += 1; // skip )
+/// Render blocks and their children as an indented Markdown outline:
+/// `- text` per block, two spaces per level, continuation lines aligned
+/// under the text.
+pub fn blocks_to_markdown(blocks: &[crate::api::types::Block]) -> String {
+    let mut out = String::new();
+    write_outline(blocks, 0, &mut out, &|block| block.string.clone());
+    out
+}
 
-    if text.is_empty() {
-        return None;
-    }
+/// Like [`blocks_to_markdown`], with each block's uid in a trailing
+/// `<!-- uid:... -->` comment on its first line, so an outline edited outside
+/// the app can be matched back to the blocks it came from.
+pub fn outline_with_uids(blocks: &[crate::api::types::Block]) -> String {
+    let mut out = String::new();
+    write_outline(blocks, 0, &mut out, &|block| {
+        let marker = format!(" {}{}{}", UID_MARKER_START, block.uid, UID_MARKER_END);
+        match block.string.split_once('\n') {
+            Some((first, rest)) => format!("{}{}\n{}", first, marker, rest),
+            None => format!("{}{}", block.string, marker),
+        }
+    });
+    out
+}
+
+const UID_MARKER_START: &str = "<!-- uid:";
+const UID_MARKER_END: &str = " -->";
 
-    Some((text, i))
+/// A block parsed back from an outline written by [`outline_with_uids`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineNode {
+    /// Uid from the block's marker; `None` for blocks typed in the editor.
+    pub uid: Option<String>,
+    pub text: String,
+    pub children: Vec<OutlineNode>,
 }
 
-fn flush_plain(plain: &mut String, style: Style, spans: &mut Vec<Span<'static>>) {
-    if !plain.is_empty() {
-        spans.push(Span::styled(plain.clone(), style));
-        plain.clear();
+/// Parse an indented `- ` outline. Lines that aren't bullets continue the
+/// block above; a bullet indented more than one level deeper than the one
+/// before it is treated as its child.
+pub fn parse_outline(text: &str) -> Vec<OutlineNode> {
+    let mut flat: Vec<(usize, Option<String>, Vec<String>)> = Vec::new();
+    for line in text.lines() {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let rest = &line[indent..];
+        let bullet = rest.strip_prefix("- ").or((rest == "-").then_some(""));
+        match (bullet, flat.last_mut()) {
+            (Some(body), last) => {
+                let max_depth = last.map_or(0, |(depth, _, _)| *depth + 1);
+                let (text, uid) = split_uid_marker(body);
+                flat.push(((indent / 2).min(max_depth), uid, vec![text.to_string()]));
+            }
+            (None, Some((depth, _, lines))) => {
+                let strip = (*depth * 2 + 2).min(indent);
+                lines.push(line[strip..].to_string());
+            }
+            (None, None) if rest.is_empty() => {}
+            (None, None) => flat.push((0, None, vec![rest.to_string()])),
+        }
     }
+    let mut items = flat.into_iter().peekable();
+    build_outline(&mut items, 0)
 }
 
-/// Find position of closing `}}` starting from `start`.
-fn find_closing_double_brace(chars: &[char], start: usize) -> Option<usize> {
-    let mut i = start;
-    while i + 1 < chars.len() {
-        if chars[i] == '}' && chars[i + 1] == '}' {
-            return Some(i);
+fn build_outline(
+    items: &mut std::iter::Peekable<impl Iterator<Item = (usize, Option<String>, Vec<String>)>>,
+    depth: usize,
+) -> Vec<OutlineNode> {
+    let mut nodes = Vec::new();
+    while let Some((item_depth, _, _)) = items.peek() {
+        if *item_depth < depth {
+            break;
+        }
+        let (item_depth, uid, mut lines) = items.next().unwrap();
+        while lines.len() > 1 && lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
         }
-        i += 1;
+        nodes.push(OutlineNode {
+            uid,
+            text: lines.join("\n"),
+            children: build_outline(items, item_depth + 1),
+        });
     }
-    None
+    nodes
 }
 
-/// Find position of single delimiter char starting from `start`.
-fn find_single_delimiter(chars: &[char], start: usize, delim: char) -> Option<usize> {
-    (start..chars.len()).find(|&i| chars[i] == delim)
+/// Split a trailing uid marker off the first line of a block.
+fn split_uid_marker(line: &str) -> (&str, Option<String>) {
+    let Some(body) = line.strip_suffix(UID_MARKER_END) else {
+        return (line, None);
+    };
+    let Some(start) = body.rfind(UID_MARKER_START) else {
+        return (line, None);
+    };
+    let uid = &body[start + UID_MARKER_START.len()..];
+    if uid.is_empty() || uid.contains(char::is_whitespace) {
+        return (line, None);
+    }
+    let text = &body[..start];
+    (
+        text.strip_suffix(' ').unwrap_or(text),
+        Some(uid.to_string()),
+    )
 }
 
-/// Find position of double delimiter (e.g., `**`) starting from `start`.
-fn find_double_delimiter(chars: &[char], start: usize, delim: char) -> Option<usize> {
-    let mut i = start;
-    while i + 1 < chars.len() {
-        if chars[i] == delim && chars[i + 1] == delim {
-            return Some(i);
+fn write_outline(
+    blocks: &[crate::api::types::Block],
+    depth: usize,
+    out: &mut String,
+    convert: &dyn Fn(&crate::api::types::Block) -> String,
+) {
+    let indent = "  ".repeat(depth);
+    for block in blocks {
+        for (i, line) in convert(block).split('\n').enumerate() {
+            let marker = if i == 0 { "- " } else { "  " };
+            out.push_str(&format!("{}{}{}\n", indent, marker, line));
         }
-        i += 1;
+        write_outline(&block.children, depth + 1, out, convert);
     }
-    None
 }
 
-/// Extract page names from `[[...]]` and `#[[...]]` links in block text.
-///
-/// Returns a deduplicated list of page names in order of first appearance.
-pub fn extract_page_links(text: &str) -> Vec<String> {
-    let chars: Vec<char> = text.chars().collect();
-    let len = chars.len();
-    let mut links = Vec::new();
-    let mut seen = HashSet::new();
-    let mut i = 0;
+/// Convert Roam block text to standard Markdown: TODO/DONE markers become
+/// task checkboxes, `__italic__` and `^^highlight^^` use Markdown delimiters,
+/// and `((uid))` refs found in `block_map` are replaced by the block's text.
+/// Everything else, inline code included, is copied unchanged.
+pub fn to_standard_markdown(text: &str, block_map: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    write_standard(&markup::parse(text), text, block_map, &mut out);
+    out
+}
 
-    while i < len {
-        // Skip `inline code` — links inside code are not navigable
-        if chars[i] == '`' {
-            if let Some(end) = find_single_delimiter(&chars, i + 1, '`') {
-                i = end + 1;
+fn write_standard(
+    nodes: &[Node],
+    src: &str,
+    block_map: &HashMap<String, String>,
+    out: &mut String,
+) {
+    for node in nodes {
+        let (delim, children) = match &node.kind {
+            NodeKind::Text(text) => {
+                out.push_str(text);
                 continue;
             }
-        }
-
-        // #[[page tag]] — extract page name without the #
-        if chars[i] == '#' && i + 2 < len && chars[i + 1] == '[' && chars[i + 2] == '[' {
-            if let Some(end) = find_double_delimiter(&chars, i + 3, ']') {
-                let name: String = chars[i + 3..end].iter().collect();
-                if !name.is_empty() && seen.insert(name.clone()) {
-                    links.push(name);
-                }
-                i = end + 2;
+            NodeKind::Todo { done } => {
+                out.push_str(if *done { "[x]" } else { "[ ]" });
                 continue;
             }
-        }
-
-        // [[page link]]
-        if chars[i] == '[' && i + 1 < len && chars[i + 1] == '[' {
-            if let Some(end) = find_double_delimiter(&chars, i + 2, ']') {
-                let name: String = chars[i + 2..end].iter().collect();
-                if !name.is_empty() && seen.insert(name.clone()) {
-                    links.push(name);
-                }
-                i = end + 2;
+            NodeKind::BlockRef(uid) if block_map.contains_key(uid) => {
+                out.push_str(&block_map[uid]);
                 continue;
             }
-        }
-
-        i += 1;
+            NodeKind::Bold(children) => ("**", children),
+            NodeKind::Italic(children) => ("*", children),
+            NodeKind::Highlight(children) => ("==", children),
+            NodeKind::Strike(children) => ("~~", children),
+            _ => {
+                out.push_str(&src[node.span.clone()]);
+                continue;
+            }
+        };
+        out.push_str(delim);
+        write_standard(children, src, block_map, out);
+        out.push_str(delim);
     }
-
-    links
 }
 
-/// Build a uid → text lookup map from loaded daily notes.
-pub fn build_block_text_map(days: &[crate::api::types::DailyNote]) -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    for day in days {
-        collect_block_texts(&day.blocks, &mut map);
-    }
-    map
-}
-
-fn collect_block_texts(blocks: &[crate::api::types::Block], map: &mut HashMap<String, String>) {
-    for block in blocks {
-        if !block.string.is_empty() {
-            map.insert(block.uid.clone(), block.string.clone());
-        }
-        collect_block_texts(&block.children, map);
-    }
+/// Like [`blocks_to_markdown`], with each block converted by [`to_standard_markdown`].
+pub fn export_markdown(
+    blocks: &[crate::api::types::Block],
+    block_map: &HashMap<String, String>,
+) -> String {
+    let mut out = String::new();
+    write_outline(blocks, 0, &mut out, &|block| {
+        to_standard_markdown(&block.string, block_map)
+    });
+    out
 }
 
 #[cfg(test)]
@@ -429,6 +527,94 @@ mod tests {
         assert!(spans[0].style.add_modifier.contains(Modifier::ITALIC));
     }
 
+    #[test]
+    fn targets_cover_rendered_links() {
+        let (spans, targets) = render_spans_with_targets(
+            "{{[[TODO]]}} [[Page]] #tag #[[Long tag]] ((abc))",
+            Style::default(),
+            None,
+        );
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "☐  Page #tag #[[Long tag]] abc");
+        assert_eq!(
+            targets,
+            vec![
+                (0..2, LinkTarget::Checkbox(0..12)),
+                (3..7, LinkTarget::Page("Page".into())),
+                (8..12, LinkTarget::Page("tag".into())),
+                (13..26, LinkTarget::Page("Long tag".into())),
+                (27..30, LinkTarget::Block("abc".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn attribute_name_is_styled_and_links_to_its_page() {
+        let (spans, targets) =
+            render_spans_with_targets("Status:: [[In Progress]]", Style::default(), None);
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "Status: In Progress");
+        assert_eq!(spans[0].content, "Status:");
+        assert_eq!(spans[0].style.fg, Some(Color::Yellow));
+        assert!(spans[0].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(
+            targets,
+            vec![
+                (0..6, LinkTarget::Page("Status".into())),
+                (8..19, LinkTarget::Page("In Progress".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn query_macro_shows_its_name_or_expression() {
+        let text: String = render_spans(
+            r#"{{query: "Open tasks" {and: [[TODO]]}}}"#,
+            Style::default(),
+        )
+        .iter()
+        .map(|s| s.content.as_ref())
+        .collect();
+        assert_eq!(text, "⌕ Open tasks");
+        let spans = render_spans("{{[[query]]: {and: [[TODO]] [[X]]}}}", Style::default());
+        assert_eq!(spans[0].content, "⌕ {and: [[TODO]] [[X]]}");
+        assert_eq!(spans[0].style.fg, Some(Color::Cyan));
+    }
+
+    #[test]
+    fn latex_renders_as_unicode_or_falls_back_to_source() {
+        let spans = render_spans(r"area $$\pi r^2$$", default_style());
+        assert_eq!(spans[1].content, "π r²");
+        assert_eq!(spans[1].style.fg, Some(Color::Yellow));
+        let spans = render_spans(r"$$\begin{matrix} a \end{matrix}$$", default_style());
+        assert_eq!(spans[0].content, r"$$\begin{matrix} a \end{matrix}$$");
+        assert_eq!(spans[0].style.fg, Some(Color::DarkGray));
+    }
+
+    #[test]
+    fn nested_markup_combines_styles_and_targets() {
+        let (spans, targets) = render_spans_with_targets(
+            "**bold [[link]]** [[page [[nested]]]]",
+            Style::default(),
+            None,
+        );
+        assert_eq!(spans[0].content, "bold ");
+        assert_eq!(spans[1].content, "link");
+        assert_eq!(spans[1].style.fg, Some(Color::Cyan));
+        assert!(spans[1].style.add_modifier.contains(Modifier::BOLD));
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "bold link page nested");
+        // The inner link comes first so a click on it opens the inner page
+        assert_eq!(
+            targets,
+            vec![
+                (5..9, LinkTarget::Page("link".into())),
+                (15..21, LinkTarget::Page("nested".into())),
+                (10..21, LinkTarget::Page("page [[nested]]".into())),
+            ]
+        );
+    }
+
     #[test]
     fn page_link_cyan_no_delimiters() {
         let spans = render_spans("[[page]]", default_style());
@@ -851,11 +1037,35 @@ mod tests {
         assert!(extract_page_links("[[unclosed").is_empty());
     }
 
+    #[test]
+    fn extract_attribute_name_before_value_links() {
+        assert_eq!(
+            extract_page_links("Status:: [[In Progress]]"),
+            vec!["Status", "In Progress"]
+        );
+    }
+
     #[test]
     fn extract_link_inside_bold() {
         assert_eq!(extract_page_links("**[[Bold Page]]**"), vec!["Bold Page"]);
     }
 
+    #[test]
+    fn extract_nested_links_and_bare_tags() {
+        assert_eq!(
+            extract_page_links("[[page [[nested]]]] #tag **#bold**"),
+            vec!["page [[nested]]", "nested", "tag", "bold"]
+        );
+    }
+
+    #[test]
+    fn extract_bare_tags_followed_by_punctuation() {
+        assert_eq!(
+            extract_page_links("#todo, #done. #x! (#idea) #why?"),
+            vec!["todo", "done", "x", "idea", "why"]
+        );
+    }
+
     #[test]
     fn extract_ignores_links_in_inline_code() {
         assert!(extract_page_links("`[[Code]]`").is_empty());
@@ -865,4 +1075,164 @@ mod tests {
     fn extract_empty_brackets_skipped() {
         assert!(extract_page_links("[[]]").is_empty());
     }
+
+    #[test]
+    fn tag_markup_brackets_titles_with_spaces() {
+        assert_eq!(tag_markup("rust"), "#rust");
+        assert_eq!(tag_markup("Daily Notes"), "#[[Daily Notes]]");
+    }
+
+    #[test]
+    fn rename_page_refs_rewrites_links_and_tags() {
+        let text = "See [[Old]], #[[Old]] and #Old today";
+        assert_eq!(
+            rename_page_refs(text, "Old", "New"),
+            "See [[New]], #[[New]] and #New today"
+        );
+    }
+
+    #[test]
+    fn rename_page_refs_brackets_bare_tag_for_multiword_title() {
+        assert_eq!(rename_page_refs("#Old", "Old", "New Name"), "#[[New Name]]");
+    }
+
+    #[test]
+    fn rename_page_refs_rewrites_tags_followed_by_punctuation() {
+        assert_eq!(
+            rename_page_refs("#Old, #Old. (#Old) #Old!", "Old", "New"),
+            "#New, #New. (#New) #New!"
+        );
+        assert_eq!(
+            rename_page_refs("#Old, done", "Old", "New Name"),
+            "#[[New Name]], done"
+        );
+    }
+
+    #[test]
+    fn rename_page_refs_leaves_other_pages_alone() {
+        let text = "[[Older]] #Oldest [[Old stuff]] word#Old";
+        assert_eq!(rename_page_refs(text, "Old", "New"), text);
+    }
+
+    #[test]
+    fn rename_page_refs_handles_nested_links() {
+        assert_eq!(
+            rename_page_refs("[[[[Old]] notes]]", "Old", "New"),
+            "[[[[New]] notes]]"
+        );
+    }
+
+    #[test]
+    fn rename_page_refs_skips_inline_code() {
+        assert_eq!(
+            rename_page_refs("`[[Old]]` [[Old]]", "Old", "New"),
+            "`[[Old]]` [[New]]"
+        );
+    }
+
+    #[test]
+    fn blocks_to_markdown_indents_children() {
+        use crate::api::types::Block;
+        let block = |uid: &str, text: &str, children: Vec<Block>| Block {
+            uid: uid.into(),
+            string: text.into(),
+            order: 0,
+            children,
+            open: false,
+            refs: vec![],
+        };
+        let blocks = vec![
+            block(
+                "a",
+                "Parent",
+                vec![block("b", "Child\nsecond line", vec![])],
+            ),
+            block("c", "Sibling", vec![]),
+        ];
+        assert_eq!(
+            blocks_to_markdown(&blocks),
+            "- Parent\n  - Child\n    second line\n- Sibling\n"
+        );
+    }
+
+    #[test]
+    fn outline_with_uids_parses_back() {
+        use crate::api::types::Block;
+        let block = |uid: &str, text: &str, children: Vec<Block>| Block {
+            uid: uid.into(),
+            string: text.into(),
+            order: 0,
+            children,
+            open: true,
+            refs: vec![],
+        };
+        let blocks = vec![
+            block(
+                "a",
+                "Parent",
+                vec![block("b", "Child\nsecond line", vec![])],
+            ),
+            block("c", "Sibling", vec![]),
+        ];
+        let text = outline_with_uids(&blocks);
+        assert_eq!(
+            text,
+            "- Parent <!-- uid:a -->\n  - Child <!-- uid:b -->\n    second line\n- Sibling <!-- uid:c -->\n"
+        );
+        let node = |uid: &str, text: &str, children: Vec<OutlineNode>| OutlineNode {
+            uid: Some(uid.into()),
+            text: text.into(),
+            children,
+        };
+        assert_eq!(
+            parse_outline(&text),
+            vec![
+                node("a", "Parent", vec![node("b", "Child\nsecond line", vec![])]),
+                node("c", "Sibling", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_outline_handles_new_and_over_indented_bullets() {
+        let nodes = parse_outline("- Top\n      - Too deep\n- New <!-- not a uid -->\n\n");
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].uid, None);
+        assert_eq!(nodes[0].children[0].text, "Too deep");
+        assert_eq!(nodes[1].text, "New <!-- not a uid -->");
+        assert_eq!(nodes[1].uid, None);
+    }
+
+    #[test]
+    fn standard_markdown_converts_roam_syntax() {
+        let mut map = HashMap::new();
+        map.insert("abc123".to_string(), "referenced text".to_string());
+        assert_eq!(
+            to_standard_markdown("{{[[TODO]]}} buy ((abc123)) __soon__", &map),
+            "[ ] buy referenced text *soon*"
+        );
+        assert_eq!(
+            to_standard_markdown("{{DONE}} ^^important^^", &map),
+            "[x] ==important=="
+        );
+    }
+
+    #[test]
+    fn standard_markdown_converts_nested_formatting() {
+        let mut map = HashMap::new();
+        map.insert("abc".to_string(), "text".to_string());
+        assert_eq!(
+            to_standard_markdown("**__both__ ((abc))** `^^raw^^`", &map),
+            "***both* text** `^^raw^^`"
+        );
+    }
+
+    #[test]
+    fn standard_markdown_keeps_unknown_refs_and_code() {
+        let map = HashMap::new();
+        assert_eq!(
+            to_standard_markdown("((missing)) `__code__` [[Page]]", &map),
+            "((missing)) `__code__` [[Page]]"
+        );
+    }
 }
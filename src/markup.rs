@@ -0,0 +1,699 @@
+//! Parser for the markup of Roam block strings.
+//!
+//! [`parse`] turns a block string into a tree of [`Node`]s. Every node keeps
+//! the byte range of the source it was parsed from, so a renderer, a link
+//! extractor and an exporter all agree on where each piece of syntax starts
+//! and ends, however deeply it is nested.
+//!
+//! ```
+//! use roam_sdk::markup::{parse, NodeKind};
+//!
+//! let nodes = parse("**bold [[Page]]**");
+//! let NodeKind::Bold(children) = &nodes[0].kind else { panic!() };
+//! assert!(matches!(&children[1].kind, NodeKind::PageRef { title, .. } if title == "Page"));
+//! ```
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// A piece of parsed markup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    /// Byte range in the parsed string, delimiters included.
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    Text(String),
+    /// `**bold**`
+    Bold(Vec<Node>),
+    /// `__italic__`
+    Italic(Vec<Node>),
+    /// `^^highlight^^`
+    Highlight(Vec<Node>),
+    /// `~~strikethrough~~`
+    Strike(Vec<Node>),
+    /// `` `code` `` or ```` ```code``` ````
+    Code(String),
+    /// `[[title]]`. `children` is the parsed title, where nested refs such as
+    /// `[[page [[nested]]]]` live.
+    PageRef {
+        title: String,
+        children: Vec<Node>,
+    },
+    /// `#tag` or `#[[tag]]`.
+    Tag {
+        title: String,
+        bracketed: bool,
+    },
+    /// `((uid))`
+    BlockRef(String),
+    /// `{{embed: ((uid))}}` or `{{[[embed]]: [[page]]}}`.
+    Embed(EmbedTarget),
+    /// `{{TODO}}` / `{{[[DONE]]}}`.
+    Todo {
+        done: bool,
+    },
+    /// `Name::` at the start of a block; the value follows as sibling nodes.
+    Attribute(String),
+    /// A bare `http://` or `https://` URL.
+    Url(String),
+    /// `[text](url)`
+    Link {
+        text: Vec<Node>,
+        url: String,
+    },
+    /// `![alt](url)`
+    Image {
+        alt: String,
+        url: String,
+    },
+    /// `$$...$$`, without the dollars.
+    Latex(String),
+    /// Any other `{{name: args}}` or `{{name}}`, e.g. `{{table}}` or
+    /// `{{query: {and: [[A]] [[B]]}}}`. Brackets around the name are removed.
+    Macro {
+        name: String,
+        args: String,
+    },
+}
+
+/// What an embed points at.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EmbedTarget {
+    Block(String),
+    Page(String),
+}
+
+/// Parse a block string. Unclosed or empty delimiters stay text.
+pub fn parse(text: &str) -> Vec<Node> {
+    let mut parser = Parser::new(text, 0..text.len());
+    let mut nodes = Vec::new();
+    if let Some(node) = parser.attribute() {
+        nodes.push(node);
+    }
+    nodes.extend(parser.inlines(None).0);
+    nodes
+}
+
+/// The name and trimmed value of a block that starts with `Name::`.
+pub fn attribute(text: &str) -> Option<(String, String)> {
+    let mut parser = Parser::new(text, 0..text.len());
+    let Some(Node {
+        kind: NodeKind::Attribute(name),
+        span,
+    }) = parser.attribute()
+    else {
+        return None;
+    };
+    Some((name, text[span.end..].trim().to_string()))
+}
+
+/// Name of the macro a block consists of, e.g. `"table"` for `{{[[table]]}}`.
+/// `None` when the block has anything besides a single macro.
+pub fn block_macro(text: &str) -> Option<String> {
+    match parse(text.trim()).as_slice() {
+        [Node {
+            kind: NodeKind::Macro { name, .. },
+            ..
+        }] => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// Target of the first embed in a block string.
+pub fn find_embed(text: &str) -> Option<EmbedTarget> {
+    if !text.contains("embed") {
+        return None;
+    }
+    let nodes = parse(text);
+    let mut found = None;
+    walk(&nodes, &mut |node| {
+        if let (None, NodeKind::Embed(target)) = (&found, &node.kind) {
+            found = Some(target.clone());
+        }
+    });
+    found
+}
+
+/// Call `f` on every node of the tree, parents before their children.
+pub fn walk<'a>(nodes: &'a [Node], f: &mut impl FnMut(&'a Node)) {
+    for node in nodes {
+        f(node);
+        if let Some(children) = node.children() {
+            walk(children, f);
+        }
+    }
+}
+
+impl Node {
+    /// Nested nodes of formatting, page refs and link text.
+    pub fn children(&self) -> Option<&[Node]> {
+        match &self.kind {
+            NodeKind::Bold(children)
+            | NodeKind::Italic(children)
+            | NodeKind::Highlight(children)
+            | NodeKind::Strike(children)
+            | NodeKind::PageRef { children, .. }
+            | NodeKind::Link { text: children, .. } => Some(children),
+            _ => None,
+        }
+    }
+}
+
+/// Punctuation that ends a bare `#tag`, as in Roam: `#todo, #done.` tags
+/// `todo` and `done`.
+const TAG_TERMINATORS: [char; 13] = [
+    ',', '.', ';', ':', '!', '?', '"', '(', ')', '[', ']', '{', '}',
+];
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// Parsing stops here; sub-parsers of a page title or link text end early.
+    end: usize,
+    /// Delimiters known not to close from a position. Without this, runs of
+    /// unclosed `**`, `__`, `^^` and `~~` are rescanned exponentially often.
+    unclosed: HashSet<(&'static str, usize)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str, range: Range<usize>) -> Self {
+        Parser {
+            src,
+            pos: range.start,
+            end: range.end,
+            unclosed: HashSet::new(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..self.end]
+    }
+
+    fn sub(&self, range: Range<usize>) -> Vec<Node> {
+        Parser::new(self.src, range).inlines(None).0
+    }
+
+    /// Position of the next `needle` at or after `from`, before `end`.
+    fn find(&self, from: usize, needle: &str) -> Option<usize> {
+        self.src[from..self.end].find(needle).map(|i| from + i)
+    }
+
+    /// `Name::` at the very start of the block.
+    fn attribute(&mut self) -> Option<Node> {
+        let first_line = self.rest().split('\n').next()?;
+        let idx = first_line.find("::")?;
+        let name = &first_line[..idx];
+        if name.trim().is_empty()
+            || name != name.trim()
+            || name.contains(['`', '[', ']', '(', ')', '{', '}', '#'])
+        {
+            return None;
+        }
+        self.pos = idx + 2;
+        Some(Node {
+            kind: NodeKind::Attribute(name.to_string()),
+            span: 0..self.pos,
+        })
+    }
+
+    /// Inline nodes up to `close` or the end. Returns whether `close` was
+    /// found; the parser is left on it.
+    fn inlines(&mut self, close: Option<&str>) -> (Vec<Node>, bool) {
+        let mut nodes = Vec::new();
+        let mut text_start = self.pos;
+        let mut closed = false;
+        while self.pos < self.end {
+            if close.is_some_and(|c| self.rest().starts_with(c)) {
+                closed = true;
+                break;
+            }
+            let start = self.pos;
+            match self.inline(close) {
+                Some(kind) => {
+                    push_text(self.src, text_start..start, &mut nodes);
+                    nodes.push(Node {
+                        kind,
+                        span: start..self.pos,
+                    });
+                    text_start = self.pos;
+                }
+                None => {
+                    self.pos = start;
+                    self.pos += self.rest().chars().next().map_or(1, char::len_utf8);
+                }
+            }
+        }
+        push_text(self.src, text_start..self.pos, &mut nodes);
+        (nodes, closed)
+    }
+
+    /// The node starting at the current position, leaving the parser after it.
+    fn inline(&mut self, close: Option<&str>) -> Option<NodeKind> {
+        let rest = self.rest();
+        if rest.starts_with("{{") {
+            self.double_brace()
+        } else if rest.starts_with('`') {
+            self.code()
+        } else if rest.starts_with("**") {
+            self.delimited("**").map(NodeKind::Bold)
+        } else if rest.starts_with("__") {
+            self.delimited("__").map(NodeKind::Italic)
+        } else if rest.starts_with("^^") {
+            self.delimited("^^").map(NodeKind::Highlight)
+        } else if rest.starts_with("~~") {
+            self.delimited("~~").map(NodeKind::Strike)
+        } else if rest.starts_with("$$") {
+            let end = self.find(self.pos + 2, "$$")?;
+            let tex = &self.src[self.pos + 2..end];
+            self.pos = end + 2;
+            (!tex.is_empty()).then(|| NodeKind::Latex(tex.to_string()))
+        } else if rest.starts_with("![") {
+            self.pos += 1;
+            let (text, url) = self.markdown_link()?;
+            Some(NodeKind::Image {
+                alt: self.src[text].to_string(),
+                url,
+            })
+        } else if rest.starts_with("[[") {
+            let (title, inner) = self.page_ref()?;
+            Some(NodeKind::PageRef {
+                title,
+                children: self.sub(inner),
+            })
+        } else if rest.starts_with('[') {
+            let (text, url) = self.markdown_link()?;
+            Some(NodeKind::Link {
+                text: self.sub(text),
+                url,
+            })
+        } else if rest.starts_with("((") {
+            let end = self.find(self.pos + 2, "))")?;
+            let uid = &self.src[self.pos + 2..end];
+            self.pos = end + 2;
+            (!uid.is_empty()).then(|| NodeKind::BlockRef(uid.to_string()))
+        } else if rest.starts_with('#') {
+            self.tag(close)
+        } else if rest.starts_with("http://") || rest.starts_with("https://") {
+            self.url(close)
+        } else {
+            None
+        }
+    }
+
+    fn delimited(&mut self, delim: &'static str) -> Option<Vec<Node>> {
+        let start = self.pos;
+        if self.unclosed.contains(&(delim, start)) {
+            return None;
+        }
+        self.pos += delim.len();
+        let (children, closed) = self.inlines(Some(delim));
+        if !closed || children.is_empty() {
+            self.unclosed.insert((delim, start));
+            return None;
+        }
+        self.pos += delim.len();
+        Some(children)
+    }
+
+    fn code(&mut self) -> Option<NodeKind> {
+        let fence = if self.rest().starts_with("```") {
+            "```"
+        } else {
+            "`"
+        };
+        let start = self.pos + fence.len();
+        let end = self.find(start, fence)?;
+        self.pos = end + fence.len();
+        Some(NodeKind::Code(self.src[start..end].to_string()))
+    }
+
+    /// `[[title]]` with balanced nested brackets: the title and its range.
+    fn page_ref(&mut self) -> Option<(String, Range<usize>)> {
+        let start = self.pos + 2;
+        let mut depth = 1;
+        let mut i = start;
+        while i < self.end {
+            let rest = &self.src[i..self.end];
+            if rest.starts_with("[[") {
+                depth += 1;
+                i += 2;
+            } else if rest.starts_with("]]") {
+                depth -= 1;
+                if depth == 0 {
+                    self.pos = i + 2;
+                    let title = &self.src[start..i];
+                    return (!title.is_empty()).then(|| (title.to_string(), start..i));
+                }
+                i += 2;
+            } else {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        None
+    }
+
+    /// `[text](url)` at the current `[`: the text range and the url.
+    fn markdown_link(&mut self) -> Option<(Range<usize>, String)> {
+        let text_start = self.pos + 1;
+        let text_end = self.find(text_start, "]")?;
+        if text_end == text_start || !self.src[text_end + 1..self.end].starts_with('(') {
+            return None;
+        }
+        let url_end = self.find(text_end + 2, ")")?;
+        self.pos = url_end + 1;
+        Some((
+            text_start..text_end,
+            self.src[text_end + 2..url_end].to_string(),
+        ))
+    }
+
+    fn tag(&mut self, close: Option<&str>) -> Option<NodeKind> {
+        let after = &self.src[self.pos + 1..self.end];
+        if after.starts_with("[[") {
+            self.pos += 1;
+            let (title, _) = self.page_ref()?;
+            return Some(NodeKind::Tag {
+                title,
+                bracketed: true,
+            });
+        }
+        let len = after
+            .char_indices()
+            .find(|(i, c)| {
+                c.is_whitespace()
+                    || TAG_TERMINATORS.contains(c)
+                    || close.is_some_and(|c| after[*i..].starts_with(c))
+            })
+            .map_or(after.len(), |(i, _)| i);
+        if len == 0 {
+            return None;
+        }
+        self.pos += 1 + len;
+        Some(NodeKind::Tag {
+            title: after[..len].to_string(),
+            bracketed: false,
+        })
+    }
+
+    /// A bare URL, up to whitespace and without trailing punctuation.
+    fn url(&mut self, close: Option<&str>) -> Option<NodeKind> {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|(i, c)| c.is_whitespace() || close.is_some_and(|c| rest[*i..].starts_with(c)))
+            .map_or(rest.len(), |(i, _)| i);
+        let url = rest[..len].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}']);
+        if url.ends_with("//") {
+            return None;
+        }
+        self.pos += url.len();
+        Some(NodeKind::Url(url.to_string()))
+    }
+
+    /// `{{...}}` with balanced braces: checkbox, embed or macro.
+    fn double_brace(&mut self) -> Option<NodeKind> {
+        let start = self.pos + 2;
+        let mut depth = 2;
+        let mut end = None;
+        for (i, c) in self.src[start..self.end].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                // The braces must close as `}}`
+                end = self.src[..start + i].ends_with('}').then(|| start + i - 1);
+                break;
+            }
+        }
+        let end = end?;
+        let inner = &self.src[start..end];
+        self.pos = end + 2;
+
+        let (name, args) = match inner.split_once(':') {
+            Some((name, args)) => (name.trim(), args.trim()),
+            None => (inner.trim(), ""),
+        };
+        let name = name
+            .strip_prefix("[[")
+            .and_then(|n| n.strip_suffix("]]"))
+            .unwrap_or(name);
+        Some(match (name, args) {
+            ("TODO", "") => NodeKind::Todo { done: false },
+            ("DONE", "") => NodeKind::Todo { done: true },
+            ("embed", target) => NodeKind::Embed(embed_target(target)),
+            _ => NodeKind::Macro {
+                name: name.to_string(),
+                args: args.to_string(),
+            },
+        })
+    }
+}
+
+fn embed_target(target: &str) -> EmbedTarget {
+    if let Some(uid) = target.strip_prefix("((").and_then(|s| s.strip_suffix("))")) {
+        EmbedTarget::Block(uid.to_string())
+    } else if let Some(page) = target.strip_prefix("[[").and_then(|s| s.strip_suffix("]]")) {
+        EmbedTarget::Page(page.to_string())
+    } else {
+        EmbedTarget::Page(target.to_string())
+    }
+}
+
+fn push_text(src: &str, range: Range<usize>, nodes: &mut Vec<Node>) {
+    if !range.is_empty() {
+        nodes.push(Node {
+            kind: NodeKind::Text(src[range.clone()].to_string()),
+            span: range,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<NodeKind> {
+        parse(text).into_iter().map(|n| n.kind).collect()
+    }
+
+    fn text(s: &str) -> NodeKind {
+        NodeKind::Text(s.to_string())
+    }
+
+    #[test]
+    fn plain_text_is_one_node() {
+        let nodes = parse("hello world");
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].kind, text("hello world"));
+        assert_eq!(nodes[0].span, 0..11);
+    }
+
+    #[test]
+    fn formatting_nests_page_refs() {
+        let nodes = parse("**bold [[link]]** after");
+        assert_eq!(nodes[0].span, 0..17);
+        let NodeKind::Bold(children) = &nodes[0].kind else {
+            panic!("expected bold, got {:?}", nodes[0].kind);
+        };
+        assert_eq!(children[0].kind, text("bold "));
+        assert_eq!(children[1].span, 7..15);
+        assert!(matches!(&children[1].kind, NodeKind::PageRef { title, .. } if title == "link"));
+        assert_eq!(nodes[1].kind, text(" after"));
+    }
+
+    #[test]
+    fn nested_page_refs_keep_the_outer_title() {
+        let nodes = parse("[[page [[nested]]]]");
+        assert_eq!(nodes.len(), 1);
+        let NodeKind::PageRef { title, children } = &nodes[0].kind else {
+            panic!("expected page ref");
+        };
+        assert_eq!(title, "page [[nested]]");
+        assert_eq!(children[0].kind, text("page "));
+        assert_eq!(children[1].span, 7..17);
+        assert!(matches!(&children[1].kind, NodeKind::PageRef { title, .. } if title == "nested"));
+    }
+
+    #[test]
+    fn tags_end_at_whitespace_punctuation_or_closing_delimiter() {
+        assert_eq!(
+            kinds("#tag, **#bold** #[[Long tag]]")[0],
+            NodeKind::Tag {
+                title: "tag".into(),
+                bracketed: false
+            }
+        );
+        assert_eq!(
+            kinds("(#idea.)"),
+            vec![
+                text("("),
+                NodeKind::Tag {
+                    title: "idea".into(),
+                    bracketed: false
+                },
+                text(".)"),
+            ]
+        );
+        let nodes = parse("**#bold**");
+        let NodeKind::Bold(children) = &nodes[0].kind else {
+            panic!("expected bold");
+        };
+        assert_eq!(
+            children[0].kind,
+            NodeKind::Tag {
+                title: "bold".into(),
+                bracketed: false
+            }
+        );
+        assert_eq!(
+            kinds("#[[Long tag]]"),
+            vec![NodeKind::Tag {
+                title: "Long tag".into(),
+                bracketed: true
+            }]
+        );
+    }
+
+    #[test]
+    fn double_braces_parse_checkboxes_embeds_and_macros() {
+        assert_eq!(kinds("{{[[TODO]]}}"), vec![NodeKind::Todo { done: false }]);
+        assert_eq!(kinds("{{DONE}}"), vec![NodeKind::Todo { done: true }]);
+        assert_eq!(
+            kinds("{{[[embed]]: ((abc))}}"),
+            vec![NodeKind::Embed(EmbedTarget::Block("abc".into()))]
+        );
+        assert_eq!(
+            kinds("{{embed: [[My Page]]}}"),
+            vec![NodeKind::Embed(EmbedTarget::Page("My Page".into()))]
+        );
+        assert_eq!(
+            kinds("{{table}}"),
+            vec![NodeKind::Macro {
+                name: "table".into(),
+                args: String::new()
+            }]
+        );
+        assert_eq!(
+            kinds("{{query: {and: [[TODO]] [[Project X]]}}} done"),
+            vec![
+                NodeKind::Macro {
+                    name: "query".into(),
+                    args: "{and: [[TODO]] [[Project X]]}".into()
+                },
+                text(" done"),
+            ]
+        );
+    }
+
+    #[test]
+    fn attribute_only_on_first_line() {
+        let nodes = parse("Status:: [[In Progress]]");
+        assert_eq!(nodes[0].kind, NodeKind::Attribute("Status".into()));
+        assert_eq!(nodes[0].span, 0..8);
+        assert_eq!(nodes[1].kind, text(" "));
+        assert!(
+            matches!(&nodes[2].kind, NodeKind::PageRef { title, .. } if title == "In Progress")
+        );
+        assert_eq!(kinds("first\nStatus:: x"), vec![text("first\nStatus:: x")]);
+        assert_eq!(kinds("`a`:: b")[0], NodeKind::Code("a".into()));
+    }
+
+    #[test]
+    fn attribute_name_and_value() {
+        assert_eq!(
+            attribute("Status:: [[In Progress]] "),
+            Some(("Status".into(), "[[In Progress]]".into()))
+        );
+        assert_eq!(attribute("Empty::"), Some(("Empty".into(), String::new())));
+        assert_eq!(attribute("plain text"), None);
+        assert_eq!(attribute("[[a]]:: b"), None);
+    }
+
+    #[test]
+    fn links_images_urls_code_and_latex() {
+        assert_eq!(
+            kinds("![cat](http://x/cat.png) see https://example.com/a_b. `[[no]]` $$x^2$$"),
+            vec![
+                NodeKind::Image {
+                    alt: "cat".into(),
+                    url: "http://x/cat.png".into()
+                },
+                text(" see "),
+                NodeKind::Url("https://example.com/a_b".into()),
+                text(". "),
+                NodeKind::Code("[[no]]".into()),
+                text(" "),
+                NodeKind::Latex("x^2".into()),
+            ]
+        );
+        let nodes = parse("[**docs**](https://docs.rs)");
+        let NodeKind::Link { text: label, url } = &nodes[0].kind else {
+            panic!("expected link");
+        };
+        assert_eq!(url, "https://docs.rs");
+        assert!(matches!(label[0].kind, NodeKind::Bold(_)));
+    }
+
+    #[test]
+    fn unclosed_and_empty_delimiters_stay_text() {
+        assert_eq!(kinds("**unclosed"), vec![text("**unclosed")]);
+        assert_eq!(kinds("[[]] (()) ****"), vec![text("[[]] (()) ****")]);
+        assert_eq!(kinds("[text] no url"), vec![text("[text] no url")]);
+        assert_eq!(
+            kinds("**a [[b**"),
+            vec![NodeKind::Bold(vec![Node {
+                kind: text("a [[b"),
+                span: 2..7,
+            }])]
+        );
+    }
+
+    #[test]
+    fn walk_visits_nested_nodes() {
+        let nodes = parse("__[[A [[B]]]]__ #C");
+        let mut titles = Vec::new();
+        walk(&nodes, &mut |node| match &node.kind {
+            NodeKind::PageRef { title, .. } | NodeKind::Tag { title, .. } => {
+                titles.push(title.clone())
+            }
+            _ => {}
+        });
+        assert_eq!(titles, vec!["A [[B]]", "B", "C"]);
+    }
+
+    #[test]
+    fn find_embed_returns_first_embed() {
+        assert_eq!(
+            find_embed("see **{{embed: ((abc))}}** and {{embed: [[P]]}}"),
+            Some(EmbedTarget::Block("abc".into()))
+        );
+        assert_eq!(
+            find_embed("{{[[embed]]: [[My Page]]}}"),
+            Some(EmbedTarget::Page("My Page".into()))
+        );
+        assert_eq!(find_embed("((abc)) {{query: [[A]]}}"), None);
+    }
+
+    #[test]
+    fn block_macro_names_a_lone_macro() {
+        assert_eq!(block_macro(" {{[[table]]}} "), Some("table".into()));
+        assert_eq!(block_macro("{{kanban}}"), Some("kanban".into()));
+        assert_eq!(block_macro("Tasks {{table}}"), None);
+        assert_eq!(block_macro("{{TODO}}"), None);
+    }
+
+    #[test]
+    fn unclosed_delimiters_parse_in_bounded_time() {
+        let src = "**a__b^^c~~d".repeat(40);
+        let started = std::time::Instant::now();
+        let nodes = parse(&src);
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert_eq!(nodes.last().map(|n| n.span.end), Some(src.len()));
+    }
+}
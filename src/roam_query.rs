@@ -0,0 +1,431 @@
+//! Roam's query blocks: `{{query: {and: [[TODO]] [[Project X]]}}}`.
+//!
+//! [`parse`] reads the arguments of a query macro into a [`QueryExpr`], and
+//! [`QueryExpr::to_datalog`] turns it into a Datalog query for
+//! `RoamClient::query`. Rows come back as `[uid, block_string, page_title]`,
+//! the shape `types::parse_block_search_results` reads.
+//!
+//! ```
+//! use roam_sdk::roam_query::{parse, QueryExpr};
+//!
+//! let query = parse("{and: [[TODO]] {or: [[A]] [[B]]}}").unwrap();
+//! assert_eq!(
+//!     query.expr,
+//!     QueryExpr::And(vec![
+//!         QueryExpr::Ref("TODO".into()),
+//!         QueryExpr::Or(vec![QueryExpr::Ref("A".into()), QueryExpr::Ref("B".into())]),
+//!     ])
+//! );
+//! ```
+
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::api::queries::parse_daily_note_title;
+use crate::markup::{self, NodeKind};
+
+/// A parsed query block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Name given to the query in Roam (`{{query: "Open tasks" {...}}}`).
+    pub title: Option<String>,
+    pub expr: QueryExpr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryExpr {
+    /// Blocks referencing the page, directly or through a parent.
+    Ref(String),
+    And(Vec<QueryExpr>),
+    Or(Vec<QueryExpr>),
+    /// Blocks matching none of the clauses.
+    Not(Vec<QueryExpr>),
+    /// Blocks on, or referencing, a daily note between the two dates (inclusive).
+    Between(NaiveDate, NaiveDate),
+}
+
+/// Why a query couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError(pub String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The query of a block containing `{{query: ...}}` or `{{[[query]]: ...}}`,
+/// or `None` when the block has no query macro.
+pub fn find_query(text: &str) -> Option<Result<Query, ParseError>> {
+    if !text.contains("query") {
+        return None;
+    }
+    markup::parse(text)
+        .into_iter()
+        .find_map(|node| match node.kind {
+            NodeKind::Macro { name, args } if name == "query" => Some(parse(&args)),
+            _ => None,
+        })
+}
+
+/// Parse the arguments of a query macro.
+pub fn parse(args: &str) -> Result<Query, ParseError> {
+    let mut parser = Parser { src: args, pos: 0 };
+    parser.skip_space();
+    let title = parser.title()?;
+    parser.skip_space();
+    let expr = parser.expr()?;
+    parser.skip_space();
+    if parser.pos < args.len() {
+        return Err(ParseError(format!(
+            "unexpected \"{}\" after the query",
+            &args[parser.pos..]
+        )));
+    }
+    Ok(Query { title, expr })
+}
+
+impl QueryExpr {
+    /// Datalog for the matching blocks, as rows of `[uid, block_string, page_title]`.
+    pub fn to_datalog(&self) -> String {
+        let mut vars = 0;
+        let filter = self.clauses(&mut vars).join(" ");
+        let base = "[?b :block/string ?s] [?b :block/uid ?uid] [?b :block/page ?p] [?p :node/title ?page-title]";
+        // `not-join` needs `?b` bound, so a purely negative query scans blocks first
+        let where_clause = if self.binds_block() {
+            format!("{} {}", filter, base)
+        } else {
+            format!("{} {}", base, filter)
+        };
+        format!("[:find ?uid ?s ?page-title :where {}]", where_clause)
+    }
+
+    /// Whether the first clause emitted binds `?b` on its own.
+    fn binds_block(&self) -> bool {
+        match self {
+            Self::Ref(_) | Self::Or(_) | Self::Between(..) => true,
+            Self::And(exprs) => exprs.iter().any(|e| e.binds_block()),
+            Self::Not(_) => false,
+        }
+    }
+
+    fn clauses(&self, vars: &mut usize) -> Vec<String> {
+        match self {
+            Self::Ref(title) => {
+                let var = format!("?r{}", *vars);
+                *vars += 1;
+                vec![
+                    format!("[?b :block/path-refs {}]", var),
+                    format!("[{} :node/title \"{}\"]", var, escape(title)),
+                ]
+            }
+            Self::And(exprs) => {
+                // Clauses binding `?b` go first so the negations can use it
+                let (positive, negative): (Vec<&QueryExpr>, Vec<&QueryExpr>) =
+                    exprs.iter().partition(|e| e.binds_block());
+                positive
+                    .into_iter()
+                    .chain(negative)
+                    .flat_map(|e| e.clauses(vars))
+                    .collect()
+            }
+            Self::Or(exprs) => {
+                let branches: Vec<String> = exprs
+                    .iter()
+                    .map(|e| format!("(and {})", e.clauses(vars).join(" ")))
+                    .collect();
+                vec![format!("(or-join [?b] {})", branches.join(" "))]
+            }
+            Self::Not(exprs) => exprs
+                .iter()
+                .map(|e| format!("(not-join [?b] {})", e.clauses(vars).join(" ")))
+                .collect(),
+            Self::Between(start, end) => {
+                let (start, end) = (day_start_millis(*start), day_start_millis(*end));
+                let end = end + 24 * 60 * 60 * 1000 - 1;
+                let day = |link: &str| {
+                    format!(
+                        "(and [?b {} ?dp] [?dp :log/id ?d] [(<= {} ?d)] [(<= ?d {})])",
+                        link, start, end
+                    )
+                };
+                vec![format!(
+                    "(or-join [?b] {} {})",
+                    day(":block/page"),
+                    day(":block/refs")
+                )]
+            }
+        }
+    }
+}
+
+/// Daily note pages carry their date in `:log/id` as epoch milliseconds.
+fn day_start_millis(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc()
+        .timestamp_millis()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', r"\\").replace('"', r#"\""#)
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_space(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn title(&mut self) -> Result<Option<String>, ParseError> {
+        let Some(rest) = self.rest().strip_prefix('"') else {
+            return Ok(None);
+        };
+        let end = rest
+            .find('"')
+            .ok_or_else(|| ParseError("unclosed query title".into()))?;
+        let title = rest[..end].to_string();
+        self.pos += end + 2;
+        Ok(Some(title))
+    }
+
+    fn expr(&mut self) -> Result<QueryExpr, ParseError> {
+        let rest = self.rest();
+        if rest.starts_with("{{") {
+            return self.checkbox();
+        }
+        if rest.starts_with('{') {
+            return self.operator();
+        }
+        if let Some(title) = self.page_ref() {
+            return Ok(QueryExpr::Ref(title));
+        }
+        // Bare words: TODO, DONE or a tag without `#`
+        let word_len = rest
+            .find(|c: char| c.is_whitespace() || c == '{' || c == '}')
+            .unwrap_or(rest.len());
+        if word_len == 0 {
+            return Err(ParseError(if rest.is_empty() {
+                "empty query".into()
+            } else {
+                format!("unexpected \"{}\"", &rest[..1])
+            }));
+        }
+        let word = rest[..word_len].to_string();
+        self.pos += word_len;
+        Ok(QueryExpr::Ref(word))
+    }
+
+    /// `{{TODO}}`, `{{[[DONE]]}}`
+    fn checkbox(&mut self) -> Result<QueryExpr, ParseError> {
+        let rest = self.rest();
+        let end = rest
+            .find("}}")
+            .ok_or_else(|| ParseError("unclosed {{".into()))?;
+        let name = &rest[2..end];
+        let name = name
+            .strip_prefix("[[")
+            .and_then(|n| n.strip_suffix("]]"))
+            .unwrap_or(name)
+            .to_string();
+        self.pos += end + 2;
+        Ok(QueryExpr::Ref(name))
+    }
+
+    /// `[[title]]`, `#[[title]]` or `#tag`, with nested brackets kept in the title.
+    fn page_ref(&mut self) -> Option<String> {
+        let rest = self.rest();
+        let after_hash = rest.strip_prefix('#');
+        let bracketed = after_hash.unwrap_or(rest);
+        if let Some(inner) = bracketed.strip_prefix("[[") {
+            let mut depth = 1;
+            let mut i = 0;
+            while i < inner.len() {
+                if inner[i..].starts_with("[[") {
+                    depth += 1;
+                    i += 2;
+                } else if inner[i..].starts_with("]]") {
+                    depth -= 1;
+                    if depth == 0 {
+                        let title = inner[..i].to_string();
+                        self.pos += rest.len() - inner.len() + i + 2;
+                        return Some(title);
+                    }
+                    i += 2;
+                } else {
+                    i += inner[i..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+            return None;
+        }
+        let tag = after_hash?;
+        let len = tag
+            .find(|c: char| c.is_whitespace() || c == '{' || c == '}')
+            .unwrap_or(tag.len());
+        if len == 0 {
+            return None;
+        }
+        self.pos += 1 + len;
+        Some(tag[..len].to_string())
+    }
+
+    /// `{and: ...}`, `{or: ...}`, `{not: ...}`, `{between: [[date]] [[date]]}`
+    fn operator(&mut self) -> Result<QueryExpr, ParseError> {
+        self.pos += 1;
+        self.skip_space();
+        let rest = self.rest();
+        let colon = rest
+            .find(':')
+            .ok_or_else(|| ParseError("expected an operator such as {and: ...}".into()))?;
+        let name = rest[..colon].trim();
+        let name = name
+            .strip_prefix("[[")
+            .and_then(|n| n.strip_suffix("]]"))
+            .unwrap_or(name)
+            .to_lowercase();
+        self.pos += colon + 1;
+
+        let mut args = Vec::new();
+        loop {
+            self.skip_space();
+            match self.rest().chars().next() {
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                None => return Err(ParseError(format!("unclosed {{{}: ...}}", name))),
+                Some(_) => args.push(self.expr()?),
+            }
+        }
+        if args.is_empty() {
+            return Err(ParseError(format!("{{{}: }} needs arguments", name)));
+        }
+
+        match name.as_str() {
+            "and" => Ok(QueryExpr::And(args)),
+            "or" => Ok(QueryExpr::Or(args)),
+            "not" => Ok(QueryExpr::Not(args)),
+            "between" => {
+                let dates: Vec<NaiveDate> = args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        QueryExpr::Ref(title) => parse_daily_note_title(title),
+                        _ => None,
+                    })
+                    .collect();
+                match dates[..] {
+                    [a, b] if args.len() == 2 => Ok(QueryExpr::Between(a.min(b), a.max(b))),
+                    _ => Err(ParseError("{between: } needs two daily note links".into())),
+                }
+            }
+            other => Err(ParseError(format!("unknown query operator \"{}\"", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expr(args: &str) -> QueryExpr {
+        parse(args).unwrap().expr
+    }
+
+    fn page(title: &str) -> QueryExpr {
+        QueryExpr::Ref(title.into())
+    }
+
+    #[test]
+    fn parses_nested_operators_and_refs() {
+        assert_eq!(
+            expr("{and: [[TODO]] {not: #done [[Archive [[2025]]]]}}"),
+            QueryExpr::And(vec![
+                page("TODO"),
+                QueryExpr::Not(vec![page("done"), page("Archive [[2025]]")]),
+            ])
+        );
+        assert_eq!(expr("[[Single]]"), page("Single"));
+    }
+
+    #[test]
+    fn todo_and_done_forms_are_page_refs() {
+        assert_eq!(
+            expr("{or: TODO {{[[DONE]]}} {{TODO}}}"),
+            QueryExpr::Or(vec![page("TODO"), page("DONE"), page("TODO")])
+        );
+    }
+
+    #[test]
+    fn between_takes_daily_note_links_in_any_order() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2026, m, d).unwrap();
+        assert_eq!(
+            expr("{between: [[March 5th, 2026]] [[February 1st, 2026]]}"),
+            QueryExpr::Between(date(2, 1), date(3, 5))
+        );
+        assert!(parse("{between: [[Project]] [[March 5th, 2026]]}").is_err());
+    }
+
+    #[test]
+    fn title_is_kept_and_errors_are_reported() {
+        let query = parse(r#""Open tasks" {and: [[TODO]]}"#).unwrap();
+        assert_eq!(query.title.as_deref(), Some("Open tasks"));
+        assert!(parse("{and: [[A]]").is_err());
+        assert!(parse("{xor: [[A]]}").is_err());
+        assert!(parse("{and: }").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn find_query_reads_both_macro_spellings() {
+        assert_eq!(
+            find_query("{{query: {and: [[TODO]] [[Project X]]}}}")
+                .unwrap()
+                .unwrap()
+                .expr,
+            QueryExpr::And(vec![page("TODO"), page("Project X")])
+        );
+        assert_eq!(
+            find_query("Tasks {{[[query]]: [[TODO]]}}")
+                .unwrap()
+                .unwrap()
+                .expr,
+            page("TODO")
+        );
+        assert!(find_query("no query here").is_none());
+    }
+
+    #[test]
+    fn datalog_uses_path_refs_and_binds_block_before_negation() {
+        let q = expr(r#"{and: {not: [[B]]} [[A "x"]]}"#).to_datalog();
+        assert!(q.starts_with("[:find ?uid ?s ?page-title :where [?b :block/path-refs ?r0]"));
+        assert!(q.contains(r#"[?r0 :node/title "A \"x\""]"#));
+        assert!(q.contains(r#"(not-join [?b] [?b :block/path-refs ?r1] [?r1 :node/title "B"])"#));
+
+        let q = expr("{not: [[B]]}").to_datalog();
+        assert!(q.contains(":where [?b :block/string ?s]"));
+    }
+
+    #[test]
+    fn datalog_for_or_and_between() {
+        let q = expr("{or: [[A]] [[B]]}").to_datalog();
+        assert!(q.contains("(or-join [?b] (and [?b :block/path-refs ?r0]"));
+        assert!(q.contains("(and [?b :block/path-refs ?r1]"));
+
+        let q = expr("{between: [[January 1st, 2026]] [[January 1st, 2026]]}").to_datalog();
+        assert!(q.contains("[(<= 1767225600000 ?d)] [(<= ?d 1767311999999)]"));
+        assert!(q.contains("[?b :block/page ?dp]"));
+        assert!(q.contains("[?b :block/refs ?dp]"));
+    }
+}
@@ -1,14 +1,18 @@
+use std::ops::Range;
+
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::Widget;
 
-use crate::api::types::{Block, DailyNote};
-use crate::app::LinkedRefsState;
+use crate::api::types::{Block, DailyNote, LinkedRefSort};
+use crate::app::blocks::embed_of;
+use crate::app::{EmbedState, LinkedRefsState, QueryResultsState, MAX_EMBED_DEPTH};
 use crate::edit_buffer::EditBuffer;
 use crate::highlight::CodeHighlighter;
-use crate::markdown;
+use crate::markdown::{self, LinkTarget};
+use crate::markup::{self, EmbedTarget};
 
 pub struct MainArea<'a> {
     pub days: &'a [DailyNote],
@@ -19,11 +23,73 @@ pub struct MainArea<'a> {
     pub edit_info: Option<EditInfo<'a>>,
     pub block_ref_cache: &'a std::collections::HashMap<String, String>,
     pub linked_refs: &'a std::collections::HashMap<String, LinkedRefsState>,
+    /// Results of `{{query}}` blocks, by block uid.
+    pub query_results: &'a std::collections::HashMap<String, QueryResultsState>,
+    /// Fetched content of `{{embed}}` blocks.
+    pub embeds: &'a std::collections::HashMap<EmbedTarget, EmbedState>,
+    /// Inclusive range of blocks selected in visual mode.
+    pub visual_range: Option<(usize, usize)>,
+    /// Rows scrolled off the top, set by the mouse wheel; `None` keeps the
+    /// selected block centered.
+    pub scroll: Option<usize>,
+}
+
+/// Where each row of the last render came from, for mapping mouse clicks
+/// back to blocks and links.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HitMap {
+    /// Area the rows were drawn in.
+    pub area: Rect,
+    /// Rows scrolled off the top.
+    pub scroll_offset: usize,
+    /// All rows, including those outside the viewport.
+    pub total_rows: usize,
+    /// The rows on screen, top to bottom.
+    pub rows: Vec<RowHit>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RowHit {
+    /// Block the row belongs to.
+    pub block_index: Option<usize>,
+    /// Clickable columns, relative to the area.
+    pub targets: Vec<(Range<u16>, LinkTarget)>,
+    /// Columns of table cells and the block each belongs to.
+    pub cells: Vec<(Range<u16>, usize)>,
+}
+
+impl HitMap {
+    /// The block at a screen position, and the link under it if any.
+    pub fn hit(&self, column: u16, row: u16) -> Option<(usize, Option<&LinkTarget>)> {
+        if !self.area.contains((column, row).into()) {
+            return None;
+        }
+        let hit = self.rows.get((row - self.area.y) as usize)?;
+        let col = column - self.area.x;
+        let target = hit
+            .targets
+            .iter()
+            .find(|(cols, _)| cols.contains(&col))
+            .map(|(_, target)| target);
+        let cell = hit
+            .cells
+            .iter()
+            .find(|(cols, _)| cols.contains(&col))
+            .map(|(_, block_index)| *block_index);
+        Some((cell.or(hit.block_index)?, target))
+    }
+
+    /// Largest scroll offset that still fills the viewport.
+    pub fn max_scroll(&self) -> usize {
+        self.total_rows.saturating_sub(self.area.height as usize)
+    }
 }
 
 pub struct EditInfo<'a> {
     pub buffer: &'a EditBuffer,
     pub block_index: usize,
+    /// Selected character range in the buffer, end exclusive.
+    pub selection: Option<(usize, usize)>,
 }
 
 #[derive(Debug, Clone)]
@@ -52,12 +118,51 @@ enum VisibleLine {
         text: String,
         block_index: usize,
     },
+    /// Result count (or status) of a `{{query}}` block, under the block.
+    QueryHeader {
+        depth: usize,
+        label: String,
+        collapsed: bool,
+        block_index: usize,
+    },
+    /// A block matched by a query; `block_index` is the query block's.
+    QueryResult {
+        depth: usize,
+        uid: String,
+        text: String,
+        page_title: String,
+        block_index: usize,
+    },
+    /// A block inside an embed, `level` deep in the embedded tree, drawn
+    /// on a row of its own after the embedding block.
+    Embedded {
+        depth: usize,
+        level: usize,
+        text: String,
+        collapsed_children: usize,
+        block_index: usize,
+    },
+    /// Children of a `{{table}}` block laid out as a grid; `block_index` is
+    /// the first cell's.
+    Table {
+        depth: usize,
+        rows: Vec<Vec<Option<TableCell>>>,
+        block_index: usize,
+    },
+    /// Children of a `{{kanban}}` block as side-by-side columns; `block_index`
+    /// is the first column's.
+    Kanban {
+        depth: usize,
+        columns: Vec<KanbanColumn>,
+        block_index: usize,
+    },
     LoadingMore,
     LinkedRefsSeparator,
     LinkedRefsHeader {
         count: usize,
         collapsed: bool,
         block_index: usize,
+        filter_label: Option<String>,
     },
     LinkedRefsGroupHeader {
         page_title: String,
@@ -69,6 +174,73 @@ enum VisibleLine {
     },
 }
 
+/// A table cell: one block of the table's tree.
+#[derive(Debug, Clone)]
+struct TableCell {
+    block_index: usize,
+    text: String,
+}
+
+/// A kanban column: its title block and the cards under it, each card's
+/// children following it one `level` deeper.
+#[derive(Debug, Clone)]
+struct KanbanColumn {
+    title: TableCell,
+    cards: Vec<(usize, TableCell)>,
+}
+
+impl VisibleLine {
+    fn block_index(&self) -> Option<usize> {
+        match self {
+            VisibleLine::Block { block_index, .. }
+            | VisibleLine::CodeLabel { block_index, .. }
+            | VisibleLine::CodeLine { block_index, .. }
+            | VisibleLine::Blockquote { block_index, .. }
+            | VisibleLine::QueryHeader { block_index, .. }
+            | VisibleLine::QueryResult { block_index, .. }
+            | VisibleLine::Embedded { block_index, .. }
+            | VisibleLine::Table { block_index, .. }
+            | VisibleLine::Kanban { block_index, .. }
+            | VisibleLine::LinkedRefsHeader { block_index, .. }
+            | VisibleLine::LinkedRefsGroupHeader { block_index, .. }
+            | VisibleLine::LinkedRefsBlock { block_index, .. } => Some(*block_index),
+            VisibleLine::DayHeading(_)
+            | VisibleLine::DaySeparator
+            | VisibleLine::LoadingMore
+            | VisibleLine::LinkedRefsSeparator => None,
+        }
+    }
+}
+
+/// Checkbox spans of a line rendered on its own are relative to the line;
+/// shift them by the line's byte `offset` in the block string.
+fn offset_checkboxes(targets: &mut [(Range<usize>, LinkTarget)], offset: usize) {
+    for (_, target) in targets {
+        if let LinkTarget::Checkbox(span) = target {
+            *span = span.start + offset..span.end + offset;
+        }
+    }
+}
+
+/// Clickable columns on a wrapped row showing rendered characters
+/// `start..start + len` after `prefix` columns.
+fn row_targets(
+    targets: &[(Range<usize>, LinkTarget)],
+    start: usize,
+    len: usize,
+    prefix: usize,
+) -> Vec<(Range<u16>, LinkTarget)> {
+    targets
+        .iter()
+        .filter(|(range, _)| range.start < start + len && range.end > start)
+        .map(|(range, target)| {
+            let from = prefix + range.start.max(start) - start;
+            let to = prefix + range.end.min(start + len) - start;
+            (from as u16..to as u16, target.clone())
+        })
+        .collect()
+}
+
 /// Check if a block's text represents a code block (starts with ```)
 fn is_code_block(text: &str) -> bool {
     text.starts_with("```")
@@ -107,6 +279,8 @@ fn build_visible_lines(
     loading_more: bool,
     highlighter: &mut CodeHighlighter,
     linked_refs: &std::collections::HashMap<String, LinkedRefsState>,
+    query_results: &std::collections::HashMap<String, QueryResultsState>,
+    embeds: &std::collections::HashMap<EmbedTarget, EmbedState>,
 ) -> Vec<VisibleLine> {
     let mut lines = Vec::new();
     let mut block_index = 0;
@@ -116,7 +290,15 @@ fn build_visible_lines(
             lines.push(VisibleLine::DaySeparator);
         }
         lines.push(VisibleLine::DayHeading(day.title.clone()));
-        flatten_blocks(&day.blocks, 0, &mut lines, &mut block_index, highlighter);
+        flatten_blocks(
+            &day.blocks,
+            0,
+            &mut lines,
+            &mut block_index,
+            highlighter,
+            query_results,
+            embeds,
+        );
 
         // Append linked references for this day
         if let Some(lr) = linked_refs.get(&day.title) {
@@ -139,14 +321,16 @@ fn append_linked_refs(lr: &LinkedRefsState, lines: &mut Vec<VisibleLine>, block_
             count: 0,
             collapsed: false,
             block_index: *block_index,
+            filter_label: None,
         });
-    } else if !lr.groups.is_empty() {
+    } else if !lr.groups.is_empty() || lr.filter.is_active() {
         let total_count: usize = lr.groups.iter().map(|g| g.blocks.len()).sum();
         lines.push(VisibleLine::LinkedRefsSeparator);
         lines.push(VisibleLine::LinkedRefsHeader {
             count: total_count,
             collapsed: lr.collapsed,
             block_index: *block_index,
+            filter_label: linked_refs_filter_label(lr),
         });
         *block_index += 1;
 
@@ -170,12 +354,94 @@ fn append_linked_refs(lr: &LinkedRefsState, lines: &mut Vec<VisibleLine>, block_
     }
 }
 
+/// Header count text when a filter or non-default sort applies,
+/// e.g. "3 of 10, newest first". `None` means the plain count is enough.
+fn linked_refs_filter_label(lr: &LinkedRefsState) -> Option<String> {
+    if !lr.filter.is_active() && lr.filter.sort == LinkedRefSort::default() {
+        return None;
+    }
+    let shown: usize = lr.groups.iter().map(|g| g.blocks.len()).sum();
+    let mut label = if lr.filter.is_active() {
+        let all_count: usize = lr.all_groups.iter().map(|g| g.blocks.len()).sum();
+        format!("{} of {}", shown, all_count)
+    } else {
+        shown.to_string()
+    };
+    if lr.filter.sort != LinkedRefSort::default() {
+        label.push_str(", ");
+        label.push_str(lr.filter.sort.label());
+    }
+    Some(label)
+}
+
+/// Header and (unless collapsed) result lines of a query block.
+fn append_query_results(
+    qr: &QueryResultsState,
+    depth: usize,
+    block_index: usize,
+    lines: &mut Vec<VisibleLine>,
+) {
+    let label = if let Some(error) = &qr.error {
+        format!("Query error: {}", error)
+    } else if qr.loading && qr.results.is_empty() {
+        "Query (loading...)".to_string()
+    } else {
+        match qr.results.len() {
+            1 => "1 result".to_string(),
+            n => format!("{} results", n),
+        }
+    };
+    lines.push(VisibleLine::QueryHeader {
+        depth,
+        label,
+        collapsed: qr.collapsed,
+        block_index,
+    });
+    if qr.collapsed || qr.error.is_some() {
+        return;
+    }
+    for hit in &qr.results {
+        lines.push(VisibleLine::QueryResult {
+            depth,
+            uid: hit.uid.clone(),
+            text: hit.string.clone(),
+            page_title: hit.page_title.clone(),
+            block_index,
+        });
+    }
+}
+
+/// Lines of an embedded tree under the embedding block, folded past
+/// `MAX_EMBED_DEPTH`, each a row of its own. Embeds inside it show as a
+/// single line.
+fn append_embed(
+    target: &EmbedTarget,
+    embed: &EmbedState,
+    depth: usize,
+    block_index: &mut usize,
+    lines: &mut Vec<VisibleLine>,
+) {
+    for (level, block) in embed.visible_blocks(target) {
+        let expanded = block.open && level + 1 < MAX_EMBED_DEPTH;
+        lines.push(VisibleLine::Embedded {
+            depth,
+            level,
+            text: block.string.clone(),
+            collapsed_children: if expanded { 0 } else { block.children.len() },
+            block_index: *block_index,
+        });
+        *block_index += 1;
+    }
+}
+
 fn flatten_blocks(
     blocks: &[Block],
     depth: usize,
     lines: &mut Vec<VisibleLine>,
     block_index: &mut usize,
     highlighter: &mut CodeHighlighter,
+    query_results: &std::collections::HashMap<String, QueryResultsState>,
+    embeds: &std::collections::HashMap<EmbedTarget, EmbedState>,
 ) {
     for block in blocks {
         if is_code_block(&block.string) {
@@ -235,11 +501,244 @@ fn flatten_blocks(
             });
         }
 
+        if let Some(qr) = query_results
+            .get(&block.uid)
+            .filter(|qr| qr.text == block.string)
+        {
+            append_query_results(qr, depth, *block_index, lines);
+        }
+
+        *block_index += 1;
+        if let Some((target, embed)) = embed_of(block, embeds) {
+            append_embed(target, embed, depth, block_index, lines);
+        }
+        if block.open && !block.children.is_empty() && is_table(&block.string) {
+            let first = *block_index;
+            let mut rows = Vec::new();
+            table_rows(&block.children, 0, &mut Vec::new(), block_index, &mut rows);
+            lines.push(VisibleLine::Table {
+                depth: depth + 1,
+                rows,
+                block_index: first,
+            });
+        } else if block.open && !block.children.is_empty() && is_kanban(&block.string) {
+            let first = *block_index;
+            let columns = kanban_columns(&block.children, block_index);
+            lines.push(VisibleLine::Kanban {
+                depth: depth + 1,
+                columns,
+                block_index: first,
+            });
+        } else if block.open {
+            flatten_blocks(
+                &block.children,
+                depth + 1,
+                lines,
+                block_index,
+                highlighter,
+                query_results,
+                embeds,
+            );
+        }
+    }
+}
+
+fn is_table(text: &str) -> bool {
+    markup::block_macro(text).as_deref() == Some("table")
+}
+
+fn is_kanban(text: &str) -> bool {
+    markup::block_macro(text).as_deref() == Some("kanban")
+}
+
+/// Columns of a board, numbered in outline order like a table's cells.
+fn kanban_columns(blocks: &[Block], block_index: &mut usize) -> Vec<KanbanColumn> {
+    fn cards(
+        blocks: &[Block],
+        level: usize,
+        block_index: &mut usize,
+        out: &mut Vec<(usize, TableCell)>,
+    ) {
+        for block in blocks {
+            out.push((
+                level,
+                TableCell {
+                    block_index: *block_index,
+                    text: block.string.clone(),
+                },
+            ));
+            *block_index += 1;
+            if block.open {
+                cards(&block.children, level + 1, block_index, out);
+            }
+        }
+    }
+    blocks
+        .iter()
+        .map(|block| {
+            let title = TableCell {
+                block_index: *block_index,
+                text: block.string.clone(),
+            };
+            *block_index += 1;
+            let mut column = KanbanColumn {
+                title,
+                cards: Vec::new(),
+            };
+            if block.open {
+                cards(&block.children, 0, block_index, &mut column.cards);
+            }
+            column
+        })
+        .collect()
+}
+
+/// Rows of a table: each path from a first-column block down to a leaf.
+/// Cells shared with the row above are left empty (`None`). Cells are
+/// numbered in outline order, so moving down the outline walks the table
+/// left to right, top to bottom.
+fn table_rows(
+    blocks: &[Block],
+    column: usize,
+    pending: &mut Vec<Option<TableCell>>,
+    block_index: &mut usize,
+    rows: &mut Vec<Vec<Option<TableCell>>>,
+) {
+    for block in blocks {
+        pending.resize(column, None);
+        pending.push(Some(TableCell {
+            block_index: *block_index,
+            text: block.string.clone(),
+        }));
         *block_index += 1;
-        if block.open {
-            flatten_blocks(&block.children, depth + 1, lines, block_index, highlighter);
+        if block.open && !block.children.is_empty() {
+            table_rows(&block.children, column + 1, pending, block_index, rows);
+        } else {
+            rows.push(std::mem::take(pending));
+        }
+    }
+}
+
+/// Column widths that fit `available`: columns narrower than an equal share
+/// keep their natural width and the rest split what's left.
+fn fit_columns(natural: &[usize], available: usize) -> Vec<usize> {
+    let mut widths = natural.to_vec();
+    if natural.iter().sum::<usize>() <= available {
+        return widths;
+    }
+    let mut remaining = available;
+    let mut wide: Vec<usize> = (0..natural.len()).collect();
+    while !wide.is_empty() {
+        let share = remaining / wide.len();
+        let (fits, rest): (Vec<usize>, Vec<usize>) =
+            wide.iter().partition(|&&c| natural[c] <= share);
+        if fits.is_empty() {
+            let extra = remaining % rest.len();
+            for (i, &c) in rest.iter().enumerate() {
+                widths[c] = (share + usize::from(i < extra)).max(1);
+            }
+            break;
+        }
+        for c in fits {
+            remaining -= natural[c];
+        }
+        wide = rest;
+    }
+    widths
+}
+
+/// Rows of the block being edited, each line of the buffer after `prefix`
+/// (`cont_prefix` past the first), and the row the cursor is on.
+fn edit_rows(
+    edit: &EditInfo,
+    prefix: Vec<Span<'static>>,
+    cont_prefix: Vec<Span<'static>>,
+    style: Style,
+) -> (Vec<Line<'static>>, usize) {
+    let buf_text = edit.buffer.to_string();
+    let cursor_pos = edit.buffer.cursor;
+
+    // Map flat cursor position to (line, col)
+    let text_lines: Vec<&str> = buf_text.split('\n').collect();
+    let mut line_start = 0;
+    let mut cursor_line = text_lines.len().saturating_sub(1);
+    let mut cursor_col = 0;
+
+    for (i, tl) in text_lines.iter().enumerate() {
+        let line_end = line_start + tl.chars().count();
+        if cursor_pos <= line_end {
+            cursor_line = i;
+            cursor_col = cursor_pos - line_start;
+            break;
+        }
+        line_start = line_end + 1; // skip \n
+    }
+
+    let mut rows = Vec::new();
+    let mut line_offset = 0;
+    for (line_idx, text_line) in text_lines.iter().enumerate() {
+        let mut spans = if line_idx == 0 {
+            prefix.clone()
+        } else {
+            cont_prefix.clone()
+        };
+        let cursor = (line_idx == cursor_line).then_some(cursor_col);
+        spans.extend(edit_line_spans(
+            text_line,
+            line_offset,
+            cursor,
+            edit.selection,
+            style,
+        ));
+        line_offset += text_line.chars().count() + 1;
+
+        rows.push(Line::from(spans));
+    }
+    (rows, cursor_line)
+}
+
+/// Spans for one line of the block being edited, starting at buffer position
+/// `offset`: the cursor (if on this line) inverted and the selection in blue.
+fn edit_line_spans(
+    line: &str,
+    offset: usize,
+    cursor: Option<usize>,
+    selection: Option<(usize, usize)>,
+    style: Style,
+) -> Vec<Span<'static>> {
+    let cursor_style = Style::default().fg(Color::Black).bg(Color::White);
+    let selected_style = Style::default().fg(Color::White).bg(Color::Blue);
+    let selected =
+        |i: usize| selection.is_some_and(|(start, end)| (start..end).contains(&(offset + i)));
+
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_style = style;
+    let chars: Vec<char> = line.chars().collect();
+    for i in 0..=chars.len() {
+        let ch = match chars.get(i) {
+            Some(&c) => c,
+            // Past the end only the cursor is drawn
+            None if cursor == Some(i) => ' ',
+            None => break,
+        };
+        let char_style = if cursor == Some(i) {
+            cursor_style
+        } else if selected(i) {
+            selected_style
+        } else {
+            style
+        };
+        if char_style != run_style && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
         }
+        run_style = char_style;
+        run.push(ch);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, run_style));
     }
+    spans
 }
 
 /// Inject a block cursor (inverted color) at the given character position within spans.
@@ -354,6 +853,18 @@ fn wrap_spans(
 
 impl<'a> Widget for MainArea<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        ratatui::widgets::StatefulWidget::render(self, area, buf, &mut HitMap::default());
+    }
+}
+
+impl<'a> ratatui::widgets::StatefulWidget for MainArea<'a> {
+    type State = HitMap;
+
+    fn render(self, area: Rect, buf: &mut Buffer, hit_map: &mut HitMap) {
+        *hit_map = HitMap {
+            area,
+            ..HitMap::default()
+        };
         if self.loading {
             render_centered_message(" Loading today's notes...", area, buf);
             return;
@@ -376,15 +887,20 @@ impl<'a> Widget for MainArea<'a> {
             self.loading_more,
             &mut highlighter,
             self.linked_refs,
+            self.query_results,
+            self.embeds,
         );
         let max_width = area.width as usize;
 
         // Phase 1: Build all visual rows (blocks may expand to multiple rows)
         let mut rows: Vec<Line<'static>> = Vec::new();
+        let mut hits: Vec<RowHit> = Vec::new();
         let mut selected_row: usize = 0;
         let mut found_selected = false;
 
         for vline in &visible_lines {
+            let mut targets_by_row = Vec::new();
+            let mut cells_by_row = Vec::new();
             match vline {
                 VisibleLine::DayHeading(title) => {
                     let text = format!("  {}", title);
@@ -426,72 +942,26 @@ impl<'a> Widget for MainArea<'a> {
                     if is_editing {
                         let edit = self.edit_info.as_ref().unwrap();
                         let style = Style::default().fg(Color::White).bg(Color::DarkGray);
-                        let prefix = format!("{}• ", indent);
-                        let cont_prefix = format!("{}  ", indent);
-                        let buf_text = edit.buffer.to_string();
-                        let cursor_pos = edit.buffer.cursor;
-
-                        // Map flat cursor position to (line, col)
-                        let text_lines: Vec<&str> = buf_text.split('\n').collect();
-                        let mut line_start = 0;
-                        let mut cursor_line = text_lines.len().saturating_sub(1);
-                        let mut cursor_col = 0;
-
-                        for (i, tl) in text_lines.iter().enumerate() {
-                            let line_end = line_start + tl.chars().count();
-                            if cursor_pos <= line_end {
-                                cursor_line = i;
-                                cursor_col = cursor_pos - line_start;
-                                break;
-                            }
-                            line_start = line_end + 1; // skip \n
-                        }
-
-                        let edit_start_row = rows.len();
-                        for (line_idx, text_line) in text_lines.iter().enumerate() {
-                            let lp = if line_idx == 0 {
-                                prefix.clone()
-                            } else {
-                                cont_prefix.clone()
-                            };
-                            let mut spans = vec![Span::styled(lp, style)];
-
-                            if line_idx == cursor_line {
-                                let line_chars: Vec<char> = text_line.chars().collect();
-                                let before: String = line_chars[..cursor_col].iter().collect();
-                                let cursor_char =
-                                    line_chars.get(cursor_col).copied().unwrap_or(' ');
-                                let after: String = if cursor_col < line_chars.len() {
-                                    line_chars[cursor_col + 1..].iter().collect()
-                                } else {
-                                    String::new()
-                                };
-
-                                spans.push(Span::styled(before, style));
-                                spans.push(Span::styled(
-                                    cursor_char.to_string(),
-                                    Style::default().fg(Color::Black).bg(Color::White),
-                                ));
-                                if !after.is_empty() {
-                                    spans.push(Span::styled(after, style));
-                                }
-                            } else {
-                                spans.push(Span::styled(text_line.to_string(), style));
-                            }
-
-                            rows.push(Line::from(spans));
-                        }
+                        let prefix = vec![Span::styled(format!("{}• ", indent), style)];
+                        let cont_prefix = vec![Span::styled(format!("{}  ", indent), style)];
+                        let (edit_rows, cursor_line) = edit_rows(edit, prefix, cont_prefix, style);
                         // Scroll to the cursor row, not the first row of the block
-                        selected_row = edit_start_row + cursor_line;
+                        selected_row = rows.len() + cursor_line;
+                        rows.extend(edit_rows);
                     } else {
+                        let in_visual = self
+                            .visual_range
+                            .is_some_and(|(start, end)| (start..=end).contains(block_index));
                         let mut style = if is_selected {
                             Style::default().fg(Color::White).bg(Color::DarkGray)
+                        } else if in_visual {
+                            Style::default().fg(Color::White).bg(Color::Blue)
                         } else {
                             Style::default().fg(Color::Gray)
                         };
 
                         // Dim for deep nesting
-                        if !is_selected && *depth >= 3 {
+                        if !is_selected && !in_visual && *depth >= 3 {
                             style = style.add_modifier(Modifier::DIM);
                         }
 
@@ -503,6 +973,8 @@ impl<'a> Widget for MainArea<'a> {
                         let bullet_style = if *collapsed_children > 0 {
                             Style::default().fg(Color::Cyan).bg(if is_selected {
                                 Color::DarkGray
+                            } else if in_visual {
+                                Color::Blue
                             } else {
                                 Color::Reset
                             })
@@ -535,12 +1007,15 @@ impl<'a> Widget for MainArea<'a> {
                         let mut is_first_row = true;
 
                         let mut rendered_char_offset = 0;
+                        let mut line_offset = 0;
                         for text_line in text.split('\n') {
-                            let mut line_spans = markdown::render_spans_with_refs(
+                            let (mut line_spans, mut targets) = markdown::render_spans_with_targets(
                                 text_line,
                                 style,
                                 Some(&block_map),
                             );
+                            offset_checkboxes(&mut targets, line_offset);
+                            line_offset += text_line.len() + 1;
 
                             // Inject cursor in normal mode for selected block
                             if is_selected && self.edit_info.is_none() {
@@ -557,7 +1032,20 @@ impl<'a> Widget for MainArea<'a> {
 
                             let w = if is_first_row { first_w } else { cont_w };
                             let wrapped = wrap_spans(line_spans, w, cont_w);
+                            let mut char_pos = 0;
                             for (wrap_idx, wline) in wrapped.into_iter().enumerate() {
+                                let len: usize =
+                                    wline.iter().map(|s| s.content.chars().count()).sum();
+                                let prefix = if is_first_row {
+                                    prefix_width
+                                } else {
+                                    cont_prefix_width
+                                };
+                                targets_by_row.push((
+                                    rows.len(),
+                                    row_targets(&targets, char_pos, len, prefix),
+                                ));
+                                char_pos += len;
                                 let mut full_spans: Vec<Span<'static>> =
                                     if is_first_row && wrap_idx == 0 {
                                         let mut v: Vec<Span<'static>> = Vec::new();
@@ -616,14 +1104,25 @@ impl<'a> Widget for MainArea<'a> {
 
                     let text_w = max_width.saturating_sub(prefix_width);
 
+                    // The text starts after the block string's "> "
+                    let mut line_offset = 2;
                     for text_line in text.split('\n') {
-                        let line_spans = markdown::render_spans_with_refs(
+                        let (line_spans, mut targets) = markdown::render_spans_with_targets(
                             text_line,
                             text_style,
                             Some(&block_map),
                         );
+                        offset_checkboxes(&mut targets, line_offset);
+                        line_offset += text_line.len() + 1;
                         let wrapped = wrap_spans(line_spans, text_w, text_w);
+                        let mut char_pos = 0;
                         for wline in wrapped {
+                            let len: usize = wline.iter().map(|s| s.content.chars().count()).sum();
+                            targets_by_row.push((
+                                rows.len(),
+                                row_targets(&targets, char_pos, len, prefix_width),
+                            ));
+                            char_pos += len;
                             let mut full_spans = vec![
                                 Span::styled(indent.clone(), text_style),
                                 Span::styled("│ ".to_string(), border_style),
@@ -682,6 +1181,192 @@ impl<'a> Widget for MainArea<'a> {
 
                     rows.push(Line::from(line_spans));
                 }
+                VisibleLine::QueryHeader {
+                    depth,
+                    label,
+                    collapsed,
+                    ..
+                } => {
+                    let arrow = if *collapsed { "▸" } else { "▾" };
+                    let text = format!("{}  {} {}", "  ".repeat(depth + 1), arrow, label);
+                    let style = Style::default().fg(Color::Cyan).add_modifier(Modifier::DIM);
+                    rows.push(Line::styled(truncate(&text, max_width), style));
+                }
+                VisibleLine::QueryResult {
+                    depth,
+                    uid,
+                    text,
+                    page_title,
+                    ..
+                } => {
+                    let style = Style::default().fg(Color::Gray);
+                    let prefix = format!("{}    ◦ ", "  ".repeat(depth + 1));
+                    let prefix_width = prefix.chars().count();
+                    let text_w = max_width.saturating_sub(prefix_width);
+                    // The checkbox belongs to the result, not to the selected block
+                    let (mut line_spans, mut targets) =
+                        markdown::render_spans_with_targets(text, style, Some(&block_map));
+                    targets.retain(|(_, target)| !matches!(target, LinkTarget::Checkbox(_)));
+                    // The page label jumps to the result in its page
+                    let text_len: usize =
+                        line_spans.iter().map(|s| s.content.chars().count()).sum();
+                    line_spans.push(Span::styled(" — ", Style::default().fg(Color::DarkGray)));
+                    line_spans.push(Span::styled(
+                        page_title.clone(),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                    let label_start = text_len + 3;
+                    targets.push((
+                        label_start..label_start + page_title.chars().count(),
+                        LinkTarget::Block(uid.clone()),
+                    ));
+                    let wrapped = wrap_spans(line_spans, text_w, text_w);
+                    let mut char_pos = 0;
+                    for (i, wline) in wrapped.into_iter().enumerate() {
+                        let len: usize = wline.iter().map(|s| s.content.chars().count()).sum();
+                        targets_by_row.push((
+                            rows.len(),
+                            row_targets(&targets, char_pos, len, prefix_width),
+                        ));
+                        char_pos += len;
+                        let pfx = if i == 0 {
+                            prefix.clone()
+                        } else {
+                            " ".repeat(prefix_width)
+                        };
+                        let mut full_spans = vec![Span::styled(pfx, style)];
+                        full_spans.extend(wline);
+                        rows.push(Line::from(full_spans));
+                    }
+                }
+                VisibleLine::Embedded {
+                    depth,
+                    level,
+                    text,
+                    collapsed_children,
+                    block_index,
+                } => {
+                    let is_selected = *block_index == self.selected_block;
+                    if !found_selected && is_selected {
+                        selected_row = rows.len();
+                        found_selected = true;
+                    }
+                    let in_visual = self
+                        .visual_range
+                        .is_some_and(|(start, end)| (start..=end).contains(block_index));
+                    let text_style = if is_selected {
+                        Style::default().fg(Color::White).bg(Color::DarkGray)
+                    } else if in_visual {
+                        Style::default().fg(Color::White).bg(Color::Blue)
+                    } else {
+                        Style::default().fg(Color::Gray)
+                    };
+                    let border_style = Style::default().fg(Color::Magenta);
+                    let indent = format!("{}  ", "  ".repeat(depth + 1));
+                    let nesting = "  ".repeat(*level);
+                    let bullet = if *collapsed_children > 0 {
+                        "▸"
+                    } else {
+                        "•"
+                    };
+                    let edit = self
+                        .edit_info
+                        .as_ref()
+                        .filter(|e| is_selected && e.block_index == *block_index);
+                    if let Some(edit) = edit {
+                        let prefix = |marker: String| {
+                            vec![
+                                Span::styled(indent.clone(), text_style),
+                                Span::styled("┃ ", border_style),
+                                Span::styled(marker, text_style),
+                            ]
+                        };
+                        let (edit_rows, cursor_line) = edit_rows(
+                            edit,
+                            prefix(format!("{}{} ", nesting, bullet)),
+                            prefix(format!("{}  ", nesting)),
+                            text_style,
+                        );
+                        selected_row = rows.len() + cursor_line;
+                        rows.extend(edit_rows);
+                    } else {
+                        let prefix_width = indent.chars().count() + 2 + nesting.chars().count() + 2;
+                        let text_w = max_width.saturating_sub(prefix_width);
+                        let mut targets = Vec::new();
+                        let mut line_spans = Vec::new();
+                        let mut line_offset = 0;
+                        for (i, text_line) in text.split('\n').enumerate() {
+                            let (spans, mut line_targets) = markdown::render_spans_with_targets(
+                                text_line,
+                                text_style,
+                                Some(&block_map),
+                            );
+                            offset_checkboxes(&mut line_targets, line_offset);
+                            line_offset += text_line.len() + 1;
+                            if i > 0 {
+                                line_spans.push(Span::styled(" ", text_style));
+                            }
+                            let offset: usize =
+                                line_spans.iter().map(|s| s.content.chars().count()).sum();
+                            targets.extend(line_targets.into_iter().map(|(range, target)| {
+                                (range.start + offset..range.end + offset, target)
+                            }));
+                            line_spans.extend(spans);
+                        }
+                        if *collapsed_children > 0 {
+                            line_spans.push(Span::styled(
+                                format!(" [{}]", collapsed_children),
+                                Style::default().fg(Color::DarkGray),
+                            ));
+                        }
+                        let wrapped = wrap_spans(line_spans, text_w, text_w);
+                        let mut char_pos = 0;
+                        for (i, wline) in wrapped.into_iter().enumerate() {
+                            let len: usize = wline.iter().map(|s| s.content.chars().count()).sum();
+                            targets_by_row.push((
+                                rows.len(),
+                                row_targets(&targets, char_pos, len, prefix_width),
+                            ));
+                            char_pos += len;
+                            let marker = if i == 0 {
+                                format!("{}{} ", nesting, bullet)
+                            } else {
+                                format!("{}  ", nesting)
+                            };
+                            let mut full_spans = vec![
+                                Span::styled(indent.clone(), text_style),
+                                Span::styled("┃ ", border_style),
+                                Span::styled(marker, text_style),
+                            ];
+                            full_spans.extend(wline);
+                            rows.push(Line::from(full_spans));
+                        }
+                    }
+                }
+                VisibleLine::Table {
+                    depth, rows: table, ..
+                } => {
+                    for row in self.table_rows(*depth, table, &block_map, max_width) {
+                        if row.selected {
+                            selected_row = rows.len();
+                            found_selected = true;
+                        }
+                        targets_by_row.push((rows.len(), row.targets));
+                        cells_by_row.push((rows.len(), row.cells));
+                        rows.push(row.line);
+                    }
+                }
+                VisibleLine::Kanban { depth, columns, .. } => {
+                    for row in self.kanban_rows(*depth, columns, &block_map, max_width) {
+                        if row.selected {
+                            selected_row = rows.len();
+                            found_selected = true;
+                        }
+                        targets_by_row.push((rows.len(), row.targets));
+                        cells_by_row.push((rows.len(), row.cells));
+                        rows.push(row.line);
+                    }
+                }
                 VisibleLine::LoadingMore => {
                     rows.push(Line::styled(
                         "  Loading more...".to_string(),
@@ -701,6 +1386,7 @@ impl<'a> Widget for MainArea<'a> {
                     count,
                     collapsed,
                     block_index,
+                    filter_label,
                 } => {
                     let is_selected = *block_index == self.selected_block;
                     if !found_selected && is_selected {
@@ -708,10 +1394,12 @@ impl<'a> Widget for MainArea<'a> {
                         found_selected = true;
                     }
                     let arrow = if *collapsed { "▸" } else { "▾" };
-                    let label = if *count == 0 {
-                        format!("  {} Linked References (loading...)", arrow)
-                    } else {
-                        format!("  {} Linked References ({})", arrow, count)
+                    let label = match filter_label {
+                        Some(filter) => format!("  {} Linked References ({})", arrow, filter),
+                        None if *count == 0 => {
+                            format!("  {} Linked References (loading...)", arrow)
+                        }
+                        None => format!("  {} Linked References ({})", arrow, count),
                     };
                     let style = if is_selected {
                         Style::default()
@@ -758,10 +1446,17 @@ impl<'a> Widget for MainArea<'a> {
                     let prefix = "      • ";
                     let prefix_width = prefix.chars().count();
                     let text_w = max_width.saturating_sub(prefix_width);
-                    let line_spans =
-                        markdown::render_spans_with_refs(text, style, Some(&block_map));
+                    let (line_spans, targets) =
+                        markdown::render_spans_with_targets(text, style, Some(&block_map));
                     let wrapped = wrap_spans(line_spans, text_w, text_w);
+                    let mut char_pos = 0;
                     for (i, wline) in wrapped.into_iter().enumerate() {
+                        let len: usize = wline.iter().map(|s| s.content.chars().count()).sum();
+                        targets_by_row.push((
+                            rows.len(),
+                            row_targets(&targets, char_pos, len, prefix_width),
+                        ));
+                        char_pos += len;
                         let pfx = if i == 0 {
                             prefix.to_string()
                         } else {
@@ -773,16 +1468,37 @@ impl<'a> Widget for MainArea<'a> {
                     }
                 }
             }
+            hits.resize(
+                rows.len(),
+                RowHit {
+                    block_index: vline.block_index(),
+                    ..RowHit::default()
+                },
+            );
+            for (row, targets) in targets_by_row {
+                hits[row].targets = targets;
+            }
+            for (row, cells) in cells_by_row {
+                hits[row].cells = cells;
+            }
         }
 
-        // Phase 2: Scroll (half-page centering on selected row)
+        // Phase 2: Scroll (half-page centering on selected row, unless scrolled with the mouse)
         let viewport_height = area.height as usize;
         let half = viewport_height / 2;
-        let scroll_offset = if selected_row > half {
-            (selected_row - half).min(rows.len().saturating_sub(viewport_height))
-        } else {
-            0
+        let max_scroll = rows.len().saturating_sub(viewport_height);
+        let scroll_offset = match self.scroll {
+            Some(scroll) => scroll.min(max_scroll),
+            None if selected_row > half => (selected_row - half).min(max_scroll),
+            None => 0,
         };
+        hit_map.scroll_offset = scroll_offset;
+        hit_map.total_rows = rows.len();
+        hit_map.rows = hits
+            .into_iter()
+            .skip(scroll_offset)
+            .take(viewport_height)
+            .collect();
 
         // Phase 3: Render rows to terminal
         for (i, row) in rows.into_iter().skip(scroll_offset).enumerate() {
@@ -796,6 +1512,462 @@ impl<'a> Widget for MainArea<'a> {
     }
 }
 
+/// A table cell's lines before wrapping.
+struct CellContent {
+    style: Style,
+    lines: Vec<Vec<Span<'static>>>,
+    /// Links of each line, by rendered character range.
+    targets: Vec<Vec<(Range<usize>, LinkTarget)>>,
+    /// `(line, column)` of the cursor while the cell is edited.
+    cursor: Option<(usize, usize)>,
+}
+
+/// Clickable columns of a row, relative to the area.
+type RowTargets = Vec<(Range<u16>, LinkTarget)>;
+
+/// One table cell, rendered and wrapped to its column.
+struct CellLayout {
+    block_index: usize,
+    style: Style,
+    /// Wrapped lines, each with its links (columns relative to the area).
+    lines: Vec<(Vec<Span<'static>>, RowTargets)>,
+    /// Line holding the cursor while the cell is edited.
+    cursor_line: Option<usize>,
+}
+
+/// One screen row of a table or board.
+struct TableRow {
+    line: Line<'static>,
+    targets: RowTargets,
+    cells: Vec<(Range<u16>, usize)>,
+    /// The row to scroll to: the selected cell's first line, or its cursor.
+    selected: bool,
+}
+
+/// One wrapped line of a kanban title or card, inside the column border.
+struct BoardLine {
+    spans: Vec<Span<'static>>,
+    /// Links, by column inside the border.
+    targets: RowTargets,
+    block_index: usize,
+    style: Style,
+    selected: bool,
+}
+
+impl MainArea<'_> {
+    /// Lay out a table as a bordered grid. Columns take their widest cell,
+    /// shrinking to fit the area, and cells wrap within their column.
+    fn table_rows(
+        &self,
+        depth: usize,
+        table: &[Vec<Option<TableCell>>],
+        block_map: &std::collections::HashMap<String, String>,
+        max_width: usize,
+    ) -> Vec<TableRow> {
+        let indent = "  ".repeat(depth + 1);
+        let indent_w = indent.chars().count();
+        let border_style = Style::default().fg(Color::DarkGray);
+        let columns = table.iter().map(Vec::len).max().unwrap_or(0);
+
+        // Unwrapped lines of every cell, to measure the columns
+        let mut natural = vec![1; columns];
+        let contents: Vec<Vec<Option<CellContent>>> = table
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(c, cell)| {
+                        let content = self.table_cell(cell.as_ref()?, block_map);
+                        for (i, line) in content.lines.iter().enumerate() {
+                            let len: usize = line.iter().map(|s| s.content.chars().count()).sum();
+                            // Room for the cursor past the end of the line
+                            let cursor_room = content.cursor == Some((i, len));
+                            natural[c] = natural[c].max(len + usize::from(cursor_room));
+                        }
+                        Some(content)
+                    })
+                    .collect()
+            })
+            .collect();
+        let widths = fit_columns(
+            &natural,
+            max_width.saturating_sub(indent_w + 3 * columns + 1),
+        );
+
+        let layouts: Vec<Vec<Option<CellLayout>>> = table
+            .iter()
+            .zip(contents)
+            .map(|(row, cells)| {
+                let mut x = indent_w + 2;
+                row.iter()
+                    .zip(cells)
+                    .enumerate()
+                    .map(|(c, (cell, content))| {
+                        let width = widths[c];
+                        let left = x;
+                        x += width + 3;
+                        let (cell, content) = (cell.as_ref()?, content?);
+                        let mut layout = CellLayout {
+                            block_index: cell.block_index,
+                            style: content.style,
+                            lines: Vec::new(),
+                            cursor_line: None,
+                        };
+                        let cursor = content.cursor;
+                        let lines = content.lines.into_iter().zip(content.targets);
+                        for (i, (line, targets)) in lines.enumerate() {
+                            let first = layout.lines.len();
+                            let mut char_pos = 0;
+                            for wline in wrap_spans(line, width, width) {
+                                let len: usize =
+                                    wline.iter().map(|s| s.content.chars().count()).sum();
+                                let row_links = row_targets(&targets, char_pos, len, left);
+                                char_pos += len;
+                                layout.lines.push((wline, row_links));
+                            }
+                            if let Some((_, col)) = cursor.filter(|(l, _)| *l == i) {
+                                let last = layout.lines.len() - 1;
+                                layout.cursor_line = Some((first + col / width.max(1)).min(last));
+                            }
+                        }
+                        Some(layout)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut out = Vec::new();
+        let push_border = |out: &mut Vec<TableRow>, drawn: &[bool], joints: [&str; 3]| {
+            out.push(TableRow {
+                line: table_border(&indent, &widths, drawn, joints, border_style),
+                targets: Vec::new(),
+                cells: Vec::new(),
+                selected: false,
+            });
+        };
+        push_border(&mut out, &vec![true; columns], ["┌", "┬", "┐"]);
+        for (r, row) in layouts.iter().enumerate() {
+            let height = row
+                .iter()
+                .flatten()
+                .map(|cell| cell.lines.len())
+                .max()
+                .unwrap_or(1);
+            for l in 0..height {
+                let mut spans = vec![Span::raw(indent.clone()), Span::styled("│", border_style)];
+                let mut targets = Vec::new();
+                let mut cells = Vec::new();
+                let mut selected = false;
+                let mut x = indent_w + 1;
+                for (c, &width) in widths.iter().enumerate() {
+                    let cell = row.get(c).and_then(Option::as_ref);
+                    let style = cell.map_or(Style::default(), |cell| cell.style);
+                    let (content, links) = cell
+                        .and_then(|cell| cell.lines.get(l).cloned())
+                        .unwrap_or_default();
+                    let len: usize = content.iter().map(|s| s.content.chars().count()).sum();
+                    spans.push(Span::styled(" ", style));
+                    spans.extend(content);
+                    spans.push(Span::styled(
+                        " ".repeat(width.saturating_sub(len) + 1),
+                        style,
+                    ));
+                    spans.push(Span::styled("│", border_style));
+                    targets.extend(links);
+                    if let Some(cell) = cell {
+                        cells.push((x as u16..(x + width + 2) as u16, cell.block_index));
+                        if cell.block_index == self.selected_block {
+                            selected |= cell.cursor_line.unwrap_or(0) == l;
+                        }
+                    }
+                    x += width + 3;
+                }
+                out.push(TableRow {
+                    line: Line::from(spans),
+                    targets,
+                    cells,
+                    selected,
+                });
+            }
+            if let Some(next) = table.get(r + 1) {
+                // No line above a cell the next row shares with this one
+                let drawn: Vec<bool> = (0..columns)
+                    .map(|c| next.get(c).is_none_or(Option::is_some))
+                    .collect();
+                push_border(&mut out, &drawn, ["├", "┼", "┤"]);
+            }
+        }
+        push_border(&mut out, &vec![true; columns], ["└", "┴", "┘"]);
+        out
+    }
+
+    /// Lay out a board: equal-width bordered columns side by side, the
+    /// column title above a rule and its cards below.
+    fn kanban_rows(
+        &self,
+        depth: usize,
+        columns: &[KanbanColumn],
+        block_map: &std::collections::HashMap<String, String>,
+        max_width: usize,
+    ) -> Vec<TableRow> {
+        let indent = "  ".repeat(depth + 1);
+        let indent_w = indent.chars().count();
+        let border_style = Style::default().fg(Color::DarkGray);
+        let count = columns.len().max(1);
+        // Each column is `│ text │`, with a space between columns
+        let inner = (max_width.saturating_sub(indent_w + count - 1) / count)
+            .saturating_sub(4)
+            .max(1);
+
+        let mut titles = Vec::new();
+        let mut cards = Vec::new();
+        for column in columns {
+            let mut title = self.board_lines(&column.title, "", "", inner, block_map);
+            for line in &mut title {
+                line.spans = line
+                    .spans
+                    .drain(..)
+                    .map(|span| {
+                        let style = span.style.add_modifier(Modifier::BOLD);
+                        span.style(style)
+                    })
+                    .collect();
+            }
+            titles.push(title);
+            cards.push(
+                column
+                    .cards
+                    .iter()
+                    .flat_map(|(level, card)| {
+                        let nesting = "  ".repeat(*level);
+                        let bullet = if *level == 0 { "▪ " } else { "• " };
+                        self.board_lines(
+                            card,
+                            &format!("{}{}", nesting, bullet),
+                            &format!("{}  ", nesting),
+                            inner,
+                            block_map,
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+        let title_height = titles.iter().map(Vec::len).max().unwrap_or(1);
+        let card_height = cards.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+        let rule = |[left, right]: [&str; 2]| {
+            let mut spans = vec![Span::raw(indent.clone())];
+            for c in 0..count {
+                if c > 0 {
+                    spans.push(Span::raw(" "));
+                }
+                spans.push(Span::styled(
+                    format!("{}{}{}", left, "─".repeat(inner + 2), right),
+                    border_style,
+                ));
+            }
+            TableRow {
+                line: Line::from(spans),
+                targets: Vec::new(),
+                cells: Vec::new(),
+                selected: false,
+            }
+        };
+        let content_row = |lines: Vec<Option<&BoardLine>>| {
+            let mut spans = vec![Span::raw(indent.clone())];
+            let mut row = TableRow {
+                line: Line::default(),
+                targets: Vec::new(),
+                cells: Vec::new(),
+                selected: false,
+            };
+            let mut x = indent_w;
+            for (c, line) in lines.into_iter().enumerate() {
+                if c > 0 {
+                    spans.push(Span::raw(" "));
+                    x += 1;
+                }
+                let style = line.map_or(Style::default(), |line| line.style);
+                spans.push(Span::styled("│", border_style));
+                spans.push(Span::styled(" ", style));
+                let mut len = 0;
+                if let Some(line) = line {
+                    len = line.spans.iter().map(|s| s.content.chars().count()).sum();
+                    spans.extend(line.spans.iter().cloned());
+                    let left = (x + 2) as u16;
+                    row.targets
+                        .extend(line.targets.iter().map(|(range, target)| {
+                            (range.start + left..range.end + left, target.clone())
+                        }));
+                    row.cells
+                        .push(((x + 1) as u16..(x + inner + 3) as u16, line.block_index));
+                    row.selected |= line.selected;
+                }
+                spans.push(Span::styled(
+                    " ".repeat(inner.saturating_sub(len) + 1),
+                    style,
+                ));
+                spans.push(Span::styled("│", border_style));
+                x += inner + 4;
+            }
+            row.line = Line::from(spans);
+            row
+        };
+
+        let mut out = vec![rule(["┌", "┐"])];
+        for l in 0..title_height {
+            out.push(content_row(titles.iter().map(|t| t.get(l)).collect()));
+        }
+        out.push(rule(["├", "┤"]));
+        for l in 0..card_height {
+            out.push(content_row(cards.iter().map(|c| c.get(l)).collect()));
+        }
+        out.push(rule(["└", "┘"]));
+        out
+    }
+
+    /// Wrapped lines of a kanban title or card, `prefix` before the first
+    /// line and `cont_prefix` before the rest.
+    fn board_lines(
+        &self,
+        cell: &TableCell,
+        prefix: &str,
+        cont_prefix: &str,
+        width: usize,
+        block_map: &std::collections::HashMap<String, String>,
+    ) -> Vec<BoardLine> {
+        let content = self.table_cell(cell, block_map);
+        let prefix_w = prefix.chars().count();
+        let text_w = width.saturating_sub(prefix_w).max(1);
+        let is_selected = cell.block_index == self.selected_block;
+        let mut out: Vec<BoardLine> = Vec::new();
+        let lines = content.lines.into_iter().zip(content.targets);
+        for (i, (line, targets)) in lines.enumerate() {
+            let first = out.len();
+            let mut char_pos = 0;
+            for wline in wrap_spans(line, text_w, text_w) {
+                let len: usize = wline.iter().map(|s| s.content.chars().count()).sum();
+                let pfx = if out.is_empty() { prefix } else { cont_prefix };
+                let mut spans = vec![Span::styled(pfx.to_string(), content.style)];
+                spans.extend(wline);
+                out.push(BoardLine {
+                    spans,
+                    targets: row_targets(&targets, char_pos, len, prefix_w),
+                    block_index: cell.block_index,
+                    style: content.style,
+                    selected: false,
+                });
+                char_pos += len;
+            }
+            if let Some((_, col)) = content.cursor.filter(|(l, _)| *l == i) {
+                let last = out.len() - 1;
+                out[(first + col / text_w).min(last)].selected = true;
+            }
+        }
+        if is_selected && !out.iter().any(|line| line.selected) {
+            if let Some(line) = out.first_mut() {
+                line.selected = true;
+            }
+        }
+        out
+    }
+
+    /// A table cell's text, or the edit buffer when the cell is being edited.
+    fn table_cell(
+        &self,
+        cell: &TableCell,
+        block_map: &std::collections::HashMap<String, String>,
+    ) -> CellContent {
+        let in_visual = self
+            .visual_range
+            .is_some_and(|(start, end)| (start..=end).contains(&cell.block_index));
+        let style = if cell.block_index == self.selected_block {
+            Style::default().fg(Color::White).bg(Color::DarkGray)
+        } else if in_visual {
+            Style::default().fg(Color::White).bg(Color::Blue)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+
+        if let Some(edit) = self
+            .edit_info
+            .as_ref()
+            .filter(|edit| edit.block_index == cell.block_index)
+        {
+            let text = edit.buffer.to_string();
+            let mut content = CellContent {
+                style,
+                lines: Vec::new(),
+                targets: Vec::new(),
+                cursor: None,
+            };
+            let mut offset = 0;
+            for (i, line) in text.split('\n').enumerate() {
+                let len = line.chars().count();
+                let col = (content.cursor.is_none() && edit.buffer.cursor <= offset + len)
+                    .then(|| edit.buffer.cursor - offset);
+                if let Some(col) = col {
+                    content.cursor = Some((i, col));
+                }
+                content
+                    .lines
+                    .push(edit_line_spans(line, offset, col, edit.selection, style));
+                content.targets.push(Vec::new());
+                offset += len + 1;
+            }
+            return content;
+        }
+
+        let mut line_offset = 0;
+        let (lines, targets) = cell
+            .text
+            .split('\n')
+            .map(|line| {
+                let (spans, mut targets) =
+                    markdown::render_spans_with_targets(line, style, Some(block_map));
+                offset_checkboxes(&mut targets, line_offset);
+                line_offset += line.len() + 1;
+                (spans, targets)
+            })
+            .unzip();
+        CellContent {
+            style,
+            lines,
+            targets,
+            cursor: None,
+        }
+    }
+}
+
+/// A horizontal table border. `drawn` says which columns get a line; the
+/// others continue the cell above.
+fn table_border(
+    indent: &str,
+    widths: &[usize],
+    drawn: &[bool],
+    [left, mid, right]: [&str; 3],
+    style: Style,
+) -> Line<'static> {
+    let mut border = indent.to_string();
+    for (c, &width) in widths.iter().enumerate() {
+        let before = c.checked_sub(1).map(|p| drawn[p]);
+        border.push_str(match (before, drawn[c]) {
+            (None, true) => left,
+            (Some(true), true) => mid,
+            (Some(false), true) => "├",
+            (Some(true), false) => "┤",
+            (None, false) | (Some(false), false) => "│",
+        });
+        border.push_str(&if drawn[c] { "─" } else { " " }.repeat(width + 2));
+    }
+    border.push_str(if drawn.last() == Some(&true) {
+        right
+    } else {
+        "│"
+    });
+    Line::styled(border, style)
+}
+
 fn truncate(s: &str, max_len: usize) -> String {
     s.chars().take(max_len).collect()
 }
@@ -855,7 +2027,14 @@ mod tests {
             vec![make_block("b1", "Hello", 0), make_block("b2", "World", 1)],
         );
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         assert_eq!(lines.len(), 3); // heading + 2 blocks
         assert!(matches!(&lines[0], VisibleLine::DayHeading(t) if t == "February 21, 2026"));
@@ -879,7 +2058,14 @@ mod tests {
         };
         let day = make_daily_note("Feb 21", 2026, 2, 21, vec![parent]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         assert_eq!(lines.len(), 3); // heading + parent + child
         assert!(matches!(
@@ -905,7 +2091,14 @@ mod tests {
         let day1 = make_daily_note("Day 1", 2026, 2, 21, vec![make_block("a", "A", 0)]);
         let day2 = make_daily_note("Day 2", 2026, 2, 20, vec![make_block("b", "B", 0)]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day1, day2], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day1, day2],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         assert_eq!(lines.len(), 5);
         assert!(matches!(&lines[2], VisibleLine::DaySeparator));
@@ -916,7 +2109,14 @@ mod tests {
     fn build_visible_lines_loading_more() {
         let day = make_daily_note("Day 1", 2026, 2, 21, vec![make_block("a", "A", 0)]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], true, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            true,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         let last = lines.last().unwrap();
         assert!(matches!(last, VisibleLine::LoadingMore));
@@ -936,6 +2136,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -957,6 +2161,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -989,6 +2197,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1018,12 +2230,101 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
         assert!(read_line(&buf, 2, area.width).contains("Second"));
     }
 
+    #[test]
+    fn highlights_visual_selection() {
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = Buffer::empty(area);
+
+        let day = make_daily_note(
+            "Feb 21",
+            2026,
+            2,
+            21,
+            vec![
+                make_block("b1", "First", 0),
+                make_block("b2", "Second", 1),
+                make_block("b3", "Third", 2),
+            ],
+        );
+
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 1,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: Some((0, 1)),
+            scroll: None,
+        };
+        widget.render(area, &mut buf);
+
+        // Row 1 is "First": in the range but not the cursor
+        assert_eq!(buf.cell((6, 1)).unwrap().bg, Color::Blue);
+        assert_eq!(buf.cell((6, 2)).unwrap().bg, Color::DarkGray);
+        assert_ne!(buf.cell((6, 3)).unwrap().bg, Color::Blue);
+    }
+
+    #[test]
+    fn highlights_selection_in_edited_block() {
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = Buffer::empty(area);
+
+        let day = make_daily_note("Feb 21", 2026, 2, 21, vec![make_block("b1", "First", 0)]);
+        let mut buffer = EditBuffer::new("First");
+        buffer.anchor = Some(1);
+        buffer.cursor = 4;
+
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 0,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: Some(EditInfo {
+                buffer: &buffer,
+                block_index: 0,
+                selection: buffer.selection(),
+            }),
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
+        };
+        widget.render(area, &mut buf);
+
+        let backgrounds: Vec<Color> = (0..area.width)
+            .map(|x| buf.cell((x, 1)).unwrap().bg)
+            .collect();
+        let selected = backgrounds.iter().filter(|&&bg| bg == Color::Blue).count();
+        let cursor = backgrounds
+            .iter()
+            .position(|&bg| bg == Color::White)
+            .unwrap();
+        assert_eq!(selected, 3); // "irs"
+        assert_eq!(backgrounds[cursor - 1], Color::Blue);
+        assert_eq!(
+            read_line(&buf, 1, 40).trim_end().chars().nth(cursor),
+            Some('t')
+        );
+    }
+
     #[test]
     fn renders_nested_block_with_indentation() {
         let area = Rect::new(0, 0, 40, 10);
@@ -1048,6 +2349,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1072,7 +2377,14 @@ mod tests {
             vec![make_block("c1", code_text, 0), make_block("b2", "After", 1)],
         );
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         // heading + lang label + 2 code lines + "After" block = 5
         let code_line_count = lines
@@ -1097,7 +2409,14 @@ mod tests {
         let code_text = "```\nrust\nfn main() {}\nlet x = 1;```";
         let day = make_daily_note("Feb 21", 2026, 2, 21, vec![make_block("c1", code_text, 0)]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         let code_lines: Vec<_> = lines
             .iter()
@@ -1118,7 +2437,14 @@ mod tests {
         let code_text = "```\nrust\nfn main() {}```";
         let day = make_daily_note("Feb 21", 2026, 2, 21, vec![make_block("c1", code_text, 0)]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         let label = lines
             .iter()
@@ -1146,37 +2472,174 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
+        };
+        widget.render(area, &mut buf);
+
+        // Line 2 (after heading + label) should contain "1" (line number)
+        // Line 3 should contain "2"
+        let line2 = read_line(&buf, 2, area.width);
+        let line3 = read_line(&buf, 3, area.width);
+        assert!(
+            line2.contains('1'),
+            "Expected line number '1' in '{}'",
+            line2
+        );
+        assert!(line2.contains("fn"), "Expected code content in '{}'", line2);
+        assert!(
+            line3.contains('2'),
+            "Expected line number '2' in '{}'",
+            line3
+        );
+    }
+
+    #[test]
+    fn renders_bold_text_with_styling() {
+        let area = Rect::new(0, 0, 60, 10);
+        let mut buf = Buffer::empty(area);
+
+        let day = make_daily_note(
+            "Feb 21",
+            2026,
+            2,
+            21,
+            vec![make_block("b1", "**bold text**", 0)],
+        );
+
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 0,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
+        };
+        widget.render(area, &mut buf);
+
+        // The rendered line should contain "bold text" (without **)
+        let line1 = read_line(&buf, 1, area.width);
+        assert!(
+            line1.contains("bold text"),
+            "Expected 'bold text', got: '{}'",
+            line1
+        );
+        assert!(
+            !line1.contains("**"),
+            "Should not contain ** delimiters, got: '{}'",
+            line1
+        );
+    }
+
+    #[test]
+    fn hit_map_maps_rows_and_links_to_blocks() {
+        let area = Rect::new(0, 1, 30, 10);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 30, 12));
+
+        let day = make_daily_note(
+            "Feb 21",
+            2026,
+            2,
+            21,
+            vec![
+                make_block("b1", "plain", 0),
+                make_block("b2", "{{TODO}} see [[my page]] and more words here", 1),
+            ],
+        );
+
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 0,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
+        };
+        let mut hit_map = HitMap::default();
+        ratatui::widgets::StatefulWidget::render(widget, area, &mut buf, &mut hit_map);
+
+        // Row 0 of the area is the day heading
+        assert_eq!(hit_map.hit(5, 1), None);
+        assert_eq!(hit_map.hit(10, 2), Some((0, None)));
+        // "  • " then the checkbox
+        assert_eq!(
+            hit_map.hit(4, 3),
+            Some((1, Some(&LinkTarget::Checkbox(0..8))))
+        );
+        assert_eq!(
+            hit_map.hit(14, 3),
+            Some((1, Some(&LinkTarget::Page("my page".into()))))
+        );
+        assert_eq!(hit_map.hit(8, 3), Some((1, None)));
+        // The wrapped row still belongs to the block
+        assert_eq!(hit_map.hit(6, 4), Some((1, None)));
+        assert_eq!(hit_map.hit(40, 3), None);
+    }
+
+    #[test]
+    fn hit_map_checkbox_spans_index_the_block_string() {
+        let area = Rect::new(0, 1, 30, 10);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 30, 12));
+        let day = make_daily_note(
+            "Feb 21",
+            2026,
+            2,
+            21,
+            vec![make_block("b1", "> quote\n{{[[DONE]]}} done", 0)],
+        );
+
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 0,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
-        widget.render(area, &mut buf);
+        let mut hit_map = HitMap::default();
+        ratatui::widgets::StatefulWidget::render(widget, area, &mut buf, &mut hit_map);
 
-        // Line 2 (after heading + label) should contain "1" (line number)
-        // Line 3 should contain "2"
-        let line2 = read_line(&buf, 2, area.width);
-        let line3 = read_line(&buf, 3, area.width);
-        assert!(
-            line2.contains('1'),
-            "Expected line number '1' in '{}'",
-            line2
-        );
-        assert!(line2.contains("fn"), "Expected code content in '{}'", line2);
-        assert!(
-            line3.contains('2'),
-            "Expected line number '2' in '{}'",
-            line3
+        // "  │ " then the checkbox on the quote's second line
+        assert_eq!(
+            hit_map.hit(4, 3),
+            Some((0, Some(&LinkTarget::Checkbox(8..20))))
         );
     }
 
     #[test]
-    fn renders_bold_text_with_styling() {
-        let area = Rect::new(0, 0, 60, 10);
+    fn hit_map_embedded_checkbox_indexes_the_embedded_string() {
+        let area = Rect::new(0, 0, 40, 6);
         let mut buf = Buffer::empty(area);
-
+        let embeds = HashMap::from([(
+            EmbedTarget::Page("Notes".into()),
+            embed_of(vec![make_block("n1", "Line\n{{[[TODO]]}} task", 0)]),
+        )]);
         let day = make_daily_note(
             "Feb 21",
             2026,
             2,
             21,
-            vec![make_block("b1", "**bold text**", 0)],
+            vec![make_block("b1", "{{embed: [[Notes]]}}", 0)],
         );
 
         let widget = MainArea {
@@ -1188,23 +2651,55 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &embeds,
+            visual_range: None,
+            scroll: None,
         };
-        widget.render(area, &mut buf);
+        let mut hit_map = HitMap::default();
+        ratatui::widgets::StatefulWidget::render(widget, area, &mut buf, &mut hit_map);
 
-        // The rendered line should contain "bold text" (without **)
-        let line1 = read_line(&buf, 1, area.width);
-        assert!(
-            line1.contains("bold text"),
-            "Expected 'bold text', got: '{}'",
-            line1
-        );
-        assert!(
-            !line1.contains("**"),
-            "Should not contain ** delimiters, got: '{}'",
-            line1
+        // "    ┃ • Line " then the checkbox
+        assert_eq!(
+            hit_map.hit(13, 2),
+            Some((1, Some(&LinkTarget::Checkbox(5..17))))
         );
     }
 
+    #[test]
+    fn scroll_overrides_centering_on_selection() {
+        let area = Rect::new(0, 0, 30, 4);
+        let mut buf = Buffer::empty(area);
+        let blocks: Vec<Block> = (0..10)
+            .map(|i| make_block(&format!("b{}", i), &format!("Block {}", i), i))
+            .collect();
+        let day = make_daily_note("Feb 21", 2026, 2, 21, blocks);
+
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 0,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: Some(100),
+        };
+        let mut hit_map = HitMap::default();
+        ratatui::widgets::StatefulWidget::render(widget, area, &mut buf, &mut hit_map);
+
+        // Clamped so the last block is on the bottom row
+        assert_eq!(hit_map.total_rows, 11);
+        assert_eq!(hit_map.scroll_offset, 7);
+        assert_eq!(hit_map.max_scroll(), 7);
+        assert!(read_line(&buf, 3, area.width).contains("Block 9"));
+        assert_eq!(hit_map.hit(5, 0), Some((6, None)));
+    }
+
     #[test]
     fn renders_page_link_without_brackets() {
         let area = Rect::new(0, 0, 60, 10);
@@ -1227,6 +2722,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1246,7 +2745,14 @@ mod tests {
             vec![make_block("b1", "> quoted text", 0)],
         );
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         assert!(matches!(
             &lines[1],
@@ -1264,7 +2770,14 @@ mod tests {
             vec![make_block("b1", "> hello world", 0)],
         );
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         if let VisibleLine::Blockquote { text, .. } = &lines[1] {
             assert!(
@@ -1290,7 +2803,14 @@ mod tests {
         };
         let day = make_daily_note("Feb 21", 2026, 2, 21, vec![parent]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         assert!(matches!(
             &lines[2],
@@ -1320,6 +2840,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1358,6 +2882,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1393,7 +2921,14 @@ mod tests {
             ],
         );
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         assert!(matches!(&lines[1], VisibleLine::Block { text, .. } if text == "normal text"));
         assert!(matches!(&lines[2], VisibleLine::Blockquote { text, .. } if text == "quoted"));
@@ -1422,6 +2957,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1534,6 +3073,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1578,6 +3121,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1616,7 +3163,14 @@ mod tests {
         };
         let day = make_daily_note("Feb 21", 2026, 2, 21, vec![parent]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &HashMap::new());
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         assert!(matches!(
             &lines[1],
@@ -1655,6 +3209,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1693,6 +3251,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1726,6 +3288,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1763,6 +3329,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1806,6 +3376,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1838,6 +3412,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -1922,10 +3500,11 @@ mod tests {
     // --- Linked References rendering tests ---
 
     use crate::api::types::{LinkedRefBlock, LinkedRefGroup};
+    use crate::app::LinkedRefFilter;
 
     fn make_linked_refs() -> LinkedRefsState {
-        LinkedRefsState {
-            groups: vec![LinkedRefGroup {
+        LinkedRefsState::from_groups(
+            vec![LinkedRefGroup {
                 page_title: "Source Page".into(),
                 blocks: vec![LinkedRefBlock {
                     uid: "lr1".into(),
@@ -1933,9 +3512,8 @@ mod tests {
                     page_title: "Source Page".into(),
                 }],
             }],
-            collapsed: false,
-            loading: false,
-        }
+            LinkedRefFilter::default(),
+        )
     }
 
     #[test]
@@ -1944,7 +3522,14 @@ mod tests {
         let lr = make_linked_refs();
         let lr_map = HashMap::from([("Feb 25".to_string(), lr)]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &lr_map);
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &lr_map,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         // heading + block + separator + header + group header + block = 6
         assert_eq!(lines.len(), 6);
@@ -1970,8 +3555,8 @@ mod tests {
     #[test]
     fn build_visible_lines_collapsed_linked_refs() {
         let day = make_daily_note("Feb 25", 2026, 2, 25, vec![make_block("b1", "Block", 0)]);
-        let lr = LinkedRefsState {
-            groups: vec![LinkedRefGroup {
+        let mut lr = LinkedRefsState::from_groups(
+            vec![LinkedRefGroup {
                 page_title: "P".into(),
                 blocks: vec![LinkedRefBlock {
                     uid: "x".into(),
@@ -1979,12 +3564,19 @@ mod tests {
                     page_title: "P".into(),
                 }],
             }],
-            collapsed: true,
-            loading: false,
-        };
+            LinkedRefFilter::default(),
+        );
+        lr.collapsed = true;
         let lr_map = HashMap::from([("Feb 25".to_string(), lr)]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &lr_map);
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &lr_map,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         // heading + block + separator + header = 4 (no group/block lines)
         assert_eq!(lines.len(), 4);
@@ -2000,14 +3592,17 @@ mod tests {
     #[test]
     fn build_visible_lines_empty_linked_refs_not_shown() {
         let day = make_daily_note("Feb 25", 2026, 2, 25, vec![make_block("b1", "Block", 0)]);
-        let lr = LinkedRefsState {
-            groups: vec![],
-            collapsed: false,
-            loading: false,
-        };
+        let lr = LinkedRefsState::from_groups(vec![], LinkedRefFilter::default());
         let lr_map = HashMap::from([("Feb 25".to_string(), lr)]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &lr_map);
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &lr_map,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         // heading + block = 2 (no linked refs section)
         assert_eq!(lines.len(), 2);
@@ -2031,6 +3626,10 @@ mod tests {
             edit_info: None,
             block_ref_cache: &HashMap::new(),
             linked_refs: &lr_map,
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
         };
         widget.render(area, &mut buf);
 
@@ -2055,7 +3654,14 @@ mod tests {
         let lr = make_linked_refs();
         let lr_map = HashMap::from([("Feb 25".to_string(), lr)]);
         let mut hl = CodeHighlighter::new();
-        let lines = build_visible_lines(&[day], false, &mut hl, &lr_map);
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &lr_map,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
 
         // Lines: [0]=heading, [1]=block(0), [2]=block(1), [3]=separator, [4]=header(2), [5]=group(3), [6]=block(4)
         if let VisibleLine::LinkedRefsHeader { block_index, .. } = &lines[4] {
@@ -2072,4 +3678,449 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn filtered_out_linked_refs_keep_header() {
+        let day = make_daily_note("Feb 25", 2026, 2, 25, vec![make_block("b1", "Block", 0)]);
+        let mut lr = make_linked_refs();
+        lr.filter.exclude.push("Target".into());
+        lr.apply_filter();
+        let lr_map = HashMap::from([("Feb 25".to_string(), lr)]);
+        let mut hl = CodeHighlighter::new();
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &lr_map,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+
+        // heading + block + separator + header = 4
+        assert_eq!(lines.len(), 4);
+        assert!(matches!(
+            &lines[3],
+            VisibleLine::LinkedRefsHeader { count: 0, filter_label: Some(label), .. }
+                if label == "0 of 1"
+        ));
+    }
+
+    #[test]
+    fn linked_refs_header_shows_sort_mode() {
+        let mut lr = make_linked_refs();
+        assert_eq!(linked_refs_filter_label(&lr), None);
+        lr.filter.sort = LinkedRefSort::NewestFirst;
+        assert_eq!(
+            linked_refs_filter_label(&lr),
+            Some("1, newest first".to_string())
+        );
+    }
+
+    fn query_results(text: &str, collapsed: bool) -> QueryResultsState {
+        QueryResultsState {
+            text: text.into(),
+            results: vec![crate::api::types::SearchHit {
+                uid: "r1".into(),
+                string: "Ship it".into(),
+                page_title: "Project X".into(),
+                is_page: false,
+            }],
+            collapsed,
+            loading: false,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn query_results_render_under_block_with_page_link() {
+        let area = Rect::new(0, 0, 60, 6);
+        let mut buf = Buffer::empty(area);
+        let text = "{{query: [[TODO]]}}";
+        let day = make_daily_note("Feb 25", 2026, 2, 25, vec![make_block("q", text, 0)]);
+        let qr_map = HashMap::from([("q".to_string(), query_results(text, false))]);
+
+        let mut hit_map = HitMap::default();
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 0,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &qr_map,
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
+        };
+        ratatui::widgets::StatefulWidget::render(widget, area, &mut buf, &mut hit_map);
+
+        assert!(read_line(&buf, 2, area.width).contains("▾ 1 result"));
+        let result = read_line(&buf, 3, area.width);
+        assert!(result.contains("◦ Ship it — Project X"), "{}", result);
+        // Result rows belong to the query block; the page label opens the result
+        assert_eq!(hit_map.rows[3].block_index, Some(0));
+        assert_eq!(
+            hit_map.rows[3].targets,
+            vec![(18..27, LinkTarget::Block("r1".into()))]
+        );
+    }
+
+    #[test]
+    fn query_results_collapse_and_skip_edited_blocks() {
+        let text = "{{query: [[TODO]]}}";
+        let day = make_daily_note("Feb 25", 2026, 2, 25, vec![make_block("q", text, 0)]);
+        let mut hl = CodeHighlighter::new();
+
+        let qr_map = HashMap::from([("q".to_string(), query_results(text, true))]);
+        let lines = build_visible_lines(
+            std::slice::from_ref(&day),
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &qr_map,
+            &HashMap::new(),
+        );
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(
+            &lines[2],
+            VisibleLine::QueryHeader {
+                collapsed: true,
+                block_index: 0,
+                ..
+            }
+        ));
+
+        // Results fetched for other text are stale
+        let qr_map = HashMap::from([("q".to_string(), query_results("old", false))]);
+        let lines = build_visible_lines(
+            &[day],
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &qr_map,
+            &HashMap::new(),
+        );
+        assert_eq!(lines.len(), 2);
+    }
+
+    fn embed_of(blocks: Vec<Block>) -> EmbedState {
+        EmbedState {
+            blocks,
+            loading: false,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn block_embed_lists_children_under_the_block() {
+        let mut root = make_block("e", "Embedded", 0);
+        let mut child = make_block("c1", "Child", 0);
+        child.children = vec![make_block("g1", "Grandchild", 0)];
+        root.children = vec![child, make_block("c2", "Sibling", 1)];
+        let embeds = HashMap::from([(EmbedTarget::Block("e".into()), embed_of(vec![root]))]);
+        let day = make_daily_note(
+            "Feb 25",
+            2026,
+            2,
+            25,
+            vec![make_block("host", "{{embed: ((e))}}", 0)],
+        );
+        let mut hl = CodeHighlighter::new();
+
+        let lines = build_visible_lines(
+            std::slice::from_ref(&day),
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &embeds,
+        );
+        let embedded: Vec<(usize, &str, usize)> = lines
+            .iter()
+            .filter_map(|l| match l {
+                VisibleLine::Embedded {
+                    level,
+                    text,
+                    block_index,
+                    ..
+                } => Some((*level, text.as_str(), *block_index)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            embedded,
+            vec![(0, "Child", 1), (1, "Grandchild", 2), (0, "Sibling", 3)]
+        );
+    }
+
+    #[test]
+    fn edits_embedded_line_in_place() {
+        let mut root = make_block("e", "Embedded", 0);
+        root.children = vec![make_block("c1", "Child", 0)];
+        let embeds = HashMap::from([(EmbedTarget::Block("e".into()), embed_of(vec![root]))]);
+        let day = make_daily_note(
+            "Feb 25",
+            2026,
+            2,
+            25,
+            vec![make_block("host", "{{embed: ((e))}}", 0)],
+        );
+        let buffer = EditBuffer::new("Changed");
+        let area = Rect::new(0, 0, 40, 4);
+        let mut buf = Buffer::empty(area);
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 1,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: Some(EditInfo {
+                buffer: &buffer,
+                block_index: 1,
+                selection: None,
+            }),
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &embeds,
+            visual_range: None,
+            scroll: None,
+        };
+        widget.render(area, &mut buf);
+
+        let line = read_line(&buf, 2, area.width);
+        assert!(line.contains("┃ • Changed"), "{}", line);
+    }
+
+    #[test]
+    fn page_embed_folds_past_max_depth() {
+        let mut level2 = make_block("l2", "Level 2", 0);
+        level2.children = vec![make_block("l3", "Level 3", 0)];
+        let mut level1 = make_block("l1", "Level 1", 0);
+        level1.children = vec![level2];
+        let mut level0 = make_block("l0", "Level 0", 0);
+        level0.children = vec![level1];
+        let embeds = HashMap::from([(EmbedTarget::Page("Notes".into()), embed_of(vec![level0]))]);
+        let day = make_daily_note(
+            "Feb 25",
+            2026,
+            2,
+            25,
+            vec![make_block("host", "{{[[embed]]: [[Notes]]}}", 0)],
+        );
+        let area = Rect::new(0, 0, 40, 6);
+        let mut buf = Buffer::empty(area);
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 0,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &embeds,
+            visual_range: None,
+            scroll: None,
+        };
+        widget.render(area, &mut buf);
+
+        assert!(read_line(&buf, 2, area.width).contains("┃ • Level 0"));
+        assert!(read_line(&buf, 3, area.width).contains("┃   • Level 1"));
+        let folded = read_line(&buf, 4, area.width);
+        assert!(folded.contains("┃     ▸ Level 2 [1]"), "{}", folded);
+        assert!(!read_line(&buf, 5, area.width).contains("Level 3"));
+    }
+
+    /// `{{table}}` with rows `A | B` and `C | D`; `D` shares `C`'s row with `E`.
+    fn table_day() -> DailyNote {
+        let chain = |uids: &[(&str, &str)]| {
+            let mut block: Option<Block> = None;
+            for (uid, text) in uids.iter().rev() {
+                let mut b = make_block(uid, text, 0);
+                b.children = block.take().into_iter().collect();
+                block = Some(b);
+            }
+            block.unwrap()
+        };
+        let mut table = make_block("t", "{{[[table]]}}", 0);
+        let mut c = chain(&[("c", "C"), ("d", "D")]);
+        c.children.push(make_block("e", "E", 1));
+        table.children = vec![chain(&[("a", "A"), ("b", "Bee")]), c];
+        make_daily_note("Feb 25", 2026, 2, 25, vec![table])
+    }
+
+    #[test]
+    fn table_rows_follow_outline_order_with_shared_cells() {
+        let day = table_day();
+        let mut hl = CodeHighlighter::new();
+        let lines = build_visible_lines(
+            std::slice::from_ref(&day),
+            false,
+            &mut hl,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        assert_eq!(lines.len(), 3);
+        let VisibleLine::Table {
+            rows, block_index, ..
+        } = &lines[2]
+        else {
+            panic!("expected a table");
+        };
+        assert_eq!(*block_index, 1);
+        let cells: Vec<Vec<Option<(usize, &str)>>> = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|c| c.as_ref().map(|c| (c.block_index, c.text.as_str())))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                vec![Some((1, "A")), Some((2, "Bee"))],
+                vec![Some((3, "C")), Some((4, "D"))],
+                vec![None, Some((5, "E"))],
+            ]
+        );
+    }
+
+    #[test]
+    fn fit_columns_shrinks_only_wide_columns() {
+        assert_eq!(fit_columns(&[3, 5], 20), vec![3, 5]);
+        assert_eq!(fit_columns(&[3, 30, 40], 23), vec![3, 10, 10]);
+        assert_eq!(fit_columns(&[10, 10], 5), vec![3, 2]);
+    }
+
+    #[test]
+    fn table_renders_bordered_grid_with_cell_hits() {
+        let area = Rect::new(0, 0, 40, 9);
+        let mut buf = Buffer::empty(area);
+        let mut hit_map = HitMap::default();
+        let widget = MainArea {
+            days: &[table_day()],
+            selected_block: 4,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
+        };
+        ratatui::widgets::StatefulWidget::render(widget, area, &mut buf, &mut hit_map);
+
+        let grid: Vec<String> = (2..9)
+            .map(|y| read_line(&buf, y, area.width).trim_end().to_string())
+            .collect();
+        assert_eq!(
+            grid,
+            vec![
+                "    ┌───┬─────┐",
+                "    │ A │ Bee │",
+                "    ├───┼─────┤",
+                "    │ C │ D   │",
+                "    │   ├─────┤",
+                "    │   │ E   │",
+                "    └───┴─────┘",
+            ]
+        );
+        // Clicking a cell selects its block
+        assert_eq!(hit_map.hit(6, 5).map(|(block, _)| block), Some(3));
+        assert_eq!(hit_map.hit(11, 5).map(|(block, _)| block), Some(4));
+        assert_eq!(hit_map.hit(11, 7).map(|(block, _)| block), Some(5));
+    }
+
+    #[test]
+    fn table_cell_wraps_and_shows_edit_buffer() {
+        let area = Rect::new(0, 0, 24, 8);
+        let mut buf = Buffer::empty(area);
+        let buffer = EditBuffer::new("one two three");
+        let widget = MainArea {
+            days: &[table_day()],
+            selected_block: 2,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: Some(EditInfo {
+                buffer: &buffer,
+                block_index: 2,
+                selection: None,
+            }),
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
+        };
+        widget.render(area, &mut buf);
+
+        assert_eq!(
+            read_line(&buf, 3, area.width).trim_end(),
+            "    │ A │ one two      │"
+        );
+        assert_eq!(
+            read_line(&buf, 4, area.width).trim_end(),
+            "    │   │ three        │"
+        );
+    }
+
+    #[test]
+    fn kanban_renders_columns_side_by_side() {
+        let mut todo = make_block("todo", "To Do", 0);
+        let mut card = make_block("c1", "Write docs", 0);
+        card.children = vec![make_block("n1", "Draft", 0)];
+        todo.children = vec![card, make_block("c2", "Ship", 1)];
+        let mut board = make_block("board", "{{kanban}}", 0);
+        board.children = vec![todo, make_block("done", "Done", 1)];
+        let day = make_daily_note("Feb 25", 2026, 2, 25, vec![board]);
+
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = Buffer::empty(area);
+        let mut hit_map = HitMap::default();
+        let widget = MainArea {
+            days: &[day],
+            selected_block: 4,
+            cursor_col: 0,
+            loading: false,
+            loading_more: false,
+            edit_info: None,
+            block_ref_cache: &HashMap::new(),
+            linked_refs: &HashMap::new(),
+            query_results: &HashMap::new(),
+            embeds: &HashMap::new(),
+            visual_range: None,
+            scroll: None,
+        };
+        ratatui::widgets::StatefulWidget::render(widget, area, &mut buf, &mut hit_map);
+
+        let board: Vec<String> = (2..9)
+            .map(|y| read_line(&buf, y, area.width).trim_end().to_string())
+            .collect();
+        assert_eq!(
+            board,
+            vec![
+                "    ┌───────────────┐ ┌───────────────┐",
+                "    │ To Do         │ │ Done          │",
+                "    ├───────────────┤ ├───────────────┤",
+                "    │ ▪ Write docs  │ │               │",
+                "    │   • Draft     │ │               │",
+                "    │ ▪ Ship        │ │               │",
+                "    └───────────────┘ └───────────────┘",
+            ]
+        );
+        // Blocks: board 0, To Do 1, Write docs 2, Draft 3, Ship 4, Done 5
+        assert_eq!(hit_map.hit(8, 7).map(|(block, _)| block), Some(4));
+        assert_eq!(hit_map.hit(28, 3).map(|(block, _)| block), Some(5));
+    }
 }
@@ -2,24 +2,33 @@ pub mod header;
 pub mod main_area;
 pub mod status_bar;
 
+use chrono::{Datelike, Local, NaiveDate};
 use ratatui::layout::{Constraint, Layout, Rect};
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block as WidgetBlock, BorderType, Borders, Clear};
 use ratatui::Frame;
 
+use crate::app::calendar::month_weeks;
+use crate::app::help::{help_lines, HelpLine};
+use crate::app::palette::CommandPaletteState;
 use crate::app::slash::SlashMenuState;
 use crate::app::{
-    AppState, AutocompleteState, InputMode, LinkPickerState, QuickSwitcherState, SearchState,
-    ViewMode,
+    AgendaFilter, AgendaGroup, AgendaGrouping, AgendaState, AppState, AttributeTableState,
+    AutocompleteKind, AutocompleteState, CalendarState, HelpState, InputMode, LinkPickerState,
+    LinkedRefFilter, LinkedRefFilterState, QuickSwitcherState, RenamePageState, SearchState,
+    ViewMode, WhichKeyState,
 };
 use crate::error::ErrorPopup;
+use crate::fuzzy;
+use crate::keys::KeySection;
 
 use header::Header;
-use main_area::{EditInfo, MainArea};
+use main_area::{EditInfo, HitMap, MainArea};
 use status_bar::StatusBar;
 
-pub fn render(frame: &mut Frame, state: &AppState) {
+/// Draw the whole screen, recording in `hit_map` where blocks and links landed.
+pub fn render(frame: &mut Frame, state: &AppState, hit_map: &mut HitMap) {
     let chunks = Layout::vertical([
         Constraint::Length(1),
         Constraint::Min(1),
@@ -38,9 +47,16 @@ pub fn render(frame: &mut Frame, state: &AppState) {
     frame.render_widget(header, chunks[0]);
 
     let edit_info = match &state.input_mode {
-        InputMode::Insert { buffer, .. } => Some(EditInfo {
+        InputMode::Insert {
+            buffer, vim_normal, ..
+        } => Some(EditInfo {
             buffer,
             block_index: state.selected_block,
+            selection: if vim_normal.is_some() {
+                buffer.selection_inclusive()
+            } else {
+                buffer.selection()
+            },
         }),
         InputMode::Normal => None,
     };
@@ -54,8 +70,12 @@ pub fn render(frame: &mut Frame, state: &AppState) {
         edit_info,
         block_ref_cache: &state.block_ref_cache,
         linked_refs: &state.linked_refs,
+        query_results: &state.query_results,
+        embeds: &state.embeds,
+        visual_range: state.visual_range(),
+        scroll: state.scroll,
     };
-    frame.render_widget(main, chunks[1]);
+    frame.render_stateful_widget(main, chunks[1], hit_map);
 
     if let Some(ac) = &state.autocomplete {
         render_autocomplete_popup(frame, ac, chunks[1]);
@@ -69,6 +89,28 @@ pub fn render(frame: &mut Frame, state: &AppState) {
         render_link_picker_popup(frame, lp, chunks[1]);
     }
 
+    if let Some(table) = &state.attribute_table {
+        render_attribute_table_popup(frame, table, chunks[1]);
+    }
+
+    if let Some(agenda) = &state.agenda {
+        render_agenda_popup(frame, agenda, Local::now().date_naive(), chunks[1]);
+    }
+
+    if let Some(calendar) = &state.calendar {
+        render_calendar_popup(frame, calendar, Local::now().date_naive(), chunks[1]);
+    }
+
+    if let Some(lf) = &state.linked_ref_filter {
+        if let Some(lr) = state.linked_refs.get(&lf.page_title) {
+            render_linked_ref_filter_popup(frame, lf, &lr.filter, chunks[1]);
+        }
+    }
+
+    if let Some(rename) = &state.rename_page {
+        render_rename_page_popup(frame, rename, chunks[1]);
+    }
+
     if let Some(qs) = &state.quick_switcher {
         render_quick_switcher_popup(frame, qs, chunks[1]);
     }
@@ -77,8 +119,16 @@ pub fn render(frame: &mut Frame, state: &AppState) {
         render_search_popup(frame, search, chunks[1]);
     }
 
-    if state.show_help {
-        render_help_popup(frame, &state.hints, chunks[1]);
+    if let Some(palette) = &state.command_palette {
+        render_command_palette_popup(frame, palette, chunks[1]);
+    }
+
+    if let Some(which_key) = &state.which_key {
+        render_which_key_popup(frame, which_key, chunks[1]);
+    }
+
+    if let Some(help) = &state.help {
+        render_help_popup(frame, help, &state.help_sections, chunks[1]);
     }
 
     if let Some(err) = &state.error_popup {
@@ -90,8 +140,38 @@ pub fn render(frame: &mut Frame, state: &AppState) {
         hints: &state.hints,
         message: state.status_message.as_deref(),
         insert_mode,
+        editor_normal: matches!(
+            state.input_mode,
+            InputMode::Insert {
+                vim_normal: Some(_),
+                ..
+            }
+        ),
+        visual_count: state.visual_range().map(|(start, end)| end - start + 1),
     };
     frame.render_widget(status, chunks[2]);
+
+    if state.light_theme {
+        let buffer = frame.buffer_mut();
+        for cell in &mut buffer.content {
+            cell.fg = light_color(cell.fg);
+            cell.bg = light_color(cell.bg);
+        }
+    }
+}
+
+/// Colors are picked for a dark terminal; swap them so text stays readable on
+/// a light background.
+fn light_color(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+        Color::Gray => Color::DarkGray,
+        Color::DarkGray => Color::Gray,
+        Color::Yellow => Color::Rgb(150, 110, 0),
+        Color::Cyan => Color::Blue,
+        other => other,
+    }
 }
 
 fn render_autocomplete_popup(frame: &mut Frame, ac: &AutocompleteState, area: Rect) {
@@ -109,10 +189,17 @@ fn render_autocomplete_popup(frame: &mut Frame, ac: &AutocompleteState, area: Re
 
     frame.render_widget(Clear, popup_area);
 
-    let title = if ac.query.is_empty() {
-        " Block ref ".to_string()
+    let label = match ac.kind {
+        AutocompleteKind::BlockRef => "Block ref",
+        AutocompleteKind::PageLink => "Page link",
+        AutocompleteKind::Tag => "Tag",
+    };
+    let title = if ac.fetching {
+        format!(" {} (loading pages...) ", label)
+    } else if ac.query.is_empty() {
+        format!(" {} ", label)
     } else {
-        format!(" Search: {} ", ac.query)
+        format!(" {}: {} ", label, ac.query)
     };
 
     let block = WidgetBlock::default()
@@ -132,23 +219,51 @@ fn render_autocomplete_popup(frame: &mut Frame, ac: &AutocompleteState, area: Re
         return;
     }
 
-    for (i, (_, text)) in ac.results.iter().take(max_items).enumerate() {
+    let scroll_offset = if ac.selected >= max_items {
+        ac.selected - max_items + 1
+    } else {
+        0
+    };
+
+    for (i, (uid, text)) in ac
+        .results
+        .iter()
+        .skip(scroll_offset)
+        .take(max_items)
+        .enumerate()
+    {
         if i as u16 >= inner.height {
             break;
         }
-        let is_selected = i == ac.selected;
+        let is_selected = (i + scroll_offset) == ac.selected;
         let style = if is_selected {
             Style::default().fg(Color::White).bg(Color::DarkGray)
         } else {
             Style::default().fg(Color::Gray)
         };
 
-        let max_text_width = inner.width as usize;
-        let display: String = text.chars().take(max_text_width).collect();
-        let padding = max_text_width.saturating_sub(display.chars().count());
-        let padded = format!("{}{}", display, " ".repeat(padding));
-
-        let line = Line::from(vec![Span::styled(padded, style)]);
+        // An empty uid on a page result is the "create new page" entry
+        let line = if uid.is_empty() && ac.kind != AutocompleteKind::BlockRef {
+            let label = format!("+ New page: {}", text);
+            Line::from(highlighted_spans(
+                &label,
+                &[],
+                inner.width as usize,
+                style.fg(Color::Green),
+            ))
+        } else {
+            let positions = if ac.kind == AutocompleteKind::BlockRef {
+                fuzzy::substring_positions(&ac.query, text)
+            } else {
+                fuzzy::match_positions(&ac.query, text)
+            };
+            Line::from(highlighted_spans(
+                text,
+                &positions,
+                inner.width as usize,
+                style,
+            ))
+        };
         let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
         frame.render_widget(line, line_area);
     }
@@ -207,36 +322,470 @@ fn render_link_picker_popup(frame: &mut Frame, lp: &LinkPickerState, area: Rect)
     }
 }
 
-fn render_help_popup(frame: &mut Frame, hints: &[(String, &str)], area: Rect) {
-    let line_count = hints.len();
-    let popup_height = (line_count + 3).min(area.height as usize) as u16; // +2 borders +1 footer
+fn render_attribute_table_popup(frame: &mut Frame, table: &AttributeTableState, area: Rect) {
+    let max_items = 12.min(table.rows.len());
+    let popup_height = (max_items + 2) as u16; // +2 for borders
     let popup_width = (area.width * 60 / 100).max(30).min(area.width);
     let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
     let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
 
+    let popup_area = Rect::new(x, y, popup_width, popup_height.min(area.height));
+    frame.render_widget(Clear, popup_area);
+
+    let block = WidgetBlock::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" Attributes: {} ", table.page_title));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let max_text_width = inner.width as usize;
+    // Names get their widest width, up to half the popup
+    let name_width = table
+        .rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(max_text_width / 2);
+
+    let scroll_offset = if table.selected >= max_items {
+        table.selected - max_items + 1
+    } else {
+        0
+    };
+
+    for (i, (name, value)) in table
+        .rows
+        .iter()
+        .skip(scroll_offset)
+        .take(max_items)
+        .enumerate()
+    {
+        if i as u16 >= inner.height {
+            break;
+        }
+        let is_selected = (i + scroll_offset) == table.selected;
+        let (name_style, value_style) = if is_selected {
+            let style = Style::default().fg(Color::White).bg(Color::DarkGray);
+            (style.add_modifier(Modifier::BOLD), style)
+        } else {
+            (
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+                Style::default(),
+            )
+        };
+
+        let name: String = name.chars().take(name_width).collect();
+        let name_cell = format!("{:<width$} │ ", name, width = name_width);
+        let value_width = max_text_width.saturating_sub(name_cell.chars().count());
+        let value: String = value.chars().take(value_width).collect();
+        let padding = value_width.saturating_sub(value.chars().count());
+
+        let line = Line::from(vec![
+            Span::styled(name_cell, name_style),
+            Span::styled(format!("{}{}", value, " ".repeat(padding)), value_style),
+        ]);
+        let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
+        frame.render_widget(line, line_area);
+    }
+}
+
+fn render_agenda_popup(frame: &mut Frame, agenda: &AgendaState, today: NaiveDate, area: Rect) {
+    let popup_height = area.height.saturating_sub(2).max(6).min(area.height);
+    let popup_width = (area.width * 80 / 100).max(40).min(area.width);
+    let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
     let popup_area = Rect::new(x, y, popup_width, popup_height);
     frame.render_widget(Clear, popup_area);
 
+    let grouping = match agenda.grouping {
+        AgendaGrouping::DueDate => "due date",
+        AgendaGrouping::Page => "page",
+    };
+    let block = WidgetBlock::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(
+            " Agenda: {} · by {} ",
+            agenda.filter.label(),
+            grouping
+        ));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let width = inner.width as usize;
+    let dim = Style::default().fg(Color::DarkGray);
+    let groups = agenda.groups(today);
+
+    // Group headings and items as one list, noting the selected line
+    let mut lines: Vec<Line> = Vec::new();
+    let mut selected_line = 0;
+    let mut index = 0;
+    for (group, items) in &groups {
+        let (heading, color) = match group {
+            AgendaGroup::Due(Some(date)) if *date < today => (
+                date.format("%a %b %-d, %Y · overdue").to_string(),
+                Color::Red,
+            ),
+            AgendaGroup::Due(Some(date)) if *date == today => (
+                date.format("%a %b %-d, %Y · today").to_string(),
+                Color::Green,
+            ),
+            AgendaGroup::Due(Some(date)) => {
+                (date.format("%a %b %-d, %Y").to_string(), Color::Yellow)
+            }
+            AgendaGroup::Due(None) => ("No date".to_string(), Color::Yellow),
+            AgendaGroup::Page(title) => (title.clone(), Color::Cyan),
+        };
+        lines.push(Line::styled(
+            heading,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+        for item in items {
+            let is_selected = index == agenda.selected;
+            let base = if is_selected {
+                Style::default().fg(Color::White).bg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            let mut spans = vec![Span::styled("  ", base)];
+            spans.extend(crate::markdown::render_spans(&item.text, base));
+            // The other grouping's key, so both are visible
+            let context = match (agenda.grouping, item.due) {
+                (AgendaGrouping::DueDate, _) => item.page_title.clone(),
+                (AgendaGrouping::Page, Some(due)) => due.format("%b %-d, %Y").to_string(),
+                (AgendaGrouping::Page, None) => String::new(),
+            };
+            if !context.is_empty() {
+                spans.push(Span::styled(
+                    format!("  · {}", context),
+                    base.fg(Color::DarkGray),
+                ));
+            }
+            if is_selected {
+                selected_line = lines.len();
+                let used: usize = spans.iter().map(|s| s.content.chars().count()).sum();
+                spans.push(Span::styled(" ".repeat(width.saturating_sub(used)), base));
+            }
+            lines.push(Line::from(spans));
+            index += 1;
+        }
+    }
+
+    let list_height = inner.height.saturating_sub(1) as usize;
+    let status = if agenda.loading {
+        Some("Loading tasks...".to_string())
+    } else if let Some(error) = &agenda.error {
+        Some(format!("Error: {}", error))
+    } else if lines.is_empty() {
+        Some(match agenda.filter {
+            AgendaFilter::All => "No open tasks".to_string(),
+            filter => format!("No tasks: {}", filter.label()),
+        })
+    } else {
+        None
+    };
+    if let Some(status) = status {
+        let line = Line::styled(status, dim);
+        frame.render_widget(line, Rect::new(inner.x, inner.y, inner.width, 1));
+    }
+
+    let scroll_offset = (selected_line + 1).saturating_sub(list_height);
+    for (i, line) in lines
+        .into_iter()
+        .skip(scroll_offset)
+        .take(list_height)
+        .enumerate()
+    {
+        let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
+        frame.render_widget(line, line_area);
+    }
+
+    let hint_y = inner.y + inner.height.saturating_sub(1);
+    let hints = Line::styled(
+        "Enter open · x toggle · < > reschedule · t today · f filter · g group · r reload · Esc close",
+        dim,
+    );
+    frame.render_widget(hints, Rect::new(inner.x, hint_y, inner.width, 1));
+}
+
+fn render_calendar_popup(
+    frame: &mut Frame,
+    calendar: &CalendarState,
+    today: NaiveDate,
+    area: Rect,
+) {
+    let weeks = month_weeks(calendar.month());
+    // Month line, weekday header, weeks, key hints
+    let popup_height = (weeks.len() + 5) as u16; // +2 borders
+    let popup_width = 34.min(area.width);
+    let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height.min(area.height));
+    frame.render_widget(Clear, popup_area);
+
+    let title = if calendar.insert {
+        " Insert date "
+    } else {
+        " Go to date "
+    };
     let block = WidgetBlock::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(Color::Cyan))
-        .title(" Help ");
+        .title(title);
 
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
 
-    for (i, (key_str, action_name)) in hints.iter().enumerate() {
+    let dim = Style::default().fg(Color::DarkGray);
+    let grid_width = 28;
+    let pad = " ".repeat((inner.width as usize).saturating_sub(grid_width) / 2);
+
+    let mut month = calendar.month().format("%B %Y").to_string();
+    if calendar.loading.is_some() {
+        month.push_str(" …");
+    }
+    let mut lines = vec![
+        Line::styled(
+            format!("{:^width$}", month, width = inner.width as usize),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Line::styled(format!("{} Mo  Tu  We  Th  Fr  Sa  Su", pad), dim),
+    ];
+    for week in &weeks {
+        let mut spans = vec![Span::raw(pad.clone())];
+        for day in week {
+            let Some(day) = day else {
+                spans.push(Span::raw("    "));
+                continue;
+            };
+            let has_content = calendar.with_content.contains(day);
+            let mut style = if has_content {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default()
+            };
+            if *day == today {
+                style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+            }
+            if *day == calendar.selected {
+                style = style.fg(Color::Black).bg(Color::Cyan);
+            }
+            let mark = if has_content { "•" } else { " " };
+            spans.push(Span::styled(format!("{:>3}{}", day.day(), mark), style));
+        }
+        lines.push(Line::from(spans));
+    }
+    for (i, line) in lines.into_iter().enumerate() {
         if i as u16 >= inner.height.saturating_sub(1) {
             break;
         }
-        let key_span = Span::styled(
-            format!("{:>12}", key_str),
-            Style::default().fg(Color::Yellow),
-        );
-        let sep = Span::styled("  ", Style::default());
-        let action_span = Span::styled(*action_name, Style::default().fg(Color::White));
-        let line = Line::from(vec![key_span, sep, action_span]);
+        let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
+        frame.render_widget(line, line_area);
+    }
+
+    let hint_y = inner.y + inner.height.saturating_sub(1);
+    let hints = if calendar.insert {
+        "Enter insert · t today · < > month"
+    } else {
+        "Enter go · t today · < > month"
+    };
+    frame.render_widget(
+        Line::styled(hints, dim),
+        Rect::new(inner.x, hint_y, inner.width, 1),
+    );
+}
+
+fn render_linked_ref_filter_popup(
+    frame: &mut Frame,
+    lf: &LinkedRefFilterState,
+    filter: &LinkedRefFilter,
+    area: Rect,
+) {
+    let max_items = 12.min(lf.pages.len()).max(1);
+    let popup_height = (max_items + 4) as u16; // +2 borders, +2 sort line and key hints
+    let popup_width = (area.width * 50 / 100).max(30).min(area.width);
+    let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height.min(area.height));
+    frame.render_widget(Clear, popup_area);
+
+    let block = WidgetBlock::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" Filter: {} ", lf.page_title));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let max_text_width = inner.width as usize;
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let sort_line = Line::from(vec![
+        Span::styled("Sort: ", dim),
+        Span::styled(filter.sort.label(), Style::default().fg(Color::Yellow)),
+    ]);
+    frame.render_widget(sort_line, Rect::new(inner.x, inner.y, inner.width, 1));
+
+    let list_y = inner.y + 1;
+    let list_height = inner.height.saturating_sub(2) as usize;
+
+    if lf.pages.is_empty() {
+        let line = Line::styled("No co-referenced pages", dim);
+        frame.render_widget(line, Rect::new(inner.x, list_y, inner.width, 1));
+    }
+
+    let scroll_offset = if lf.selected >= list_height {
+        lf.selected - list_height + 1
+    } else {
+        0
+    };
+
+    for (i, (page, count)) in lf
+        .pages
+        .iter()
+        .skip(scroll_offset)
+        .take(list_height)
+        .enumerate()
+    {
+        let (marker, color) = if filter.include.contains(page) {
+            ("+", Color::Green)
+        } else if filter.exclude.contains(page) {
+            ("-", Color::Red)
+        } else {
+            (" ", Color::Cyan)
+        };
+        let is_selected = (i + scroll_offset) == lf.selected;
+        let style = if is_selected {
+            Style::default().fg(Color::White).bg(Color::DarkGray)
+        } else {
+            Style::default().fg(color)
+        };
+
+        let text = format!("{} {} ({})", marker, page, count);
+        let display: String = text.chars().take(max_text_width).collect();
+        let padding = max_text_width.saturating_sub(display.chars().count());
+        let padded = format!("{}{}", display, " ".repeat(padding));
+
+        let line = Line::from(vec![Span::styled(padded, style)]);
+        let line_area = Rect::new(inner.x, list_y + i as u16, inner.width, 1);
+        frame.render_widget(line, line_area);
+    }
+
+    let hint_y = inner.y + inner.height.saturating_sub(1);
+    let hints = Line::styled(
+        "Enter include · x exclude · s sort · c clear · Esc close",
+        dim,
+    );
+    frame.render_widget(hints, Rect::new(inner.x, hint_y, inner.width, 1));
+}
+
+/// Spans for `text` cut or padded to `width` chars, with the chars at
+/// `positions` (fuzzy match indices) highlighted on top of `style`.
+fn highlighted_spans(
+    text: &str,
+    positions: &[usize],
+    width: usize,
+    style: Style,
+) -> Vec<Span<'static>> {
+    let match_style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_is_match = false;
+    let mut count = 0;
+    for (i, c) in text.chars().take(width).enumerate() {
+        let is_match = positions.contains(&i);
+        if is_match != run_is_match && !run.is_empty() {
+            let run_style = if run_is_match { match_style } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run_is_match = is_match;
+        run.push(c);
+        count += 1;
+    }
+    if !run.is_empty() {
+        let run_style = if run_is_match { match_style } else { style };
+        spans.push(Span::styled(run, run_style));
+    }
+    if count < width {
+        spans.push(Span::styled(" ".repeat(width - count), style));
+    }
+    spans
+}
+
+fn render_help_popup(frame: &mut Frame, help: &HelpState, sections: &[KeySection], area: Rect) {
+    let lines = help_lines(sections, &help.query);
+    let popup_height = (lines.len().max(1) + 3).min(area.height as usize * 80 / 100) as u16; // +2 borders +1 footer
+    let popup_width = (area.width * 60 / 100).max(40).min(area.width);
+    let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+    frame.render_widget(Clear, popup_area);
+
+    let title = if help.query.is_empty() && !help.searching {
+        " Help ".to_string()
+    } else {
+        format!(" Help: /{} ", help.query)
+    };
+    let block = WidgetBlock::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(title);
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let visible = inner.height.saturating_sub(1) as usize;
+    let scroll = help.scroll.min(lines.len().saturating_sub(visible));
+    let key_width = lines
+        .iter()
+        .map(|line| match line {
+            HelpLine::Binding { keys, .. } => keys.chars().count(),
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0)
+        .min(inner.width as usize / 2);
+
+    if lines.is_empty() {
+        let line = Line::styled("No matching bindings", Style::default().fg(Color::DarkGray));
+        frame.render_widget(line, Rect::new(inner.x, inner.y, inner.width, 1));
+    }
+    for (i, line) in lines.iter().skip(scroll).take(visible).enumerate() {
+        let line = match line {
+            HelpLine::Header(title) => Line::styled(
+                *title,
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            HelpLine::Binding { keys, label } => {
+                let keys: String = keys.chars().take(key_width).collect();
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>width$}", keys, width = key_width),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::raw("  "),
+                    Span::styled(*label, Style::default().fg(Color::White)),
+                ])
+            }
+            HelpLine::Blank => Line::default(),
+        };
         let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
         frame.render_widget(line, line_area);
     }
@@ -244,10 +793,12 @@ fn render_help_popup(frame: &mut Frame, hints: &[(String, &str)], area: Rect) {
     // Footer
     if inner.height > 0 {
         let footer_y = inner.y + inner.height - 1;
-        let footer = Line::styled(
-            "Press any key to close",
-            Style::default().fg(Color::DarkGray),
-        );
+        let text = if help.searching {
+            "Type to filter · Enter keep · Esc clear"
+        } else {
+            "j/k scroll · / search · Esc close"
+        };
+        let footer = Line::styled(text, Style::default().fg(Color::DarkGray));
         let footer_area = Rect::new(inner.x, footer_y, inner.width, 1);
         frame.render_widget(footer, footer_area);
     }
@@ -422,35 +973,158 @@ fn render_slash_menu_popup(frame: &mut Frame, sm: &SlashMenuState, area: Rect) {
             )
         };
 
-        let name_display: String = format!("/{}", cmd.name)
-            .chars()
-            .take(name_col_width)
+        // Offset match positions by one for the leading '/'
+        let positions: Vec<usize> = fuzzy::match_positions(&sm.query, cmd.name)
+            .into_iter()
+            .map(|p| p + 1)
             .collect();
-        let name_padding = name_col_width.saturating_sub(name_display.chars().count());
-        let name_padded = format!("{}{}", name_display, " ".repeat(name_padding));
+        let mut spans = highlighted_spans(
+            &format!("/{}", cmd.name),
+            &positions,
+            name_col_width,
+            name_style,
+        );
 
         let desc_width = (inner.width as usize).saturating_sub(name_col_width + 1);
         let desc_display: String = cmd.description.chars().take(desc_width).collect();
         let desc_padding = desc_width.saturating_sub(desc_display.chars().count());
         let desc_padded = format!("{}{}", desc_display, " ".repeat(desc_padding));
 
-        let line = Line::from(vec![
-            Span::styled(name_padded, name_style),
-            Span::styled(
-                " ",
-                if is_selected {
-                    Style::default().bg(Color::DarkGray)
-                } else {
-                    Style::default()
-                },
-            ),
-            Span::styled(desc_padded, desc_style),
-        ]);
+        spans.push(Span::styled(
+            " ",
+            if is_selected {
+                Style::default().bg(Color::DarkGray)
+            } else {
+                Style::default()
+            },
+        ));
+        spans.push(Span::styled(desc_padded, desc_style));
+        let line = Line::from(spans);
         let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
         frame.render_widget(line, line_area);
     }
 }
 
+fn render_rename_page_popup(frame: &mut Frame, rename: &RenamePageState, area: Rect) {
+    let max_items = rename
+        .preview
+        .as_ref()
+        .map(|p| 12.min(p.len()).max(1))
+        .unwrap_or(0);
+    // +2 borders, title input, status line, key hints
+    let popup_height = (max_items + 5) as u16;
+    let popup_width = (area.width * 70 / 100).max(30).min(area.width);
+    let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height.min(area.height));
+    frame.render_widget(Clear, popup_area);
+
+    let block = WidgetBlock::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title(format!(" Rename: {} ", rename.old_title));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let max_text_width = inner.width as usize;
+    let dim = Style::default().fg(Color::DarkGray);
+
+    // Title input, with a block cursor while it's being edited
+    let text = rename.input.to_string();
+    let mut input_spans = vec![Span::styled("New title: ", dim)];
+    if rename.preview.is_none() {
+        let chars: Vec<char> = text.chars().collect();
+        let cursor = rename.input.cursor.min(chars.len());
+        let cursor_style = Style::default().fg(Color::Black).bg(Color::White);
+        input_spans.push(Span::raw(chars[..cursor].iter().collect::<String>()));
+        let at_cursor = chars
+            .get(cursor)
+            .map(|c| c.to_string())
+            .unwrap_or(" ".into());
+        input_spans.push(Span::styled(at_cursor, cursor_style));
+        if cursor + 1 < chars.len() {
+            input_spans.push(Span::raw(chars[cursor + 1..].iter().collect::<String>()));
+        }
+    } else {
+        input_spans.push(Span::styled(text, Style::default().fg(Color::Yellow)));
+    }
+    frame.render_widget(
+        Line::from(input_spans),
+        Rect::new(inner.x, inner.y, inner.width, 1),
+    );
+
+    let status = if let Some(err) = &rename.error {
+        Line::styled(err.clone(), Style::default().fg(Color::Red))
+    } else if rename.waiting_for_refs {
+        Line::styled("Loading linked references...", dim)
+    } else if let Some(preview) = &rename.preview {
+        let pages: std::collections::HashSet<&str> =
+            preview.iter().map(|e| e.page_title.as_str()).collect();
+        Line::styled(
+            format!(
+                "{} block{} on {} page{} will be updated",
+                preview.len(),
+                if preview.len() == 1 { "" } else { "s" },
+                pages.len(),
+                if pages.len() == 1 { "" } else { "s" }
+            ),
+            dim,
+        )
+    } else {
+        Line::styled("References in other blocks are rewritten too", dim)
+    };
+    frame.render_widget(status, Rect::new(inner.x, inner.y + 1, inner.width, 1));
+
+    if let Some(preview) = &rename.preview {
+        let list_y = inner.y + 2;
+        let list_height = inner.height.saturating_sub(3) as usize;
+        let scroll_offset = if rename.selected >= list_height {
+            rename.selected - list_height + 1
+        } else {
+            0
+        };
+        for (i, edit) in preview
+            .iter()
+            .skip(scroll_offset)
+            .take(list_height)
+            .enumerate()
+        {
+            let is_selected = (i + scroll_offset) == rename.selected;
+            let style = if is_selected {
+                Style::default().fg(Color::White).bg(Color::DarkGray)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            let page = format!("{}: ", edit.page_title);
+            let page_width = page.chars().count().min(max_text_width);
+            let page_display: String = page.chars().take(page_width).collect();
+            let text_width = max_text_width - page_width;
+            let first_line = edit.new_text.lines().next().unwrap_or("");
+            let display: String = first_line.chars().take(text_width).collect();
+            let padding = text_width.saturating_sub(display.chars().count());
+            let line = Line::from(vec![
+                Span::styled(page_display, style.fg(Color::DarkGray)),
+                Span::styled(format!("{}{}", display, " ".repeat(padding)), style),
+            ]);
+            frame.render_widget(line, Rect::new(inner.x, list_y + i as u16, inner.width, 1));
+        }
+    }
+
+    let hint_y = inner.y + inner.height.saturating_sub(1);
+    let hints = if rename.preview.is_some() {
+        "Enter/y rename · j/k scroll · Esc back"
+    } else {
+        "Enter preview · Esc cancel"
+    };
+    frame.render_widget(
+        Line::styled(hints, dim),
+        Rect::new(inner.x, hint_y, inner.width, 1),
+    );
+}
+
 fn render_quick_switcher_popup(frame: &mut Frame, qs: &QuickSwitcherState, area: Rect) {
     let max_visible = 10;
     let visible_count = if qs.filtered.is_empty() {
@@ -519,12 +1193,147 @@ fn render_quick_switcher_popup(frame: &mut Frame, qs: &QuickSwitcherState, area:
             Style::default().fg(Color::Gray)
         };
 
-        let max_text_width = inner.width as usize;
-        let display: String = title.chars().take(max_text_width).collect();
-        let padding = max_text_width.saturating_sub(display.chars().count());
-        let padded = format!("{}{}", display, " ".repeat(padding));
+        let positions = fuzzy::match_positions(&qs.query, title);
+        let line = Line::from(highlighted_spans(
+            title,
+            &positions,
+            inner.width as usize,
+            style,
+        ));
+        let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
+        frame.render_widget(line, line_area);
+    }
+}
 
-        let line = Line::from(vec![Span::styled(padded, style)]);
+fn render_command_palette_popup(frame: &mut Frame, palette: &CommandPaletteState, area: Rect) {
+    let max_visible = 10;
+    let visible_count = if palette.filtered.is_empty() {
+        1
+    } else {
+        max_visible.min(palette.filtered.len())
+    };
+    let popup_height = (visible_count + 2) as u16;
+    let popup_width = (area.width * 80 / 100).max(30).min(area.width);
+    let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let y = area.y + area.height.saturating_sub(popup_height) / 3;
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+    frame.render_widget(Clear, popup_area);
+
+    let name = if palette.date_prompt {
+        "Go to date"
+    } else {
+        "Commands"
+    };
+    let title = if palette.query.is_empty() {
+        format!(" {} ", name)
+    } else {
+        format!(" {}: {} ", name, palette.query)
+    };
+
+    let block = WidgetBlock::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Magenta))
+        .title(title);
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    if palette.filtered.is_empty() {
+        let (msg, style) = match (&palette.error, palette.date_prompt) {
+            (Some(error), _) => (error.as_str(), Style::default().fg(Color::Red)),
+            (None, true) => (
+                "next friday, in 3 days, mar 5, 2026-03-01, …",
+                Style::default().fg(Color::DarkGray),
+            ),
+            (None, false) => ("No matching commands", Style::default().fg(Color::DarkGray)),
+        };
+        let line = Line::from(vec![Span::styled(msg, style)]);
+        let line_area = Rect::new(inner.x, inner.y, inner.width, 1);
+        frame.render_widget(line, line_area);
+        return;
+    }
+
+    let scroll_offset = if palette.selected >= max_visible {
+        palette.selected - max_visible + 1
+    } else {
+        0
+    };
+
+    for (i, entry) in palette
+        .filtered
+        .iter()
+        .skip(scroll_offset)
+        .take(visible_count)
+        .enumerate()
+    {
+        if i as u16 >= inner.height {
+            break;
+        }
+        let is_selected = (i + scroll_offset) == palette.selected;
+        let style = if is_selected {
+            Style::default().fg(Color::White).bg(Color::DarkGray)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+
+        // Keys are right-aligned after the label, which gets the rest of the row
+        let keys_width = entry.keys.chars().count().min(inner.width as usize / 2);
+        let label_width = (inner.width as usize).saturating_sub(keys_width + 1);
+        let positions = fuzzy::match_positions(&palette.query, &entry.label);
+        let mut spans = highlighted_spans(&entry.label, &positions, label_width, style);
+        spans.push(Span::styled(" ", style));
+        let keys: String = entry.keys.chars().take(keys_width).collect();
+        spans.push(Span::styled(keys, style.fg(Color::Cyan)));
+        let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
+        frame.render_widget(Line::from(spans), line_area);
+    }
+}
+
+/// Possible next keys of a partly typed sequence, in the bottom-right corner.
+fn render_which_key_popup(frame: &mut Frame, which_key: &WhichKeyState, area: Rect) {
+    let key_width = which_key
+        .entries
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    let label_width = which_key
+        .entries
+        .iter()
+        .map(|(_, label)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let title = format!(" {} ", which_key.prefix);
+    let popup_width = ((key_width + label_width + 2).max(title.chars().count()) + 2)
+        .min(area.width as usize) as u16;
+    let popup_height = (which_key.entries.len() + 2).min(area.height as usize) as u16;
+    let x = area.x + area.width.saturating_sub(popup_width);
+    let y = area.y + area.height.saturating_sub(popup_height);
+
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+    frame.render_widget(Clear, popup_area);
+
+    let block = WidgetBlock::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Magenta))
+        .title(title);
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    for (i, (key, label)) in which_key.entries.iter().enumerate() {
+        if i as u16 >= inner.height {
+            break;
+        }
+        let line = Line::from(vec![
+            Span::styled(
+                format!("{:<width$}  ", key, width = key_width),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(label.clone(), Style::default().fg(Color::Gray)),
+        ]);
         let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
         frame.render_widget(line, line_area);
     }
@@ -547,6 +1356,8 @@ fn render_search_popup(frame: &mut Frame, search: &SearchState, area: Rect) {
 
     let title = if search.query.is_empty() {
         " Search ".to_string()
+    } else if search.pending_requests > 0 {
+        format!(" Search: {} (searching graph...) ", search.query)
     } else {
         format!(" Search: {} ", search.query)
     };
@@ -575,7 +1386,7 @@ fn render_search_popup(frame: &mut Frame, search: &SearchState, area: Rect) {
         0
     };
 
-    for (i, (_, text)) in search
+    for (i, result) in search
         .results
         .iter()
         .skip(scroll_offset)
@@ -586,18 +1397,47 @@ fn render_search_popup(frame: &mut Frame, search: &SearchState, area: Rect) {
             break;
         }
         let is_selected = (i + scroll_offset) == search.selected;
-        let style = if is_selected {
-            Style::default().fg(Color::White).bg(Color::DarkGray)
+        let (style, context_style) = if is_selected {
+            (
+                Style::default().fg(Color::White).bg(Color::DarkGray),
+                Style::default().fg(Color::Yellow).bg(Color::DarkGray),
+            )
+        } else if result.is_page {
+            (
+                Style::default().fg(Color::Cyan),
+                Style::default().fg(Color::DarkGray),
+            )
         } else {
-            Style::default().fg(Color::Gray)
+            (
+                Style::default().fg(Color::Gray),
+                Style::default().fg(Color::DarkGray),
+            )
         };
 
         let max_text_width = inner.width as usize;
-        let display: String = text.chars().take(max_text_width).collect();
-        let padding = max_text_width.saturating_sub(display.chars().count());
-        let padded = format!("{}{}", display, " ".repeat(padding));
+        let text = if result.is_page {
+            format!("[[{}]]", result.text)
+        } else {
+            result.text.replace('\n', " ")
+        };
+        let context = match (&result.page_title, result.is_page) {
+            (Some(page), false) => format!("  {}", page),
+            _ => String::new(),
+        };
+        let context_width = context.chars().count().min(max_text_width / 3);
+        let text_width = max_text_width - context_width;
+        let offset = if result.is_page { 2 } else { 0 }; // leading "[["
+        let positions = if result.is_page {
+            fuzzy::match_positions(&search.query, &result.text)
+        } else {
+            fuzzy::substring_positions(&search.query, &result.text)
+        };
+        let positions: Vec<usize> = positions.into_iter().map(|p| p + offset).collect();
+        let context: String = context.chars().take(context_width).collect();
 
-        let line = Line::from(vec![Span::styled(padded, style)]);
+        let mut spans = highlighted_spans(&text, &positions, text_width, style);
+        spans.push(Span::styled(context, context_style));
+        let line = Line::from(spans);
         let line_area = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
         frame.render_widget(line, line_area);
     }
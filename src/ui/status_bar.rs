@@ -8,10 +8,23 @@ pub struct StatusBar<'a> {
     pub hints: &'a [(String, &'static str)],
     pub message: Option<&'a str>,
     pub insert_mode: bool,
+    /// Vim normal mode inside the block editor.
+    pub editor_normal: bool,
+    /// Number of selected rows while in visual mode.
+    pub visual_count: Option<usize>,
 }
 
 impl<'a> Widget for StatusBar<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.editor_normal {
+            let line = Line::from(Span::styled(
+                " -- NORMAL -- (ESC para salvar) ",
+                Style::default().fg(Color::Cyan),
+            ));
+            line.render(area, buf);
+            return;
+        }
+
         if self.insert_mode {
             let line = Line::from(Span::styled(
                 " -- INSERT -- (ESC para salvar) ",
@@ -21,6 +34,21 @@ impl<'a> Widget for StatusBar<'a> {
             return;
         }
 
+        if let Some(count) = self.visual_count {
+            let mut spans = vec![Span::styled(
+                format!(" -- VISUAL -- {} selected ", count),
+                Style::default().fg(Color::Blue),
+            )];
+            if let Some(msg) = self.message {
+                spans.push(Span::styled(
+                    format!(" {} ", msg),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            Line::from(spans).render(area, buf);
+            return;
+        }
+
         if let Some(msg) = self.message {
             let line = Line::from(Span::styled(
                 format!(" {} ", msg),
@@ -68,6 +96,8 @@ mod tests {
             hints: &hints,
             message: None,
             insert_mode: false,
+            editor_normal: false,
+            visual_count: None,
         };
         bar.render(area, &mut buf);
 
@@ -98,6 +128,8 @@ mod tests {
             hints: &hints,
             message: Some("Loading pages..."),
             insert_mode: false,
+            editor_normal: false,
+            visual_count: None,
         };
         bar.render(area, &mut buf);
 
@@ -125,6 +157,8 @@ mod tests {
             hints: &[],
             message: None,
             insert_mode: true,
+            editor_normal: false,
+            visual_count: None,
         };
         bar.render(area, &mut buf);
 
@@ -141,4 +175,62 @@ mod tests {
 
         assert!(content.contains("INSERT"));
     }
+
+    #[test]
+    fn status_bar_shows_editor_normal_mode() {
+        let area = Rect::new(0, 0, 60, 1);
+        let mut buf = Buffer::empty(area);
+
+        let bar = StatusBar {
+            hints: &[],
+            message: None,
+            insert_mode: true,
+            editor_normal: true,
+            visual_count: None,
+        };
+        bar.render(area, &mut buf);
+
+        let content: String = (0..area.width)
+            .map(|x| {
+                buf.cell((x, 0))
+                    .unwrap()
+                    .symbol()
+                    .chars()
+                    .next()
+                    .unwrap_or(' ')
+            })
+            .collect();
+
+        assert!(content.contains("NORMAL"));
+        assert!(!content.contains("INSERT"));
+    }
+
+    #[test]
+    fn status_bar_shows_visual_selection() {
+        let area = Rect::new(0, 0, 60, 1);
+        let mut buf = Buffer::empty(area);
+
+        let bar = StatusBar {
+            hints: &[],
+            message: Some("Copied 3 blocks"),
+            insert_mode: false,
+            editor_normal: false,
+            visual_count: Some(3),
+        };
+        bar.render(area, &mut buf);
+
+        let content: String = (0..area.width)
+            .map(|x| {
+                buf.cell((x, 0))
+                    .unwrap()
+                    .symbol()
+                    .chars()
+                    .next()
+                    .unwrap_or(' ')
+            })
+            .collect();
+
+        assert!(content.contains("VISUAL -- 3 selected"));
+        assert!(content.contains("Copied 3 blocks"));
+    }
 }